@@ -0,0 +1,51 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use evaluator::{core::rule::CompoundPredicate, predicate};
+use serde_json::json;
+use std::hint::black_box;
+
+/// An `any` predicate whose children each search a large array without finding their value, so
+/// every child has to be evaluated.
+fn expensive_any(children: usize) -> CompoundPredicate {
+    CompoundPredicate::Any(
+        (0..children)
+            .map(|i| predicate!("values" contains format!("missing-{i}")).into())
+            .collect(),
+    )
+}
+
+fn bench_any(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .build()
+        .expect("runtime should be built");
+
+    let input = json!({
+        "values": (0..10_000).map(|i| format!("value-{i}")).collect::<Vec<_>>(),
+    });
+
+    let mut group = c.benchmark_group("any");
+
+    for children in [2, 8, 32] {
+        let predicate = expensive_any(children);
+
+        group.bench_with_input(
+            BenchmarkId::new("sequential", children),
+            &predicate,
+            |b, predicate| b.iter(|| predicate.evaluate(black_box(&input))),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("concurrent", children),
+            &predicate,
+            |b, predicate| {
+                b.iter(|| {
+                    runtime.block_on(predicate.evaluate_concurrent_with(black_box(&input), 0))
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_any);
+criterion_main!(benches);
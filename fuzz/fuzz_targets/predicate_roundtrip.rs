@@ -0,0 +1,16 @@
+#![no_main]
+
+use evaluator::core::rule::Predicate;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(predicate) = serde_json::from_slice::<Predicate>(data) else {
+        return;
+    };
+
+    let serialized = serde_json::to_string(&predicate).expect("failed to serialize predicate");
+    let deserialized: Predicate =
+        serde_json::from_str(&serialized).expect("failed to deserialize serialized predicate");
+
+    assert_eq!(predicate, deserialized);
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Unstructured};
+use evaluator::core::{arbitrary::json_value, rule::Rule};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+
+    let (Ok(rule), Ok(input)) = (Rule::arbitrary(&mut u), json_value(&mut u)) else {
+        return;
+    };
+
+    let _ = rule.evaluate(&input);
+});
@@ -0,0 +1,179 @@
+use clap::{Parser, ValueEnum};
+use evaluator::{
+    core::eval::FieldPresence,
+    repository::{
+        Evaluation, EvaluationOptions, EvaluationResult, InMemRuleRepository, RuleRepository,
+        format::RuleFileFormat,
+    },
+};
+use std::{path::PathBuf, process::ExitCode};
+
+/// Evaluates rules from a file against a JSON input without running the HTTP server.
+///
+/// Exits with code 0 if the input passes, 1 if it fails and 2 on any error.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// File containing the rules, as JSON, TOML or YAML depending on its extension. Files with
+    /// any other extension are read as JSON.
+    #[arg(long, default_value = "rules.json")]
+    rules_file: PathBuf,
+
+    /// JSON input to evaluate the rules against.
+    #[arg(long)]
+    input: String,
+
+    /// Comma separated ids of the rules to evaluate.
+    #[arg(long, value_delimiter = ',', required_unless_present = "all")]
+    rule_ids: Vec<String>,
+
+    /// Evaluate every rule in the rules file.
+    #[arg(long, conflicts_with = "rule_ids")]
+    all: bool,
+
+    /// Check the input against the rules first and error if any referenced fields are missing or
+    /// have a type the rule can't be evaluated on.
+    #[arg(long)]
+    strict: bool,
+
+    /// Stop evaluating as soon as a rule fails.
+    #[arg(long)]
+    stop_on_first_failure: bool,
+
+    /// Include the value read from the input in each reason.
+    #[arg(long)]
+    include_input_values: bool,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    output: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    match run(Args::parse()).await {
+        Ok(EvaluationResult::Fail) => ExitCode::from(1),
+        Ok(EvaluationResult::Error) => ExitCode::from(2),
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<EvaluationResult, String> {
+    let input: serde_json::Value =
+        serde_json::from_str(&args.input).map_err(|err| format!("invalid input: {err}"))?;
+
+    let rules_file = tokio::fs::File::open(&args.rules_file)
+        .await
+        .map_err(|err| format!("failed to open {}: {err}", args.rules_file.display()))?;
+
+    let format = RuleFileFormat::detect(&args.rules_file).unwrap_or_default();
+
+    let repository = InMemRuleRepository::import_from_reader_as(rules_file, format)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let rules = match args.all {
+        true => {
+            let mut rules = repository.get_all().await.map_err(|err| err.to_string())?;
+            rules.sort_by(|a, b| a.id.cmp(&b.id));
+            rules
+        }
+        false => {
+            let mut rules = Vec::with_capacity(args.rule_ids.len());
+            for id in &args.rule_ids {
+                rules.push(repository.get(id).await.map_err(|err| err.to_string())?);
+            }
+            rules
+        }
+    };
+
+    if args.strict {
+        let issues: Vec<String> = rules
+            .iter()
+            .flat_map(|rule| {
+                let missing = rule
+                    .missing_paths(&input)
+                    .into_iter()
+                    .map(|path| format!("{}: missing field `{path}`", rule.id));
+
+                missing.chain(rule.dry_run(&input)).collect::<Vec<_>>()
+            })
+            .collect();
+
+        if !issues.is_empty() {
+            return Err(format!(
+                "input failed strict checks:\n{}",
+                issues.join("\n")
+            ));
+        }
+    }
+
+    let ids: Vec<String> = rules.into_iter().map(|rule| rule.id).collect();
+    let options = EvaluationOptions {
+        stop_on_first_failure: args.stop_on_first_failure,
+        include_input_values: args.include_input_values,
+        field_presence: match args.strict {
+            true => FieldPresence::Strict,
+            false => FieldPresence::Lenient,
+        },
+        ..Default::default()
+    };
+
+    let evaluation = repository
+        .evaluate(&ids, input, options)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    match args.output {
+        OutputFormat::Json => print_json(&evaluation)?,
+        OutputFormat::Csv => print_csv(&evaluation)?,
+    }
+
+    Ok(evaluation.result)
+}
+
+fn print_json(evaluation: &Evaluation) -> Result<(), String> {
+    let output = serde_json::to_string_pretty(evaluation).map_err(|err| err.to_string())?;
+    println!("{output}");
+    Ok(())
+}
+
+/// Prints a row per reason with the same columns for every reason, leaving `inputValue` empty
+/// when there's none. The input value is written as JSON as it can be any JSON value.
+fn print_csv(evaluation: &Evaluation) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(std::io::stdout());
+
+    writer
+        .write_record(["rule", "requirement", "evaluation", "weight", "inputValue"])
+        .map_err(|err| err.to_string())?;
+
+    for reason in &evaluation.reasons {
+        let evaluation = serde_json::to_value(reason.evaluation).map_err(|err| err.to_string())?;
+        let input_value = reason
+            .input_value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+
+        writer
+            .write_record([
+                reason.rule.as_str(),
+                reason.requirement.as_str(),
+                evaluation.as_str().unwrap_or_default(),
+                &reason.weight.to_string(),
+                &input_value,
+            ])
+            .map_err(|err| err.to_string())?;
+    }
+
+    writer.flush().map_err(|err| err.to_string())
+}
@@ -1,24 +1,74 @@
+pub mod analysis;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod cardinality;
+pub mod elasticsearch;
 pub mod eval;
+pub mod json_logic;
 pub mod rule;
+pub mod transform;
 
 #[macro_export]
 macro_rules! rule {
-    ($id:literal, $message:literal, $predicate:expr) => {
+    ($id:expr, $message:expr, $predicate:expr, depends_on: [$($dependency:expr),* $(,)?]) => {
+        $crate::core::rule::Rule {
+            depends_on: vec![$(String::from($dependency)),*],
+            ..$crate::rule!($id, $message, $predicate)
+        }
+    };
+    ($id:expr, $message:expr, $predicate:expr) => {
         $crate::core::rule::Rule {
             id: String::from($id),
             message: String::from($message),
             predicate: $crate::core::rule::Predicate::from($predicate),
+            depends_on: Vec::new(),
+            active_from: None,
+            active_until: None,
+            condition: None,
+            weight: 1.0,
+            category: None,
+            max_evaluations: None,
+            path_aliases: ::std::collections::HashMap::new(),
+            input_schema: None,
+            retry_on_error: false,
+            max_retries: 1,
+            input_defaults: None,
+            group_by: None,
+            author: None,
+            reviewed_by: None,
+            created_at: None,
+            updated_at: None,
         }
     };
 }
 
 #[macro_export]
 macro_rules! predicate {
+            ($path:literal is_null) => {
+                    $crate::core::rule::RawPredicate {
+                    path: $path.to_owned(),
+                    path_syntax: $crate::core::rule::PathSyntax::Dot,
+                    operator: $crate::core::rule::Operator::IsNull,
+                    value: serde_json::Value::Null,
+                    quantifier: $crate::core::rule::Quantifier::Any
+                }
+            };
+            ($path:literal is_not_null) => {
+                    $crate::core::rule::RawPredicate {
+                    path: $path.to_owned(),
+                    path_syntax: $crate::core::rule::PathSyntax::Dot,
+                    operator: $crate::core::rule::Operator::IsNotNull,
+                    value: serde_json::Value::Null,
+                    quantifier: $crate::core::rule::Quantifier::Any
+                }
+            };
             ($path:literal $operator:tt $value:expr) => {
                     $crate::core::rule::RawPredicate {
                     path: $path.to_owned(),
+                    path_syntax: $crate::core::rule::PathSyntax::Dot,
                     operator: predicate!(operator $operator),
-                    value: serde_json::Value::from($value)
+                    value: serde_json::Value::from($value),
+                    quantifier: $crate::core::rule::Quantifier::Any
                 }
             };
             (operator ==) => {$crate::core::rule::Operator::Equal};
@@ -28,6 +78,10 @@ macro_rules! predicate {
             (operator <=) => {$crate::core::rule::Operator::LessEqual};
             (operator !=) => {$crate::core::rule::Operator::NotEqual};
             (operator contains) => {$crate::core::rule::Operator::Contains};
+            (operator starts_with) => {$crate::core::rule::Operator::StartsWith};
+            (operator ends_with) => {$crate::core::rule::Operator::EndsWith};
+            (operator divisible) => {$crate::core::rule::Operator::Divisible};
+            (operator not_divisible) => {$crate::core::rule::Operator::NotDivisible};
         }
 
 #[macro_export]
@@ -39,6 +93,10 @@ macro_rules! all { ($($predicate:expr),*) => {$crate::core::rule::CompoundPredic
 #[macro_export]
 macro_rules! none { ($($predicate:expr),*) => {$crate::core::rule::CompoundPredicate::None(vec![$($crate::core::rule::Predicate::from($predicate),)*])}; }
 
+/// `one_of!("status", "a", "b")` checks the value at `status` is `"a"` or `"b"`.
+#[macro_export]
+macro_rules! one_of { ($path:expr, $($value:expr),*) => {$crate::core::rule::CompoundPredicate::OneOf { path: $path.to_owned(), values: vec![$(serde_json::Value::from($value),)*] }}; }
+
 #[macro_export]
 macro_rules! not {
     ($predicate:expr) => {
@@ -47,3 +105,40 @@ macro_rules! not {
         )))
     };
 }
+
+/// `if_then_else!(predicate!("country" == "US"), predicate!("age" >= 21), predicate!("age" >= 18))`
+/// checks `age` against 21 in the US and 18 elsewhere.
+#[macro_export]
+macro_rules! if_then_else {
+    ($condition:expr, $then:expr, $otherwise:expr) => {
+        $crate::core::rule::CompoundPredicate::If {
+            condition: Box::new($crate::core::rule::Predicate::from($condition)),
+            then: Box::new($crate::core::rule::Predicate::from($then)),
+            otherwise: Box::new($crate::core::rule::Predicate::from($otherwise)),
+        }
+    };
+}
+
+/// `for_each!("items", predicate!("price" is_not_null))` checks every element of `items` has a
+/// `price`.
+#[macro_export]
+macro_rules! for_each {
+    ($array_path:expr, $predicate:expr) => {
+        $crate::core::rule::CompoundPredicate::ForEach {
+            array_path: $array_path.to_owned(),
+            predicate: Box::new($crate::core::rule::Predicate::from($predicate)),
+        }
+    };
+}
+
+/// `exists_in!("users", predicate!("role" == "admin"))` checks at least one element of `users` has
+/// the `admin` role.
+#[macro_export]
+macro_rules! exists_in {
+    ($array_path:expr, $predicate:expr) => {
+        $crate::core::rule::CompoundPredicate::ExistsIn {
+            array_path: $array_path.to_owned(),
+            predicate: Box::new($crate::core::rule::Predicate::from($predicate)),
+        }
+    };
+}
@@ -0,0 +1,469 @@
+use crate::core::rule::{CompoundPredicate, Operator, Predicate, RawPredicate, Rule};
+
+/// How two rules conflict, see `RuleConflictAnalyzer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictType {
+    /// No input can pass both rules.
+    Contradictory,
+    /// The rules have identical predicates, so one of them is unnecessary.
+    Redundant,
+    /// Every input passing one rule also passes the other, e.g. one rule is an `any` with the
+    /// other rule's predicate as a branch.
+    Overlapping,
+}
+
+/// A pair of rules which are likely a mistake to deploy together. `rule_a` comes before `rule_b`
+/// in the analyzed rules.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RuleConflict {
+    pub rule_a: String,
+    pub rule_b: String,
+    pub conflict_type: ConflictType,
+}
+
+/// Best effort detection of conflicts between rules, comparing only their predicates. Like
+/// `Predicate::conflicts_with`, finding no conflicts doesn't mean the rules are compatible.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuleConflictAnalyzer;
+
+impl RuleConflictAnalyzer {
+    /// Every conflicting pair of `rules`, in the order the rules are given. Each pair has at most
+    /// one conflict, checked for in the order `Redundant`, `Contradictory`, `Overlapping`.
+    pub fn analyze(rules: &[Rule]) -> Vec<RuleConflict> {
+        let mut conflicts = Vec::new();
+
+        for (i, a) in rules.iter().enumerate() {
+            for b in &rules[i + 1..] {
+                let (a_predicate, b_predicate) = (&a.predicate, &b.predicate);
+
+                let conflict_type = if a_predicate == b_predicate {
+                    ConflictType::Redundant
+                } else if a_predicate.contradicts(b_predicate) {
+                    ConflictType::Contradictory
+                } else if a_predicate.implies(b_predicate) || b_predicate.implies(a_predicate) {
+                    ConflictType::Overlapping
+                } else {
+                    continue;
+                };
+
+                conflicts.push(RuleConflict {
+                    rule_a: a.id.clone(),
+                    rule_b: b.id.clone(),
+                    conflict_type,
+                });
+            }
+        }
+
+        conflicts
+    }
+}
+
+impl Rule {
+    /// See `Predicate::conflicts_with`.
+    pub fn conflicts_with(&self, other: &Rule) -> bool {
+        self.predicate.conflicts_with(&other.predicate)
+    }
+}
+
+impl Predicate {
+    /// Best effort check for whether two predicates are identical or contradict each other, i.e.
+    /// no input can pass both. Only constraints every passing input must satisfy are compared, so
+    /// branches of an `Any` are ignored. A `false` result doesn't mean the predicates are
+    /// compatible.
+    pub fn conflicts_with(&self, other: &Predicate) -> bool {
+        self == other || self.contradicts(other)
+    }
+
+    /// Whether no input can pass both predicates, see `conflicts_with`.
+    fn contradicts(&self, other: &Predicate) -> bool {
+        let other_constraints = other.constraints();
+
+        self.constraints().iter().any(|constraint| {
+            other_constraints
+                .iter()
+                .any(|other| constraint.contradicts(other))
+        })
+    }
+
+    /// Whether every input passing `self` passes `other`, judged only by `other` being an `Any`
+    /// with `self` as a branch or `self` being an `All` with `other` as one of its predicates.
+    fn implies(&self, other: &Predicate) -> bool {
+        let is_branch_of_other = matches!(
+            other,
+            Predicate::Compound(CompoundPredicate::Any(branches)) if branches.contains(self)
+        );
+        let other_is_part_of_self = matches!(
+            self,
+            Predicate::Compound(CompoundPredicate::All(predicates)) if predicates.contains(other)
+        );
+
+        is_branch_of_other || other_is_part_of_self
+    }
+
+    /// Raw predicates every passing input has to satisfy.
+    fn constraints(&self) -> Vec<RawPredicate> {
+        match self {
+            Predicate::Raw(raw) => vec![raw.clone()],
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                predicates.iter().flat_map(Predicate::constraints).collect()
+            }
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => {
+                negated_constraints(std::slice::from_ref(predicate))
+            }
+            Predicate::Compound(CompoundPredicate::None(predicates)) => {
+                negated_constraints(predicates)
+            }
+            Predicate::Compound(
+                CompoundPredicate::Any(_)
+                | CompoundPredicate::OneOf { .. }
+                | CompoundPredicate::If { .. }
+                | CompoundPredicate::ForEach { .. }
+                | CompoundPredicate::ExistsIn { .. },
+            ) => Vec::new(),
+        }
+    }
+}
+
+/// Every raw predicate in `predicates` must fail, so their negations must pass.
+fn negated_constraints(predicates: &[Predicate]) -> Vec<RawPredicate> {
+    predicates
+        .iter()
+        .filter_map(|predicate| match predicate {
+            Predicate::Raw(raw) => raw.negated(),
+            Predicate::Compound(_) => None,
+        })
+        .collect()
+}
+
+/// Which side of a range a comparison bounds and whether the bound itself is included.
+struct Bound {
+    value: f64,
+    is_lower: bool,
+    is_inclusive: bool,
+}
+
+impl RawPredicate {
+    fn negated(&self) -> Option<RawPredicate> {
+        let operator = match self.operator {
+            Operator::Equal => Operator::NotEqual,
+            Operator::NotEqual => Operator::Equal,
+            Operator::Greater => Operator::LessEqual,
+            Operator::LessEqual => Operator::Greater,
+            Operator::Less => Operator::GreaterEqual,
+            Operator::GreaterEqual => Operator::Less,
+            Operator::IsNull => Operator::IsNotNull,
+            Operator::IsNotNull => Operator::IsNull,
+            Operator::Divisible => Operator::NotDivisible,
+            Operator::NotDivisible => Operator::Divisible,
+            Operator::Contains | Operator::StartsWith | Operator::EndsWith => return None,
+        };
+
+        Some(RawPredicate {
+            operator,
+            ..self.clone()
+        })
+    }
+
+    fn bound(&self) -> Option<Bound> {
+        let (is_lower, is_inclusive) = match self.operator {
+            Operator::Greater => (true, false),
+            Operator::GreaterEqual => (true, true),
+            Operator::Less => (false, false),
+            Operator::LessEqual => (false, true),
+            _ => return None,
+        };
+
+        Some(Bound {
+            value: self.value.as_f64()?,
+            is_lower,
+            is_inclusive,
+        })
+    }
+
+    /// Whether no input can satisfy both predicates.
+    fn contradicts(&self, other: &RawPredicate) -> bool {
+        self.path == other.path && (self.excludes(other) || other.excludes(self))
+    }
+
+    /// Checks for contradictions between operator pairs in one order only, see `contradicts`.
+    fn excludes(&self, other: &RawPredicate) -> bool {
+        match (self.operator, other.operator) {
+            (Operator::IsNull, Operator::IsNotNull) => true,
+            (Operator::IsNull, Operator::Equal) => !other.value.is_null(),
+            (Operator::IsNull, Operator::Contains) => true,
+            (Operator::IsNotNull, Operator::Equal) => other.value.is_null(),
+            (Operator::Equal, Operator::Equal) => self.value != other.value,
+            (Operator::Equal, Operator::NotEqual) => self.value == other.value,
+            (Operator::Divisible, Operator::NotDivisible) => self.value == other.value,
+            (Operator::Equal, Operator::Contains) => match (&self.value, &other.value) {
+                (serde_json::Value::Array(values), value) => !values.contains(value),
+                (serde_json::Value::String(string), serde_json::Value::String(substring)) => {
+                    !string.contains(substring.as_str())
+                }
+                _ => true,
+            },
+            _ => match (self.bound(), other.bound()) {
+                (Some(a), Some(b)) => a.is_disjoint(&b),
+                (None, Some(b)) => match self.operator {
+                    Operator::IsNull => true,
+                    Operator::Equal => self.value.as_f64().is_none_or(|value| !b.contains(value)),
+                    _ => false,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+impl Bound {
+    fn contains(&self, value: f64) -> bool {
+        match (self.is_lower, self.is_inclusive) {
+            (true, true) => value >= self.value,
+            (true, false) => value > self.value,
+            (false, true) => value <= self.value,
+            (false, false) => value < self.value,
+        }
+    }
+
+    fn is_disjoint(&self, other: &Bound) -> bool {
+        let (lower, upper) = match (self.is_lower, other.is_lower) {
+            (true, false) => (self, other),
+            (false, true) => (other, self),
+            // Two lower or two upper bounds always overlap.
+            _ => return false,
+        };
+
+        lower.value > upper.value
+            || (lower.value == upper.value && !(lower.is_inclusive && upper.is_inclusive))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all, any, none, not, predicate, rule};
+
+    macro_rules! assert_conflict {
+        ($a:expr, $b:expr, $expected:expr) => {
+            let a = rule!("a", "message", $a);
+            let b = rule!("b", "message", $b);
+
+            assert_eq!(a.conflicts_with(&b), $expected, "{a:?} vs {b:?}");
+            assert_eq!(b.conflicts_with(&a), $expected, "{b:?} vs {a:?}");
+        };
+    }
+
+    #[test]
+    fn test_identical() {
+        assert_conflict!(predicate!("x" == 5), predicate!("x" == 5), true);
+        assert_conflict!(
+            any!(predicate!("x" == 5), predicate!("y" > 2)),
+            any!(predicate!("x" == 5), predicate!("y" > 2)),
+            true
+        );
+    }
+
+    #[test]
+    fn test_equality() {
+        assert_conflict!(predicate!("x" == 5), predicate!("x" == 6), true);
+        assert_conflict!(predicate!("x" == 5), predicate!("x" != 5), true);
+        assert_conflict!(predicate!("x" == 5), predicate!("x" != 6), false);
+        assert_conflict!(predicate!("x" == 5), predicate!("y" == 6), false);
+        assert_conflict!(predicate!("x" == "a"), predicate!("x" == "b"), true);
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_conflict!(predicate!("x" > 5), predicate!("x" < 3), true);
+        assert_conflict!(predicate!("x" > 5), predicate!("x" < 5), true);
+        assert_conflict!(predicate!("x" >= 5), predicate!("x" <= 5), false);
+        assert_conflict!(predicate!("x" >= 5), predicate!("x" < 5), true);
+        assert_conflict!(predicate!("x" > 5), predicate!("x" > 10), false);
+        assert_conflict!(predicate!("x" > 5), predicate!("x" < 10), false);
+
+        assert_conflict!(predicate!("x" == 5), predicate!("x" > 5), true);
+        assert_conflict!(predicate!("x" == 5), predicate!("x" >= 5), false);
+        assert_conflict!(predicate!("x" == "5"), predicate!("x" >= 5), true);
+    }
+
+    #[test]
+    fn test_divisible() {
+        assert_conflict!(
+            predicate!("x" divisible 2),
+            predicate!("x" not_divisible 2),
+            true
+        );
+        assert_conflict!(
+            predicate!("x" divisible 2),
+            not!(predicate!("x" divisible 2)),
+            true
+        );
+        assert_conflict!(
+            predicate!("x" divisible 2),
+            predicate!("x" not_divisible 3),
+            false
+        );
+    }
+
+    #[test]
+    fn test_null() {
+        assert_conflict!(predicate!("x" is_null), predicate!("x" is_not_null), true);
+        assert_conflict!(predicate!("x" is_null), predicate!("x" == 5), true);
+        assert_conflict!(predicate!("x" is_null), predicate!("x" > 5), true);
+        assert_conflict!(predicate!("x" is_null), predicate!("x" == ()), false);
+        assert_conflict!(predicate!("x" is_not_null), predicate!("x" == ()), true);
+    }
+
+    #[test]
+    fn test_contains() {
+        assert_conflict!(
+            predicate!("x" == vec![1, 2]),
+            predicate!("x" contains 3),
+            true
+        );
+        assert_conflict!(
+            predicate!("x" == vec![1, 2]),
+            predicate!("x" contains 2),
+            false
+        );
+        assert_conflict!(
+            predicate!("x" contains 2),
+            predicate!("x" contains 3),
+            false
+        );
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_conflict!(
+            all!(predicate!("age" >= 18), predicate!("country" == "UK")),
+            all!(predicate!("age" < 18), predicate!("vip" == true)),
+            true
+        );
+        assert_conflict!(
+            predicate!("status" == "active"),
+            not!(predicate!("status" == "active")),
+            true
+        );
+        assert_conflict!(
+            predicate!("age" > 30),
+            none!(predicate!("age" > 20), predicate!("vip" == true)),
+            true
+        );
+
+        // Either branch of an `any` could pass, so it's not treated as a conflict.
+        assert_conflict!(
+            predicate!("x" == 5),
+            any!(predicate!("x" == 6), predicate!("y" == 1)),
+            false
+        );
+    }
+
+    mod analyzer {
+        use super::*;
+
+        macro_rules! assert_analysis {
+            ($a:expr, $b:expr, $expected:expr) => {
+                let rules = [rule!("a", "message", $a), rule!("b", "message", $b)];
+
+                let expected: Option<ConflictType> = $expected;
+                let expected: Vec<_> = expected
+                    .into_iter()
+                    .map(|conflict_type| RuleConflict {
+                        rule_a: "a".to_owned(),
+                        rule_b: "b".to_owned(),
+                        conflict_type,
+                    })
+                    .collect();
+
+                assert_eq!(RuleConflictAnalyzer::analyze(&rules), expected, "{rules:?}");
+            };
+        }
+
+        #[test]
+        fn test_contradictory() {
+            assert_analysis!(
+                predicate!("x" == 5),
+                predicate!("x" == 6),
+                Some(ConflictType::Contradictory)
+            );
+            assert_analysis!(
+                predicate!("x" == 5),
+                predicate!("x" != 5),
+                Some(ConflictType::Contradictory)
+            );
+            assert_analysis!(predicate!("x" == 5), predicate!("x" != 6), None);
+            assert_analysis!(predicate!("x" == 5), predicate!("y" == 6), None);
+        }
+
+        #[test]
+        fn test_redundant() {
+            assert_analysis!(
+                predicate!("x" == 5),
+                predicate!("x" == 5),
+                Some(ConflictType::Redundant)
+            );
+            assert_analysis!(
+                all!(predicate!("x" == 5), predicate!("y" is_null)),
+                all!(predicate!("x" == 5), predicate!("y" is_null)),
+                Some(ConflictType::Redundant)
+            );
+        }
+
+        #[test]
+        fn test_overlapping() {
+            assert_analysis!(
+                predicate!("x" == 5),
+                any!(predicate!("x" == 5), predicate!("y" == 1)),
+                Some(ConflictType::Overlapping)
+            );
+            assert_analysis!(
+                any!(predicate!("x" == 5), predicate!("y" == 1)),
+                predicate!("x" == 5),
+                Some(ConflictType::Overlapping)
+            );
+            assert_analysis!(
+                all!(predicate!("x" == 5), predicate!("y" == 1)),
+                predicate!("y" == 1),
+                Some(ConflictType::Overlapping)
+            );
+            assert_analysis!(
+                predicate!("x" == 5),
+                any!(predicate!("x" == 6), predicate!("y" == 1)),
+                None
+            );
+        }
+
+        #[test]
+        fn test_analyze_pairs() {
+            let rules = [
+                rule!("a", "message", predicate!("x" == 5)),
+                rule!("b", "message", predicate!("x" == 6)),
+                rule!("c", "message", predicate!("y" is_null)),
+                rule!("d", "message", predicate!("x" == 5)),
+            ];
+
+            assert_eq!(
+                RuleConflictAnalyzer::analyze(&rules),
+                [
+                    RuleConflict {
+                        rule_a: "a".to_owned(),
+                        rule_b: "b".to_owned(),
+                        conflict_type: ConflictType::Contradictory,
+                    },
+                    RuleConflict {
+                        rule_a: "a".to_owned(),
+                        rule_b: "d".to_owned(),
+                        conflict_type: ConflictType::Redundant,
+                    },
+                    RuleConflict {
+                        rule_a: "b".to_owned(),
+                        rule_b: "d".to_owned(),
+                        conflict_type: ConflictType::Contradictory,
+                    },
+                ]
+            );
+            assert!(RuleConflictAnalyzer::analyze(&[]).is_empty());
+        }
+    }
+}
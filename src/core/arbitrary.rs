@@ -0,0 +1,39 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+use serde_json::{Map, Number, Value};
+
+const MAX_DEPTH: usize = 4;
+const MAX_LEN: usize = 4;
+
+/// Generates an arbitrary JSON value, for use with `#[arbitrary(with = ...)]` and fuzz targets.
+pub fn json_value(u: &mut Unstructured) -> Result<Value> {
+    json_value_with_depth(u, 0)
+}
+
+/// Generates a list of arbitrary JSON values, see `json_value`.
+pub fn json_values(u: &mut Unstructured) -> Result<Vec<Value>> {
+    (0..u.int_in_range(0..=MAX_LEN)?)
+        .map(|_| json_value(u))
+        .collect()
+}
+
+fn json_value_with_depth(u: &mut Unstructured, depth: usize) -> Result<Value> {
+    let kinds = if depth >= MAX_DEPTH { 5 } else { 7 };
+
+    Ok(match u.choose_index(kinds)? {
+        0 => Value::Null,
+        1 => Value::Bool(bool::arbitrary(u)?),
+        2 => Value::Number(Number::from(i64::arbitrary(u)?)),
+        3 => Number::from_f64(f64::arbitrary(u)?).map_or(Value::Null, Value::Number),
+        4 => Value::String(String::arbitrary(u)?),
+        5 => Value::Array(
+            (0..u.int_in_range(0..=MAX_LEN)?)
+                .map(|_| json_value_with_depth(u, depth + 1))
+                .collect::<Result<_>>()?,
+        ),
+        _ => Value::Object(
+            (0..u.int_in_range(0..=MAX_LEN)?)
+                .map(|_| Ok((String::arbitrary(u)?, json_value_with_depth(u, depth + 1)?)))
+                .collect::<Result<Map<_, _>>>()?,
+        ),
+    })
+}
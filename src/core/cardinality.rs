@@ -0,0 +1,197 @@
+use crate::core::rule::{CompoundPredicate, Operator, Predicate, RawPredicate};
+
+/// A rough, static estimate of how many inputs a predicate matches, for use by query planners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CardinalityHint {
+    Low,
+    Medium,
+    High,
+    /// The predicate can't be estimated statically, e.g. as it depends on the length of an array.
+    Unknown,
+}
+
+impl CardinalityHint {
+    fn invert(self) -> Self {
+        match self {
+            CardinalityHint::Low => CardinalityHint::High,
+            CardinalityHint::High => CardinalityHint::Low,
+            other => other,
+        }
+    }
+}
+
+impl Predicate {
+    pub fn estimate_cardinality(&self) -> CardinalityHint {
+        match self {
+            Predicate::Raw(predicate) => predicate.estimate_cardinality(),
+            Predicate::Compound(predicate) => predicate.estimate_cardinality(),
+        }
+    }
+}
+
+impl RawPredicate {
+    /// Equality narrows the input down to few values, unless the value is one of the few values a
+    /// field like a flag can have (`null`, `true` or `false`), in which case it's likely common.
+    pub fn estimate_cardinality(&self) -> CardinalityHint {
+        let is_common_value = self.value.is_null() || self.value.is_boolean();
+
+        match self.operator {
+            Operator::Equal if is_common_value => CardinalityHint::Medium,
+            Operator::Equal => CardinalityHint::Low,
+            Operator::NotEqual if is_common_value => CardinalityHint::Medium,
+            Operator::NotEqual => CardinalityHint::High,
+            Operator::Greater | Operator::Less | Operator::GreaterEqual | Operator::LessEqual => {
+                CardinalityHint::Medium
+            }
+            Operator::Contains if is_common_value => CardinalityHint::High,
+            Operator::Contains => CardinalityHint::Medium,
+            Operator::StartsWith | Operator::EndsWith => CardinalityHint::Medium,
+            Operator::Divisible | Operator::NotDivisible => CardinalityHint::Medium,
+            Operator::IsNull | Operator::IsNotNull => CardinalityHint::Medium,
+        }
+    }
+}
+
+/// An `Any` matches at least as much as its largest child, so a child which can't be estimated
+/// makes the whole predicate unknown. An empty `Any` matches nothing.
+fn any_cardinality(predicates: &[Predicate]) -> CardinalityHint {
+    predicates
+        .iter()
+        .map(Predicate::estimate_cardinality)
+        .max()
+        .unwrap_or(CardinalityHint::Low)
+}
+
+impl CompoundPredicate {
+    pub fn estimate_cardinality(&self) -> CardinalityHint {
+        match self {
+            CompoundPredicate::Not(predicate) => predicate.estimate_cardinality().invert(),
+            CompoundPredicate::Any(predicates) => any_cardinality(predicates),
+            // An `All` matches at most as much as its smallest child, so children which can't be
+            // estimated are ignored unless none of them can be. An empty `All` matches everything.
+            CompoundPredicate::All(predicates) => predicates
+                .iter()
+                .map(Predicate::estimate_cardinality)
+                .min()
+                .unwrap_or(CardinalityHint::High),
+            CompoundPredicate::None(predicates) => any_cardinality(predicates).invert(),
+            CompoundPredicate::OneOf { .. } => self.expand_one_of().estimate_cardinality(),
+            CompoundPredicate::If { .. } => self.expand_if().estimate_cardinality(),
+            // More selective the more elements there are for a `ForEach` and less so for an
+            // `ExistsIn`, which depends on the input.
+            CompoundPredicate::ForEach { .. } | CompoundPredicate::ExistsIn { .. } => {
+                CardinalityHint::Unknown
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all, any, none, not, predicate};
+
+    fn for_each(predicate: impl Into<Predicate>) -> CompoundPredicate {
+        CompoundPredicate::ForEach {
+            array_path: "items".to_owned(),
+            predicate: Box::new(predicate.into()),
+        }
+    }
+
+    macro_rules! assert_cardinality {
+        ($predicate:expr, $expected:ident) => {
+            assert_eq!(
+                Predicate::from($predicate).estimate_cardinality(),
+                CardinalityHint::$expected
+            )
+        };
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_cardinality!(predicate!("id" == "abc-123"), Low);
+        assert_cardinality!(predicate!("enabled" == true), Medium);
+        assert_cardinality!(predicate!("id" != "abc-123"), High);
+        assert_cardinality!(predicate!("deleted" != ()), Medium);
+        assert_cardinality!(predicate!("age" > 18), Medium);
+        assert_cardinality!(predicate!("age" < 18), Medium);
+        assert_cardinality!(predicate!("age" >= 18), Medium);
+        assert_cardinality!(predicate!("age" <= 18), Medium);
+        assert_cardinality!(predicate!("flags" contains true), High);
+        assert_cardinality!(predicate!("tags" contains "fraud"), Medium);
+    }
+
+    #[test]
+    fn test_not() {
+        assert_cardinality!(not!(predicate!("id" == "abc-123")), High);
+        assert_cardinality!(not!(predicate!("id" != "abc-123")), Low);
+        assert_cardinality!(not!(predicate!("age" > 18)), Medium);
+    }
+
+    #[test]
+    fn test_all() {
+        assert_cardinality!(all!(), High);
+        assert_cardinality!(
+            all!(predicate!("age" > 18), predicate!("id" == "abc-123")),
+            Low
+        );
+        assert_cardinality!(
+            all!(predicate!("age" > 18), predicate!("id" != "abc-123")),
+            Medium
+        );
+        assert_cardinality!(all!(predicate!("age" > 18), not!(any!())), Medium);
+    }
+
+    #[test]
+    fn test_any() {
+        assert_cardinality!(any!(), Low);
+        assert_cardinality!(
+            any!(predicate!("age" > 18), predicate!("id" == "abc-123")),
+            Medium
+        );
+        assert_cardinality!(
+            any!(predicate!("age" > 18), predicate!("id" != "abc-123")),
+            High
+        );
+    }
+
+    #[test]
+    fn test_none() {
+        assert_cardinality!(none!(), High);
+        assert_cardinality!(
+            none!(predicate!("id" == "abc-123"), predicate!("id" == "def-456")),
+            High
+        );
+        assert_cardinality!(
+            none!(predicate!("age" > 18), predicate!("id" != "abc-123")),
+            Low
+        );
+    }
+
+    #[test]
+    fn test_unknown() {
+        assert_cardinality!(for_each(predicate!("price" > 0)), Unknown);
+        assert_cardinality!(
+            CompoundPredicate::ExistsIn {
+                array_path: "items".to_owned(),
+                predicate: Box::new(predicate!("price" > 0).into()),
+            },
+            Unknown
+        );
+        assert_cardinality!(not!(for_each(predicate!("price" > 0))), Unknown);
+
+        // An unknown child makes an `Any` unknown, but is ignored by an `All`.
+        assert_cardinality!(
+            any!(
+                predicate!("id" == "abc-123"),
+                for_each(predicate!("price" > 0))
+            ),
+            Unknown
+        );
+        assert_cardinality!(
+            all!(predicate!("age" > 18), for_each(predicate!("price" > 0))),
+            Medium
+        );
+        assert_cardinality!(all!(for_each(predicate!("price" > 0))), Unknown);
+    }
+}
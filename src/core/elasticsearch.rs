@@ -0,0 +1,340 @@
+use serde_json::json;
+use thiserror::Error;
+
+use crate::core::{
+    eval::json_type,
+    rule::{CompoundPredicate, Operator, Predicate, RawPredicate, Rule},
+};
+
+type JsonValue = serde_json::Value;
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum EsConversionError {
+    #[error("operator {0:?} has no elasticsearch equivalent")]
+    UnsupportedOperator(Operator),
+    #[error("cannot use value of type {kind} with operator {operator:?} in an elasticsearch query")]
+    UnsupportedValue {
+        kind: &'static str,
+        operator: Operator,
+    },
+    #[error("{0} predicates have no elasticsearch equivalent")]
+    UnsupportedPredicate(&'static str),
+}
+
+impl Rule {
+    pub fn to_elasticsearch_query(&self) -> Result<JsonValue, EsConversionError> {
+        self.predicate.to_elasticsearch_query()
+    }
+}
+
+impl Predicate {
+    /// Converts the predicate into an elasticsearch query using each `path` as the field name.
+    pub fn to_elasticsearch_query(&self) -> Result<JsonValue, EsConversionError> {
+        match self {
+            Predicate::Raw(predicate) => predicate.to_elasticsearch_query(),
+            Predicate::Compound(predicate) => predicate.to_elasticsearch_query(),
+        }
+    }
+}
+
+fn to_elasticsearch_queries(predicates: &[Predicate]) -> Result<Vec<JsonValue>, EsConversionError> {
+    predicates
+        .iter()
+        .map(Predicate::to_elasticsearch_query)
+        .collect()
+}
+
+/// Escapes the characters with a special meaning in a wildcard query.
+fn escape_wildcard(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('*', "\\*")
+        .replace('?', "\\?")
+}
+
+impl RawPredicate {
+    pub fn to_elasticsearch_query(&self) -> Result<JsonValue, EsConversionError> {
+        // Elasticsearch addresses nested fields with dots whatever the predicate's path syntax.
+        let path = self.path_segments().join(".");
+
+        let unsupported_value = || EsConversionError::UnsupportedValue {
+            kind: json_type(&self.value),
+            operator: self.operator,
+        };
+
+        let term = || match &self.value {
+            JsonValue::Null => Ok(json!({"bool": {"must_not": [{"exists": {"field": path}}]}})),
+            JsonValue::Array(_) | JsonValue::Object(_) => Err(unsupported_value()),
+            value => Ok(json!({"term": {&path: value}})),
+        };
+
+        match self.operator {
+            Operator::Equal => term(),
+            Operator::NotEqual => Ok(json!({"bool": {"must_not": [term()?]}})),
+            Operator::Greater | Operator::Less | Operator::GreaterEqual | Operator::LessEqual => {
+                if !self.value.is_number() {
+                    return Err(unsupported_value());
+                }
+
+                let range = match self.operator {
+                    Operator::Greater => "gt",
+                    Operator::Less => "lt",
+                    Operator::GreaterEqual => "gte",
+                    Operator::LessEqual => "lte",
+                    other => unreachable!("got unexpected non-mathematical operator {other:?}"),
+                };
+
+                Ok(json!({"range": {&path: {range: self.value}}}))
+            }
+            // elasticsearch doesn't distinguish between a field and an array of values so a term
+            // query matches documents where any element of the array is equal to the value. A
+            // string value may also be a substring, which this can't tell apart from an element
+            // containing it, so that matches too.
+            Operator::Contains => match &self.value {
+                JsonValue::String(substring) => Ok(json!({"bool": {
+                    "should": [term()?, {"wildcard": {&path: {"value": format!("*{}*", escape_wildcard(substring))}}}],
+                    "minimum_should_match": 1
+                }})),
+                _ => term(),
+            },
+            Operator::StartsWith => {
+                let prefix = self.value.as_str().ok_or_else(unsupported_value)?;
+
+                Ok(json!({"prefix": {&path: prefix}}))
+            }
+            Operator::EndsWith => {
+                let suffix = self.value.as_str().ok_or_else(unsupported_value)?;
+
+                Ok(json!({"wildcard": {&path: {"value": format!("*{}", escape_wildcard(suffix))}}}))
+            }
+            Operator::Divisible | Operator::NotDivisible => {
+                Err(EsConversionError::UnsupportedOperator(self.operator))
+            }
+            Operator::IsNull => Ok(json!({"bool": {"must_not": [{"exists": {"field": path}}]}})),
+            Operator::IsNotNull => Ok(json!({"exists": {"field": path}})),
+        }
+    }
+}
+
+impl CompoundPredicate {
+    pub fn to_elasticsearch_query(&self) -> Result<JsonValue, EsConversionError> {
+        match self {
+            CompoundPredicate::Not(predicate) => Ok(json!({
+                "bool": {"must_not": [predicate.to_elasticsearch_query()?]}
+            })),
+            CompoundPredicate::Any(predicates) => Ok(json!({
+                "bool": {
+                    "should": to_elasticsearch_queries(predicates)?,
+                    "minimum_should_match": 1
+                }
+            })),
+            CompoundPredicate::All(predicates) => Ok(json!({
+                "bool": {"must": to_elasticsearch_queries(predicates)?}
+            })),
+            CompoundPredicate::None(predicates) => Ok(json!({
+                "bool": {"must_not": to_elasticsearch_queries(predicates)?}
+            })),
+            CompoundPredicate::OneOf { .. } => self.expand_one_of().to_elasticsearch_query(),
+            CompoundPredicate::If { .. } => self.expand_if().to_elasticsearch_query(),
+            // A `nested` query needs the array to be mapped as nested, which can't be assumed.
+            CompoundPredicate::ForEach { .. } => {
+                Err(EsConversionError::UnsupportedPredicate("forEach"))
+            }
+            CompoundPredicate::ExistsIn { .. } => {
+                Err(EsConversionError::UnsupportedPredicate("existsIn"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rule::PathSyntax;
+    use crate::{all, any, exists_in, for_each, none, not, predicate, rule};
+
+    macro_rules! assert_es_query {
+        ($predicate:expr, $($expected:tt)*) => {
+            assert_eq!(
+                Predicate::from($predicate).to_elasticsearch_query(),
+                Ok(json!($($expected)*))
+            )
+        };
+    }
+
+    #[test]
+    fn test_equal() {
+        assert_es_query!(predicate!("foo" == 10), {"term": {"foo": 10}});
+        assert_es_query!(predicate!("foo.bar" == "baz"), {"term": {"foo.bar": "baz"}});
+        assert_es_query!(
+            predicate!("foo" == ()),
+            {"bool": {"must_not": [{"exists": {"field": "foo"}}]}}
+        );
+
+        assert_es_query!(
+            predicate!("foo" != true),
+            {"bool": {"must_not": [{"term": {"foo": true}}]}}
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        assert_es_query!(predicate!("age" > 12), {"range": {"age": {"gt": 12}}});
+        assert_es_query!(predicate!("age" < 12), {"range": {"age": {"lt": 12}}});
+        assert_es_query!(predicate!("age" >= 12.5), {"range": {"age": {"gte": 12.5}}});
+        assert_es_query!(predicate!("age" <= 12), {"range": {"age": {"lte": 12}}});
+    }
+
+    #[test]
+    fn test_contains() {
+        assert_es_query!(
+            predicate!("tags" contains "red*"),
+            {"bool": {
+                "should": [{"term": {"tags": "red*"}}, {"wildcard": {"tags": {"value": "*red\\**"}}}],
+                "minimum_should_match": 1
+            }}
+        );
+        assert_es_query!(predicate!("ids" contains 10), {"term": {"ids": 10}});
+    }
+
+    #[test]
+    fn test_null() {
+        assert_es_query!(
+            predicate!("email" is_null),
+            {"bool": {"must_not": [{"exists": {"field": "email"}}]}}
+        );
+        assert_es_query!(predicate!("email" is_not_null), {"exists": {"field": "email"}});
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        let predicate = RawPredicate {
+            path: "/user/age".to_owned(),
+            path_syntax: PathSyntax::JsonPointer,
+            ..predicate!("unused" > 18)
+        };
+
+        assert_eq!(
+            predicate.to_elasticsearch_query(),
+            Ok(json!({"range": {"user.age": {"gt": 18}}}))
+        );
+    }
+
+    #[test]
+    fn test_affixes() {
+        assert_es_query!(predicate!("name" starts_with "Jo"), {"prefix": {"name": "Jo"}});
+        assert_es_query!(
+            predicate!("email" ends_with "*@example.com"),
+            {"wildcard": {"email": {"value": "*\\*@example.com"}}}
+        );
+    }
+
+    #[test]
+    fn test_unsupported_operator_err() {
+        assert_eq!(
+            predicate!("count" divisible 5).to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedOperator(Operator::Divisible))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_predicate_err() {
+        assert_eq!(
+            for_each!("items", predicate!("price" > 0)).to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedPredicate("forEach"))
+        );
+        assert_eq!(
+            all!(exists_in!("users", predicate!("role" == "admin"))).to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedPredicate("existsIn"))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_value_err() {
+        assert_eq!(
+            predicate!("age" > "12").to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedValue {
+                kind: "string",
+                operator: Operator::Greater
+            })
+        );
+
+        assert_eq!(
+            predicate!("foo" == json!({"bar": 10})).to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedValue {
+                kind: "object",
+                operator: Operator::Equal
+            })
+        );
+
+        assert_eq!(
+            all!(predicate!("foo" == 10), predicate!("bar" contains [1, 2]))
+                .to_elasticsearch_query(),
+            Err(EsConversionError::UnsupportedValue {
+                kind: "array",
+                operator: Operator::Contains
+            })
+        );
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_es_query!(
+            all!(predicate!("fizz" == 3), predicate!("buzz" == 5)),
+            {"bool": {"must": [{"term": {"fizz": 3}}, {"term": {"buzz": 5}}]}}
+        );
+
+        assert_es_query!(
+            any!(predicate!("color" == "red"), predicate!("color" == "blue")),
+            {"bool": {
+                "should": [{"term": {"color": "red"}}, {"term": {"color": "blue"}}],
+                "minimum_should_match": 1
+            }}
+        );
+
+        assert_es_query!(
+            none!(predicate!("color" == "red"), predicate!("color" == "blue")),
+            {"bool": {"must_not": [{"term": {"color": "red"}}, {"term": {"color": "blue"}}]}}
+        );
+
+        assert_es_query!(
+            not!(predicate!("foo" == 10)),
+            {"bool": {"must_not": [{"term": {"foo": 10}}]}}
+        );
+    }
+
+    #[test]
+    fn test_nested_rule() {
+        let rule = rule!(
+            "id",
+            "rule failed",
+            all!(
+                predicate!("age" >= 12),
+                any!(
+                    predicate!("height.feet" > 5),
+                    all!(
+                        predicate!("height.feet" == 5),
+                        predicate!("height.inches" >= 2)
+                    )
+                )
+            )
+        );
+
+        assert_eq!(
+            rule.to_elasticsearch_query(),
+            Ok(json!({"bool": {"must": [
+                {"range": {"age": {"gte": 12}}},
+                {"bool": {
+                    "should": [
+                        {"range": {"height.feet": {"gt": 5}}},
+                        {"bool": {"must": [
+                            {"term": {"height.feet": 5}},
+                            {"range": {"height.inches": {"gte": 2}}}
+                        ]}}
+                    ],
+                    "minimum_should_match": 1
+                }}
+            ]}}))
+        );
+    }
+}
@@ -1,19 +1,76 @@
+use std::sync::Arc;
+
 use thiserror::Error;
+use tokio::task::JoinSet;
 
-use crate::core::rule::{CompoundPredicate, Operator, Predicate, RawPredicate, Rule};
+use crate::core::{
+    rule::{CompoundPredicate, Operator, PathSyntax, Predicate, Quantifier, RawPredicate, Rule},
+    transform::merge_defaults,
+};
 
 type JsonValue = serde_json::Value;
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum EvaluationError {
     #[error("cannot read field `{field}` of type {kind}")]
     NotAnObject { field: String, kind: &'static str },
-    #[error("cannot compare {lhs} with {rhs} using operator {operator:?}")]
+    #[error(
+        "cannot compare {lhs} with {rhs} using operator {operator:?}{}",
+        expected_types(*operator)
+    )]
     TypeMismatch {
         lhs: &'static str,
         rhs: &'static str,
         operator: Operator,
     },
+    #[error("field `{path}` does not exist")]
+    MissingField { path: String },
+    #[error("cannot check divisibility by zero")]
+    DivisionByZero,
+    #[error("cannot check each element of `{path}` of type {kind}")]
+    NotAnArray { path: String, kind: &'static str },
+    /// An error of a child of a compound predicate, with where the child is in the predicate, e.g.
+    /// `All[1].Any[0]` for the first child of the second child of an `all` predicate.
+    #[error("{context}: {source}")]
+    WithContext {
+        context: String,
+        source: Box<EvaluationError>,
+    },
+}
+
+/// Describes the types an operator accepts, for operators where that isn't evident from the
+/// operator alone, to follow a `TypeMismatch` message.
+fn expected_types(operator: Operator) -> &'static str {
+    match operator {
+        Operator::Contains => {
+            ", which checks for an element of an array or a substring of a string value"
+        }
+        _ => "",
+    }
+}
+
+/// Compound predicates with fewer children than this are evaluated sequentially by
+/// `CompoundPredicate::evaluate_concurrent`, as spawning tasks costs more than it saves.
+pub const DEFAULT_CONCURRENCY_THRESHOLD: usize = 8;
+
+/// Extension point for predicates which have to wait on something to be evaluated, e.g. a lookup
+/// in a database. `RawPredicate` implements it by evaluating its operator synchronously.
+pub trait AsyncRawPredicate {
+    fn evaluate_async(
+        &self,
+        input: &JsonValue,
+    ) -> impl Future<Output = Result<bool, EvaluationError>> + Send;
+}
+
+/// How a path which doesn't exist in the input is read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FieldPresence {
+    /// Missing fields read as `null`, so `isNull` can't tell them apart from an explicit `null`.
+    #[default]
+    Lenient,
+    /// Reading a missing field is an `EvaluationError::MissingField`.
+    Strict,
 }
 
 impl EvaluationError {
@@ -31,24 +88,222 @@ impl EvaluationError {
             operator,
         }
     }
+
+    /// Wraps `err` with `context`. The context of an error which already has one is appended to
+    /// `context`, so nested compound predicates give a single path to the failing predicate.
+    pub fn with_context(context: String, err: EvaluationError) -> Self {
+        match err {
+            EvaluationError::WithContext {
+                context: inner,
+                source,
+            } => Self::WithContext {
+                context: format!("{context}.{inner}"),
+                source,
+            },
+            err => Self::WithContext {
+                context,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// Whether the error is caused by the input, e.g. a field being `null` or missing, rather than
+    /// by the rule itself, so evaluating again with more complete input may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.root_cause(),
+            EvaluationError::NotAnObject { .. }
+                | EvaluationError::TypeMismatch { .. }
+                | EvaluationError::MissingField { .. }
+                | EvaluationError::NotAnArray { .. }
+        )
+    }
+
+    /// The error without any context, i.e. the error of the predicate which failed.
+    pub fn root_cause(&self) -> &EvaluationError {
+        match self {
+            EvaluationError::WithContext { source, .. } => source.root_cause(),
+            err => err,
+        }
+    }
+}
+
+/// Evaluates each of `rules` against `input` without a repository, returning the results in the
+/// same order. Like `Rule::evaluate` only the predicates are evaluated, so conditions, active
+/// periods and dependencies are ignored.
+pub fn evaluate_many(rules: &[Rule], input: &JsonValue) -> Vec<Result<bool, EvaluationError>> {
+    rules.iter().map(|rule| rule.evaluate(input)).collect()
 }
 
 impl Rule {
+    /// Evaluates the predicate with the rule's `path_aliases` applied, as do the other methods.
     pub fn evaluate(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
-        self.predicate.evaluate(input)
+        self.resolve_aliases().predicate.evaluate(input)
+    }
+
+    /// Like `evaluate`, but also returns the value read from `input` if the rule's predicate is a
+    /// raw predicate.
+    pub fn evaluate_with_value<'a>(
+        &self,
+        input: &'a JsonValue,
+        presence: FieldPresence,
+    ) -> Result<(bool, Option<&'a JsonValue>), EvaluationError> {
+        self.resolve_aliases()
+            .predicate
+            .evaluate_with_value(input, presence)
+    }
+
+    /// Like `evaluate_with_value`, but when the rule has `retry_on_error` set and evaluating fails
+    /// because of the input, see `EvaluationError::is_retryable`, the rule is evaluated again up
+    /// to `max_retries` times, with `input_defaults` merged into `input` if given.
+    pub fn evaluate_with_retries(
+        &self,
+        input: &JsonValue,
+        presence: FieldPresence,
+    ) -> Result<(bool, Option<JsonValue>), EvaluationError> {
+        let evaluate = |input: &JsonValue| {
+            self.evaluate_with_value(input, presence)
+                .map(|(result, value)| (result, value.cloned()))
+        };
+
+        let mut result = evaluate(input);
+
+        if !self.retry_on_error {
+            return result;
+        }
+
+        let retry_input = match &self.input_defaults {
+            Some(defaults) => merge_defaults(input.clone(), defaults),
+            None => input.clone(),
+        };
+
+        for attempt in 1..=self.max_retries {
+            match &result {
+                Err(err) if err.is_retryable() => {
+                    tracing::debug!(rule = %self.id, attempt, %err, "retrying rule evaluation");
+                }
+                _ => break,
+            }
+
+            result = evaluate(&retry_input);
+        }
+
+        result
+    }
+
+    /// How many raw predicates of the rule's predicate are true for `input`, see
+    /// `Predicate::match_count`. The rule's condition isn't counted.
+    pub fn match_count(&self, input: &JsonValue) -> Result<usize, EvaluationError> {
+        self.resolve_aliases().predicate.match_count(input)
+    }
+
+    /// Async version of `evaluate`, see `AsyncRawPredicate`.
+    pub async fn evaluate_async(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        self.resolve_aliases().predicate.evaluate_async(input).await
     }
 }
 
 impl Predicate {
     pub fn evaluate(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        self.evaluate_in(input, FieldPresence::default())
+    }
+
+    pub fn evaluate_in(
+        &self,
+        input: &JsonValue,
+        presence: FieldPresence,
+    ) -> Result<bool, EvaluationError> {
+        match self {
+            Predicate::Raw(predicate) => predicate.evaluate_in(input, presence),
+            Predicate::Compound(predicate) => predicate.evaluate_in(input, presence),
+        }
+    }
+
+    /// How many of the raw predicates in the tree are true for `input`, regardless of the compound
+    /// predicates they're nested in, e.g. for ranking inputs by how closely they match. Unlike
+    /// `evaluate` nothing is short circuited, so any raw predicate failing to evaluate is an error.
+    pub fn match_count(&self, input: &JsonValue) -> Result<usize, EvaluationError> {
+        self.match_count_in(input, FieldPresence::default())
+    }
+
+    pub fn match_count_in(
+        &self,
+        input: &JsonValue,
+        presence: FieldPresence,
+    ) -> Result<usize, EvaluationError> {
+        match self {
+            // The raw predicates of a `ForEach` count for the element matching the fewest of them.
+            Predicate::Compound(CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            }) => {
+                let mut fewest = predicate.raw_predicates().len();
+
+                for element in read_elements(array_path, input, presence)? {
+                    fewest = fewest.min(predicate.match_count_in(element, presence)?);
+                }
+
+                Ok(fewest)
+            }
+            // The raw predicates of an `ExistsIn` count for the element matching the most of them.
+            Predicate::Compound(CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            }) => {
+                let mut most = 0;
+
+                for element in read_elements(array_path, input, presence)? {
+                    most = most.max(predicate.match_count_in(element, presence)?);
+                }
+
+                Ok(most)
+            }
+            Predicate::Raw(_) | Predicate::Compound(CompoundPredicate::OneOf { .. }) => {
+                let mut count = 0;
+
+                for predicate in self.raw_predicates() {
+                    if predicate.evaluate_in(input, presence)? {
+                        count += 1;
+                    }
+                }
+
+                Ok(count)
+            }
+            _ => self
+                .children()
+                .into_iter()
+                .map(|child| child.match_count_in(input, presence))
+                .sum(),
+        }
+    }
+
+    /// Like `evaluate`, but also returns the value read from `input` for raw predicates. Compound
+    /// predicates read many values so return `None`.
+    pub fn evaluate_with_value<'a>(
+        &self,
+        input: &'a JsonValue,
+        presence: FieldPresence,
+    ) -> Result<(bool, Option<&'a JsonValue>), EvaluationError> {
         match self {
-            Predicate::Raw(predicate) => predicate.evaluate(input),
-            Predicate::Compound(predicate) => predicate.evaluate(input),
+            Predicate::Raw(predicate) => predicate
+                .evaluate_with_value(input, presence)
+                .map(|(result, value)| (result, Some(value))),
+            Predicate::Compound(predicate) => predicate
+                .evaluate_in(input, presence)
+                .map(|result| (result, None)),
+        }
+    }
+
+    /// Async version of `evaluate`, see `AsyncRawPredicate`.
+    pub async fn evaluate_async(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        match self {
+            Predicate::Raw(predicate) => AsyncRawPredicate::evaluate_async(predicate, input).await,
+            Predicate::Compound(predicate) => predicate.evaluate_async(input).await,
         }
     }
 }
 
-fn json_type(value: &JsonValue) -> &'static str {
+pub(crate) fn json_type(value: &JsonValue) -> &'static str {
     match value {
         serde_json::Value::Null => "null",
         serde_json::Value::Bool(_) => "boolean",
@@ -59,7 +314,24 @@ fn json_type(value: &JsonValue) -> &'static str {
     }
 }
 
-fn follow_path<'a>(path: &str, input: &'a JsonValue) -> Result<&'a JsonValue, EvaluationError> {
+/// Reads the value at a dot separated `path` such as `foo.bar`, with missing fields reading as
+/// `null`. Useful for implementing `AsyncRawPredicate`.
+pub fn follow_path<'a>(path: &str, input: &'a JsonValue) -> Result<&'a JsonValue, EvaluationError> {
+    follow_path_with(path, input, FieldPresence::Lenient)
+}
+
+pub(crate) fn follow_path_with<'a>(
+    path: &str,
+    input: &'a JsonValue,
+    presence: FieldPresence,
+) -> Result<&'a JsonValue, EvaluationError> {
+    static NULL: JsonValue = JsonValue::Null;
+
+    // The root, e.g. each element of a `ForEach` or `ExistsIn`.
+    if path.is_empty() {
+        return Ok(input);
+    }
+
     let mut head = input;
 
     for field in path.split(".") {
@@ -67,19 +339,212 @@ fn follow_path<'a>(path: &str, input: &'a JsonValue) -> Result<&'a JsonValue, Ev
             return Err(EvaluationError::not_an_object(field.to_owned(), head));
         }
 
-        head = &head[field];
+        head = match (head.get(field), presence) {
+            (Some(value), _) => value,
+            (None, FieldPresence::Lenient) => &NULL,
+            (None, FieldPresence::Strict) => {
+                return Err(EvaluationError::MissingField {
+                    path: path.to_owned(),
+                });
+            }
+        };
     }
 
     Ok(head)
 }
 
+/// Reads the values at a dot separated `path` which may contain `*` wildcards, each of which
+/// reads every element of an array or value of an object, e.g. `items.*.price`, and nothing from
+/// `null`. A path without a wildcard reads a single value, see `follow_path`.
+pub fn follow_path_multi<'a>(
+    path: &str,
+    input: &'a JsonValue,
+) -> Result<Vec<&'a JsonValue>, EvaluationError> {
+    follow_path_multi_with(path, input, FieldPresence::Lenient)
+}
+
+pub(crate) fn follow_path_multi_with<'a>(
+    path: &str,
+    input: &'a JsonValue,
+    presence: FieldPresence,
+) -> Result<Vec<&'a JsonValue>, EvaluationError> {
+    if !path.split(".").any(|field| field == WILDCARD) {
+        return follow_path_with(path, input, presence).map(|value| vec![value]);
+    }
+
+    static NULL: JsonValue = JsonValue::Null;
+
+    let mut heads = vec![input];
+
+    for field in path.split(".") {
+        let mut next = Vec::with_capacity(heads.len());
+
+        for head in heads {
+            match (head, field) {
+                (JsonValue::Array(values), WILDCARD) => next.extend(values),
+                (JsonValue::Object(values), WILDCARD) => next.extend(values.values()),
+                // e.g. a missing array, which has no values rather than being a type error.
+                (JsonValue::Null, WILDCARD) => {}
+                (JsonValue::Object(values), _) => match (values.get(field), presence) {
+                    (Some(value), _) => next.push(value),
+                    (None, FieldPresence::Lenient) => next.push(&NULL),
+                    (None, FieldPresence::Strict) => {
+                        return Err(EvaluationError::MissingField {
+                            path: path.to_owned(),
+                        });
+                    }
+                },
+                _ => return Err(EvaluationError::not_an_object(field.to_owned(), head)),
+            }
+        }
+
+        heads = next;
+    }
+
+    Ok(heads)
+}
+
+/// The elements of the array at the dot separated `path`, none if it's `null`.
+fn read_elements<'a>(
+    path: &str,
+    input: &'a JsonValue,
+    presence: FieldPresence,
+) -> Result<&'a [JsonValue], EvaluationError> {
+    match follow_path_with(path, input, presence)? {
+        JsonValue::Array(elements) => Ok(elements),
+        JsonValue::Null => Ok(&[]),
+        other => Err(EvaluationError::NotAnArray {
+            path: path.to_owned(),
+            kind: json_type(other),
+        }),
+    }
+}
+
+/// A segment of a dot separated path reading every value, see `follow_path_multi`.
+pub(crate) const WILDCARD: &str = "*";
+
+/// Reads the value at a JSON Pointer (RFC 6901) such as `/foo/bar/0`, see
+/// `serde_json::Value::pointer`, with missing fields reading as `null`. Unlike dot paths, pointers
+/// can index into arrays.
+pub fn follow_path_json_pointer<'a>(
+    pointer: &str,
+    input: &'a JsonValue,
+) -> Result<&'a JsonValue, EvaluationError> {
+    follow_path_json_pointer_with(pointer, input, FieldPresence::Lenient)
+}
+
+/// A pointer which can't be followed, either because a field is missing or because it steps into
+/// a value which isn't an object or array, reads as `null` or is a `MissingField` depending on
+/// `presence`.
+pub(crate) fn follow_path_json_pointer_with<'a>(
+    pointer: &str,
+    input: &'a JsonValue,
+    presence: FieldPresence,
+) -> Result<&'a JsonValue, EvaluationError> {
+    static NULL: JsonValue = JsonValue::Null;
+
+    match (input.pointer(pointer), presence) {
+        (Some(value), _) => Ok(value),
+        (None, FieldPresence::Lenient) => Ok(&NULL),
+        (None, FieldPresence::Strict) => Err(EvaluationError::MissingField {
+            path: pointer.to_owned(),
+        }),
+    }
+}
+
 impl RawPredicate {
+    /// Reads the values at `path` from `input` according to `path_syntax`. Only dot separated
+    /// paths with a wildcard can read more or less than one value.
+    pub(crate) fn read<'a>(
+        &self,
+        input: &'a JsonValue,
+        presence: FieldPresence,
+    ) -> Result<Vec<&'a JsonValue>, EvaluationError> {
+        match self.path_syntax {
+            PathSyntax::Dot => follow_path_multi_with(&self.path, input, presence),
+            PathSyntax::JsonPointer => {
+                follow_path_json_pointer_with(&self.path, input, presence).map(|value| vec![value])
+            }
+        }
+    }
+
+    /// Checks that `data` has a type the operator can be applied to without evaluating it.
+    pub(crate) fn type_check(&self, data: &JsonValue) -> Result<(), EvaluationError> {
+        let is_valid = match self.operator {
+            Operator::Equal | Operator::NotEqual | Operator::IsNull | Operator::IsNotNull => true,
+            Operator::Greater | Operator::Less | Operator::GreaterEqual | Operator::LessEqual => {
+                data.is_number() && self.value.is_number()
+            }
+            Operator::Contains => data.is_array() || (data.is_string() && self.value.is_string()),
+            Operator::StartsWith | Operator::EndsWith => data.is_string() && self.value.is_string(),
+            Operator::Divisible | Operator::NotDivisible => {
+                data.as_i64().is_some() && self.value.as_i64().is_some()
+            }
+        };
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(EvaluationError::type_mismatch(
+                data,
+                &self.value,
+                self.operator,
+            ))
+        }
+    }
+
     pub fn evaluate(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
-        let data = follow_path(&self.path, input)?;
+        self.evaluate_in(input, FieldPresence::default())
+    }
+
+    pub fn evaluate_in(
+        &self,
+        input: &JsonValue,
+        presence: FieldPresence,
+    ) -> Result<bool, EvaluationError> {
+        self.evaluate_with_value(input, presence)
+            .map(|(result, _)| result)
+    }
+
+    /// Like `evaluate`, but also returns the value found at `path` in `input`. When `path` reads
+    /// many values, it's the first one deciding the result according to `quantifier`, or the last
+    /// value if none did.
+    pub fn evaluate_with_value<'a>(
+        &self,
+        input: &'a JsonValue,
+        presence: FieldPresence,
+    ) -> Result<(bool, &'a JsonValue), EvaluationError> {
+        static NULL: JsonValue = JsonValue::Null;
+
+        // The result of a value which decides the overall result without reading any more.
+        let decisive = match self.quantifier {
+            Quantifier::Any => true,
+            Quantifier::All => false,
+        };
+
+        let values = self.read(input, presence)?;
+
+        for &data in &values {
+            if self.apply(data)? == decisive {
+                return Ok((decisive, data));
+            }
+        }
+
+        Ok((!decisive, values.last().copied().unwrap_or(&NULL)))
+    }
+
+    /// Async version of `evaluate`. Every operator is currently synchronous, so this is equivalent
+    /// to `evaluate`.
+    pub async fn evaluate_async(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        self.evaluate(input)
+    }
 
+    fn apply(&self, data: &JsonValue) -> Result<bool, EvaluationError> {
         match self.operator {
             Operator::Equal => Ok(data == &self.value),
             Operator::NotEqual => Ok(data != &self.value),
+            Operator::IsNull => Ok(data.is_null()),
+            Operator::IsNotNull => Ok(!data.is_null()),
             Operator::Greater | Operator::Less | Operator::GreaterEqual | Operator::LessEqual => {
                 let (Some(lhs), Some(rhs)) = (data.as_f64(), self.value.as_f64()) else {
                     return Err(EvaluationError::type_mismatch(
@@ -97,8 +562,31 @@ impl RawPredicate {
                     other => unreachable!("got unexpected non-mathematical operator {other:?}"),
                 })
             }
-            Operator::Contains => {
-                let Some(lhs) = data.as_array() else {
+            Operator::Contains => match (data, &self.value) {
+                (JsonValue::Array(lhs), rhs) => Ok(lhs.contains(rhs)),
+                (JsonValue::String(lhs), JsonValue::String(rhs)) => Ok(lhs.contains(rhs.as_str())),
+                _ => Err(EvaluationError::type_mismatch(
+                    data,
+                    &self.value,
+                    self.operator,
+                )),
+            },
+            Operator::StartsWith | Operator::EndsWith => {
+                let (Some(lhs), Some(rhs)) = (data.as_str(), self.value.as_str()) else {
+                    return Err(EvaluationError::type_mismatch(
+                        data,
+                        &self.value,
+                        self.operator,
+                    ));
+                };
+
+                Ok(match self.operator {
+                    Operator::StartsWith => lhs.starts_with(rhs),
+                    _ => lhs.ends_with(rhs),
+                })
+            }
+            Operator::Divisible | Operator::NotDivisible => {
+                let (Some(lhs), Some(rhs)) = (data.as_i64(), self.value.as_i64()) else {
                     return Err(EvaluationError::type_mismatch(
                         data,
                         &self.value,
@@ -106,19 +594,139 @@ impl RawPredicate {
                     ));
                 };
 
-                Ok(lhs.contains(&self.value))
+                if rhs == 0 {
+                    return Err(EvaluationError::DivisionByZero);
+                }
+
+                // The only overflow is `i64::MIN % -1`, which is divisible.
+                let is_divisible = lhs.checked_rem(rhs).is_none_or(|remainder| remainder == 0);
+
+                Ok(is_divisible == (self.operator == Operator::Divisible))
             }
         }
     }
 }
 
+impl AsyncRawPredicate for RawPredicate {
+    fn evaluate_async(
+        &self,
+        input: &JsonValue,
+    ) -> impl Future<Output = Result<bool, EvaluationError>> + Send {
+        RawPredicate::evaluate_async(self, input)
+    }
+}
+
 impl CompoundPredicate {
     pub fn evaluate(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        self.evaluate_in(input, FieldPresence::default())
+    }
+
+    pub fn evaluate_in(
+        &self,
+        input: &JsonValue,
+        presence: FieldPresence,
+    ) -> Result<bool, EvaluationError> {
+        let evaluate_child = |index: usize, predicate: &Predicate| {
+            predicate
+                .evaluate_in(input, presence)
+                .map_err(|err| self.child_error(index, err))
+        };
+
+        match self {
+            CompoundPredicate::Not(predicate) => evaluate_child(0, predicate).map(|b| !b),
+            CompoundPredicate::Any(predicates) => {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if evaluate_child(index, predicate)? {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
+            }
+            CompoundPredicate::All(predicates) => {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if !evaluate_child(index, predicate)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            CompoundPredicate::None(predicates) => {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if evaluate_child(index, predicate)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            CompoundPredicate::OneOf { path, values } => {
+                let data = follow_path_multi_with(path, input, presence)?;
+
+                Ok(data.into_iter().any(|data| values.contains(data)))
+            }
+            CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            } => match evaluate_child(0, condition)? {
+                true => evaluate_child(1, then),
+                false => evaluate_child(2, otherwise),
+            },
+            CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            } => {
+                for (index, element) in read_elements(array_path, input, presence)?
+                    .iter()
+                    .enumerate()
+                {
+                    if !predicate
+                        .evaluate_in(element, presence)
+                        .map_err(|err| self.child_error(index, err))?
+                    {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            } => {
+                for (index, element) in read_elements(array_path, input, presence)?
+                    .iter()
+                    .enumerate()
+                {
+                    if predicate
+                        .evaluate_in(element, presence)
+                        .map_err(|err| self.child_error(index, err))?
+                    {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
+            }
+        }
+    }
+
+    /// Async version of `evaluate`, evaluating children one at a time.
+    pub async fn evaluate_async(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        // Children are boxed as the future would otherwise contain itself.
+        let evaluate_child = async |index: usize, predicate: &Predicate| {
+            Box::pin(predicate.evaluate_async(input))
+                .await
+                .map_err(|err| self.child_error(index, err))
+        };
+
         match self {
-            CompoundPredicate::Not(predicate) => predicate.evaluate(input).map(|b| !b),
+            CompoundPredicate::Not(predicate) => evaluate_child(0, predicate).await.map(|b| !b),
             CompoundPredicate::Any(predicates) => {
-                for predicate in predicates {
-                    if predicate.evaluate(input)? {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if evaluate_child(index, predicate).await? {
                         return Ok(true);
                     }
                 }
@@ -126,8 +734,8 @@ impl CompoundPredicate {
                 Ok(false)
             }
             CompoundPredicate::All(predicates) => {
-                for predicate in predicates {
-                    if !predicate.evaluate(input)? {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if !evaluate_child(index, predicate).await? {
                         return Ok(false);
                     }
                 }
@@ -135,22 +743,159 @@ impl CompoundPredicate {
                 Ok(true)
             }
             CompoundPredicate::None(predicates) => {
-                for predicate in predicates {
-                    if predicate.evaluate(input)? {
+                for (index, predicate) in predicates.iter().enumerate() {
+                    if evaluate_child(index, predicate).await? {
                         return Ok(false);
                     }
                 }
 
                 Ok(true)
             }
+            CompoundPredicate::OneOf { .. } => self.evaluate(input),
+            CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            } => match evaluate_child(0, condition).await? {
+                true => evaluate_child(1, then).await,
+                false => evaluate_child(2, otherwise).await,
+            },
+            CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            } => {
+                let elements = read_elements(array_path, input, FieldPresence::default())?;
+
+                for (index, element) in elements.iter().enumerate() {
+                    if !Box::pin(predicate.evaluate_async(element))
+                        .await
+                        .map_err(|err| self.child_error(index, err))?
+                    {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            } => {
+                let elements = read_elements(array_path, input, FieldPresence::default())?;
+
+                for (index, element) in elements.iter().enumerate() {
+                    if Box::pin(predicate.evaluate_async(element))
+                        .await
+                        .map_err(|err| self.child_error(index, err))?
+                    {
+                        return Ok(true);
+                    }
+                }
+
+                Ok(false)
+            }
+        }
+    }
+
+    /// Like `evaluate`, but evaluates the children of `any`, `all` and `none` predicates as
+    /// separate Tokio tasks when there are at least `DEFAULT_CONCURRENCY_THRESHOLD` of them. Must
+    /// be called from within a Tokio runtime.
+    ///
+    /// The input is cloned so it can be shared between the tasks, so this only pays off when the
+    /// children are expensive to evaluate relative to the size of the input (see
+    /// `benches/concurrent.rs`).
+    pub async fn evaluate_concurrent(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+        self.evaluate_concurrent_with(input, DEFAULT_CONCURRENCY_THRESHOLD)
+            .await
+    }
+
+    /// Like `evaluate_concurrent`, with predicates having fewer than `threshold` children evaluated
+    /// sequentially instead.
+    ///
+    /// The remaining tasks are cancelled as soon as one child decides the result. If several
+    /// children error, or one errors and another decides the result, whichever finishes first is
+    /// returned, which may differ from `evaluate`.
+    pub async fn evaluate_concurrent_with(
+        &self,
+        input: &JsonValue,
+        threshold: usize,
+    ) -> Result<bool, EvaluationError> {
+        // The value of a child which decides the result of the whole predicate.
+        let (predicates, deciding_value) = match self {
+            CompoundPredicate::Any(predicates) | CompoundPredicate::None(predicates) => {
+                (predicates, true)
+            }
+            CompoundPredicate::All(predicates) => (predicates, false),
+            CompoundPredicate::Not(_)
+            | CompoundPredicate::OneOf { .. }
+            | CompoundPredicate::If { .. }
+            | CompoundPredicate::ForEach { .. }
+            | CompoundPredicate::ExistsIn { .. } => {
+                return self.evaluate(input);
+            }
+        };
+
+        if predicates.len() < threshold {
+            return self.evaluate(input);
+        }
+
+        let input = Arc::new(input.clone());
+        let mut tasks = JoinSet::new();
+
+        for (index, predicate) in predicates.iter().enumerate() {
+            let predicate = predicate.clone();
+            let input = Arc::clone(&input);
+            tasks.spawn(async move { (index, predicate.evaluate(&input)) });
+        }
+
+        let mut is_decided = false;
+
+        while let Some(result) = tasks.join_next().await {
+            let (index, result) =
+                result.unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()));
+
+            if result.map_err(|err| self.child_error(index, err))? == deciding_value {
+                is_decided = true;
+                break;
+            }
         }
+
+        // Dropping `tasks` aborts any children still being evaluated.
+        Ok(match self {
+            CompoundPredicate::Any(_) => is_decided,
+            _ => !is_decided,
+        })
+    }
+
+    /// Wraps the error of the child at `index` with where it is in the predicate, e.g. `All[1]`.
+    fn child_error(&self, index: usize, err: EvaluationError) -> EvaluationError {
+        let context = match self {
+            CompoundPredicate::Not(_) => "Not".to_owned(),
+            CompoundPredicate::Any(_) => format!("Any[{index}]"),
+            CompoundPredicate::All(_) => format!("All[{index}]"),
+            CompoundPredicate::None(_) => format!("None[{index}]"),
+            CompoundPredicate::OneOf { .. } => "OneOf".to_owned(),
+            CompoundPredicate::If { .. } => match index {
+                0 => "If.condition".to_owned(),
+                1 => "If.then".to_owned(),
+                _ => "If.else".to_owned(),
+            },
+            CompoundPredicate::ForEach { array_path, .. } => {
+                format!("ForEach({array_path})[{index}]")
+            }
+            CompoundPredicate::ExistsIn { array_path, .. } => {
+                format!("ExistsIn({array_path})[{index}]")
+            }
+        };
+
+        EvaluationError::with_context(context, err)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{all, any, none, not, predicate, rule};
+    use crate::{all, any, exists_in, for_each, if_then_else, none, not, one_of, predicate, rule};
     use serde_json::json;
 
     macro_rules! not_an_object_err {
@@ -162,6 +907,21 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_evaluate_many() {
+        let rules = [
+            rule!("rule-1", "message", predicate!("foo" == 10)),
+            rule!("rule-2", "message", predicate!("foo" > 20)),
+            rule!("rule-3", "message", predicate!("foo.bar" == 10)),
+        ];
+
+        assert_eq!(
+            evaluate_many(&rules, &json!({"foo": 10})),
+            [Ok(true), Ok(false), not_an_object_err!("bar", "number")]
+        );
+        assert!(evaluate_many(&[], &json!({})).is_empty());
+    }
+
     #[test]
     fn test_follow_path() {
         assert_eq!(follow_path("foo", &json!({"foo": 10})), Ok(&json!(10)));
@@ -184,28 +944,185 @@ mod tests {
         );
     }
 
-    mod evaluate {
-        use super::*;
+    #[test]
+    fn test_follow_path_multi() {
+        // Paths without a wildcard read a single value.
+        assert_eq!(
+            follow_path_multi("foo.bar", &json!({"foo": {"bar": 123}})),
+            Ok(vec![&json!(123)])
+        );
+        assert_eq!(
+            follow_path_multi("foo", &json!({})),
+            Ok(vec![&JsonValue::Null])
+        );
 
-        mod operators {
-            use super::*;
+        let input = json!({
+            "items": [
+                {"price": 10, "tags": ["a"]},
+                {"price": 20},
+                {"tags": ["b", "c"]}
+            ],
+            "totals": {"gross": 1, "net": 2}
+        });
 
-            macro_rules! test_op {
-                ($operator:tt, $expected:expr, $value:expr, $($input:tt)*) => {
-                    assert_eq!(
-                        predicate!("field" $operator $value).evaluate(&json!({
-                            "field": $($input)*
-                        })),
-                        $expected
-                    );
-                };
-            }
+        assert_eq!(
+            follow_path_multi("items.*.price", &input),
+            Ok(vec![&json!(10), &json!(20), &JsonValue::Null])
+        );
+        assert_eq!(
+            follow_path_multi("items.*.tags.*", &input),
+            Ok(vec![&json!("a"), &json!("b"), &json!("c")])
+        );
+        assert_eq!(
+            follow_path_multi("totals.*", &input),
+            Ok(vec![&json!(1), &json!(2)])
+        );
+        assert_eq!(follow_path_multi("*.*", &json!({"a": []})), Ok(vec![]));
 
-            macro_rules! type_err {
-                ($lhs:literal,$rhs:literal, $operator:expr) => {
-                    Err(EvaluationError::TypeMismatch {
-                        lhs: $lhs,
-                        rhs: $rhs,
+        assert_eq!(
+            follow_path_multi("items.*.price.*", &input),
+            not_an_object_err!("*", "number")
+        );
+        assert_eq!(
+            follow_path_multi_with("items.*.price", &input, FieldPresence::Strict),
+            Err(EvaluationError::MissingField {
+                path: "items.*.price".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_follow_path_json_pointer() {
+        let input = json!({"a": {"b": {"c": [1, 2, 3]}, "d/e": 5, "f~g": 6}});
+
+        for (path, pointer) in [("a", "/a"), ("a.b", "/a/b"), ("a.b.c", "/a/b/c")] {
+            assert_eq!(
+                follow_path_json_pointer(pointer, &input),
+                follow_path(path, &input),
+                "{path} vs {pointer}"
+            );
+        }
+
+        assert_eq!(follow_path_json_pointer("", &input), Ok(&input));
+        assert_eq!(follow_path_json_pointer("/a/b/c/1", &input), Ok(&json!(2)));
+        assert_eq!(follow_path_json_pointer("/a/d~1e", &input), Ok(&json!(5)));
+        assert_eq!(follow_path_json_pointer("/a/f~0g", &input), Ok(&json!(6)));
+
+        assert_eq!(follow_path_json_pointer("/a/x", &input), Ok(&json!(null)));
+        assert_eq!(
+            follow_path_json_pointer("/a/b/c/5", &input),
+            Ok(&json!(null))
+        );
+        assert_eq!(
+            follow_path_json_pointer_with("/a/b/c/5", &input, FieldPresence::Strict),
+            Err(EvaluationError::MissingField {
+                path: "/a/b/c/5".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_json_pointer_predicates() {
+        let inputs = [
+            json!({"applicant": {"income": 50000, "tags": ["vip"]}}),
+            json!({"applicant": {"income": 10000, "tags": []}}),
+            json!({"applicant": {"name": "Jo"}}),
+        ];
+
+        let pairs = [
+            (predicate!("applicant.income" >= 20000), "/applicant/income"),
+            (
+                predicate!("applicant.tags" contains "vip"),
+                "/applicant/tags",
+            ),
+            (predicate!("applicant.income" is_null), "/applicant/income"),
+        ];
+
+        for (dot, pointer) in pairs {
+            let pointer = RawPredicate {
+                path: pointer.to_owned(),
+                path_syntax: PathSyntax::JsonPointer,
+                ..dot.clone()
+            };
+
+            for input in &inputs {
+                assert_eq!(
+                    pointer.evaluate(input),
+                    dot.evaluate(input),
+                    "{pointer:?} vs {dot:?} on {input}"
+                );
+            }
+        }
+
+        let first_tag = RawPredicate {
+            path: "/applicant/tags/0".to_owned(),
+            path_syntax: PathSyntax::JsonPointer,
+            ..predicate!("unused" == "vip")
+        };
+        assert_eq!(first_tag.evaluate(&inputs[0]), Ok(true));
+        assert_eq!(first_tag.evaluate(&inputs[1]), Ok(false));
+
+        // Unlike a dot path, a pointer through a missing object reads `null` rather than failing.
+        let pointer = RawPredicate {
+            path: "/applicant/income".to_owned(),
+            path_syntax: PathSyntax::JsonPointer,
+            ..predicate!("unused" is_null)
+        };
+        assert_eq!(pointer.evaluate(&json!({})), Ok(true));
+        assert_eq!(
+            predicate!("applicant.income" is_null).evaluate(&json!({})),
+            not_an_object_err!("income", "null")
+        );
+    }
+
+    #[test]
+    fn test_follow_path_with_presence() {
+        let input = json!({"a": {"b": null}});
+
+        assert_eq!(
+            follow_path_with("a.b", &input, FieldPresence::Strict),
+            Ok(&json!(null))
+        );
+        assert_eq!(
+            follow_path_with("a.c", &input, FieldPresence::Lenient),
+            Ok(&json!(null))
+        );
+        assert_eq!(
+            follow_path_with("a.c", &input, FieldPresence::Strict),
+            Err(EvaluationError::MissingField {
+                path: "a.c".to_owned()
+            })
+        );
+        assert_eq!(
+            follow_path_with("c.d", &input, FieldPresence::Strict),
+            Err(EvaluationError::MissingField {
+                path: "c.d".to_owned()
+            })
+        );
+    }
+
+    mod evaluate {
+        use super::*;
+
+        mod operators {
+            use super::*;
+
+            macro_rules! test_op {
+                ($operator:tt, $expected:expr, $value:expr, $($input:tt)*) => {
+                    assert_eq!(
+                        predicate!("field" $operator $value).evaluate(&json!({
+                            "field": $($input)*
+                        })),
+                        $expected
+                    );
+                };
+            }
+
+            macro_rules! type_err {
+                ($lhs:literal,$rhs:literal, $operator:expr) => {
+                    Err(EvaluationError::TypeMismatch {
+                        lhs: $lhs,
+                        rhs: $rhs,
                         operator: $operator,
                     })
                 };
@@ -411,6 +1328,19 @@ mod tests {
                     test_op!(contains, Ok(true), json!({"foo": {"bar": 10}}), [{"foo": {"bar": 12}}, {"foo": {"bar": 10}}]);
                 }
 
+                #[test]
+                fn test_contains_substring() {
+                    test_op!(contains, Ok(true), "Hello", "Hello World");
+                    test_op!(contains, Ok(true), "o W", "Hello World");
+                    test_op!(contains, Ok(true), "", "Hello World");
+                    test_op!(contains, Ok(false), "hello", "Hello World");
+                    test_op!(contains, Ok(false), "Hello World!", "Hello World");
+
+                    // An array of strings is searched for the whole string.
+                    test_op!(contains, Ok(false), "Hello", ["Hello World"]);
+                    test_op!(contains, Ok(true), "Hello", ["Hello", "World"]);
+                }
+
                 #[test]
                 fn test_contains_type_err() {
                     test_op!(
@@ -429,9 +1359,216 @@ mod tests {
 
                     test_op!(
                         contains,
-                        type_err!("string", "string", Operator::Contains),
-                        "Hello",
-                        "Hello World"
+                        type_err!("string", "number", Operator::Contains),
+                        10,
+                        "Hello 10"
+                    );
+
+                    test_op!(
+                        contains,
+                        type_err!("number", "string", Operator::Contains),
+                        "1",
+                        1
+                    );
+
+                    assert_eq!(
+                        EvaluationError::type_mismatch(&json!(1), &json!(1), Operator::Contains)
+                            .to_string(),
+                        "cannot compare number with number using operator Contains, which checks \
+                         for an element of an array or a substring of a string value"
+                    );
+                }
+            }
+
+            mod divisibility {
+                use super::*;
+
+                #[test]
+                fn test_divisible() {
+                    test_op!(divisible, Ok(true), 1, 7);
+                    test_op!(divisible, Ok(true), 2, 8);
+                    test_op!(divisible, Ok(false), 2, 7);
+                    test_op!(divisible, Ok(true), 10, 120);
+                    test_op!(divisible, Ok(false), 10, 125);
+                    test_op!(divisible, Ok(true), 10, 0);
+                }
+
+                #[test]
+                fn test_not_divisible() {
+                    test_op!(not_divisible, Ok(false), 1, 7);
+                    test_op!(not_divisible, Ok(true), 2, 7);
+                    test_op!(not_divisible, Ok(false), 10, 120);
+                }
+
+                #[test]
+                fn test_divisible_negative() {
+                    test_op!(divisible, Ok(true), 5, -10);
+                    test_op!(divisible, Ok(true), -5, 10);
+                    test_op!(divisible, Ok(false), -3, -10);
+                    test_op!(divisible, Ok(true), -1, i64::MIN);
+                }
+
+                #[test]
+                fn test_divisible_by_zero_err() {
+                    test_op!(divisible, Err(EvaluationError::DivisionByZero), 0, 10);
+                    test_op!(not_divisible, Err(EvaluationError::DivisionByZero), 0, 0);
+                }
+
+                #[test]
+                fn test_divisible_type_err() {
+                    test_op!(
+                        divisible,
+                        type_err!("number", "number", Operator::Divisible),
+                        2,
+                        2.5
+                    );
+                    test_op!(
+                        divisible,
+                        type_err!("string", "number", Operator::Divisible),
+                        2,
+                        "4"
+                    );
+                    test_op!(
+                        not_divisible,
+                        type_err!("number", "string", Operator::NotDivisible),
+                        "2",
+                        4
+                    );
+                }
+            }
+
+            mod affixes {
+                use super::*;
+
+                #[test]
+                fn test_starts_with() {
+                    test_op!(starts_with, Ok(true), "Hello", "Hello World");
+                    test_op!(starts_with, Ok(true), "", "Hello World");
+                    test_op!(starts_with, Ok(false), "World", "Hello World");
+                    test_op!(starts_with, Ok(false), "hello", "Hello World");
+                }
+
+                #[test]
+                fn test_ends_with() {
+                    test_op!(ends_with, Ok(true), "World", "Hello World");
+                    test_op!(ends_with, Ok(true), "", "Hello World");
+                    test_op!(ends_with, Ok(false), "Hello", "Hello World");
+                    test_op!(ends_with, Ok(false), "world", "Hello World");
+                }
+
+                #[test]
+                fn test_affix_type_err() {
+                    test_op!(
+                        starts_with,
+                        type_err!("number", "string", Operator::StartsWith),
+                        "1",
+                        10
+                    );
+                    test_op!(
+                        ends_with,
+                        type_err!("array", "string", Operator::EndsWith),
+                        "a",
+                        ["a"]
+                    );
+                    test_op!(
+                        ends_with,
+                        type_err!("string", "number", Operator::EndsWith),
+                        1,
+                        "1"
+                    );
+                }
+
+                #[test]
+                fn test_affix_rule() {
+                    let rule = rule!(
+                        "email_rule",
+                        "email must be a company address",
+                        all!(
+                            predicate!("email" ends_with "@example.com"),
+                            not!(predicate!("email" starts_with "admin"))
+                        )
+                    );
+
+                    assert_eq!(rule.evaluate(&json!({"email": "jo@example.com"})), Ok(true));
+                    assert_eq!(
+                        rule.evaluate(&json!({"email": "admin@example.com"})),
+                        Ok(false)
+                    );
+                    assert_eq!(
+                        rule.evaluate(&json!({"email": "jo@example.org"})),
+                        Ok(false)
+                    );
+                }
+            }
+
+            mod null {
+                use super::*;
+
+                macro_rules! test_null_op {
+                    ($input:expr, $presence:expr, $is_null:expr, $is_not_null:expr) => {
+                        assert_eq!(
+                            predicate!("field" is_null).evaluate_in(&$input, $presence),
+                            $is_null
+                        );
+                        assert_eq!(
+                            predicate!("field" is_not_null).evaluate_in(&$input, $presence),
+                            $is_not_null
+                        );
+                    };
+                }
+
+                #[test]
+                fn test_absent() {
+                    test_null_op!(json!({}), FieldPresence::Lenient, Ok(true), Ok(false));
+                }
+
+                #[test]
+                fn test_null() {
+                    test_null_op!(
+                        json!({"field": null}),
+                        FieldPresence::Lenient,
+                        Ok(true),
+                        Ok(false)
+                    );
+                    test_null_op!(
+                        json!({"field": null}),
+                        FieldPresence::Strict,
+                        Ok(true),
+                        Ok(false)
+                    );
+                }
+
+                #[test]
+                fn test_non_null() {
+                    for value in [json!(0), json!(false), json!(""), json!([]), json!({})] {
+                        test_null_op!(
+                            json!({"field": value}),
+                            FieldPresence::Lenient,
+                            Ok(false),
+                            Ok(true)
+                        );
+                        test_null_op!(
+                            json!({"field": value}),
+                            FieldPresence::Strict,
+                            Ok(false),
+                            Ok(true)
+                        );
+                    }
+                }
+
+                #[test]
+                fn test_absent_strict() {
+                    let missing_field = || {
+                        Err(EvaluationError::MissingField {
+                            path: "field".to_owned(),
+                        })
+                    };
+
+                    test_null_op!(
+                        json!({}),
+                        FieldPresence::Strict,
+                        missing_field(),
+                        missing_field()
                     );
                 }
             }
@@ -484,6 +1621,79 @@ mod tests {
                 assert_rule_eval!(rule, create_input(12, 3, 1), Ok(false));
             }
 
+            #[test]
+            fn test_error_context() {
+                let predicate = all!(
+                    predicate!("age" >= 12),
+                    any!(
+                        predicate!("height.feet" > 5),
+                        not!(none!(predicate!("height.inches" >= 2)))
+                    )
+                );
+
+                let err = predicate
+                    .evaluate(&json!({"age": 12, "height": {"feet": 5, "inches": "two"}}))
+                    .expect_err("comparing a string should fail");
+
+                assert_eq!(
+                    err.to_string(),
+                    "All[1].Any[1].Not.None[0]: cannot compare string with number using operator \
+                     GreaterEqual"
+                );
+                assert_eq!(
+                    err.root_cause(),
+                    &EvaluationError::TypeMismatch {
+                        lhs: "string",
+                        rhs: "number",
+                        operator: Operator::GreaterEqual,
+                    }
+                );
+
+                assert_eq!(
+                    predicate.evaluate(&json!({"age": "twelve"})),
+                    Err(EvaluationError::WithContext {
+                        context: "All[0]".to_owned(),
+                        source: Box::new(EvaluationError::TypeMismatch {
+                            lhs: "string",
+                            rhs: "number",
+                            operator: Operator::GreaterEqual,
+                        }),
+                    })
+                );
+
+                // Raw predicates have no context.
+                assert_eq!(
+                    predicate!("age" >= 12)
+                        .evaluate(&json!({"age": "twelve"}))
+                        .map_err(|err| err.to_string()),
+                    Err("cannot compare string with number using operator GreaterEqual".to_owned())
+                );
+            }
+
+            #[test]
+            fn test_with_context() {
+                let err = EvaluationError::with_context(
+                    "All[2]".to_owned(),
+                    EvaluationError::with_context(
+                        "Any[0]".to_owned(),
+                        EvaluationError::DivisionByZero,
+                    ),
+                );
+
+                assert_eq!(
+                    err,
+                    EvaluationError::WithContext {
+                        context: "All[2].Any[0]".to_owned(),
+                        source: Box::new(EvaluationError::DivisionByZero),
+                    }
+                );
+                assert_eq!(err.root_cause(), &EvaluationError::DivisionByZero);
+                assert_eq!(
+                    EvaluationError::DivisionByZero.root_cause(),
+                    &EvaluationError::DivisionByZero
+                );
+            }
+
             #[test]
             fn test_simple_rule() {
                 assert_rule_eval!(
@@ -634,6 +1844,336 @@ mod tests {
                 );
             }
 
+            #[test]
+            fn test_one_of() {
+                let one_of = one_of!("color", "red", "blue", 10);
+                let any = any!(
+                    predicate!("color" == "red"),
+                    predicate!("color" == "blue"),
+                    predicate!("color" == 10)
+                );
+
+                for input in [
+                    json!({"color": "red"}),
+                    json!({"color": "blue"}),
+                    json!({"color": 10}),
+                    json!({"color": "10"}),
+                    json!({"color": "green"}),
+                    json!({}),
+                    json!({"color": {"red": true}}),
+                    json!(10),
+                ] {
+                    // Unlike `one_of`, the error of `any` has which equality failed as context.
+                    assert_eq!(
+                        one_of.evaluate(&input).map_err(|err| err.to_string()),
+                        any.evaluate(&input)
+                            .map_err(|err| err.root_cause().to_string()),
+                        "{input}"
+                    );
+                }
+
+                let wildcard = one_of!("items.*.color", "red");
+                assert_eq!(
+                    wildcard.evaluate(&json!({"items": [{"color": "blue"}, {"color": "red"}]})),
+                    Ok(true)
+                );
+                assert_eq!(wildcard.evaluate(&json!({"items": []})), Ok(false));
+
+                assert_eq!(
+                    one_of!("color",).evaluate(&json!({"color": "red"})),
+                    Ok(false)
+                );
+                assert_eq!(
+                    one_of.evaluate_in(&json!({}), FieldPresence::Strict),
+                    Err(EvaluationError::MissingField {
+                        path: "color".to_owned()
+                    })
+                );
+            }
+
+            #[test]
+            fn test_if() {
+                let predicate = if_then_else!(
+                    predicate!("country" == "US"),
+                    predicate!("age" >= 21),
+                    predicate!("age" >= 18)
+                );
+
+                for (input, expected) in [
+                    (json!({"country": "US", "age": 21}), true),
+                    (json!({"country": "US", "age": 18}), false),
+                    (json!({"country": "UK", "age": 18}), true),
+                    (json!({"country": "UK", "age": 17}), false),
+                ] {
+                    assert_eq!(predicate.evaluate(&input), Ok(expected), "{input}");
+                }
+            }
+
+            #[test]
+            fn test_if_err() {
+                let predicate = if_then_else!(
+                    predicate!("flag" > 0),
+                    predicate!("then.value" == 1),
+                    predicate!("else.value" == 1)
+                );
+                let context_of = |input: JsonValue| match predicate.evaluate(&input) {
+                    Err(EvaluationError::WithContext { context, .. }) => context,
+                    other => panic!("expected an error with context, got {other:?}"),
+                };
+
+                assert_eq!(context_of(json!({"flag": "yes"})), "If.condition");
+                assert_eq!(context_of(json!({"flag": 1, "then": 1})), "If.then");
+                assert_eq!(context_of(json!({"flag": 0, "else": 1})), "If.else");
+
+                // Only the branch taken is evaluated, so an error in the other doesn't matter.
+                assert_eq!(
+                    predicate.evaluate(&json!({"flag": 1, "then": {"value": 1}, "else": 1})),
+                    Ok(true)
+                );
+                assert_eq!(
+                    predicate.evaluate(&json!({"flag": 0, "then": 1, "else": {"value": 2}})),
+                    Ok(false)
+                );
+            }
+
+            #[test]
+            fn test_for_each() {
+                let predicate = for_each!("items", predicate!("price" > 0));
+
+                for (input, expected) in [
+                    (json!({"items": []}), true),
+                    (json!({}), true),
+                    (json!({"items": [{"price": 1}, {"price": 2}]}), true),
+                    (json!({"items": [{"price": 0}, {"price": 2}]}), false),
+                    (json!({"items": [{"price": 1}, {"price": 0}]}), false),
+                ] {
+                    assert_eq!(predicate.evaluate(&input), Ok(expected), "{input}");
+                }
+
+                // An empty path reads the whole element.
+                let tags = for_each!("tags", predicate!("" contains "a"));
+                assert_eq!(tags.evaluate(&json!({"tags": ["abc", "bar"]})), Ok(true));
+                assert_eq!(tags.evaluate(&json!({"tags": ["abc", "foo"]})), Ok(false));
+
+                let nested = for_each!("orders", for_each!("items", predicate!("price" > 0)));
+                assert_eq!(
+                    nested.evaluate(&json!({"orders": [{"items": [{"price": 1}]}, {"items": []}]})),
+                    Ok(true)
+                );
+                assert_eq!(
+                    nested.evaluate(&json!({"orders": [{"items": [{"price": 1}, {"price": 0}]}]})),
+                    Ok(false)
+                );
+            }
+
+            #[test]
+            fn test_for_each_err() {
+                let predicate = for_each!("items", predicate!("price" > 0));
+
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": {"price": 1}})),
+                    Err(EvaluationError::NotAnArray {
+                        path: "items".to_owned(),
+                        kind: "object"
+                    })
+                );
+                assert_eq!(
+                    predicate.evaluate_in(&json!({}), FieldPresence::Strict),
+                    Err(EvaluationError::MissingField {
+                        path: "items".to_owned()
+                    })
+                );
+
+                let err = predicate
+                    .evaluate(&json!({"items": [{"price": 1}, {"price": "1"}]}))
+                    .expect_err("the second element can't be compared");
+                assert_eq!(
+                    err.to_string(),
+                    "ForEach(items)[1]: cannot compare string with number using operator Greater"
+                );
+
+                // Evaluation stops at the first failing element.
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 0}, {"price": "1"}]})),
+                    Ok(false)
+                );
+            }
+
+            #[test]
+            fn test_for_each_match_count() {
+                let predicate: Predicate = all!(
+                    predicate!("total" > 0),
+                    for_each!(
+                        "items",
+                        all!(predicate!("price" > 0), predicate!("name" is_not_null))
+                    )
+                )
+                .into();
+
+                assert_eq!(predicate.raw_predicates().len(), 3);
+
+                for (input, expected) in [
+                    (json!({"total": 1, "items": []}), 3),
+                    (json!({"total": 1, "items": [{"price": 1, "name": "a"}]}), 3),
+                    (
+                        json!({"total": 0, "items": [{"price": 1}, {"price": 1, "name": "b"}]}),
+                        1,
+                    ),
+                    (json!({"total": 1, "items": [{"price": 0}]}), 1),
+                ] {
+                    assert_eq!(predicate.match_count(&input), Ok(expected), "{input}");
+                }
+            }
+
+            #[tokio::test]
+            async fn test_for_each_async() {
+                let predicate = for_each!("items", predicate!("price" > 0));
+
+                for input in [
+                    json!({"items": []}),
+                    json!({"items": [{"price": 1}, {"price": 2}]}),
+                    json!({"items": [{"price": 1}, {"price": 0}]}),
+                    json!({"items": [{"price": "1"}]}),
+                    json!({"items": 1}),
+                ] {
+                    assert_eq!(
+                        predicate.evaluate_async(&input).await,
+                        predicate.evaluate(&input),
+                        "{input}"
+                    );
+                }
+            }
+
+            #[test]
+            fn test_exists_in() {
+                let predicate = exists_in!("users", predicate!("role" == "admin"));
+
+                for (input, expected) in [
+                    (json!({"users": []}), false),
+                    (json!({}), false),
+                    (
+                        json!({"users": [{"role": "user"}, {"role": "admin"}]}),
+                        true,
+                    ),
+                    (
+                        json!({"users": [{"role": "user"}, {"role": "guest"}]}),
+                        false,
+                    ),
+                ] {
+                    assert_eq!(predicate.evaluate(&input), Ok(expected), "{input}");
+                }
+
+                // All items are in stock and at least one is on sale.
+                let order = all!(
+                    for_each!("items", predicate!("stock" > 0)),
+                    exists_in!("items", predicate!("onSale" == true))
+                );
+                assert_eq!(
+                    order.evaluate(&json!({"items": [
+                        {"stock": 1, "onSale": false},
+                        {"stock": 2, "onSale": true}
+                    ]})),
+                    Ok(true)
+                );
+                assert_eq!(
+                    order.evaluate(&json!({"items": [{"stock": 1}, {"stock": 2}]})),
+                    Ok(false)
+                );
+                assert_eq!(
+                    order.evaluate(&json!({"items": [{"stock": 0, "onSale": true}]})),
+                    Ok(false)
+                );
+                assert_eq!(order.evaluate(&json!({"items": []})), Ok(false));
+            }
+
+            #[test]
+            fn test_exists_in_err() {
+                let predicate = exists_in!("items", predicate!("price" > 0));
+
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": "none"})),
+                    Err(EvaluationError::NotAnArray {
+                        path: "items".to_owned(),
+                        kind: "string"
+                    })
+                );
+
+                let err = predicate
+                    .evaluate(&json!({"items": [{"price": 0}, {"price": "1"}]}))
+                    .expect_err("the second element can't be compared");
+                assert_eq!(
+                    err.to_string(),
+                    "ExistsIn(items)[1]: cannot compare string with number using operator Greater"
+                );
+
+                // Evaluation stops at the first matching element.
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 1}, {"price": "1"}]})),
+                    Ok(true)
+                );
+            }
+
+            #[test]
+            fn test_exists_in_match_count() {
+                let predicate: Predicate = exists_in!(
+                    "users",
+                    all!(predicate!("role" == "admin"), predicate!("active" == true))
+                )
+                .into();
+
+                for (input, expected) in [
+                    (json!({"users": []}), 0),
+                    (json!({"users": [{"role": "user"}, {"active": true}]}), 1),
+                    (
+                        json!({"users": [{"role": "user"}, {"role": "admin", "active": true}]}),
+                        2,
+                    ),
+                ] {
+                    assert_eq!(predicate.match_count(&input), Ok(expected), "{input}");
+                }
+            }
+
+            #[tokio::test]
+            async fn test_exists_in_async() {
+                let predicate = exists_in!("items", predicate!("price" > 0));
+
+                for input in [
+                    json!({"items": []}),
+                    json!({"items": [{"price": 0}, {"price": 2}]}),
+                    json!({"items": [{"price": 0}, {"price": 0}]}),
+                    json!({"items": [{"price": "1"}]}),
+                    json!({"items": 1}),
+                ] {
+                    assert_eq!(
+                        predicate.evaluate_async(&input).await,
+                        predicate.evaluate(&input),
+                        "{input}"
+                    );
+                }
+            }
+
+            #[tokio::test]
+            async fn test_if_async() {
+                let predicate = if_then_else!(
+                    predicate!("flag" == true),
+                    predicate!("value" > 1),
+                    not!(predicate!("value" > 1))
+                );
+
+                for input in [
+                    json!({"flag": true, "value": 2}),
+                    json!({"flag": false, "value": 2}),
+                    json!({"flag": "yes", "value": 0}),
+                    json!({"flag": false, "value": "2"}),
+                ] {
+                    assert_eq!(
+                        predicate.evaluate_async(&input).await,
+                        predicate.evaluate(&input),
+                        "{input}"
+                    );
+                }
+            }
+
             #[test]
             fn test_not() {
                 assert_rule_eval!(
@@ -649,5 +2189,524 @@ mod tests {
                 );
             }
         }
+
+        mod wildcard {
+            use super::*;
+
+            fn every(predicate: RawPredicate) -> RawPredicate {
+                RawPredicate {
+                    quantifier: Quantifier::All,
+                    ..predicate
+                }
+            }
+
+            #[test]
+            fn test_any() {
+                let predicate = predicate!("items.*.price" > 15);
+
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 10}, {"price": 20}]})),
+                    Ok(true)
+                );
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 10}, {"price": 12}]})),
+                    Ok(false)
+                );
+                assert_eq!(predicate.evaluate(&json!({"items": []})), Ok(false));
+            }
+
+            #[test]
+            fn test_all() {
+                let predicate = every(predicate!("items.*.price" > 15));
+
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 20}, {"price": 30}]})),
+                    Ok(true)
+                );
+                assert_eq!(
+                    predicate.evaluate(&json!({"items": [{"price": 20}, {"price": 10}]})),
+                    Ok(false)
+                );
+                assert_eq!(predicate.evaluate(&json!({"items": []})), Ok(true));
+            }
+
+            #[test]
+            fn test_single_value() {
+                // Without a wildcard the quantifier makes no difference.
+                for predicate in [predicate!("price" > 15), every(predicate!("price" > 15))] {
+                    assert_eq!(predicate.evaluate(&json!({"price": 20})), Ok(true));
+                    assert_eq!(predicate.evaluate(&json!({"price": 10})), Ok(false));
+                }
+            }
+
+            #[test]
+            fn test_deciding_value() {
+                let input = json!({"items": [{"price": 10}, {"price": 20}, {"price": 30}]});
+
+                assert_eq!(
+                    predicate!("items.*.price" > 15)
+                        .evaluate_with_value(&input, FieldPresence::Lenient),
+                    Ok((true, &json!(20)))
+                );
+                assert_eq!(
+                    every(predicate!("items.*.price" > 15))
+                        .evaluate_with_value(&input, FieldPresence::Lenient),
+                    Ok((false, &json!(10)))
+                );
+                assert_eq!(
+                    every(predicate!("items.*.price" > 5))
+                        .evaluate_with_value(&input, FieldPresence::Lenient),
+                    Ok((true, &json!(30)))
+                );
+            }
+
+            #[test]
+            fn test_type_mismatch() {
+                let input = json!({"items": [{"price": 20}, {"price": "ten"}]});
+
+                // Values after the deciding one aren't evaluated.
+                assert_eq!(predicate!("items.*.price" > 15).evaluate(&input), Ok(true));
+                assert!(matches!(
+                    every(predicate!("items.*.price" > 15)).evaluate(&input),
+                    Err(EvaluationError::TypeMismatch { .. })
+                ));
+            }
+
+            #[test]
+            fn test_serde() {
+                let predicate: RawPredicate = serde_json::from_value(json!({
+                    "path": "items.*.price",
+                    "operator": ">",
+                    "value": 15,
+                    "quantifier": "all"
+                }))
+                .expect("predicate should parse");
+                assert_eq!(predicate, every(predicate!("items.*.price" > 15)));
+
+                assert_eq!(
+                    serde_json::to_value(predicate!("items.*.price" > 15))
+                        .expect("predicate should serialize"),
+                    json!({"path": "items.*.price", "operator": "greater", "value": 15})
+                );
+            }
+        }
+
+        mod match_count {
+            use super::*;
+
+            #[test]
+            fn test_raw() {
+                let predicate: Predicate = predicate!("foo" == 10).into();
+
+                assert_eq!(predicate.match_count(&json!({"foo": 10})), Ok(1));
+                assert_eq!(predicate.match_count(&json!({"foo": 15})), Ok(0));
+            }
+
+            #[test]
+            fn test_compound() {
+                let predicate: Predicate = all!(
+                    predicate!("foo" == 10),
+                    any!(predicate!("bar" > 5), predicate!("bar" < 0)),
+                    none!(predicate!("baz" is_null))
+                )
+                .into();
+                let input = json!({"foo": 10, "bar": 6});
+
+                // The predicate fails, but three of its four raw predicates match.
+                assert_eq!(predicate.evaluate(&input), Ok(false));
+                assert_eq!(predicate.match_count(&input), Ok(3));
+                assert_eq!(predicate.raw_predicates().len(), 4);
+
+                assert_eq!(
+                    predicate.match_count(&json!({"foo": 1, "bar": 2, "baz": 3})),
+                    Ok(0)
+                );
+            }
+
+            #[test]
+            fn test_not_is_ignored() {
+                // Raw predicates are counted by their own result, not the result of `not`.
+                let predicate: Predicate = not!(predicate!("foo" == 10)).into();
+
+                assert_eq!(predicate.evaluate(&json!({"foo": 10})), Ok(false));
+                assert_eq!(predicate.match_count(&json!({"foo": 10})), Ok(1));
+                assert_eq!(predicate.match_count(&json!({"foo": 15})), Ok(0));
+            }
+
+            #[test]
+            fn test_no_short_circuit() {
+                // `any` stops at its first child, but every raw predicate is counted.
+                let predicate: Predicate = any!(
+                    predicate!("foo" == 10),
+                    predicate!("bar" == 10),
+                    predicate!("baz" == 10)
+                )
+                .into();
+                let input = json!({"foo": 10, "bar": 10, "baz": 10});
+
+                assert_eq!(predicate.match_count(&input), Ok(3));
+            }
+
+            #[test]
+            fn test_error() {
+                // The error in the second child is reached even though `any` already passed.
+                let predicate: Predicate =
+                    any!(predicate!("foo" == 10), predicate!("bar" > 5)).into();
+
+                assert_eq!(
+                    predicate.match_count(&json!({"foo": 10, "bar": "six"})),
+                    Err(EvaluationError::TypeMismatch {
+                        lhs: "string",
+                        rhs: "number",
+                        operator: Operator::Greater
+                    })
+                );
+            }
+
+            #[test]
+            fn test_strict_presence() {
+                let predicate: Predicate =
+                    all!(predicate!("foo" == 10), predicate!("bar" is_null)).into();
+
+                assert_eq!(predicate.match_count(&json!({"foo": 10})), Ok(2));
+                assert_eq!(
+                    predicate.match_count_in(&json!({"foo": 10}), FieldPresence::Strict),
+                    Err(EvaluationError::MissingField {
+                        path: "bar".to_owned()
+                    })
+                );
+            }
+
+            #[test]
+            fn test_rule() {
+                let rule = Rule {
+                    condition: Some(predicate!("env" == "prod").into()),
+                    ..rule!(
+                        "id",
+                        "rule failed",
+                        all!(predicate!("foo" == 10), predicate!("bar" == 10))
+                    )
+                };
+
+                // The condition isn't counted.
+                assert_eq!(rule.match_count(&json!({"foo": 10, "bar": 5})), Ok(1));
+                assert_eq!(
+                    rule.match_count(&json!({"foo": 10, "bar": 10, "env": "prod"})),
+                    Ok(2)
+                );
+            }
+        }
+
+        mod concurrent {
+            use super::*;
+
+            /// `x == 0`, `x == 1`, ... with `size` children.
+            fn equalities(size: i64) -> Vec<Predicate> {
+                (0..size).map(|i| predicate!("x" == i).into()).collect()
+            }
+
+            macro_rules! assert_concurrent_eval {
+                ($predicate:expr, $input:expr, $expected:expr) => {
+                    let predicate = $predicate;
+                    let input = $input;
+
+                    assert_eq!(predicate.evaluate(&input), $expected, "{predicate:?}");
+
+                    for threshold in [0, DEFAULT_CONCURRENCY_THRESHOLD, usize::MAX] {
+                        assert_eq!(
+                            predicate.evaluate_concurrent_with(&input, threshold).await,
+                            $expected,
+                            "{predicate:?} with threshold {threshold}"
+                        );
+                    }
+                };
+            }
+
+            #[tokio::test(flavor = "multi_thread")]
+            async fn test_any() {
+                assert_concurrent_eval!(
+                    CompoundPredicate::Any(equalities(16)),
+                    json!({"x": 11}),
+                    Ok(true)
+                );
+                assert_concurrent_eval!(
+                    CompoundPredicate::Any(equalities(16)),
+                    json!({"x": 20}),
+                    Ok(false)
+                );
+            }
+
+            #[tokio::test(flavor = "multi_thread")]
+            async fn test_all() {
+                let predicates = (0..16)
+                    .map(|i| predicate!("x" >= i).into())
+                    .collect::<Vec<_>>();
+
+                assert_concurrent_eval!(
+                    CompoundPredicate::All(predicates.clone()),
+                    json!({"x": 20}),
+                    Ok(true)
+                );
+                assert_concurrent_eval!(
+                    CompoundPredicate::All(predicates),
+                    json!({"x": 11}),
+                    Ok(false)
+                );
+            }
+
+            #[tokio::test(flavor = "multi_thread")]
+            async fn test_none() {
+                assert_concurrent_eval!(
+                    CompoundPredicate::None(equalities(16)),
+                    json!({"x": 11}),
+                    Ok(false)
+                );
+                assert_concurrent_eval!(
+                    CompoundPredicate::None(equalities(16)),
+                    json!({"x": 20}),
+                    Ok(true)
+                );
+            }
+
+            #[tokio::test(flavor = "multi_thread")]
+            async fn test_error() {
+                let predicate = CompoundPredicate::Any(vec![predicate!("x" > 1).into(); 16]);
+                let input = json!({"x": "a"});
+                let expected = EvaluationError::TypeMismatch {
+                    lhs: "string",
+                    rhs: "number",
+                    operator: Operator::Greater,
+                };
+
+                assert_eq!(
+                    predicate.evaluate(&input),
+                    Err(EvaluationError::with_context("Any[0]".to_owned(), expected))
+                );
+
+                // Whichever child errors first is returned, so only the cause is deterministic.
+                for threshold in [0, DEFAULT_CONCURRENCY_THRESHOLD, usize::MAX] {
+                    let err = predicate
+                        .evaluate_concurrent_with(&input, threshold)
+                        .await
+                        .expect_err("every child should error");
+
+                    assert!(
+                        matches!(
+                            err.root_cause(),
+                            EvaluationError::TypeMismatch {
+                                lhs: "string",
+                                rhs: "number",
+                                operator: Operator::Greater,
+                            }
+                        ),
+                        "{err} with threshold {threshold}"
+                    );
+                }
+            }
+        }
+
+        mod asynchronous {
+            use super::*;
+            use std::collections::HashSet;
+
+            /// Checks whether the value at `path` is one of a set of values which would usually
+            /// be looked up externally.
+            struct ExternalLookupPredicate {
+                path: String,
+                known_values: HashSet<String>,
+            }
+
+            impl AsyncRawPredicate for ExternalLookupPredicate {
+                async fn evaluate_async(&self, input: &JsonValue) -> Result<bool, EvaluationError> {
+                    let data = follow_path(&self.path, input)?;
+                    tokio::task::yield_now().await;
+
+                    Ok(data
+                        .as_str()
+                        .is_some_and(|value| self.known_values.contains(value)))
+                }
+            }
+
+            #[tokio::test]
+            async fn test_matches_sync() {
+                let rules = [
+                    rule!("id", "message", predicate!("foo" == 10)),
+                    rule!("id", "message", predicate!("foo" > 5)),
+                    rule!(
+                        "id",
+                        "message",
+                        all!(predicate!("foo" > 5), not!(predicate!("bar" == "x")))
+                    ),
+                    rule!(
+                        "id",
+                        "message",
+                        any!(predicate!("foo.baz" == 1), predicate!("bar" contains 1))
+                    ),
+                    rule!(
+                        "id",
+                        "message",
+                        none!(predicate!("foo" == 1), predicate!("bar" is_null))
+                    ),
+                ];
+
+                let inputs = [
+                    json!({"foo": 10, "bar": "x"}),
+                    json!({"foo": 3, "bar": [1, 2]}),
+                    json!({"foo": "10"}),
+                    json!({}),
+                ];
+
+                for rule in &rules {
+                    for input in &inputs {
+                        assert_eq!(
+                            rule.evaluate_async(input).await,
+                            rule.evaluate(input),
+                            "{rule:?} on {input}"
+                        );
+                    }
+                }
+            }
+
+            #[tokio::test]
+            async fn test_external_operator() {
+                let predicate = ExternalLookupPredicate {
+                    path: "user.country".to_owned(),
+                    known_values: HashSet::from(["UK".to_owned(), "FR".to_owned()]),
+                };
+
+                assert_eq!(
+                    predicate
+                        .evaluate_async(&json!({"user": {"country": "UK"}}))
+                        .await,
+                    Ok(true)
+                );
+                assert_eq!(
+                    predicate
+                        .evaluate_async(&json!({"user": {"country": "US"}}))
+                        .await,
+                    Ok(false)
+                );
+                assert_eq!(
+                    predicate.evaluate_async(&json!({"user": 10})).await,
+                    not_an_object_err!("country", "number")
+                );
+            }
+
+            #[tokio::test]
+            async fn test_send() {
+                let predicate: Predicate =
+                    all!(predicate!("foo" == 10), any!(predicate!("bar" == 1))).into();
+
+                let result = tokio::spawn(async move {
+                    predicate
+                        .evaluate_async(&json!({"foo": 10, "bar": 1}))
+                        .await
+                })
+                .await
+                .expect("task should not panic");
+
+                assert_eq!(result, Ok(true));
+            }
+        }
+    }
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Finite numbers, as `NaN` and the infinities aren't valid JSON.
+        fn number_strategy() -> impl Strategy<Value = JsonValue> {
+            prop_oneof![
+                any::<i64>().prop_map(JsonValue::from),
+                any::<u64>().prop_map(JsonValue::from),
+                (prop::num::f64::NORMAL
+                    | prop::num::f64::SUBNORMAL
+                    | prop::num::f64::ZERO
+                    | prop::num::f64::NEGATIVE
+                    | prop::num::f64::POSITIVE)
+                    .prop_map(JsonValue::from),
+            ]
+        }
+
+        fn json_strategy() -> impl Strategy<Value = JsonValue> {
+            let leaf = prop_oneof![
+                Just(JsonValue::Null),
+                any::<bool>().prop_map(JsonValue::from),
+                number_strategy(),
+                ".{0,8}".prop_map(JsonValue::from),
+            ];
+
+            leaf.prop_recursive(3, 32, 4, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..4).prop_map(JsonValue::from),
+                    prop::collection::btree_map("[a-z]{1,2}", inner, 0..4)
+                        .prop_map(|map| JsonValue::Object(map.into_iter().collect())),
+                ]
+            })
+        }
+
+        fn raw_predicate_strategy() -> impl Strategy<Value = RawPredicate> {
+            (
+                prop::sample::select(vec!["x", "y", "x.y"]),
+                prop::sample::select(vec![
+                    Operator::Equal,
+                    Operator::NotEqual,
+                    Operator::Greater,
+                    Operator::Less,
+                    Operator::GreaterEqual,
+                    Operator::LessEqual,
+                    Operator::Contains,
+                    Operator::IsNull,
+                    Operator::IsNotNull,
+                ]),
+                json_strategy(),
+            )
+                .prop_map(|(path, operator, value)| RawPredicate {
+                    path: path.to_owned(),
+                    path_syntax: PathSyntax::Dot,
+                    operator,
+                    value,
+                    quantifier: Quantifier::Any,
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn test_equal_to_itself(v in json_strategy()) {
+                prop_assert_eq!(
+                    predicate!("x" == v.clone()).evaluate(&json!({"x": v})),
+                    Ok(true)
+                );
+            }
+
+            #[test]
+            fn test_greater_is_inverse_of_less(a in number_strategy(), b in number_strategy()) {
+                let input = json!({"x": b});
+
+                let greater = predicate!("x" > a.clone()).evaluate(&input);
+                let less = predicate!("x" < a.clone()).evaluate(&input);
+
+                prop_assert!(
+                    greater == less.map(|r| !r) || a.as_f64() == b.as_f64(),
+                    "x = {b}, a = {a}"
+                );
+            }
+
+            #[test]
+            fn test_not_is_inverse(
+                predicate in raw_predicate_strategy(),
+                x in json_strategy(),
+                y in json_strategy(),
+            ) {
+                let input = json!({"x": x, "y": y});
+
+                prop_assert_eq!(
+                    not!(predicate.clone()).evaluate(&input),
+                    predicate
+                        .evaluate(&input)
+                        .map(|b| !b)
+                        .map_err(|err| EvaluationError::with_context("Not".to_owned(), err))
+                );
+            }
+        }
     }
 }
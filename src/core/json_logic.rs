@@ -0,0 +1,986 @@
+use serde_json::json;
+use thiserror::Error;
+
+use crate::core::{
+    eval::json_type,
+    rule::{CompoundPredicate, Operator, PathSyntax, Predicate, Quantifier, RawPredicate, Rule},
+};
+
+type JsonValue = serde_json::Value;
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum ConversionError {
+    #[error("operator {0:?} has no JSON Logic equivalent")]
+    UnsupportedOperator(Operator),
+    #[error("cannot use value of type {kind} with operator {operator:?} in JSON Logic")]
+    UnsupportedValue {
+        kind: &'static str,
+        operator: Operator,
+    },
+    #[error("path {0} can't be read by a JSON Logic var")]
+    UnsupportedPath(String),
+    #[error("JSON Logic operator {0} has no equivalent predicate")]
+    UnknownOperator(String),
+    #[error("invalid JSON Logic: {0}")]
+    Malformed(String),
+}
+
+impl Rule {
+    /// Converts the rule's predicate into a [JSON Logic](https://jsonlogic.com) rule, with the
+    /// rule's `path_aliases` applied. The rule's condition, active window and dependencies aren't
+    /// part of the result.
+    pub fn to_json_logic(&self) -> Result<JsonValue, ConversionError> {
+        self.resolve_aliases().predicate.to_json_logic()
+    }
+}
+
+impl Predicate {
+    /// Converts the predicate into a JSON Logic rule reading each `path` with a `var`.
+    ///
+    /// JSON Logic compares loosely like JavaScript's `==`, so e.g. `{"foo": "10"}` is equal to
+    /// `10` there while the predicate `foo == 10` is false for it, and ordering operators compare
+    /// any type rather than erroring.
+    pub fn to_json_logic(&self) -> Result<JsonValue, ConversionError> {
+        match self {
+            Predicate::Raw(predicate) => predicate.to_json_logic(),
+            Predicate::Compound(predicate) => predicate.to_json_logic(),
+        }
+    }
+}
+
+fn to_json_logic_rules(predicates: &[Predicate]) -> Result<Vec<JsonValue>, ConversionError> {
+    predicates.iter().map(Predicate::to_json_logic).collect()
+}
+
+/// A `var` reading `path`, which has to be readable with dots and can't have wildcards.
+fn var(
+    path: &str,
+    path_syntax: PathSyntax,
+    segments: &[String],
+) -> Result<JsonValue, ConversionError> {
+    let is_readable = segments.iter().all(|segment| {
+        segment != "*" && (path_syntax == PathSyntax::Dot || !segment.contains('.'))
+    });
+
+    if is_readable {
+        Ok(json!({"var": segments.join(".")}))
+    } else {
+        Err(ConversionError::UnsupportedPath(path.to_owned()))
+    }
+}
+
+impl RawPredicate {
+    pub fn to_json_logic(&self) -> Result<JsonValue, ConversionError> {
+        let var = var(&self.path, self.path_syntax, &self.path_segments())?;
+
+        // JSON Logic compares arrays and objects by reference, so they're never equal to anything.
+        let scalar = || match &self.value {
+            JsonValue::Array(_) | JsonValue::Object(_) => Err(ConversionError::UnsupportedValue {
+                kind: json_type(&self.value),
+                operator: self.operator,
+            }),
+            value => Ok(value),
+        };
+
+        match self.operator {
+            Operator::Equal => Ok(json!({"==": [var, scalar()?]})),
+            Operator::NotEqual => Ok(json!({"!=": [var, scalar()?]})),
+            Operator::Greater => Ok(json!({">": [var, self.value]})),
+            Operator::Less => Ok(json!({"<": [var, self.value]})),
+            Operator::GreaterEqual => Ok(json!({">=": [var, self.value]})),
+            Operator::LessEqual => Ok(json!({"<=": [var, self.value]})),
+            // `in` checks for an element of an array or a substring of a string, like `contains`.
+            Operator::Contains => Ok(json!({"in": [scalar()?, var]})),
+            Operator::IsNull => Ok(json!({"==": [var, null]})),
+            Operator::IsNotNull => Ok(json!({"!=": [var, null]})),
+            Operator::Divisible => Ok(json!({"==": [{"%": [var, self.value]}, 0]})),
+            Operator::NotDivisible => Ok(json!({"!=": [{"%": [var, self.value]}, 0]})),
+            Operator::StartsWith | Operator::EndsWith => {
+                Err(ConversionError::UnsupportedOperator(self.operator))
+            }
+        }
+    }
+}
+
+impl CompoundPredicate {
+    pub fn to_json_logic(&self) -> Result<JsonValue, ConversionError> {
+        // An empty `and` or `or` is falsy in JSON Logic, so empty predicates are constants.
+        match self {
+            CompoundPredicate::Not(predicate) => Ok(json!({"!": [predicate.to_json_logic()?]})),
+            CompoundPredicate::Any(predicates) if predicates.is_empty() => Ok(json!(false)),
+            CompoundPredicate::Any(predicates) => {
+                Ok(json!({"or": to_json_logic_rules(predicates)?}))
+            }
+            CompoundPredicate::All(predicates) if predicates.is_empty() => Ok(json!(true)),
+            CompoundPredicate::All(predicates) => {
+                Ok(json!({"and": to_json_logic_rules(predicates)?}))
+            }
+            CompoundPredicate::None(predicates) if predicates.is_empty() => Ok(json!(true)),
+            CompoundPredicate::None(predicates) => {
+                Ok(json!({"!": [{"or": to_json_logic_rules(predicates)?}]}))
+            }
+            CompoundPredicate::OneOf { path, values } => {
+                let segments: Vec<_> = path.split('.').map(String::from).collect();
+                let var = var(path, PathSyntax::Dot, &segments)?;
+
+                // Like `Equal`, arrays and objects can't be compared, whereas `in` compares
+                // strictly like a `OneOf` does.
+                if let Some(value) = values
+                    .iter()
+                    .find(|value| value.is_array() || value.is_object())
+                {
+                    return Err(ConversionError::UnsupportedValue {
+                        kind: json_type(value),
+                        operator: Operator::Equal,
+                    });
+                }
+
+                Ok(json!({"in": [var, values]}))
+            }
+            CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            } => Ok(json!({"if": [
+                condition.to_json_logic()?,
+                then.to_json_logic()?,
+                otherwise.to_json_logic()?
+            ]})),
+            // `all` fails for an empty array, unlike a `ForEach`, so the array is checked to be
+            // empty, or missing, first.
+            CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            } => {
+                let segments: Vec<_> = array_path.split('.').map(String::from).collect();
+                let var = var(array_path, PathSyntax::Dot, &segments)?;
+
+                Ok(json!({"or": [
+                    {"!": [var]},
+                    {"all": [var, predicate.to_json_logic()?]}
+                ]}))
+            }
+            // Like an `ExistsIn`, `some` fails for an empty or missing array.
+            CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            } => {
+                let segments: Vec<_> = array_path.split('.').map(String::from).collect();
+                let var = var(array_path, PathSyntax::Dot, &segments)?;
+
+                Ok(json!({"some": [var, predicate.to_json_logic()?]}))
+            }
+        }
+    }
+}
+
+impl Predicate {
+    /// Parses a JSON Logic rule using the operations `to_json_logic` produces: `==`, `!=`, `===`,
+    /// `!==`, `>`, `<`, `>=`, `<=`, `in`, `%` compared to `0`, `and`, `or`, `!`, `if` with a
+    /// single condition and `all` and `some` of a `var`, with each comparison being between a
+    /// `var` and a literal value, and `true` and `false`.
+    ///
+    /// The result is a predicate which evaluates the same as the rule in JSON Logic, except that
+    /// comparisons are strict rather than loose, see `to_json_logic`, and that `all` passes for an
+    /// empty array.
+    pub fn from_json_logic(logic: &JsonValue) -> Result<Predicate, ConversionError> {
+        let (operator, args) = match logic {
+            JsonValue::Bool(true) => return Ok(CompoundPredicate::All(Vec::new()).into()),
+            JsonValue::Bool(false) => return Ok(CompoundPredicate::Any(Vec::new()).into()),
+            JsonValue::Object(logic) if logic.len() == 1 => {
+                logic.iter().next().expect("the object has an entry")
+            }
+            other => {
+                return Err(ConversionError::Malformed(format!(
+                    "expected an operation, got {other}"
+                )));
+            }
+        };
+
+        // A single argument doesn't have to be wrapped in an array.
+        let args = match args {
+            JsonValue::Array(args) => args.as_slice(),
+            arg => std::slice::from_ref(arg),
+        };
+
+        let children = || {
+            args.iter()
+                .map(Predicate::from_json_logic)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        match operator.as_str() {
+            // An empty `and` is falsy in JSON Logic, unlike an empty `all`.
+            "and" if args.is_empty() => Err(ConversionError::Malformed(
+                "and takes at least 1 argument".to_owned(),
+            )),
+            "and" => Ok(CompoundPredicate::All(children()?).into()),
+            // `to_json_logic` writes a `forEach` predicate as an `all` if the array isn't empty.
+            "or" => match args {
+                [empty, all] if for_each_path(empty, all)?.is_some() => {
+                    Predicate::from_json_logic(all)
+                }
+                _ => Ok(CompoundPredicate::Any(children()?).into()),
+            },
+            "all" | "some" => {
+                let [array, predicate] = args else {
+                    return Err(wrong_arg_count(operator, 2));
+                };
+
+                let Some(array_path) = var_path(array)? else {
+                    return Err(ConversionError::Malformed(format!(
+                        "{operator} must check the elements of a var"
+                    )));
+                };
+
+                let predicate = Box::new(Predicate::from_json_logic(predicate)?);
+
+                Ok(match operator.as_str() {
+                    "all" => CompoundPredicate::ForEach {
+                        array_path,
+                        predicate,
+                    },
+                    _ => CompoundPredicate::ExistsIn {
+                        array_path,
+                        predicate,
+                    },
+                }
+                .into())
+            }
+            "!" => {
+                let [arg] = args else {
+                    return Err(wrong_arg_count(operator, 1));
+                };
+
+                // `to_json_logic` writes a `none` predicate as the negation of an `or`.
+                match Predicate::from_json_logic(arg)? {
+                    Predicate::Compound(CompoundPredicate::Any(predicates))
+                        if arg.get("or").is_some() =>
+                    {
+                        Ok(CompoundPredicate::None(predicates).into())
+                    }
+                    predicate => Ok(CompoundPredicate::Not(Box::new(predicate)).into()),
+                }
+            }
+            "if" => {
+                let [condition, then, otherwise] = args else {
+                    return Err(wrong_arg_count(operator, 3));
+                };
+
+                Ok(CompoundPredicate::If {
+                    condition: Box::new(Predicate::from_json_logic(condition)?),
+                    then: Box::new(Predicate::from_json_logic(then)?),
+                    otherwise: Box::new(Predicate::from_json_logic(otherwise)?),
+                }
+                .into())
+            }
+            "==" | "===" | "!=" | "!==" | ">" | "<" | ">=" | "<=" => {
+                let [lhs, rhs] = args else {
+                    return Err(wrong_arg_count(operator, 2));
+                };
+
+                from_json_logic_comparison(operator, lhs, rhs).map(Predicate::from)
+            }
+            "in" => {
+                let [lhs, rhs] = args else {
+                    return Err(wrong_arg_count(operator, 2));
+                };
+
+                if let Some(path) = var_path(rhs)? {
+                    return Ok(
+                        raw(path, Operator::Contains, literal(lhs, Operator::Contains)?).into(),
+                    );
+                }
+
+                match (var_path(lhs)?, rhs) {
+                    (Some(path), JsonValue::Array(values)) => Ok(CompoundPredicate::OneOf {
+                        path,
+                        values: values
+                            .iter()
+                            .map(|value| literal(value, Operator::Equal))
+                            .collect::<Result<_, _>>()?,
+                    }
+                    .into()),
+                    _ => Err(ConversionError::Malformed(
+                        "in must check for a value in a var or for a var in an array".to_owned(),
+                    )),
+                }
+            }
+            other => Err(ConversionError::UnknownOperator(other.to_owned())),
+        }
+    }
+}
+
+/// The path of the array if `empty` checks that the array `all` checks the elements of is empty.
+fn for_each_path(empty: &JsonValue, all: &JsonValue) -> Result<Option<String>, ConversionError> {
+    let (Some([negated]), Some([array, _])) = (
+        empty
+            .get("!")
+            .and_then(JsonValue::as_array)
+            .map(Vec::as_slice),
+        all.get("all")
+            .and_then(JsonValue::as_array)
+            .map(Vec::as_slice),
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(var_path(negated)?.filter(|_| negated == array))
+}
+
+fn wrong_arg_count(operator: &str, expected: usize) -> ConversionError {
+    ConversionError::Malformed(format!("{operator} takes {expected} argument(s)"))
+}
+
+/// The path read by `logic` if it's a `var`. A `var` with a default value or reading the whole
+/// input has no equivalent path.
+fn var_path(logic: &JsonValue) -> Result<Option<String>, ConversionError> {
+    let Some(path) = logic
+        .as_object()
+        .filter(|logic| logic.len() == 1)
+        .and_then(|logic| logic.get("var"))
+    else {
+        return Ok(None);
+    };
+
+    let path = match path {
+        JsonValue::Array(args) if args.len() == 1 => &args[0],
+        path => path,
+    };
+
+    match path {
+        // An empty path reads the whole input, e.g. each element checked by `all`.
+        JsonValue::String(path) => Ok(Some(path.clone())),
+        other => Err(ConversionError::Malformed(format!(
+            "var must read a path without a default, got {other}"
+        ))),
+    }
+}
+
+/// `value` if it's a literal `operator` can be converted with, see `RawPredicate::to_json_logic`.
+fn literal(value: &JsonValue, operator: Operator) -> Result<JsonValue, ConversionError> {
+    match value {
+        JsonValue::Array(_) | JsonValue::Object(_) => Err(ConversionError::UnsupportedValue {
+            kind: json_type(value),
+            operator,
+        }),
+        value => Ok(value.clone()),
+    }
+}
+
+fn raw(path: String, operator: Operator, value: JsonValue) -> RawPredicate {
+    RawPredicate {
+        path,
+        path_syntax: PathSyntax::Dot,
+        operator,
+        value,
+        quantifier: Quantifier::Any,
+    }
+}
+
+/// A comparison of a `var` with a literal value in either order, or of the remainder of dividing a
+/// `var` with `0`.
+fn from_json_logic_comparison(
+    operator: &str,
+    lhs: &JsonValue,
+    rhs: &JsonValue,
+) -> Result<RawPredicate, ConversionError> {
+    let operator_for = |operator: &str| match operator {
+        "==" | "===" => Operator::Equal,
+        "!=" | "!==" => Operator::NotEqual,
+        ">" => Operator::Greater,
+        "<" => Operator::Less,
+        ">=" => Operator::GreaterEqual,
+        _ => Operator::LessEqual,
+    };
+
+    // The operator with its arguments swapped, e.g. `5 < x` is `x > 5`.
+    let flipped = |operator: &str| match operator {
+        ">" => Operator::Less,
+        "<" => Operator::Greater,
+        ">=" => Operator::LessEqual,
+        "<=" => Operator::GreaterEqual,
+        other => operator_for(other),
+    };
+
+    let (path, converted, value) = if let Some(path) = var_path(lhs)? {
+        (path, operator_for(operator), rhs)
+    } else if let Some(path) = var_path(rhs)? {
+        (path, flipped(operator), lhs)
+    } else if let Some(remainder) = lhs.get("%").filter(|_| rhs == &json!(0)) {
+        let operator = match operator_for(operator) {
+            Operator::Equal => Operator::Divisible,
+            Operator::NotEqual => Operator::NotDivisible,
+            _ => {
+                return Err(ConversionError::Malformed(
+                    "a remainder can only be compared to 0 for equality".to_owned(),
+                ));
+            }
+        };
+
+        let Some([dividend, divisor]) = remainder.as_array().map(Vec::as_slice) else {
+            return Err(wrong_arg_count("%", 2));
+        };
+        let Some(path) = var_path(dividend)? else {
+            return Err(ConversionError::Malformed(
+                "the dividend of % must be a var".to_owned(),
+            ));
+        };
+
+        return Ok(raw(path, operator, literal(divisor, operator)?));
+    } else {
+        return Err(ConversionError::Malformed(format!(
+            "{operator} must compare a var with a value"
+        )));
+    };
+
+    if value.is_object() {
+        return Err(ConversionError::Malformed(format!(
+            "{operator} must compare a var with a value"
+        )));
+    }
+
+    Ok(match (converted, value) {
+        (Operator::Equal, JsonValue::Null) => raw(path, Operator::IsNull, JsonValue::Null),
+        (Operator::NotEqual, JsonValue::Null) => raw(path, Operator::IsNotNull, JsonValue::Null),
+        (operator, value) => raw(path, operator, literal(value, operator)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all, any, exists_in, for_each, if_then_else, none, not, one_of, predicate, rule};
+
+    /// A minimal JSON Logic interpreter following <https://jsonlogic.com/operations.html>, for the
+    /// operations `to_json_logic` produces.
+    mod reference {
+        use super::JsonValue;
+
+        pub fn apply(logic: &JsonValue, data: &JsonValue) -> JsonValue {
+            let Some((operator, args)) = logic.as_object().and_then(|logic| logic.iter().next())
+            else {
+                return logic.clone();
+            };
+
+            let args = match args {
+                JsonValue::Array(args) => args.clone(),
+                arg => vec![arg.clone()],
+            };
+
+            if operator == "var" {
+                return var(args[0].as_str().unwrap_or_default(), data);
+            }
+
+            // The predicate is applied to each element rather than evaluated up front.
+            if operator == "all" || operator == "some" {
+                let elements = match apply(&args[0], data) {
+                    JsonValue::Array(elements) => elements,
+                    _ => Vec::new(),
+                };
+                let mut results = elements
+                    .iter()
+                    .map(|element| truthy(&apply(&args[1], element)));
+
+                return JsonValue::Bool(match operator.as_str() {
+                    "all" => !elements.is_empty() && results.all(|result| result),
+                    _ => results.any(|result| result),
+                });
+            }
+
+            let values: Vec<_> = args.iter().map(|arg| apply(arg, data)).collect();
+
+            match operator.as_str() {
+                "==" => JsonValue::Bool(loose_eq(&values[0], &values[1])),
+                "!=" => JsonValue::Bool(!loose_eq(&values[0], &values[1])),
+                "<" | ">" | "<=" | ">=" => {
+                    let ordering = match (&values[0], &values[1]) {
+                        (JsonValue::String(lhs), JsonValue::String(rhs)) => lhs.partial_cmp(rhs),
+                        (lhs, rhs) => to_number(lhs).partial_cmp(&to_number(rhs)),
+                    };
+
+                    JsonValue::Bool(ordering.is_some_and(|ordering| match operator.as_str() {
+                        "<" => ordering.is_lt(),
+                        ">" => ordering.is_gt(),
+                        "<=" => ordering.is_le(),
+                        _ => ordering.is_ge(),
+                    }))
+                }
+                "%" => serde_json::json!(to_number(&values[0]) % to_number(&values[1])),
+                "in" => JsonValue::Bool(match (&values[0], &values[1]) {
+                    (JsonValue::String(lhs), JsonValue::String(rhs)) => rhs.contains(lhs.as_str()),
+                    (lhs, JsonValue::Array(rhs)) => rhs.iter().any(|value| strict_eq(lhs, value)),
+                    _ => false,
+                }),
+                "!" => JsonValue::Bool(!truthy(&values[0])),
+                "if" if truthy(&values[0]) => values[1].clone(),
+                "if" => values[2].clone(),
+                "and" => values
+                    .iter()
+                    .find(|value| !truthy(value))
+                    .or(values.last())
+                    .cloned()
+                    .unwrap_or_default(),
+                "or" => values
+                    .iter()
+                    .find(|value| truthy(value))
+                    .or(values.last())
+                    .cloned()
+                    .unwrap_or_default(),
+                other => panic!("unsupported operation {other}"),
+            }
+        }
+
+        fn var(path: &str, data: &JsonValue) -> JsonValue {
+            if path.is_empty() {
+                return data.clone();
+            }
+
+            path.split('.')
+                .try_fold(data, |data, field| match data {
+                    JsonValue::Object(fields) => fields.get(field),
+                    JsonValue::Array(values) => {
+                        field.parse().ok().and_then(|i: usize| values.get(i))
+                    }
+                    _ => None,
+                })
+                .cloned()
+                .unwrap_or_default()
+        }
+
+        pub fn truthy(value: &JsonValue) -> bool {
+            match value {
+                JsonValue::Null => false,
+                JsonValue::Bool(value) => *value,
+                JsonValue::Number(value) => value.as_f64().is_some_and(|value| value != 0.0),
+                JsonValue::String(value) => !value.is_empty(),
+                JsonValue::Array(values) => !values.is_empty(),
+                JsonValue::Object(_) => true,
+            }
+        }
+
+        /// JavaScript's `ToNumber`.
+        fn to_number(value: &JsonValue) -> f64 {
+            match value {
+                JsonValue::Null => 0.0,
+                JsonValue::Bool(value) => f64::from(u8::from(*value)),
+                JsonValue::Number(value) => value.as_f64().unwrap_or(f64::NAN),
+                JsonValue::String(value) if value.trim().is_empty() => 0.0,
+                JsonValue::String(value) => value.trim().parse().unwrap_or(f64::NAN),
+                JsonValue::Array(_) | JsonValue::Object(_) => f64::NAN,
+            }
+        }
+
+        /// JavaScript's `===`, which compares arrays and objects by reference.
+        fn strict_eq(lhs: &JsonValue, rhs: &JsonValue) -> bool {
+            match (lhs, rhs) {
+                (JsonValue::Number(_), JsonValue::Number(_)) => to_number(lhs) == to_number(rhs),
+                (JsonValue::Array(_) | JsonValue::Object(_), _) => false,
+                _ => lhs == rhs,
+            }
+        }
+
+        /// JavaScript's `==`, without converting arrays and objects to primitives.
+        fn loose_eq(lhs: &JsonValue, rhs: &JsonValue) -> bool {
+            match (lhs, rhs) {
+                (JsonValue::Null, rhs) => rhs.is_null(),
+                (_, JsonValue::Null) => false,
+                (JsonValue::Array(_) | JsonValue::Object(_), _)
+                | (_, JsonValue::Array(_) | JsonValue::Object(_)) => false,
+                (JsonValue::String(lhs), JsonValue::String(rhs)) => lhs == rhs,
+                _ => to_number(lhs) == to_number(rhs),
+            }
+        }
+    }
+
+    macro_rules! assert_json_logic {
+        ($predicate:expr, $($expected:tt)*) => {
+            assert_eq!(
+                Predicate::from($predicate).to_json_logic(),
+                Ok(json!($($expected)*))
+            )
+        };
+    }
+
+    #[test]
+    fn test_raw() {
+        assert_json_logic!(predicate!("foo" == 10), {"==": [{"var": "foo"}, 10]});
+        assert_json_logic!(predicate!("foo.bar" != "baz"), {"!=": [{"var": "foo.bar"}, "baz"]});
+        assert_json_logic!(predicate!("age" > 12), {">": [{"var": "age"}, 12]});
+        assert_json_logic!(predicate!("age" < 12), {"<": [{"var": "age"}, 12]});
+        assert_json_logic!(predicate!("age" >= 12.5), {">=": [{"var": "age"}, 12.5]});
+        assert_json_logic!(predicate!("age" <= 12), {"<=": [{"var": "age"}, 12]});
+        assert_json_logic!(predicate!("tags" contains "red"), {"in": ["red", {"var": "tags"}]});
+        assert_json_logic!(predicate!("email" is_null), {"==": [{"var": "email"}, null]});
+        assert_json_logic!(predicate!("email" is_not_null), {"!=": [{"var": "email"}, null]});
+        assert_json_logic!(
+            predicate!("count" divisible 5),
+            {"==": [{"%": [{"var": "count"}, 5]}, 0]}
+        );
+        assert_json_logic!(
+            predicate!("count" not_divisible 5),
+            {"!=": [{"%": [{"var": "count"}, 5]}, 0]}
+        );
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        let predicate = RawPredicate {
+            path: "/user/age".to_owned(),
+            path_syntax: PathSyntax::JsonPointer,
+            ..predicate!("unused" > 18)
+        };
+
+        assert_eq!(
+            predicate.to_json_logic(),
+            Ok(json!({">": [{"var": "user.age"}, 18]}))
+        );
+    }
+
+    #[test]
+    fn test_compound() {
+        assert_json_logic!(
+            all!(predicate!("fizz" == 3), predicate!("buzz" == 5)),
+            {"and": [{"==": [{"var": "fizz"}, 3]}, {"==": [{"var": "buzz"}, 5]}]}
+        );
+        assert_json_logic!(
+            any!(predicate!("color" == "red"), predicate!("color" == "blue")),
+            {"or": [{"==": [{"var": "color"}, "red"]}, {"==": [{"var": "color"}, "blue"]}]}
+        );
+        assert_json_logic!(
+            none!(predicate!("color" == "red"), predicate!("color" == "blue")),
+            {"!": [{"or": [{"==": [{"var": "color"}, "red"]}, {"==": [{"var": "color"}, "blue"]}]}]}
+        );
+        assert_json_logic!(not!(predicate!("foo" == 10)), {"!": [{"==": [{"var": "foo"}, 10]}]});
+        assert_json_logic!(
+            one_of!("color", "red", "blue"),
+            {"in": [{"var": "color"}, ["red", "blue"]]}
+        );
+
+        assert_json_logic!(
+            if_then_else!(
+                predicate!("country" == "US"),
+                predicate!("age" >= 21),
+                predicate!("age" >= 18)
+            ),
+            {"if": [
+                {"==": [{"var": "country"}, "US"]},
+                {">=": [{"var": "age"}, 21]},
+                {">=": [{"var": "age"}, 18]}
+            ]}
+        );
+
+        assert_json_logic!(
+            for_each!("items", predicate!("price" > 0)),
+            {"or": [
+                {"!": [{"var": "items"}]},
+                {"all": [{"var": "items"}, {">": [{"var": "price"}, 0]}]}
+            ]}
+        );
+        assert_json_logic!(
+            exists_in!("users", predicate!("role" == "admin")),
+            {"some": [{"var": "users"}, {"==": [{"var": "role"}, "admin"]}]}
+        );
+
+        assert_json_logic!(all!(), true);
+        assert_json_logic!(any!(), false);
+        assert_json_logic!(none!(), true);
+    }
+
+    #[test]
+    fn test_rule_aliases() {
+        let rule = Rule {
+            path_aliases: [("user".to_owned(), "request.user".to_owned())].into(),
+            ..rule!("id", "message", predicate!("user.age" >= 18))
+        };
+
+        assert_eq!(
+            rule.to_json_logic(),
+            Ok(json!({">=": [{"var": "request.user.age"}, 18]}))
+        );
+    }
+
+    #[test]
+    fn test_err() {
+        assert_eq!(
+            predicate!("name" starts_with "Jo").to_json_logic(),
+            Err(ConversionError::UnsupportedOperator(Operator::StartsWith))
+        );
+        assert_eq!(
+            all!(predicate!("foo" == 10), predicate!("name" ends_with "son")).to_json_logic(),
+            Err(ConversionError::UnsupportedOperator(Operator::EndsWith))
+        );
+        assert_eq!(
+            predicate!("foo" == [1, 2]).to_json_logic(),
+            Err(ConversionError::UnsupportedValue {
+                kind: "array",
+                operator: Operator::Equal
+            })
+        );
+        assert_eq!(
+            predicate!("items.*.price" > 10).to_json_logic(),
+            Err(ConversionError::UnsupportedPath("items.*.price".to_owned()))
+        );
+
+        let predicate = RawPredicate {
+            path: "/user/first.name".to_owned(),
+            path_syntax: PathSyntax::JsonPointer,
+            ..predicate!("unused" == "Jo")
+        };
+        assert_eq!(
+            predicate.to_json_logic(),
+            Err(ConversionError::UnsupportedPath(
+                "/user/first.name".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_matches_reference_interpreter() {
+        let predicates: Vec<Predicate> = vec![
+            predicate!("foo" == 10).into(),
+            predicate!("foo" != 10).into(),
+            predicate!("foo" > 10).into(),
+            predicate!("foo" <= 10.5).into(),
+            predicate!("foo" divisible 5).into(),
+            predicate!("foo" not_divisible 3).into(),
+            predicate!("foo" is_null).into(),
+            predicate!("name" is_not_null).into(),
+            predicate!("name" == "Jo").into(),
+            predicate!("name" contains "o").into(),
+            predicate!("tags" contains "red").into(),
+            predicate!("user.age" >= 18).into(),
+            one_of!("name", "Jo", "Jane").into(),
+            all!(
+                predicate!("user.age" >= 12),
+                any!(
+                    predicate!("user.height" > 5),
+                    all!(predicate!("user.height" == 5), predicate!("foo" >= 2))
+                )
+            )
+            .into(),
+            none!(
+                predicate!("name" == "Jo"),
+                predicate!("tags" contains "blue")
+            )
+            .into(),
+            not!(any!(predicate!("foo" < 0), predicate!("name" is_null))).into(),
+            if_then_else!(
+                predicate!("name" == "Jo"),
+                predicate!("foo" > 9),
+                predicate!("foo" < 0)
+            )
+            .into(),
+            for_each!("tags", predicate!("" != "red")).into(),
+            for_each!("items", predicate!("price" > 2)).into(),
+            exists_in!("tags", predicate!("" == "red")).into(),
+            exists_in!("items", predicate!("price" > 4)).into(),
+            all!().into(),
+            any!().into(),
+            none!().into(),
+        ];
+
+        let inputs = [
+            json!({}),
+            json!({"foo": 10, "name": "Jo", "tags": ["red"], "user": {"age": 18, "height": 5}}),
+            json!({"foo": 15, "name": "Jane", "tags": ["blue", "green"], "user": {"age": 11}}),
+            json!({"foo": -3, "name": "Bob", "tags": [], "user": {"age": 30, "height": 6}}),
+            json!({"items": [{"price": 3}, {"price": 5}], "tags": ["green"]}),
+            json!({"items": [{"price": 3}, {"price": 1}], "tags": null}),
+            json!({"foo": 9, "name": null, "tags": ["red", "blue"], "user": {"age": 12, "height": 5}}),
+            json!({"foo": 10.5, "user": {"age": 17.5, "height": 4.5}}),
+        ];
+
+        let mut compared = 0;
+
+        for predicate in &predicates {
+            let logic = predicate
+                .to_json_logic()
+                .expect("predicate should be convertible");
+
+            for input in &inputs {
+                // JSON Logic has no notion of a type error, so only inputs the predicate can be
+                // evaluated against are comparable.
+                let Ok(expected) = predicate.evaluate(input) else {
+                    continue;
+                };
+
+                let result = reference::apply(&logic, input);
+                assert_eq!(
+                    reference::truthy(&result),
+                    expected,
+                    "{logic} evaluated to {result} for {input}"
+                );
+
+                compared += 1;
+            }
+        }
+
+        assert!(compared > predicates.len() * 3);
+    }
+
+    #[test]
+    fn test_loose_equality() {
+        // Documented difference from evaluating the predicate, see `Predicate::to_json_logic`.
+        let predicate = predicate!("foo" == 10);
+        let input = json!({"foo": "10"});
+
+        assert_eq!(predicate.evaluate(&input), Ok(false));
+        assert_eq!(
+            reference::apply(&predicate.to_json_logic().expect("should convert"), &input),
+            json!(true)
+        );
+    }
+
+    macro_rules! assert_from_json_logic {
+        ($predicate:expr, $($logic:tt)*) => {
+            assert_eq!(
+                Predicate::from_json_logic(&json!($($logic)*)),
+                Ok(Predicate::from($predicate))
+            )
+        };
+    }
+
+    #[test]
+    fn test_from_json_logic() {
+        assert_from_json_logic!(predicate!("foo" == 10), {"==": [{"var": "foo"}, 10]});
+        assert_from_json_logic!(predicate!("foo" == 10), {"===": [10, {"var": ["foo"]}]});
+        assert_from_json_logic!(predicate!("foo.bar" != "baz"), {"!==": [{"var": "foo.bar"}, "baz"]});
+        assert_from_json_logic!(predicate!("age" > 12), {">": [{"var": "age"}, 12]});
+        assert_from_json_logic!(predicate!("age" > 12), {"<": [12, {"var": "age"}]});
+        assert_from_json_logic!(predicate!("age" <= 12), {">=": [12, {"var": "age"}]});
+        assert_from_json_logic!(predicate!("email" is_null), {"==": [{"var": "email"}, null]});
+        assert_from_json_logic!(predicate!("email" is_not_null), {"!=": [null, {"var": "email"}]});
+        assert_from_json_logic!(predicate!("tags" contains "red"), {"in": ["red", {"var": "tags"}]});
+        assert_from_json_logic!(
+            one_of!("color", "red", "blue"),
+            {"in": [{"var": "color"}, ["red", "blue"]]}
+        );
+        assert_from_json_logic!(
+            predicate!("count" divisible 5),
+            {"==": [{"%": [{"var": "count"}, 5]}, 0]}
+        );
+
+        assert_from_json_logic!(
+            all!(predicate!("fizz" == 3), any!(predicate!("buzz" == 5))),
+            {"and": [{"==": [{"var": "fizz"}, 3]}, {"or": [{"==": [{"var": "buzz"}, 5]}]}]}
+        );
+        assert_from_json_logic!(not!(predicate!("foo" == 10)), {"!": {"==": [{"var": "foo"}, 10]}});
+        assert_from_json_logic!(
+            none!(predicate!("foo" == 10)),
+            {"!": [{"or": [{"==": [{"var": "foo"}, 10]}]}]}
+        );
+        assert_from_json_logic!(not!(any!()), {"!": [false]});
+        assert_from_json_logic!(all!(), true);
+    }
+
+    #[test]
+    fn test_from_json_logic_err() {
+        let from = |logic: JsonValue| Predicate::from_json_logic(&logic);
+
+        assert_eq!(
+            from(json!({"cat": ["a", "b"]})),
+            Err(ConversionError::UnknownOperator("cat".to_owned()))
+        );
+        assert_eq!(
+            from(json!({"and": [{"==": [1, 1]}, {"substr": [{"var": "name"}, 1]}]})),
+            Err(ConversionError::Malformed(
+                "== must compare a var with a value".to_owned()
+            ))
+        );
+        assert_eq!(
+            from(json!({"and": [{"==": [{"var": "a"}, 1]}, {"substr": [{"var": "name"}, 1]}]})),
+            Err(ConversionError::UnknownOperator("substr".to_owned()))
+        );
+        assert_eq!(
+            from(json!({"==": [{"var": "foo"}, [1, 2]]})),
+            Err(ConversionError::UnsupportedValue {
+                kind: "array",
+                operator: Operator::Equal
+            })
+        );
+        assert!(matches!(
+            from(json!({"==": [{"var": "foo"}, {"var": "bar"}]})),
+            Err(ConversionError::Malformed(_))
+        ));
+        assert!(matches!(
+            from(json!({"==": [{"var": ["foo", 10]}, 10]})),
+            Err(ConversionError::Malformed(_))
+        ));
+        assert!(matches!(
+            from(json!({"<": [1, {"var": "foo"}, 3]})),
+            Err(ConversionError::Malformed(_))
+        ));
+        assert!(matches!(
+            from(json!({"and": []})),
+            Err(ConversionError::Malformed(_))
+        ));
+        assert!(matches!(
+            from(json!(10)),
+            Err(ConversionError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let predicates: Vec<Predicate> = vec![
+            predicate!("foo" == 10).into(),
+            predicate!("foo" != "bar").into(),
+            predicate!("foo.bar" > 10).into(),
+            predicate!("foo" <= 10.5).into(),
+            predicate!("foo" divisible 5).into(),
+            predicate!("foo" not_divisible 3).into(),
+            predicate!("foo" is_null).into(),
+            predicate!("foo" is_not_null).into(),
+            predicate!("tags" contains "red").into(),
+            one_of!("name", "Jo", "Jane").into(),
+            all!(
+                predicate!("user.age" >= 12),
+                any!(predicate!("user.height" > 5), not!(predicate!("foo" == 2))),
+                none!(predicate!("name" == "Jo"))
+            )
+            .into(),
+            if_then_else!(
+                predicate!("country" == "US"),
+                predicate!("age" >= 21),
+                any!(predicate!("age" >= 18), predicate!("guardian" is_not_null))
+            )
+            .into(),
+            for_each!("tags", predicate!("" contains "a")).into(),
+            for_each!(
+                "items",
+                all!(predicate!("price" > 0), predicate!("sku" is_not_null))
+            )
+            .into(),
+            exists_in!(
+                "users",
+                any!(predicate!("role" == "admin"), predicate!("owner" == true))
+            )
+            .into(),
+            all!().into(),
+            any!().into(),
+        ];
+
+        for predicate in predicates {
+            let logic = predicate
+                .to_json_logic()
+                .expect("predicate should be convertible");
+
+            assert_eq!(Predicate::from_json_logic(&logic), Ok(predicate), "{logic}");
+        }
+
+        // `all` alone doesn't pass for a missing array, but is still read as checking each element.
+        assert_eq!(
+            Predicate::from_json_logic(
+                &json!({"all": [{"var": "items"}, {">": [{"var": "price"}, 0]}]})
+            ),
+            Ok(for_each!("items", predicate!("price" > 0)).into())
+        );
+
+        // An empty `none` is written as `true`, so reads back as an empty `all`.
+        let logic = Predicate::from(none!())
+            .to_json_logic()
+            .expect("predicate should be convertible");
+        assert_eq!(Predicate::from_json_logic(&logic), Ok(all!().into()));
+    }
+}
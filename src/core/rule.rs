@@ -1,87 +1,3149 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, Cow};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use thiserror::Error;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+use crate::core::eval::{EvaluationError, FieldPresence, WILDCARD};
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum RuleValidationError {
+    #[error("the id of a rule must not be empty")]
+    EmptyId,
+    #[error("the message of rule {0} must not be empty")]
+    EmptyMessage(String),
+    #[error("the path of every predicate of rule {0} must not be empty")]
+    EmptyPath(String),
+    #[error("every any, all, none and oneOf predicate of rule {0} must not be empty")]
+    EmptyCompound(String),
+    #[error("the weight of rule {0} must be a finite, non-negative number")]
+    InvalidWeight(String),
+    #[error("the input schema of rule {0} is not a valid JSON Schema: {1}")]
+    InvalidInputSchema(String, String),
+    #[error("rule {0} uses the {1} operator, which is not allowed")]
+    DisallowedOperator(String, Operator),
+    #[error("the rule has {} problems", .0.len())]
+    Multiple(Vec<RuleValidationError>),
+}
+
+impl RuleValidationError {
+    /// Describes each problem when there's more than one of them.
+    pub fn details(&self) -> Option<Vec<String>> {
+        match self {
+            RuleValidationError::Multiple(errors) => {
+                Some(errors.iter().map(ToString::to_string).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct Rule {
+    /// Also read from `name`, which older rule files used instead.
+    #[serde(alias = "name")]
     pub id: String,
     pub predicate: Predicate,
     pub message: String,
+    /// Ids of rules that must be evaluated before this one when requested together.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// The rule is skipped when evaluated before this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<DateTime<Utc>>,
+    /// The rule is skipped when evaluated at or after this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<DateTime<Utc>>,
+    /// The rule is skipped for inputs that don't satisfy this predicate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Predicate>,
+    /// How much the rule contributes to the weighted score of an evaluation.
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    #[schema(default = 1.0)]
+    pub weight: f64,
+    /// A coarse grained classification, e.g. `fraud`, allowing related rules to be fetched and
+    /// evaluated together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// The rule is skipped once it has been evaluated this many times, e.g. to cap how often an
+    /// expensive rule runs. Evaluations are counted by the repository, see
+    /// `RuleRepository::stats`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_evaluations: Option<u64>,
+    /// Path prefixes to rewrite before evaluating, e.g. `{"applicant": "data.applicant"}` lets
+    /// the predicate read `applicant.income` from `data.applicant.income`. See
+    /// `Rule::normalize_paths`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub path_aliases: HashMap<String, String>,
+    /// A JSON Schema the input has to satisfy before the rule is evaluated against it, see
+    /// `Rule::validate_input`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub input_schema: Option<serde_json::Value>,
+    /// Whether to evaluate the rule again when it fails to evaluate because of the input, e.g. a
+    /// field which is normally populated being `null`, see `Rule::evaluate_with_retries`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retry_on_error: bool,
+    /// How many times to retry when `retry_on_error` is set.
+    #[serde(
+        default = "default_max_retries",
+        skip_serializing_if = "is_default_max_retries"
+    )]
+    #[schema(default = 1)]
+    pub max_retries: u8,
+    /// Values merged into the input when retrying, filling in fields which are missing or `null`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(default))]
+    pub input_defaults: Option<serde_json::Value>,
+    /// The dot separated path whose value groups inputs evaluated together, e.g. `tenantId` to
+    /// check each tenant's records separately, see `evaluate_grouped`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<String>,
+    /// Who created the rule, see `reviewed_by`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Who approved the rule, `None` if it hasn't been reviewed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_by: Option<String>,
+    /// When the rule was created, set by the repository storing it and kept when the rule is
+    /// updated. Not part of the rule's definition, so ignored when comparing rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// When the rule was last created or updated, set by the repository storing it. Not part of
+    /// the rule's definition, so ignored when comparing rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Rules are equal when their definitions are, not just their ids. It's not `Eq`, as `weight` is
+/// a float, so use `ById` to put rules in a `HashSet` or as `HashMap` keys.
+impl PartialEq for Rule {
+    fn eq(&self, other: &Self) -> bool {
+        let Rule {
+            id,
+            predicate,
+            message,
+            depends_on,
+            active_from,
+            active_until,
+            condition,
+            weight,
+            category,
+            max_evaluations,
+            path_aliases,
+            input_schema,
+            retry_on_error,
+            max_retries,
+            input_defaults,
+            group_by,
+            author,
+            reviewed_by,
+            created_at: _,
+            updated_at: _,
+        } = self;
+
+        *id == other.id
+            && *predicate == other.predicate
+            && *message == other.message
+            && *depends_on == other.depends_on
+            && *active_from == other.active_from
+            && *active_until == other.active_until
+            && *condition == other.condition
+            && *weight == other.weight
+            && *category == other.category
+            && *max_evaluations == other.max_evaluations
+            && *path_aliases == other.path_aliases
+            && *input_schema == other.input_schema
+            && *retry_on_error == other.retry_on_error
+            && *max_retries == other.max_retries
+            && *input_defaults == other.input_defaults
+            && *group_by == other.group_by
+            && *author == other.author
+            && *reviewed_by == other.reviewed_by
+    }
+}
+
+/// Hashes only the id, which is consistent with `PartialEq` as equal rules have equal ids.
+impl Hash for Rule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// A rule compared and hashed by its id alone, e.g. to deduplicate rules in a `HashSet`
+/// regardless of their definitions. It can be looked up in sets and maps by the id.
+#[derive(Debug, Clone)]
+pub struct ById(pub Rule);
+
+impl PartialEq for ById {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for ById {}
+
+impl Hash for ById {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+    }
+}
+
+impl Borrow<str> for ById {
+    fn borrow(&self) -> &str {
+        &self.0.id
+    }
+}
+
+pub(crate) fn default_weight() -> f64 {
+    1.0
+}
+
+pub(crate) fn is_default_weight(weight: &f64) -> bool {
+    *weight == default_weight()
+}
+
+pub(crate) fn default_max_retries() -> u8 {
+    1
+}
+
+fn is_default_max_retries(max_retries: &u8) -> bool {
+    *max_retries == default_max_retries()
+}
+
+/// A rule's compiled `input_schema`. A schema that fails to compile rejects every input.
+#[derive(Debug, Clone)]
+pub struct InputValidator(Result<Arc<jsonschema::Validator>, String>);
+
+impl InputValidator {
+    /// Checks `input` against the schema, describing each way it doesn't satisfy it, prefixed by
+    /// the JSON Pointer to the offending value unless it's the whole input.
+    pub fn validate(&self, input: &serde_json::Value) -> Result<(), Vec<String>> {
+        let validator = self.0.as_ref().map_err(|err| vec![err.clone()])?;
+
+        let errors: Vec<String> = validator
+            .iter_errors(input)
+            .map(|err| match err.instance_path().as_str() {
+                "" => err.to_string(),
+                path => format!("{path}: {err}"),
+            })
+            .collect();
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+impl Rule {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn predicate(&self) -> &Predicate {
+        &self.predicate
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn depends_on(&self) -> &[String] {
+        &self.depends_on
+    }
+
+    /// The paths as written in the predicate, before any `path_aliases` are applied.
+    pub fn referenced_paths(&self) -> Vec<&str> {
+        self.predicate.referenced_paths()
+    }
+
+    /// Rewrites the paths of the predicate and condition, see `Predicate::normalize_paths`.
+    pub fn normalize_paths(mut self, aliases: &HashMap<String, String>) -> Rule {
+        self.predicate = self.predicate.normalize_paths(aliases);
+        self.condition = self
+            .condition
+            .map(|condition| condition.normalize_paths(aliases));
+
+        self
+    }
+
+    /// Whole paths of the predicate and condition replaced, see `Predicate::apply_input_mapping`.
+    pub fn apply_input_mapping(mut self, mapping: &HashMap<String, String>) -> Rule {
+        self.predicate = self.predicate.apply_input_mapping(mapping);
+        self.condition = self
+            .condition
+            .map(|condition| condition.apply_input_mapping(mapping));
+
+        self
+    }
+
+    /// The rule as it's evaluated, with its `path_aliases` applied. The aliases are cleared from
+    /// the result so they can't be applied twice.
+    pub fn resolve_aliases(&self) -> Cow<'_, Rule> {
+        if self.path_aliases.is_empty() {
+            return Cow::Borrowed(self);
+        }
+
+        let mut rule = self.clone();
+        let aliases = std::mem::take(&mut rule.path_aliases);
+
+        Cow::Owned(rule.normalize_paths(&aliases))
+    }
+
+    /// How many times the rule's predicate uses each operator, see `Predicate::count_by_operator`.
+    pub fn operator_histogram(&self) -> HashMap<Operator, usize> {
+        self.predicate.count_by_operator()
+    }
+
+    /// The unique operators used by the rule's predicate, see `Predicate::referenced_operators`.
+    pub fn applicable_operators(&self) -> HashSet<Operator> {
+        self.predicate.referenced_operators()
+    }
+
+    /// Whether every path in `referenced_paths` is declared by a JSON Schema (draft-07), as a
+    /// required or optional property through nested `properties`, or `items` for array indices.
+    /// Other keywords like `$ref` or `additionalProperties` aren't followed, so a path has to be
+    /// declared explicitly.
+    pub fn applies_to_schema(&self, schema: &serde_json::Value) -> bool {
+        self.resolve_aliases()
+            .predicate
+            .raw_predicates()
+            .into_iter()
+            .all(|raw| schema_declares_path(schema, &raw.path_segments()))
+    }
+
+    /// The unique constants in the predicate and condition, see `Predicate::extract_constants`.
+    pub fn extract_constants(&self) -> Vec<serde_json::Value> {
+        let constants = self
+            .condition
+            .iter()
+            .flat_map(Predicate::constants)
+            .chain(self.predicate.constants());
+
+        unique_constants(constants).into_iter().cloned().collect()
+    }
+
+    /// Checks that the predicate and condition only use operators in `allowed`, reporting the
+    /// first one that isn't.
+    pub fn validate_operators(
+        &self,
+        allowed: &HashSet<Operator>,
+    ) -> Result<(), RuleValidationError> {
+        let disallowed = self
+            .predicate
+            .raw_predicates()
+            .into_iter()
+            .chain(self.condition.iter().flat_map(Predicate::raw_predicates))
+            .find(|raw| !allowed.contains(&raw.operator));
+
+        match disallowed {
+            Some(raw) => Err(RuleValidationError::DisallowedOperator(
+                self.id.clone(),
+                raw.operator,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks every constraint on the rule, reporting all of the problems found.
+    pub fn validate(&self) -> Result<(), RuleValidationError> {
+        let mut errors = Vec::new();
+
+        if self.id.trim().is_empty() {
+            errors.push(RuleValidationError::EmptyId);
+        }
+
+        if self.message.trim().is_empty() {
+            errors.push(RuleValidationError::EmptyMessage(self.id.clone()));
+        }
+
+        let has_empty_path = self
+            .predicate
+            .raw_predicates()
+            .into_iter()
+            .chain(self.condition.iter().flat_map(Predicate::raw_predicates))
+            .any(|raw| raw.path.trim().is_empty());
+
+        if has_empty_path {
+            errors.push(RuleValidationError::EmptyPath(self.id.clone()));
+        }
+
+        let has_empty_compound = std::iter::once(&self.predicate)
+            .chain(&self.condition)
+            .any(Predicate::has_empty_compound);
+
+        if has_empty_compound {
+            errors.push(RuleValidationError::EmptyCompound(self.id.clone()));
+        }
+
+        if !(self.weight.is_finite() && self.weight >= 0.0) {
+            errors.push(RuleValidationError::InvalidWeight(self.id.clone()));
+        }
+
+        if let Some(Err(err)) = self.input_schema.as_ref().map(jsonschema::validator_for) {
+            errors.push(RuleValidationError::InvalidInputSchema(
+                self.id.clone(),
+                err.to_string(),
+            ));
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(RuleValidationError::Multiple(errors)),
+        }
+    }
+
+    /// Checks `input` against the rule's `input_schema`, see `InputValidator::validate`. Rules
+    /// without a schema accept any input.
+    pub fn validate_input(&self, input: &serde_json::Value) -> Result<(), Vec<String>> {
+        self.input_validator()
+            .map_or(Ok(()), |validator| validator.validate(input))
+    }
+
+    /// Compiles the rule's `input_schema` so it can be checked against many inputs, or `None` if
+    /// the rule has no schema.
+    pub fn input_validator(&self) -> Option<InputValidator> {
+        self.input_schema.as_ref().map(|schema| {
+            InputValidator(
+                jsonschema::validator_for(schema)
+                    .map(Arc::new)
+                    .map_err(|err| format!("invalid input schema: {err}")),
+            )
+        })
+    }
+
+    pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+        self.active_from.is_none_or(|from| from <= at)
+            && self.active_until.is_none_or(|until| at < until)
+    }
+
+    /// Whether the rule was created at or after `from` and before `to`, either of which may be left
+    /// open. A rule without a `created_at` is only within an unbounded range.
+    pub fn was_created_within(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> bool {
+        match self.created_at {
+            Some(created_at) => {
+                from.is_none_or(|from| from <= created_at) && to.is_none_or(|to| created_at < to)
+            }
+            None => from.is_none() && to.is_none(),
+        }
+    }
+
+    /// Compound predicates are written as single key maps (e.g. `all: [...]`) rather than YAML
+    /// tags so the output has the same shape as the JSON representation and can be parsed back.
+    pub fn to_yaml(&self) -> Result<String, serde_yaml::Error> {
+        let mut serializer = serde_yaml::Serializer::new(Vec::new());
+        serde_yaml::with::singleton_map_recursive::serialize(self, &mut serializer)?;
+
+        Ok(String::from_utf8_lossy(&serializer.into_inner()?).into_owned())
+    }
+
+    pub fn from_yaml(s: &str) -> Result<Rule, serde_yaml::Error> {
+        serde_yaml::from_str(s)
+    }
+
+    /// Renders the predicate tree in the style of the `tree` command, e.g.
+    ///
+    /// ```text
+    /// all
+    /// ├── age >= 18
+    /// └── any
+    ///     ├── status == "active"
+    ///     └── role == "admin"
+    /// ```
+    pub fn to_predicate_diagram(&self) -> String {
+        fn draw(predicate: &Predicate, prefix: &str, lines: &mut Vec<String>) {
+            let children = predicate.children();
+
+            for (i, child) in children.iter().enumerate() {
+                let (branch, indent) = match i + 1 == children.len() {
+                    true => ("└── ", "    "),
+                    false => ("├── ", "│   "),
+                };
+
+                lines.push(format!("{prefix}{branch}{}", child.label()));
+                draw(child, &format!("{prefix}{indent}"), lines);
+            }
+        }
+
+        let mut lines = vec![self.predicate.label()];
+        draw(&self.predicate, "", &mut lines);
+
+        lines.join("\n")
+    }
+
+    /// Checks that every path in the rule can be read from `input` and has a type compatible with
+    /// its operator, without evaluating any of the operators. Returns a description of each issue.
+    pub fn dry_run(&self, input: &serde_json::Value) -> Vec<String> {
+        self.resolve_aliases()
+            .predicate
+            .raw_predicates()
+            .into_iter()
+            .filter_map(|raw| {
+                raw.read(input, FieldPresence::Lenient)
+                    .and_then(|values| values.into_iter().try_for_each(|data| raw.type_check(data)))
+                    .err()
+            })
+            .map(|err| format!("{}: {err}", self.id))
+            .collect()
+    }
+
+    /// The paths of the rule which are missing from `input`, as opposed to reading as `null`, see
+    /// `FieldPresence::Strict`. Paths are read according to their syntax, so JSON pointers and
+    /// wildcards are supported, and are reported with any `path_aliases` applied.
+    pub fn missing_paths(&self, input: &serde_json::Value) -> Vec<String> {
+        let mut paths = Vec::new();
+
+        for raw in self.resolve_aliases().predicate.raw_predicates() {
+            let is_missing = matches!(
+                raw.read(input, FieldPresence::Strict),
+                Err(EvaluationError::MissingField { .. })
+            );
+
+            if is_missing && !paths.contains(&raw.path) {
+                paths.push(raw.path.clone());
+            }
+        }
+
+        paths
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(untagged, deny_unknown_fields, rename_all = "camelCase")]
+pub enum Predicate {
+    Raw(RawPredicate),
+    Compound(CompoundPredicate),
+}
+
+impl Predicate {
+    /// All raw predicates in the tree, in the order they appear, including the `Equal` predicates
+    /// a `OneOf` is shorthand for. The raw predicates of a `ForEach` or `ExistsIn` read every
+    /// element of its array, e.g. `price > 0` of a `ForEach` over `items` is `items.*.price > 0`
+    /// for all elements, and of an `ExistsIn` for any element.
+    pub fn raw_predicates(&self) -> Vec<Cow<'_, RawPredicate>> {
+        fn collect<'a>(predicate: &'a Predicate, raw_predicates: &mut Vec<Cow<'a, RawPredicate>>) {
+            match predicate {
+                Predicate::Raw(raw) => raw_predicates.push(Cow::Borrowed(raw)),
+                Predicate::Compound(CompoundPredicate::OneOf { path, values }) => raw_predicates
+                    .extend(CompoundPredicate::equalities(path, values).map(Cow::Owned)),
+                Predicate::Compound(CompoundPredicate::Not(predicate)) => {
+                    collect(predicate, raw_predicates)
+                }
+                Predicate::Compound(
+                    CompoundPredicate::Any(predicates)
+                    | CompoundPredicate::All(predicates)
+                    | CompoundPredicate::None(predicates),
+                ) => {
+                    for predicate in predicates {
+                        collect(predicate, raw_predicates);
+                    }
+                }
+                Predicate::Compound(CompoundPredicate::If {
+                    condition,
+                    then,
+                    otherwise,
+                }) => {
+                    for predicate in [condition, then, otherwise] {
+                        collect(predicate, raw_predicates);
+                    }
+                }
+                Predicate::Compound(
+                    compound @ (CompoundPredicate::ForEach {
+                        array_path,
+                        predicate,
+                    }
+                    | CompoundPredicate::ExistsIn {
+                        array_path,
+                        predicate,
+                    }),
+                ) => {
+                    let quantifier = match compound {
+                        CompoundPredicate::ForEach { .. } => Quantifier::All,
+                        _ => Quantifier::Any,
+                    };
+
+                    raw_predicates.extend(predicate.raw_predicates().into_iter().map(|raw| {
+                        Cow::Owned(RawPredicate {
+                            path: element_path(array_path, &raw.path_segments()),
+                            path_syntax: PathSyntax::Dot,
+                            quantifier,
+                            ..raw.into_owned()
+                        })
+                    }))
+                }
+            }
+        }
+
+        let mut raw_predicates = Vec::new();
+        collect(self, &mut raw_predicates);
+
+        raw_predicates
+    }
+
+    /// Whether an `Any`, `All`, `None` or `OneOf` anywhere in the predicate has no children. These are
+    /// allowed to be deserialized and evaluated, vacuously passing for `All` and `None` and
+    /// failing for `Any`, but are rejected by `Rule::validate` as they're rarely intended.
+    fn has_empty_compound(&self) -> bool {
+        match self {
+            Predicate::Raw(_) => false,
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => {
+                predicate.has_empty_compound()
+            }
+            Predicate::Compound(CompoundPredicate::OneOf { values, .. }) => values.is_empty(),
+            _ => {
+                let children = self.children();
+                children.is_empty() || children.into_iter().any(Predicate::has_empty_compound)
+            }
+        }
+    }
+
+    /// Direct sub-predicates of a compound predicate. Raw predicates and `OneOf` have none, an
+    /// `If` has its condition, then and otherwise branches in that order and a `ForEach` or
+    /// `ExistsIn` the predicate applied to each element.
+    pub fn children(&self) -> Vec<&Predicate> {
+        match self {
+            Predicate::Raw(_) | Predicate::Compound(CompoundPredicate::OneOf { .. }) => Vec::new(),
+            Predicate::Compound(
+                CompoundPredicate::Not(predicate)
+                | CompoundPredicate::ForEach { predicate, .. }
+                | CompoundPredicate::ExistsIn { predicate, .. },
+            ) => vec![predicate],
+            Predicate::Compound(
+                CompoundPredicate::Any(predicates)
+                | CompoundPredicate::All(predicates)
+                | CompoundPredicate::None(predicates),
+            ) => predicates.iter().collect(),
+            Predicate::Compound(CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            }) => vec![condition, then, otherwise],
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Predicate::Raw(raw) if raw.operator.is_unary() => {
+                format!("{} {}", raw.path, raw.operator.symbol())
+            }
+            Predicate::Raw(raw) => {
+                format!("{} {} {}", raw.path, raw.operator.symbol(), raw.value)
+            }
+            Predicate::Compound(CompoundPredicate::Not(_)) => "not".to_owned(),
+            Predicate::Compound(CompoundPredicate::Any(_)) => "any".to_owned(),
+            Predicate::Compound(CompoundPredicate::All(_)) => "all".to_owned(),
+            Predicate::Compound(CompoundPredicate::None(_)) => "none".to_owned(),
+            Predicate::Compound(CompoundPredicate::OneOf { path, values }) => {
+                format!("{path} one of {}", serde_json::Value::from(values.clone()))
+            }
+            Predicate::Compound(CompoundPredicate::If { .. }) => "if".to_owned(),
+            Predicate::Compound(CompoundPredicate::ForEach { array_path, .. }) => {
+                format!("for each {array_path}")
+            }
+            Predicate::Compound(CompoundPredicate::ExistsIn { array_path, .. }) => {
+                format!("exists in {array_path}")
+            }
+        }
+    }
+
+    /// The unique paths read by the predicate, in the order they first appear. Only the array of a
+    /// `ForEach` or `ExistsIn` is included, as the paths of its predicate are read from each
+    /// element.
+    pub fn referenced_paths(&self) -> Vec<&str> {
+        fn collect<'a>(predicate: &'a Predicate, paths: &mut Vec<&'a str>) {
+            let path = match predicate {
+                Predicate::Raw(raw) => &raw.path,
+                Predicate::Compound(
+                    CompoundPredicate::OneOf { path, .. }
+                    | CompoundPredicate::ForEach {
+                        array_path: path, ..
+                    }
+                    | CompoundPredicate::ExistsIn {
+                        array_path: path, ..
+                    },
+                ) => path,
+                _ => {
+                    for child in predicate.children() {
+                        collect(child, paths);
+                    }
+
+                    return;
+                }
+            };
+
+            if !paths.contains(&path.as_str()) {
+                paths.push(path);
+            }
+        }
+
+        let mut paths = Vec::new();
+        collect(self, &mut paths);
+
+        paths
+    }
+
+    /// Replaces a prefix of each raw predicate's path with the path it's an alias for, e.g. with
+    /// `{"applicant": "data.applicant"}` the path `applicant.income` becomes
+    /// `data.applicant.income`. Prefixes are matched on whole segments, `app` isn't a prefix of
+    /// `applicant.income`, and the longest matching alias wins. Paths without a matching alias are
+    /// left as they are.
+    ///
+    /// Aliases are written in the syntax of the paths they apply to, so JSON pointer paths need
+    /// JSON pointer aliases like `{"/applicant": "/data/applicant"}`.
+    pub fn normalize_paths(self, aliases: &HashMap<String, String>) -> Predicate {
+        self.map_paths(&|path, path_syntax| normalized_path(path, path_syntax, aliases))
+    }
+
+    /// Replaces each raw predicate's path which is a key of `mapping` with its value, e.g. with
+    /// `{"user.age": "customer.age"}` the path `user.age` becomes `customer.age`. Unlike
+    /// `normalize_paths` only whole paths are matched, so `user.age.years` is left as it is, as
+    /// are paths without a mapping.
+    pub fn apply_input_mapping(self, mapping: &HashMap<String, String>) -> Predicate {
+        self.map_paths(&|path, _| {
+            mapping
+                .get(path)
+                .cloned()
+                .unwrap_or_else(|| path.to_owned())
+        })
+    }
+
+    /// Replaces the path of each raw predicate with `map` of it and its syntax. Only the array of a
+    /// `ForEach` or `ExistsIn` is replaced, the paths of its predicate are relative to each
+    /// element.
+    fn map_paths(self, map: &impl Fn(&str, PathSyntax) -> String) -> Predicate {
+        let map_all = |predicates: Vec<Predicate>| {
+            predicates
+                .into_iter()
+                .map(|predicate| predicate.map_paths(map))
+                .collect()
+        };
+
+        match self {
+            Predicate::Raw(mut raw) => {
+                raw.path = map(&raw.path, raw.path_syntax);
+                raw.into()
+            }
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => {
+                CompoundPredicate::Not(Box::new(predicate.map_paths(map))).into()
+            }
+            Predicate::Compound(CompoundPredicate::Any(predicates)) => {
+                CompoundPredicate::Any(map_all(predicates)).into()
+            }
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                CompoundPredicate::All(map_all(predicates)).into()
+            }
+            Predicate::Compound(CompoundPredicate::None(predicates)) => {
+                CompoundPredicate::None(map_all(predicates)).into()
+            }
+            Predicate::Compound(CompoundPredicate::OneOf { path, values }) => {
+                CompoundPredicate::OneOf {
+                    path: map(&path, PathSyntax::Dot),
+                    values,
+                }
+                .into()
+            }
+            Predicate::Compound(CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            }) => CompoundPredicate::If {
+                condition: Box::new(condition.map_paths(map)),
+                then: Box::new(then.map_paths(map)),
+                otherwise: Box::new(otherwise.map_paths(map)),
+            }
+            .into(),
+            Predicate::Compound(CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            }) => CompoundPredicate::ForEach {
+                array_path: map(&array_path, PathSyntax::Dot),
+                predicate,
+            }
+            .into(),
+            Predicate::Compound(CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            }) => CompoundPredicate::ExistsIn {
+                array_path: map(&array_path, PathSyntax::Dot),
+                predicate,
+            }
+            .into(),
+        }
+    }
+
+    /// The unique operators used by the predicate's raw predicates.
+    pub fn referenced_operators(&self) -> HashSet<Operator> {
+        self.raw_predicates()
+            .into_iter()
+            .map(|raw| raw.operator)
+            .collect()
+    }
+
+    /// How many raw predicates use each operator, e.g. to judge how complex a rule is. A `OneOf`
+    /// counts as an `Equal` for each of its values. Operators which aren't used are left out.
+    pub fn count_by_operator(&self) -> HashMap<Operator, usize> {
+        let mut counts = HashMap::new();
+
+        for raw in self.raw_predicates() {
+            *counts.entry(raw.operator).or_default() += 1;
+        }
+
+        counts
+    }
+
+    /// The unique values compared against by the predicate's raw predicates, in the order they
+    /// first appear. Values are returned as written, so an array is a single constant, and the
+    /// value of a unary operator like `isNull` is ignored.
+    pub fn extract_constants(&self) -> Vec<serde_json::Value> {
+        unique_constants(self.constants())
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// The constants which are strings, see `extract_constants`.
+    pub fn extract_string_constants(&self) -> Vec<&str> {
+        unique_constants(self.constants())
+            .into_iter()
+            .filter_map(serde_json::Value::as_str)
+            .collect()
+    }
+
+    /// The constants which are numbers, see `extract_constants`.
+    pub fn extract_numeric_constants(&self) -> Vec<f64> {
+        unique_constants(self.constants())
+            .into_iter()
+            .filter_map(serde_json::Value::as_f64)
+            .collect()
+    }
+
+    /// Every value compared against, in the order they appear and including duplicates.
+    fn constants(&self) -> Vec<&serde_json::Value> {
+        match self {
+            Predicate::Raw(raw) if raw.operator.is_unary() => Vec::new(),
+            Predicate::Raw(raw) => vec![&raw.value],
+            Predicate::Compound(CompoundPredicate::OneOf { values, .. }) => values.iter().collect(),
+            _ => self
+                .children()
+                .into_iter()
+                .flat_map(Predicate::constants)
+                .collect(),
+        }
+    }
+}
+
+fn schema_declares_path(schema: &serde_json::Value, segments: &[String]) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return true;
+    };
+
+    let property = schema
+        .get("properties")
+        .and_then(|properties| properties.get(segment));
+
+    // A wildcard reads every element, so has to be declared for all of them.
+    let item = match segment.as_str() {
+        WILDCARD => schema.get("items").filter(|items| !items.is_array()),
+        segment => segment
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| match schema.get("items")? {
+                serde_json::Value::Array(items) => items.get(index),
+                items => Some(items),
+            }),
+    };
+
+    property
+        .into_iter()
+        .chain(item)
+        .any(|schema| schema_declares_path(schema, rest))
+}
+
+fn unique_constants<'a>(
+    values: impl IntoIterator<Item = &'a serde_json::Value>,
+) -> Vec<&'a serde_json::Value> {
+    let mut constants: Vec<&serde_json::Value> = Vec::new();
+
+    for value in values {
+        if !constants.contains(&value) {
+            constants.push(value);
+        }
+    }
+
+    constants
+}
+
+/// `path`, written in `path_syntax`, with the longest alias matching a prefix of it replaced.
+fn normalized_path(
+    path: &str,
+    path_syntax: PathSyntax,
+    aliases: &HashMap<String, String>,
+) -> String {
+    let separator = match path_syntax {
+        PathSyntax::Dot => '.',
+        PathSyntax::JsonPointer => '/',
+    };
+
+    aliases
+        .iter()
+        .filter_map(|(alias, target)| {
+            let rest = path.strip_prefix(alias.as_str())?;
+            (rest.is_empty() || rest.starts_with(separator)).then_some((alias, target, rest))
+        })
+        .max_by_key(|(alias, _, _)| alias.len())
+        .map_or_else(
+            || path.to_owned(),
+            |(_, target, rest)| format!("{target}{rest}"),
+        )
+}
+
+/// The dot separated path reading the value at `segments` of every element of the array at
+/// `array_path`.
+fn element_path(array_path: &str, segments: &[String]) -> String {
+    std::iter::once(array_path)
+        .chain(std::iter::once(WILDCARD))
+        .chain(
+            segments
+                .iter()
+                .map(String::as_str)
+                .filter(|segment| !segment.is_empty()),
+        )
+        .collect::<Vec<_>>()
+        .join(".")
 }
 
-impl Rule {
-    pub fn id(&self) -> &str {
-        &self.id
+/// `data` nested in objects at the dot separated `path`.
+fn nest(path: &str, data: serde_json::Value) -> serde_json::Value {
+    path.split('.')
+        .rev()
+        .fold(data, |data, field| serde_json::json!({ field: data }))
+}
+
+/// A predicate in conjunctive normal form as a list of clauses which must all pass, each clause
+/// being a list of literals (raw predicates or their negation) of which at least one must pass.
+/// A `ForEach` or `ExistsIn` is treated like a raw predicate, as it can't be rewritten in terms of
+/// the others.
+type Clauses = Vec<Vec<Predicate>>;
+
+impl Predicate {
+    /// Rewrites the predicate into conjunctive normal form: an `All` of `Any`s whose children are
+    /// raw predicates or a `Not` of one. `None` is rewritten using `Not`, negations are pushed
+    /// down to raw predicates using De Morgan's laws and `Any` is distributed over `All`. Single
+    /// element `All`s and `Any`s are left out.
+    ///
+    /// The result can be exponentially larger than the original predicate.
+    pub fn to_cnf(self) -> Predicate {
+        let mut clauses = self.cnf_clauses(false);
+
+        let mut into_clause = |mut literals: Vec<Predicate>| match literals.len() {
+            1 => literals.remove(0),
+            _ => CompoundPredicate::Any(literals).into(),
+        };
+
+        match clauses.len() {
+            1 => into_clause(clauses.remove(0)),
+            _ => CompoundPredicate::All(clauses.into_iter().map(&mut into_clause).collect()).into(),
+        }
+    }
+
+    /// Whether the predicate is in the form produced by `to_cnf`, which also includes a single
+    /// clause or literal on its own.
+    pub fn is_cnf(&self) -> bool {
+        fn is_literal(predicate: &Predicate) -> bool {
+            fn is_atom(predicate: &Predicate) -> bool {
+                matches!(
+                    predicate,
+                    Predicate::Raw(_)
+                        | Predicate::Compound(
+                            CompoundPredicate::ForEach { .. } | CompoundPredicate::ExistsIn { .. }
+                        )
+                )
+            }
+
+            match predicate {
+                Predicate::Compound(CompoundPredicate::Not(predicate)) => is_atom(predicate),
+                predicate => is_atom(predicate),
+            }
+        }
+
+        fn is_clause(predicate: &Predicate) -> bool {
+            match predicate {
+                Predicate::Compound(CompoundPredicate::Any(predicates)) => {
+                    predicates.iter().all(is_literal)
+                }
+                predicate => is_literal(predicate),
+            }
+        }
+
+        match self {
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                predicates.iter().all(is_clause)
+            }
+            predicate => is_clause(predicate),
+        }
+    }
+
+    /// The clauses of the predicate, or of its negation if `negate` is set.
+    fn cnf_clauses(self, negate: bool) -> Clauses {
+        /// Clauses for `All` of the predicates.
+        fn conjunction(predicates: Vec<Predicate>, negate: bool) -> Clauses {
+            predicates
+                .into_iter()
+                .flat_map(|predicate| predicate.cnf_clauses(negate))
+                .collect()
+        }
+
+        /// Clauses for `Any` of the predicates, distributing the `Any` over each predicate's
+        /// clauses.
+        fn disjunction(predicates: Vec<Predicate>, negate: bool) -> Clauses {
+            predicates
+                .into_iter()
+                .fold(vec![Vec::new()], |clauses, predicate| {
+                    let other = predicate.cnf_clauses(negate);
+
+                    clauses
+                        .iter()
+                        .flat_map(|clause| {
+                            other.iter().map(move |literals| {
+                                clause.iter().chain(literals).cloned().collect()
+                            })
+                        })
+                        .collect()
+                })
+        }
+
+        match (self, negate) {
+            (Predicate::Raw(raw), false) => vec![vec![raw.into()]],
+            (Predicate::Raw(raw), true) => {
+                vec![vec![CompoundPredicate::Not(Box::new(raw.into())).into()]]
+            }
+            (Predicate::Compound(CompoundPredicate::Not(predicate)), negate) => {
+                predicate.cnf_clauses(!negate)
+            }
+            (Predicate::Compound(CompoundPredicate::All(predicates)), false)
+            | (Predicate::Compound(CompoundPredicate::Any(predicates)), true) => {
+                conjunction(predicates, negate)
+            }
+            (Predicate::Compound(CompoundPredicate::Any(predicates)), false)
+            | (Predicate::Compound(CompoundPredicate::All(predicates)), true) => {
+                disjunction(predicates, negate)
+            }
+            (Predicate::Compound(CompoundPredicate::None(predicates)), false) => {
+                conjunction(predicates, true)
+            }
+            (Predicate::Compound(CompoundPredicate::None(predicates)), true) => {
+                disjunction(predicates, false)
+            }
+            (Predicate::Compound(one_of @ CompoundPredicate::OneOf { .. }), negate) => {
+                Predicate::from(one_of.expand_one_of().into_owned()).cnf_clauses(negate)
+            }
+            (Predicate::Compound(if_ @ CompoundPredicate::If { .. }), negate) => {
+                Predicate::from(if_.expand_if().into_owned()).cnf_clauses(negate)
+            }
+            (
+                atom @ Predicate::Compound(
+                    CompoundPredicate::ForEach { .. } | CompoundPredicate::ExistsIn { .. },
+                ),
+                false,
+            ) => vec![vec![atom]],
+            (
+                atom @ Predicate::Compound(
+                    CompoundPredicate::ForEach { .. } | CompoundPredicate::ExistsIn { .. },
+                ),
+                true,
+            ) => vec![vec![CompoundPredicate::Not(Box::new(atom)).into()]],
+        }
+    }
+}
+
+impl Predicate {
+    /// Whether the predicate passes for every input it can be evaluated against, judged only by
+    /// its structure: an empty `All` or `None`, and compounds made up of those. Raw predicates are
+    /// never tautologies, as reading their path can fail.
+    pub fn is_tautology(&self) -> bool {
+        match self {
+            Predicate::Raw(_) => false,
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => predicate.is_contradiction(),
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                predicates.iter().all(Predicate::is_tautology)
+            }
+            Predicate::Compound(CompoundPredicate::Any(predicates)) => {
+                predicates.iter().any(Predicate::is_tautology)
+            }
+            Predicate::Compound(CompoundPredicate::None(predicates)) => {
+                predicates.iter().all(Predicate::is_contradiction)
+            }
+            Predicate::Compound(CompoundPredicate::OneOf { .. }) => false,
+            Predicate::Compound(CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            }) => {
+                (then.is_tautology() && otherwise.is_tautology())
+                    || (condition.is_tautology() && then.is_tautology())
+                    || (condition.is_contradiction() && otherwise.is_tautology())
+            }
+            Predicate::Compound(CompoundPredicate::ForEach { predicate, .. }) => {
+                predicate.is_tautology()
+            }
+            // An empty array fails whatever the predicate.
+            Predicate::Compound(CompoundPredicate::ExistsIn { .. }) => false,
+        }
+    }
+
+    /// Whether the predicate fails for every input it can be evaluated against, see
+    /// `is_tautology`.
+    pub fn is_contradiction(&self) -> bool {
+        match self {
+            Predicate::Raw(_) => false,
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => predicate.is_tautology(),
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                predicates.iter().any(Predicate::is_contradiction)
+            }
+            Predicate::Compound(CompoundPredicate::Any(predicates)) => {
+                predicates.iter().all(Predicate::is_contradiction)
+            }
+            Predicate::Compound(CompoundPredicate::None(predicates)) => {
+                predicates.iter().any(Predicate::is_tautology)
+            }
+            Predicate::Compound(CompoundPredicate::OneOf { .. }) => false,
+            Predicate::Compound(CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            }) => {
+                (then.is_contradiction() && otherwise.is_contradiction())
+                    || (condition.is_tautology() && then.is_contradiction())
+                    || (condition.is_contradiction() && otherwise.is_contradiction())
+            }
+            // An empty array passes whatever the predicate.
+            Predicate::Compound(CompoundPredicate::ForEach { .. }) => false,
+            Predicate::Compound(CompoundPredicate::ExistsIn { predicate, .. }) => {
+                predicate.is_contradiction()
+            }
+        }
+    }
+
+    /// Removes children which can't affect the result: tautologies from an `All` and
+    /// contradictions from an `Any` or `None`. A predicate which is a tautology or contradiction
+    /// as a whole becomes an empty `All` or `Any` respectively, so e.g. a `None` with a tautology
+    /// as one of its children becomes an empty `Any`.
+    ///
+    /// The result evaluates the same for every input the original can be evaluated against. It can
+    /// evaluate without error where the original doesn't, as children which would have failed to
+    /// evaluate may be removed. Empty compounds are rejected by `Rule::validate`, so the result is
+    /// meant to be evaluated rather than stored.
+    pub fn prune_dead_branches(self) -> Predicate {
+        if self.is_tautology() {
+            return CompoundPredicate::All(Vec::new()).into();
+        }
+
+        if self.is_contradiction() {
+            return CompoundPredicate::Any(Vec::new()).into();
+        }
+
+        let prune_all = |predicates: Vec<Predicate>, is_dead: fn(&Predicate) -> bool| {
+            predicates
+                .into_iter()
+                .map(Predicate::prune_dead_branches)
+                .filter(|predicate| !is_dead(predicate))
+                .collect()
+        };
+
+        match self {
+            Predicate::Compound(CompoundPredicate::Not(predicate)) => {
+                CompoundPredicate::Not(Box::new(predicate.prune_dead_branches())).into()
+            }
+            Predicate::Compound(CompoundPredicate::All(predicates)) => {
+                CompoundPredicate::All(prune_all(predicates, Predicate::is_tautology)).into()
+            }
+            Predicate::Compound(CompoundPredicate::Any(predicates)) => {
+                CompoundPredicate::Any(prune_all(predicates, Predicate::is_contradiction)).into()
+            }
+            Predicate::Compound(CompoundPredicate::None(predicates)) => {
+                CompoundPredicate::None(prune_all(predicates, Predicate::is_contradiction)).into()
+            }
+            Predicate::Compound(CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            }) => {
+                if condition.is_tautology() {
+                    return then.prune_dead_branches();
+                }
+
+                if condition.is_contradiction() {
+                    return otherwise.prune_dead_branches();
+                }
+
+                CompoundPredicate::If {
+                    condition: Box::new(condition.prune_dead_branches()),
+                    then: Box::new(then.prune_dead_branches()),
+                    otherwise: Box::new(otherwise.prune_dead_branches()),
+                }
+                .into()
+            }
+            Predicate::Compound(CompoundPredicate::ForEach {
+                array_path,
+                predicate,
+            }) => CompoundPredicate::ForEach {
+                array_path,
+                predicate: Box::new(predicate.prune_dead_branches()),
+            }
+            .into(),
+            Predicate::Compound(CompoundPredicate::ExistsIn {
+                array_path,
+                predicate,
+            }) => CompoundPredicate::ExistsIn {
+                array_path,
+                predicate: Box::new(predicate.prune_dead_branches()),
+            }
+            .into(),
+            predicate => predicate,
+        }
+    }
+
+    /// Whether `prune_dead_branches` would leave the predicate as it is.
+    pub fn is_optimized(&self) -> bool {
+        self.clone().prune_dead_branches() == *self
+    }
+}
+
+impl Rule {
+    /// See `Predicate::unapply`.
+    pub fn unapply(&self, output: bool) -> Option<serde_json::Value> {
+        self.predicate.unapply(output)
+    }
+
+    /// See `Predicate::examples`. The examples are for the predicate with the rule's
+    /// `path_aliases` applied, so they evaluate to `output` with `Rule::evaluate`.
+    pub fn examples(&self, output: bool, limit: usize) -> Vec<serde_json::Value> {
+        self.resolve_aliases().predicate.examples(output, limit)
+    }
+}
+
+impl Predicate {
+    /// Best effort search for an input which evaluates to `output`. `All` and `Any` combine the
+    /// inputs of their children, which fails if they need different values at the same path.
+    /// Returns `None` if no input is found, which doesn't mean one doesn't exist.
+    pub fn unapply(&self, output: bool) -> Option<serde_json::Value> {
+        self.unapply_unchecked(output)
+            .filter(|input| self.evaluate(input) == Ok(output))
+    }
+
+    /// An input the predicate passes for, see `unapply`.
+    pub fn satisfying_example(&self) -> Option<serde_json::Value> {
+        self.unapply(true)
+    }
+
+    /// An input the predicate fails for, see `unapply`.
+    pub fn unsatisfying_example(&self) -> Option<serde_json::Value> {
+        self.unapply(false)
+    }
+
+    /// Up to `limit` distinct inputs which evaluate to `output`, starting with the one found by
+    /// `unapply`. Where any one of several children decides the result, e.g. an `Any` passing,
+    /// each child contributes an input of its own. Raw predicates have at most one.
+    pub fn examples(&self, output: bool, limit: usize) -> Vec<serde_json::Value> {
+        let candidates = self
+            .unapply_unchecked(output)
+            .into_iter()
+            .chain(self.candidate_examples(output));
+
+        let mut examples = Vec::new();
+
+        for candidate in candidates {
+            if examples.len() == limit {
+                break;
+            }
+
+            if !examples.contains(&candidate) && self.evaluate(&candidate) == Ok(output) {
+                examples.push(candidate);
+            }
+        }
+
+        examples
+    }
+
+    /// Inputs which may evaluate to `output`, one for each child deciding the result on its own.
+    fn candidate_examples(&self, output: bool) -> Vec<serde_json::Value> {
+        let each = |predicates: &[Predicate], output: bool| {
+            predicates
+                .iter()
+                .flat_map(|predicate| predicate.candidate_examples(output))
+                .collect()
+        };
+
+        match (self, output) {
+            (Predicate::Compound(CompoundPredicate::Not(predicate)), output) => {
+                predicate.candidate_examples(!output)
+            }
+            (Predicate::Compound(CompoundPredicate::All(predicates)), false)
+            | (Predicate::Compound(CompoundPredicate::Any(predicates)), true) => {
+                each(predicates, output)
+            }
+            (Predicate::Compound(CompoundPredicate::None(predicates)), false) => {
+                each(predicates, true)
+            }
+            (Predicate::Compound(one_of @ CompoundPredicate::OneOf { .. }), output) => {
+                Predicate::from(one_of.expand_one_of().into_owned()).candidate_examples(output)
+            }
+            (Predicate::Compound(if_ @ CompoundPredicate::If { .. }), output) => {
+                Predicate::from(if_.expand_if().into_owned()).candidate_examples(output)
+            }
+            (predicate, output) => predicate.unapply_unchecked(output).into_iter().collect(),
+        }
+    }
+
+    /// Like `unapply`, without checking that the input actually evaluates to `output`.
+    fn unapply_unchecked(&self, output: bool) -> Option<serde_json::Value> {
+        /// An input for which every predicate evaluates to `output`.
+        fn every(predicates: &[Predicate], output: bool) -> Option<serde_json::Value> {
+            predicates
+                .iter()
+                .try_fold(serde_json::json!({}), |input, predicate| {
+                    merge(input, predicate.unapply_unchecked(output)?)
+                })
+        }
+
+        /// An input for which one of the predicates evaluates to `output`.
+        fn some(predicates: &[Predicate], output: bool) -> Option<serde_json::Value> {
+            predicates
+                .iter()
+                .find_map(|predicate| predicate.unapply(output))
+        }
+
+        match (self, output) {
+            (Predicate::Raw(raw), output) => raw.unapply_unchecked(output),
+            (Predicate::Compound(CompoundPredicate::Not(predicate)), output) => {
+                predicate.unapply_unchecked(!output)
+            }
+            (Predicate::Compound(CompoundPredicate::All(predicates)), true)
+            | (Predicate::Compound(CompoundPredicate::Any(predicates)), false) => {
+                every(predicates, output)
+            }
+            (Predicate::Compound(CompoundPredicate::None(predicates)), true) => {
+                every(predicates, false)
+            }
+            (Predicate::Compound(CompoundPredicate::All(predicates)), false)
+            | (Predicate::Compound(CompoundPredicate::Any(predicates)), true) => {
+                some(predicates, output)
+            }
+            (Predicate::Compound(CompoundPredicate::None(predicates)), false) => {
+                some(predicates, true)
+            }
+            (Predicate::Compound(one_of @ CompoundPredicate::OneOf { .. }), output) => {
+                Predicate::from(one_of.expand_one_of().into_owned()).unapply_unchecked(output)
+            }
+            (Predicate::Compound(if_ @ CompoundPredicate::If { .. }), output) => {
+                Predicate::from(if_.expand_if().into_owned()).unapply_unchecked(output)
+            }
+            // An empty array passes, and an array of a single failing element fails.
+            (Predicate::Compound(CompoundPredicate::ForEach { array_path, .. }), true) => {
+                Some(nest(array_path, serde_json::json!([])))
+            }
+            (
+                Predicate::Compound(CompoundPredicate::ForEach {
+                    array_path,
+                    predicate,
+                }),
+                false,
+            ) => Some(nest(
+                array_path,
+                serde_json::json!([predicate.unapply(false)?]),
+            )),
+            // An array of a single passing element passes, and an empty array fails.
+            (
+                Predicate::Compound(CompoundPredicate::ExistsIn {
+                    array_path,
+                    predicate,
+                }),
+                true,
+            ) => Some(nest(
+                array_path,
+                serde_json::json!([predicate.unapply(true)?]),
+            )),
+            (Predicate::Compound(CompoundPredicate::ExistsIn { array_path, .. }), false) => {
+                Some(nest(array_path, serde_json::json!([])))
+            }
+        }
+    }
+}
+
+impl RawPredicate {
+    /// Best effort search for an input which evaluates to `output`, e.g. `{"age": 19}` for
+    /// `age > 18`. Returns `None` for comparisons against values which aren't numbers.
+    pub fn unapply(&self, output: bool) -> Option<serde_json::Value> {
+        self.unapply_unchecked(output)
+            .filter(|input| self.evaluate(input) == Ok(output))
+    }
+
+    fn unapply_unchecked(&self, output: bool) -> Option<serde_json::Value> {
+        use serde_json::Value;
+
+        let offset = |delta: i64| -> Option<Value> {
+            match self.value.as_i64() {
+                Some(value) => value.checked_add(delta).map(Value::from),
+                None => serde_json::Number::from_f64(self.value.as_f64()? + delta as f64)
+                    .map(Value::Number),
+            }
+        };
+
+        // A value which is different from `self.value`.
+        let other = || match &self.value {
+            Value::Null => Value::Bool(true),
+            _ => Value::Null,
+        };
+
+        let data = match (self.operator, output) {
+            (Operator::Equal, true) | (Operator::NotEqual, false) => self.value.clone(),
+            (Operator::Equal, false) | (Operator::NotEqual, true) => other(),
+            (Operator::Greater, true) | (Operator::LessEqual, false) => offset(1)?,
+            (Operator::Less, true) | (Operator::GreaterEqual, false) => offset(-1)?,
+            (Operator::GreaterEqual, true)
+            | (Operator::LessEqual, true)
+            | (Operator::Greater, false)
+            | (Operator::Less, false) => offset(0)?,
+            (Operator::StartsWith | Operator::EndsWith, true) => self.value.clone(),
+            (Operator::StartsWith | Operator::EndsWith, false) => Value::String(String::new()),
+            (Operator::Divisible, true) | (Operator::NotDivisible, false) => {
+                self.value.as_i64().filter(|divisor| *divisor != 0)?.into()
+            }
+            // Every integer is divisible by 1 and -1, and only 0 by 0.
+            (Operator::Divisible, false) | (Operator::NotDivisible, true) => {
+                self.value
+                    .as_i64()
+                    .filter(|divisor| divisor.unsigned_abs() > 1)?;
+                Value::from(1)
+            }
+            (Operator::Contains, output) => match output {
+                true => Value::Array(vec![self.value.clone()]),
+                false => Value::Array(Vec::new()),
+            },
+            (Operator::IsNull, true) | (Operator::IsNotNull, false) => Value::Null,
+            (Operator::IsNull, false) | (Operator::IsNotNull, true) => Value::Bool(true),
+        };
+
+        Some(
+            self.path_segments()
+                .into_iter()
+                .rev()
+                .fold(data, |data, field| serde_json::json!({ field: data })),
+        )
+    }
+
+    /// The field names making up `path`, with JSON Pointer escapes undone.
+    pub(crate) fn path_segments(&self) -> Vec<String> {
+        match self.path_syntax {
+            PathSyntax::Dot => self.path.split('.').map(String::from).collect(),
+            PathSyntax::JsonPointer => self
+                .path
+                .split('/')
+                .skip(1)
+                .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                .collect(),
+        }
+    }
+}
+
+/// Deep merges two objects, returning `None` if they have different values at the same path.
+fn merge(lhs: serde_json::Value, rhs: serde_json::Value) -> Option<serde_json::Value> {
+    use serde_json::Value;
+
+    match (lhs, rhs) {
+        (Value::Object(mut lhs), Value::Object(rhs)) => {
+            for (key, value) in rhs {
+                let value = match lhs.remove(&key) {
+                    Some(existing) => merge(existing, value)?,
+                    None => value,
+                };
+
+                lhs.insert(key, value);
+            }
+
+            Some(Value::Object(lhs))
+        }
+        (lhs, rhs) => (lhs == rhs).then_some(lhs),
+    }
+}
+
+impl From<RawPredicate> for Predicate {
+    fn from(value: RawPredicate) -> Self {
+        Predicate::Raw(value)
+    }
+}
+
+impl From<CompoundPredicate> for Predicate {
+    fn from(value: CompoundPredicate) -> Self {
+        Predicate::Compound(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct RawPredicate {
+    pub path: String,
+    /// How `path` is written, dot separated field names by default.
+    #[serde(default, skip_serializing_if = "PathSyntax::is_dot")]
+    pub path_syntax: PathSyntax,
+    pub operator: Operator,
+    /// Ignored by unary operators like `isNull`, so may be left out for them.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    #[cfg_attr(
+        feature = "arbitrary",
+        arbitrary(with = crate::core::arbitrary::json_value)
+    )]
+    pub value: serde_json::Value,
+    /// Whether any or every value read has to satisfy the operator, when `path` has a `*`
+    /// wildcard and so reads many values.
+    #[serde(default, skip_serializing_if = "Quantifier::is_any")]
+    pub quantifier: Quantifier,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+pub enum Quantifier {
+    /// At least one of the values satisfies the operator. Nothing does if no values are read.
+    #[default]
+    Any,
+    /// Every value satisfies the operator, including when no values are read.
+    All,
+}
+
+impl Quantifier {
+    fn is_any(&self) -> bool {
+        *self == Quantifier::Any
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+pub enum PathSyntax {
+    /// Field names separated by dots, e.g. `applicant.income`. A `*` reads every element of an
+    /// array or value of an object, e.g. `items.*.price`.
+    #[default]
+    Dot,
+    /// A JSON Pointer as defined by RFC 6901, e.g. `/applicant/income` or `/tags/0`.
+    JsonPointer,
+}
+
+impl PathSyntax {
+    fn is_dot(&self) -> bool {
+        *self == PathSyntax::Dot
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+#[schema(no_recursion)]
+#[non_exhaustive]
+pub enum CompoundPredicate {
+    Not(Box<Predicate>),
+    Any(Vec<Predicate>),
+    All(Vec<Predicate>),
+    None(Vec<Predicate>),
+    /// Whether the value at the dot separated `path` equals one of `values`, shorthand for an
+    /// `Any` of `Equal` predicates, see `CompoundPredicate::equalities`.
+    OneOf {
+        path: String,
+        #[cfg_attr(
+            feature = "arbitrary",
+            arbitrary(with = crate::core::arbitrary::json_values)
+        )]
+        values: Vec<serde_json::Value>,
+    },
+    /// `then` if `condition` holds for the input, otherwise `otherwise`. Serialized as
+    /// `{"if": {"condition": ..., "then": ..., "else": ...}}`, as `else` is a Rust keyword.
+    If {
+        condition: Box<Predicate>,
+        then: Box<Predicate>,
+        #[serde(rename = "else")]
+        otherwise: Box<Predicate>,
+    },
+    /// Whether `predicate` holds for every element of the array at the dot separated
+    /// `array_path`, with the paths of `predicate` read from the element, so `""` reads the whole
+    /// element. Passes for an empty or missing array. Serialized as
+    /// `{"forEach": {"path": ..., "predicate": ...}}`.
+    ForEach {
+        #[serde(rename = "path")]
+        array_path: String,
+        predicate: Box<Predicate>,
+    },
+    /// Whether `predicate` holds for at least one element of the array at the dot separated
+    /// `array_path`, read like a `ForEach`. Fails for an empty or missing array. Serialized as
+    /// `{"existsIn": {"path": ..., "predicate": ...}}`.
+    ExistsIn {
+        #[serde(rename = "path")]
+        array_path: String,
+        predicate: Box<Predicate>,
+    },
+}
+
+impl CompoundPredicate {
+    /// The `Equal` predicates a `OneOf` of `path` and `values` is the `Any` of.
+    pub fn equalities<'a>(
+        path: &'a str,
+        values: &'a [serde_json::Value],
+    ) -> impl Iterator<Item = RawPredicate> + 'a {
+        values.iter().map(move |value| RawPredicate {
+            path: path.to_owned(),
+            path_syntax: PathSyntax::Dot,
+            operator: Operator::Equal,
+            value: value.clone(),
+            quantifier: Quantifier::Any,
+        })
+    }
+
+    /// The predicate with a `OneOf` replaced by the `Any` it's shorthand for.
+    pub(crate) fn expand_one_of(&self) -> Cow<'_, CompoundPredicate> {
+        match self {
+            CompoundPredicate::OneOf { path, values } => Cow::Owned(CompoundPredicate::Any(
+                CompoundPredicate::equalities(path, values)
+                    .map(Predicate::from)
+                    .collect(),
+            )),
+            predicate => Cow::Borrowed(predicate),
+        }
+    }
+
+    /// The predicate with an `If` replaced by the `Any` of its branches each guarded by the
+    /// condition, `any(all(condition, then), all(not(condition), otherwise))`. It passes for the
+    /// same inputs, but unlike the `If` may evaluate the branch not taken.
+    pub(crate) fn expand_if(&self) -> Cow<'_, CompoundPredicate> {
+        match self {
+            CompoundPredicate::If {
+                condition,
+                then,
+                otherwise,
+            } => Cow::Owned(CompoundPredicate::Any(vec![
+                CompoundPredicate::All(vec![(**condition).clone(), (**then).clone()]).into(),
+                CompoundPredicate::All(vec![
+                    CompoundPredicate::Not(condition.clone()).into(),
+                    (**otherwise).clone(),
+                ])
+                .into(),
+            ])),
+            predicate => Cow::Borrowed(predicate),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema, JsonSchema)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub enum Operator {
+    #[serde(alias = "==")]
+    Equal,
+    #[serde(alias = ">")]
+    Greater,
+    #[serde(alias = "<")]
+    Less,
+    #[serde(alias = ">=")]
+    GreaterEqual,
+    #[serde(alias = "<=")]
+    LessEqual,
+    #[serde(alias = "!=")]
+    NotEqual,
+    #[serde(alias = "in")]
+    Contains,
+    StartsWith,
+    EndsWith,
+    /// The input is an integer divisible by the value, i.e. leaves no remainder.
+    Divisible,
+    NotDivisible,
+    IsNull,
+    IsNotNull,
+}
+
+impl Operator {
+    /// Short form of the operator as written in a predicate, e.g. `>=`.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Equal => "==",
+            Operator::Greater => ">",
+            Operator::Less => "<",
+            Operator::GreaterEqual => ">=",
+            Operator::LessEqual => "<=",
+            Operator::NotEqual => "!=",
+            Operator::Contains => "contains",
+            Operator::StartsWith => "starts_with",
+            Operator::EndsWith => "ends_with",
+            Operator::Divisible => "divisible",
+            Operator::NotDivisible => "not_divisible",
+            Operator::IsNull => "is null",
+            Operator::IsNotNull => "is not null",
+        }
+    }
+
+    /// Whether the operator only looks at the input, ignoring the predicate's `value`.
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Operator::IsNull | Operator::IsNotNull)
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{all, any, exists_in, for_each, if_then_else, none, not, one_of, predicate, rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_by_id() {
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        let reworded = Rule {
+            message: "another message".to_owned(),
+            ..rule.clone()
+        };
+
+        assert_ne!(rule, reworded);
+        assert_eq!(ById(rule.clone()), ById(reworded.clone()));
+        assert_ne!(
+            ById(rule.clone()),
+            ById(rule!("rule-2", "message", predicate!("foo" == 10)))
+        );
+
+        let rules: HashSet<ById> = [ById(rule), ById(reworded)].into_iter().collect();
+        assert_eq!(rules.len(), 1);
+        assert!(rules.contains("rule-1"));
+        assert!(!rules.contains("rule-2"));
+    }
+
+    #[test]
+    fn test_validate_input() {
+        let rule = rule!("rule-1", "message", predicate!("age" >= 18));
+        assert_eq!(rule.validate_input(&json!("anything")), Ok(()));
+
+        let rule = Rule {
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {"age": {"type": "integer"}},
+                "required": ["age"]
+            })),
+            ..rule
+        };
+
+        assert_eq!(rule.validate_input(&json!({"age": 20})), Ok(()));
+        assert_eq!(
+            rule.validate_input(&json!({"age": "20"})),
+            Err(vec![r#"/age: "20" is not of type "integer""#.to_owned()])
+        );
+        assert_eq!(
+            rule.validate_input(&json!({})),
+            Err(vec![r#""age" is a required property"#.to_owned()])
+        );
+        assert_eq!(rule.validate(), Ok(()));
+
+        let rule = Rule {
+            input_schema: Some(json!({"type": "unknown"})),
+            ..rule
+        };
+
+        assert!(matches!(
+            rule.validate(),
+            Err(RuleValidationError::InvalidInputSchema(id, _)) if id == "rule-1"
+        ));
+        assert!(rule.validate_input(&json!({})).is_err());
+
+        let validator = rule
+            .input_validator()
+            .expect("rule should have a validator");
+        assert!(validator.validate(&json!({})).is_err());
+        assert!(
+            rule!("rule-1", "message", predicate!("age" >= 18))
+                .input_validator()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_is_active_at() {
+        let at = |s: &str| s.parse::<DateTime<Utc>>().expect("invalid timestamp");
+
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        assert!(rule.is_active_at(at("2024-01-01T00:00:00Z")));
+
+        let rule = Rule {
+            active_from: Some(at("2024-01-01T00:00:00Z")),
+            active_until: Some(at("2025-01-01T00:00:00Z")),
+            ..rule
+        };
+
+        assert!(!rule.is_active_at(at("2023-12-31T23:59:59Z")));
+        assert!(rule.is_active_at(at("2024-01-01T00:00:00Z")));
+        assert!(rule.is_active_at(at("2024-06-01T00:00:00Z")));
+        assert!(!rule.is_active_at(at("2025-01-01T00:00:00Z")));
+    }
+
+    #[test]
+    fn test_validate() {
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        assert_eq!(rule.validate(), Ok(()));
+
+        for weight in [0.0, 0.5, 100.0] {
+            assert_eq!(
+                Rule {
+                    weight,
+                    ..rule.clone()
+                }
+                .validate(),
+                Ok(())
+            );
+        }
+
+        for weight in [-0.5, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(
+                Rule {
+                    weight,
+                    ..rule.clone()
+                }
+                .validate(),
+                Err(RuleValidationError::InvalidWeight("rule-1".to_owned()))
+            );
+        }
+
+        assert_eq!(
+            rule!("rule-1", " ", any!(predicate!("foo" == 10))).validate(),
+            Err(RuleValidationError::EmptyMessage("rule-1".to_owned()))
+        );
+        assert_eq!(
+            Rule {
+                condition: Some(predicate!("" is_null).into()),
+                ..rule.clone()
+            }
+            .validate(),
+            Err(RuleValidationError::EmptyPath("rule-1".to_owned()))
+        );
+
+        let error = Rule {
+            weight: -1.0,
+            ..rule!("", "", all!(predicate!("" == 10)))
+        }
+        .validate()
+        .expect_err("rule should be invalid");
+
+        assert_eq!(
+            error,
+            RuleValidationError::Multiple(vec![
+                RuleValidationError::EmptyId,
+                RuleValidationError::EmptyMessage(String::new()),
+                RuleValidationError::EmptyPath(String::new()),
+                RuleValidationError::InvalidWeight(String::new()),
+            ])
+        );
+        assert_eq!(error.details().map(|details| details.len()), Some(4));
+        assert_eq!(RuleValidationError::EmptyId.details(), None);
+    }
+
+    #[test]
+    fn test_legacy_name() {
+        let legacy: Rule = serde_json::from_value(json!({
+            "name": "rule-1",
+            "message": "message",
+            "predicate": {"path": "foo", "operator": "==", "value": 10}
+        }))
+        .expect("rules with a name should deserialize");
+
+        assert_eq!(legacy, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        // Rules are always written back with an id.
+        assert_eq!(
+            serde_json::to_value(&legacy).expect("rule should serialize")["id"],
+            "rule-1"
+        );
+
+        assert!(
+            serde_json::from_value::<Rule>(json!({
+                "id": "rule-1",
+                "name": "rule-1",
+                "message": "message",
+                "predicate": {"path": "foo", "operator": "==", "value": 10}
+            }))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_count_by_operator() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            any!(
+                predicate!("age" >= 12),
+                not!(all!(
+                    predicate!("tags" contains "foo"),
+                    predicate!("age" >= 18)
+                )),
+                one_of!("status", "a", "b"),
+                predicate!("height" >= 150)
+            )
+        );
+
+        assert_eq!(
+            rule.operator_histogram(),
+            HashMap::from([
+                (Operator::GreaterEqual, 3),
+                (Operator::Contains, 1),
+                (Operator::Equal, 2)
+            ])
+        );
+        assert_eq!(
+            rule.operator_histogram(),
+            rule.predicate.count_by_operator()
+        );
+    }
+
+    #[test]
+    fn test_count_by_operator_every_operator() {
+        let predicate = Predicate::from(all!(
+            predicate!("a" == 1),
+            predicate!("a" > 1),
+            predicate!("a" < 1),
+            predicate!("a" >= 1),
+            predicate!("a" <= 1),
+            predicate!("a" != 1),
+            predicate!("a" contains 1),
+            predicate!("a" starts_with "x"),
+            predicate!("a" ends_with "x"),
+            predicate!("a" divisible 2),
+            predicate!("a" not_divisible 2),
+            predicate!("a" is_null),
+            predicate!("a" is_not_null)
+        ));
+
+        let counts = predicate.count_by_operator();
+
+        assert_eq!(counts.len(), 13);
+        assert!(counts.values().all(|count| *count == 1));
+        assert_eq!(
+            counts.keys().copied().collect::<HashSet<_>>(),
+            predicate.referenced_operators()
+        );
+    }
+
+    #[test]
+    fn test_referenced_operators() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            any!(
+                predicate!("age" >= 12),
+                not!(all!(
+                    predicate!("tags" contains "foo"),
+                    predicate!("age" >= 18)
+                ))
+            )
+        );
+
+        assert_eq!(
+            rule.predicate.referenced_operators(),
+            HashSet::from([Operator::GreaterEqual, Operator::Contains])
+        );
+        assert_eq!(
+            rule.applicable_operators(),
+            rule.predicate.referenced_operators()
+        );
+        assert_eq!(
+            rule.validate_operators(&HashSet::from([Operator::GreaterEqual, Operator::Contains])),
+            Ok(())
+        );
+        assert_eq!(
+            rule.validate_operators(&HashSet::from([Operator::GreaterEqual])),
+            Err(RuleValidationError::DisallowedOperator(
+                "rule-1".to_owned(),
+                Operator::Contains
+            ))
+        );
+        assert_eq!(
+            rule.validate_operators(&HashSet::new()),
+            Err(RuleValidationError::DisallowedOperator(
+                "rule-1".to_owned(),
+                Operator::GreaterEqual
+            ))
+        );
+    }
+
+    #[test]
+    fn test_validate_empty_compound() {
+        for json in [
+            json!({"any": []}),
+            json!({"all": []}),
+            json!({"none": []}),
+            json!({"oneOf": {"path": "foo", "values": []}}),
+            json!({"not": {"all": [{"path": "foo", "operator": "isNull"}, {"any": []}]}}),
+        ] {
+            let predicate: Predicate =
+                serde_json::from_value(json.clone()).expect("empty compounds should deserialize");
+
+            assert_eq!(
+                rule!("rule-1", "message", predicate).validate(),
+                Err(RuleValidationError::EmptyCompound("rule-1".to_owned())),
+                "{json}"
+            );
+        }
+
+        assert_eq!(
+            Rule {
+                condition: Some(none!().into()),
+                ..rule!("rule-1", "message", any!(predicate!("foo" == 10)))
+            }
+            .validate(),
+            Err(RuleValidationError::EmptyCompound("rule-1".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            any!(
+                predicate!("age" >= 12),
+                all!(predicate!("tags" contains "foo"), predicate!("a.b" == 10))
+            )
+        );
+
+        assert_eq!(
+            rule.dry_run(&json!({"age": 10, "tags": [], "a": {"b": 15}})),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            rule.dry_run(&json!({"age": "10", "tags": {}, "a": 10})),
+            [
+                "rule-1: cannot compare string with number using operator GreaterEqual",
+                "rule-1: cannot compare object with string using operator Contains, which checks for an element of an array or a substring of a string value",
+                "rule-1: cannot read field `b` of type number"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_paths() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            all!(
+                predicate!("a.b" == 10),
+                RawPredicate {
+                    path_syntax: PathSyntax::JsonPointer,
+                    ..predicate!("/tags/0" == "foo")
+                },
+                for_each!("items", predicate!("price" > 0))
+            )
+        );
+
+        assert_eq!(
+            rule.missing_paths(
+                &json!({"a": {"b": null}, "tags": ["foo"], "items": [{"price": 1}]})
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            rule.missing_paths(&json!({"a": {}, "tags": [], "items": [{"price": 1}, {}]})),
+            ["a.b", "/tags/0", "items.*.price"]
+        );
+    }
+
+    #[test]
+    fn test_to_predicate_diagram_flat() {
+        let rule = rule!("rule-1", "message", predicate!("age" >= 18));
+
+        assert_eq!(rule.to_predicate_diagram(), "age >= 18");
+    }
+
+    #[test]
+    fn test_to_predicate_diagram_nested() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            all!(predicate!("age" >= 18), predicate!("tags" contains "admin"))
+        );
+
+        assert_eq!(
+            rule.to_predicate_diagram(),
+            "all\n\
+             ├── age >= 18\n\
+             └── tags contains \"admin\""
+        );
+    }
+
+    #[test]
+    fn test_to_predicate_diagram_three_levels() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            all!(
+                any!(
+                    predicate!("status" == "active"),
+                    not!(predicate!("role" != "admin"))
+                ),
+                predicate!("age" >= 18)
+            )
+        );
+
+        assert_eq!(
+            rule.to_predicate_diagram(),
+            "all\n\
+             ├── any\n\
+             │   ├── status == \"active\"\n\
+             │   └── not\n\
+             │       └── role != \"admin\"\n\
+             └── age >= 18"
+        );
+    }
+
+    #[test]
+    fn test_referenced_paths() {
+        assert_eq!(
+            rule!("id", "message", predicate!("foo" == 10)).referenced_paths(),
+            ["foo"]
+        );
+
+        assert_eq!(
+            rule!(
+                "id",
+                "message",
+                all!(
+                    predicate!("age" >= 12),
+                    any!(
+                        predicate!("height.feet" > 5),
+                        all!(
+                            predicate!("height.feet" == 5),
+                            predicate!("height.inches" >= 2)
+                        )
+                    ),
+                    not!(none!(predicate!("age" < 100)))
+                )
+            )
+            .referenced_paths(),
+            ["age", "height.feet", "height.inches"]
+        );
+
+        assert_eq!(
+            rule!(
+                "id",
+                "message",
+                any!(one_of!("status", "a", "b"), predicate!("age" > 18))
+            )
+            .referenced_paths(),
+            ["status", "age"]
+        );
+    }
+
+    #[test]
+    fn test_for_each_raw_predicates() {
+        let rule = rule!(
+            "rule-1",
+            "message",
+            for_each!(
+                "items",
+                all!(predicate!("price" > 0), predicate!("" is_not_null))
+            )
+        );
+
+        let every = |raw: RawPredicate| {
+            Cow::Owned(RawPredicate {
+                quantifier: Quantifier::All,
+                ..raw
+            })
+        };
+        assert_eq!(
+            rule.predicate.raw_predicates(),
+            [
+                every(predicate!("items.*.price" > 0)),
+                every(predicate!("items.*" is_not_null))
+            ]
+        );
+        assert_eq!(rule.referenced_paths(), ["items"]);
+        assert_eq!(rule.validate(), Ok(()));
+
+        assert_eq!(
+            rule.dry_run(&json!({"items": [{"price": 1}, {"price": "1"}]})),
+            ["rule-1: cannot compare string with number using operator Greater"]
+        );
+
+        let schema = |items: serde_json::Value| json!({"type": "object", "properties": {"items": {"type": "array", "items": items}}});
+        assert!(rule.applies_to_schema(&schema(json!({"properties": {"price": {}}}))));
+        assert!(!rule.applies_to_schema(&schema(json!({"properties": {"cost": {}}}))));
+        assert!(!rule.applies_to_schema(&schema(json!([{"properties": {"price": {}}}]))));
+
+        assert_eq!(
+            rule.to_predicate_diagram(),
+            "for each items\n\
+             └── all\n    \
+                 ├── price > 0\n    \
+                 └──  is not null"
+        );
+    }
+
+    #[test]
+    fn test_for_each_paths() {
+        let predicate = Predicate::from(for_each!("items", predicate!("items" is_not_null)));
+        let mapping = HashMap::from([("items".to_owned(), "order.items".to_owned())]);
+
+        // The paths of the predicate are read from each element, so are left as they are.
+        assert_eq!(
+            predicate.clone().normalize_paths(&mapping),
+            for_each!("order.items", predicate!("items" is_not_null)).into()
+        );
+        assert_eq!(
+            predicate.apply_input_mapping(&mapping),
+            for_each!("order.items", predicate!("items" is_not_null)).into()
+        );
+    }
+
+    #[test]
+    fn test_serialize_for_each() {
+        let json = r#"{"forEach": {"path": "items", "predicate": {"path": "price", "operator": ">", "value": 0}}}"#;
+        let predicate = for_each!("items", predicate!("price" > 0));
+
+        let parsed: CompoundPredicate = serde_json::from_str(json).expect("unable to parse JSON");
+        assert_eq!(parsed, predicate);
+        assert_eq!(
+            serde_json::to_value(&predicate).expect("predicate should serialize"),
+            json!({"forEach": {"path": "items", "predicate": {"path": "price", "operator": "greater", "value": 0}}})
+        );
+    }
+
+    #[test]
+    fn test_exists_in() {
+        let predicate = Predicate::from(exists_in!(
+            "users",
+            all!(predicate!("role" == "admin"), predicate!("" is_not_null))
+        ));
+
+        let some = |raw: RawPredicate| {
+            Cow::Owned(RawPredicate {
+                quantifier: Quantifier::Any,
+                ..raw
+            })
+        };
+        assert_eq!(
+            predicate.raw_predicates(),
+            [
+                some(predicate!("users.*.role" == "admin")),
+                some(predicate!("users.*" is_not_null))
+            ]
+        );
+        assert_eq!(predicate.referenced_paths(), ["users"]);
+        assert_eq!(
+            rule!("rule-1", "message", predicate.clone()).to_predicate_diagram(),
+            "exists in users\n\
+             └── all\n    \
+                 ├── role == \"admin\"\n    \
+                 └──  is not null"
+        );
+
+        let mapping = HashMap::from([("users".to_owned(), "team.users".to_owned())]);
+        assert_eq!(
+            predicate.apply_input_mapping(&mapping),
+            exists_in!(
+                "team.users",
+                all!(predicate!("role" == "admin"), predicate!("" is_not_null))
+            )
+            .into()
+        );
+
+        let json = r#"{"existsIn": {"path": "users", "predicate": {"path": "role", "operator": "==", "value": "admin"}}}"#;
+        let parsed: CompoundPredicate = serde_json::from_str(json).expect("unable to parse JSON");
+        assert_eq!(parsed, exists_in!("users", predicate!("role" == "admin")));
+    }
+
+    #[test]
+    fn test_one_of_raw_predicates() {
+        let predicate = Predicate::from(all!(
+            one_of!("status", "a", "b", "a"),
+            predicate!("status" == "c")
+        ));
+
+        assert_eq!(
+            predicate.raw_predicates(),
+            [
+                predicate!("status" == "a"),
+                predicate!("status" == "b"),
+                predicate!("status" == "a"),
+                predicate!("status" == "c")
+            ]
+            .map(Cow::Owned)
+        );
+        assert_eq!(predicate.extract_string_constants(), ["a", "b", "c"]);
+        assert_eq!(
+            predicate.referenced_operators(),
+            HashSet::from([Operator::Equal])
+        );
+        assert_eq!(
+            rule!("rule-1", "message", predicate).to_predicate_diagram(),
+            "all\n├── status one of [\"a\",\"b\",\"a\"]\n└── status == \"c\""
+        );
+    }
+
+    #[test]
+    fn test_extract_constants_flat() {
+        let predicate = Predicate::from(predicate!("foo" == "bar"));
+        assert_eq!(predicate.extract_constants(), [json!("bar")]);
+        assert_eq!(predicate.extract_string_constants(), ["bar"]);
+        assert!(predicate.extract_numeric_constants().is_empty());
+
+        let predicate = Predicate::from(predicate!("foo" is_null));
+        assert!(predicate.extract_constants().is_empty());
+    }
+
+    #[test]
+    fn test_extract_constants_nested() {
+        let predicate = Predicate::from(all!(
+            predicate!("age" >= 12),
+            any!(
+                predicate!("status" == "active"),
+                not!(predicate!("role" contains ["admin", "owner"])),
+                predicate!("deleted" is_null)
+            ),
+            none!(predicate!("age" > 12.5), predicate!("status" == "banned"))
+        ));
+
+        assert_eq!(
+            predicate.extract_constants(),
+            [
+                json!(12),
+                json!("active"),
+                json!(["admin", "owner"]),
+                json!(12.5),
+                json!("banned")
+            ]
+        );
+        assert_eq!(predicate.extract_string_constants(), ["active", "banned"]);
+        assert_eq!(predicate.extract_numeric_constants(), [12.0, 12.5]);
+    }
+
+    #[test]
+    fn test_extract_constants_deduplicated() {
+        let predicate = Predicate::from(any!(
+            predicate!("foo" == 10),
+            predicate!("bar" == "baz"),
+            predicate!("qux" < 10),
+            predicate!("baz" == "baz")
+        ));
+
+        assert_eq!(predicate.extract_constants(), [json!(10), json!("baz")]);
+
+        let rule = Rule {
+            condition: Some(predicate!("env" == "prod").into()),
+            ..rule!(
+                "id",
+                "message",
+                any!(predicate!("foo" == 10), predicate!("env" == "prod"))
+            )
+        };
+        assert_eq!(rule.extract_constants(), [json!("prod"), json!(10)]);
+    }
+
+    #[test]
+    fn test_applies_to_schema() {
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-07/schema#",
+            "type": "object",
+            "required": ["age"],
+            "properties": {
+                "age": {"type": "integer"},
+                "height": {
+                    "type": "object",
+                    "properties": {
+                        "feet": {"type": "integer"},
+                        "inches": {"type": "integer"}
+                    }
+                },
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "pairs": {
+                    "type": "array",
+                    "items": [{"type": "string"}, {"type": "object", "properties": {"x": {}}}]
+                }
+            }
+        });
+
+        let compatible = rule!(
+            "id",
+            "message",
+            all!(
+                predicate!("age" >= 12),
+                any!(
+                    predicate!("height.feet" > 5),
+                    not!(predicate!("height.inches" is_null))
+                ),
+                predicate!("tags.0" == "vip"),
+                predicate!("pairs.1.x" == 1)
+            )
+        );
+        assert!(compatible.applies_to_schema(&schema));
+
+        for path in [
+            "weight",
+            "height.metres",
+            "age.years",
+            "tags.first",
+            "pairs.2",
+        ] {
+            let rule = rule!(
+                "id",
+                "message",
+                all!(
+                    predicate!("age" >= 12),
+                    RawPredicate {
+                        path: path.to_owned(),
+                        ..predicate!("placeholder" is_null)
+                    }
+                )
+            );
+            assert!(!rule.applies_to_schema(&schema), "{path}");
+        }
+
+        let pointer = rule!(
+            "id",
+            "message",
+            RawPredicate {
+                path_syntax: PathSyntax::JsonPointer,
+                ..predicate!("/height/feet" == 5)
+            }
+        );
+        assert!(pointer.applies_to_schema(&schema));
+        assert!(!pointer.applies_to_schema(&json!({"type": "object"})));
+    }
+
+    mod unapply {
+        use super::*;
+        use serde_json::json;
+
+        macro_rules! assert_unapply {
+            ($predicate:expr, $output:expr, $expected:expr) => {
+                let predicate = Predicate::from($predicate);
+                let input = predicate.unapply($output);
+
+                assert_eq!(input, $expected, "{predicate:?}");
+
+                if let Some(input) = input {
+                    assert_eq!(predicate.evaluate(&input), Ok($output), "{predicate:?}");
+                }
+            };
+        }
+
+        #[test]
+        fn test_raw() {
+            assert_unapply!(predicate!("a.b" == 5), true, Some(json!({"a": {"b": 5}})));
+            assert_unapply!(predicate!("a" == 5), false, Some(json!({"a": null})));
+            assert_unapply!(predicate!("a" != "x"), false, Some(json!({"a": "x"})));
+            assert_unapply!(predicate!("a" > 18), true, Some(json!({"a": 19})));
+            assert_unapply!(predicate!("a" > 18), false, Some(json!({"a": 18})));
+            assert_unapply!(predicate!("a" >= 1.5), false, Some(json!({"a": 0.5})));
+            assert_unapply!(predicate!("a" < 0), true, Some(json!({"a": -1})));
+            assert_unapply!(predicate!("a" <= 0), false, Some(json!({"a": 1})));
+            assert_unapply!(predicate!("a" contains 3), true, Some(json!({"a": [3]})));
+            assert_unapply!(predicate!("a" contains 3), false, Some(json!({"a": []})));
+            assert_unapply!(predicate!("a" is_null), false, Some(json!({"a": true})));
+            assert_unapply!(predicate!("a" is_not_null), false, Some(json!({"a": null})));
+
+            assert_unapply!(predicate!("a" divisible 3), true, Some(json!({"a": 3})));
+            assert_unapply!(predicate!("a" divisible 3), false, Some(json!({"a": 1})));
+            assert_unapply!(predicate!("a" not_divisible 3), true, Some(json!({"a": 1})));
+
+            assert_unapply!(predicate!("a" > "x"), true, None);
+            assert_unapply!(predicate!("a" divisible 0), true, None);
+            assert_unapply!(predicate!("a" divisible -1), false, None);
+
+            assert_unapply!(
+                RawPredicate {
+                    path: "/a/b~1c".to_owned(),
+                    path_syntax: PathSyntax::JsonPointer,
+                    ..predicate!("unused" == 5)
+                },
+                true,
+                Some(json!({"a": {"b/c": 5}}))
+            );
+        }
+
+        #[test]
+        fn test_compound() {
+            assert_unapply!(
+                all!(
+                    predicate!("a" == 1),
+                    predicate!("b.c" > 2),
+                    predicate!("b.d" is_null)
+                ),
+                true,
+                Some(json!({"a": 1, "b": {"c": 3, "d": null}}))
+            );
+            assert_unapply!(
+                all!(predicate!("a" == 1), predicate!("b" == 2)),
+                false,
+                Some(json!({"a": null}))
+            );
+            assert_unapply!(
+                any!(
+                    all!(predicate!("a" == 1), predicate!("a" == 2)),
+                    predicate!("b" == 2)
+                ),
+                true,
+                Some(json!({"b": 2}))
+            );
+            assert_unapply!(
+                none!(predicate!("a" == 1), predicate!("b" == 2)),
+                true,
+                Some(json!({"a": null, "b": null}))
+            );
+            assert_unapply!(not!(predicate!("a" < 5)), true, Some(json!({"a": 5})));
+        }
+
+        #[test]
+        fn test_unknown() {
+            // Both children need a different value for `a`.
+            assert_unapply!(all!(predicate!("a" > 5), predicate!("a" < 10)), true, None);
+            assert_unapply!(all!(predicate!("a" == 1), predicate!("a" == 2)), true, None);
+        }
+
+        #[test]
+        fn test_examples() {
+            // `b > 5` fails to evaluate without `b`, so it comes last.
+            let predicate = Predicate::from(any!(
+                predicate!("a" == 1),
+                one_of!("d", "x", "y"),
+                all!(predicate!("b" > 5), predicate!("c" is_null))
+            ));
+
+            assert_eq!(predicate.satisfying_example(), Some(json!({"a": 1})));
+            assert_eq!(
+                predicate.unsatisfying_example(),
+                Some(json!({"a": null, "b": 5, "d": null}))
+            );
+
+            assert_eq!(
+                predicate.examples(true, 3),
+                [json!({"a": 1}), json!({"d": "x"}), json!({"d": "y"})]
+            );
+            assert_eq!(predicate.examples(true, 10)[3], json!({"b": 6, "c": null}));
+            assert_eq!(
+                predicate.examples(false, 3),
+                [json!({"a": null, "b": 5, "d": null})]
+            );
+
+            let predicate = Predicate::from(predicate!("a" > 18));
+            assert_eq!(predicate.examples(true, 3), [json!({"a": 19})]);
+
+            let predicate = Predicate::from(exists_in!("users", predicate!("role" == "admin")));
+            assert_eq!(
+                predicate.satisfying_example(),
+                Some(json!({"users": [{"role": "admin"}]}))
+            );
+            assert_eq!(predicate.unsatisfying_example(), Some(json!({"users": []})));
+            assert!(
+                Predicate::from(all!(predicate!("a" == 1), predicate!("a" == 2)))
+                    .examples(true, 3)
+                    .is_empty()
+            );
+        }
     }
 
-    pub fn predicate(&self) -> &Predicate {
-        &self.predicate
+    mod cnf {
+        use super::*;
+        use proptest::prelude::*;
+
+        #[test]
+        fn test_to_cnf() {
+            let raw = || Predicate::from(predicate!("a" == 1));
+            assert_eq!(raw().to_cnf(), raw());
+
+            assert_eq!(
+                Predicate::from(none!(predicate!("a" == 1), predicate!("b" == 2))).to_cnf(),
+                all!(not!(predicate!("a" == 1)), not!(predicate!("b" == 2))).into()
+            );
+
+            assert_eq!(
+                Predicate::from(not!(any!(predicate!("a" == 1), not!(predicate!("b" == 2)))))
+                    .to_cnf(),
+                all!(not!(predicate!("a" == 1)), predicate!("b" == 2)).into()
+            );
+
+            // (a ∧ b) ∨ c = (a ∨ c) ∧ (b ∨ c)
+            assert_eq!(
+                Predicate::from(any!(
+                    all!(predicate!("a" == 1), predicate!("b" == 2)),
+                    predicate!("c" == 3)
+                ))
+                .to_cnf(),
+                all!(
+                    any!(predicate!("a" == 1), predicate!("c" == 3)),
+                    any!(predicate!("b" == 2), predicate!("c" == 3))
+                )
+                .into()
+            );
+        }
+
+        #[test]
+        fn test_is_cnf() {
+            assert!(Predicate::from(predicate!("a" == 1)).is_cnf());
+            assert!(Predicate::from(not!(predicate!("a" == 1))).is_cnf());
+            assert!(Predicate::from(any!(predicate!("a" == 1), predicate!("b" == 2))).is_cnf());
+            assert!(
+                Predicate::from(all!(
+                    any!(predicate!("a" == 1), not!(predicate!("b" == 2))),
+                    predicate!("c" == 3)
+                ))
+                .is_cnf()
+            );
+
+            assert!(!Predicate::from(none!(predicate!("a" == 1))).is_cnf());
+            assert!(!Predicate::from(not!(not!(predicate!("a" == 1)))).is_cnf());
+            assert!(
+                !Predicate::from(any!(
+                    all!(predicate!("a" == 1), predicate!("b" == 2)),
+                    predicate!("c" == 3)
+                ))
+                .is_cnf()
+            );
+        }
+
+        /// Predicates over the numeric fields `a`, `b` and `c` using operators which can't fail to
+        /// evaluate on numbers, so evaluation never errors.
+        pub(super) fn predicate_strategy() -> impl Strategy<Value = Predicate> {
+            let raw = (
+                prop::sample::select(vec!["a", "b", "c"]),
+                prop::sample::select(vec![
+                    Operator::Equal,
+                    Operator::NotEqual,
+                    Operator::Greater,
+                    Operator::Less,
+                    Operator::GreaterEqual,
+                    Operator::LessEqual,
+                    Operator::IsNull,
+                    Operator::IsNotNull,
+                ]),
+                0..4i64,
+            )
+                .prop_map(|(path, operator, value)| {
+                    Predicate::from(RawPredicate {
+                        path: path.to_owned(),
+                        path_syntax: PathSyntax::Dot,
+                        operator,
+                        value: value.into(),
+                        quantifier: Quantifier::Any,
+                    })
+                });
+
+            let one_of = (
+                prop::sample::select(vec!["a", "b", "c"]),
+                prop::collection::vec(0..4i64, 0..4),
+            )
+                .prop_map(|(path, values)| {
+                    Predicate::from(CompoundPredicate::OneOf {
+                        path: path.to_owned(),
+                        values: values.into_iter().map(Into::into).collect(),
+                    })
+                });
+
+            prop_oneof![3 => raw, 1 => one_of].prop_recursive(4, 24, 4, |inner| {
+                prop_oneof![
+                    inner
+                        .clone()
+                        .prop_map(|p| CompoundPredicate::Not(Box::new(p)).into()),
+                    prop::collection::vec(inner.clone(), 0..4)
+                        .prop_map(|ps| CompoundPredicate::Any(ps).into()),
+                    prop::collection::vec(inner.clone(), 0..4)
+                        .prop_map(|ps| CompoundPredicate::All(ps).into()),
+                    prop::collection::vec(inner.clone(), 0..4)
+                        .prop_map(|ps| CompoundPredicate::None(ps).into()),
+                    (inner.clone(), inner.clone(), inner).prop_map(
+                        |(condition, then, otherwise)| {
+                            CompoundPredicate::If {
+                                condition: Box::new(condition),
+                                then: Box::new(then),
+                                otherwise: Box::new(otherwise),
+                            }
+                            .into()
+                        }
+                    ),
+                ]
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn test_to_cnf_is_equivalent(
+                predicate in predicate_strategy(),
+                inputs in prop::collection::vec((0..4, 0..4, 0..4), 1..8),
+            ) {
+                let cnf = predicate.clone().to_cnf();
+                prop_assert!(cnf.is_cnf(), "{cnf:?} is not in CNF");
+
+                for (a, b, c) in inputs {
+                    let input = json!({"a": a, "b": b, "c": c});
+
+                    prop_assert_eq!(predicate.evaluate(&input), cnf.evaluate(&input));
+                }
+            }
+        }
     }
 
-    pub fn message(&self) -> &str {
-        &self.message
+    mod prune_dead_branches {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn always() -> Predicate {
+            all!().into()
+        }
+
+        fn never() -> Predicate {
+            any!().into()
+        }
+
+        #[test]
+        fn test_prune() {
+            let raw = || Predicate::from(predicate!("a" == 1));
+            assert_eq!(raw().prune_dead_branches(), raw());
+
+            assert_eq!(
+                Predicate::from(all!(predicate!("a" == 1), all!(), predicate!("b" == 2)))
+                    .prune_dead_branches(),
+                all!(predicate!("a" == 1), predicate!("b" == 2)).into()
+            );
+            assert_eq!(
+                Predicate::from(none!(predicate!("a" == 1), not!(any!()))).prune_dead_branches(),
+                never()
+            );
+            assert_eq!(
+                Predicate::from(any!(predicate!("a" == 1), none!(), predicate!("b" == 2)))
+                    .prune_dead_branches(),
+                always()
+            );
+            assert_eq!(
+                Predicate::from(any!(predicate!("a" == 1), any!(), not!(all!())))
+                    .prune_dead_branches(),
+                any!(predicate!("a" == 1)).into()
+            );
+            assert_eq!(
+                Predicate::from(not!(all!(predicate!("a" == 1), none!(any!()))))
+                    .prune_dead_branches(),
+                not!(all!(predicate!("a" == 1))).into()
+            );
+
+            // An `ExistsIn` fails for an empty array, so is never a tautology.
+            assert_eq!(
+                Predicate::from(exists_in!("a", all!())).prune_dead_branches(),
+                exists_in!("a", all!()).into()
+            );
+            assert_eq!(
+                Predicate::from(exists_in!("a", any!(predicate!("" == 1), any!())))
+                    .prune_dead_branches(),
+                exists_in!("a", any!(predicate!("" == 1))).into()
+            );
+            assert_eq!(
+                Predicate::from(exists_in!("a", none!(all!()))).prune_dead_branches(),
+                never()
+            );
+        }
+
+        #[test]
+        fn test_is_optimized() {
+            assert!(Predicate::from(predicate!("a" == 1)).is_optimized());
+            assert!(always().is_optimized());
+            assert!(never().is_optimized());
+            assert!(
+                Predicate::from(all!(predicate!("a" == 1), predicate!("b" == 2))).is_optimized()
+            );
+
+            assert!(!Predicate::from(none!()).is_optimized());
+            assert!(!Predicate::from(all!(predicate!("a" == 1), all!())).is_optimized());
+        }
+
+        proptest! {
+            #[test]
+            fn test_prune_is_equivalent(
+                predicate in super::cnf::predicate_strategy(),
+                inputs in prop::collection::vec((0..4, 0..4, 0..4), 1..8),
+            ) {
+                let pruned = predicate.clone().prune_dead_branches();
+                prop_assert!(pruned.is_optimized(), "{pruned:?} can be pruned further");
+
+                for (a, b, c) in inputs {
+                    let input = json!({"a": a, "b": b, "c": c});
+
+                    prop_assert_eq!(predicate.evaluate(&input), pruned.evaluate(&input));
+                }
+            }
+        }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(untagged, deny_unknown_fields, rename_all = "camelCase")]
-pub enum Predicate {
-    Raw(RawPredicate),
-    Compound(CompoundPredicate),
-}
+    mod normalize_paths {
+        use super::*;
 
-impl From<RawPredicate> for Predicate {
-    fn from(value: RawPredicate) -> Self {
-        Predicate::Raw(value)
+        fn alias_map(aliases: &[(&str, &str)]) -> HashMap<String, String> {
+            aliases
+                .iter()
+                .map(|(alias, target)| (alias.to_string(), target.to_string()))
+                .collect()
+        }
+
+        #[test]
+        fn test_prefix_substitution() {
+            let aliases = alias_map(&[
+                ("applicant", "data.applicant"),
+                ("applicant.address", "addr"),
+            ]);
+
+            let normalize =
+                |predicate: RawPredicate| Predicate::from(predicate).normalize_paths(&aliases);
+
+            assert_eq!(
+                normalize(predicate!("applicant.income" > 100)),
+                predicate!("data.applicant.income" > 100).into()
+            );
+            assert_eq!(
+                normalize(predicate!("applicant" is_not_null)),
+                predicate!("data.applicant" is_not_null).into()
+            );
+            // The longest matching alias wins.
+            assert_eq!(
+                normalize(predicate!("applicant.address.city" == "Dublin")),
+                predicate!("addr.city" == "Dublin").into()
+            );
+
+            let pointer = RawPredicate {
+                path: "/applicant/income".to_owned(),
+                path_syntax: PathSyntax::JsonPointer,
+                ..predicate!("" > 100)
+            };
+            assert_eq!(
+                Predicate::from(pointer.clone())
+                    .normalize_paths(&alias_map(&[("/applicant", "/data/applicant")])),
+                RawPredicate {
+                    path: "/data/applicant/income".to_owned(),
+                    ..pointer
+                }
+                .into()
+            );
+        }
+
+        #[test]
+        fn test_no_match() {
+            let aliases = alias_map(&[("applicant", "data.applicant")]);
+
+            for predicate in [
+                predicate!("income" > 100),
+                predicate!("applicants.income" > 100),
+                predicate!("data.applicant" is_null),
+            ] {
+                assert_eq!(
+                    Predicate::from(predicate.clone()).normalize_paths(&aliases),
+                    predicate.into()
+                );
+            }
+
+            assert_eq!(
+                Predicate::from(predicate!("applicant.income" > 100))
+                    .normalize_paths(&HashMap::new()),
+                predicate!("applicant.income" > 100).into()
+            );
+        }
+
+        #[test]
+        fn test_nested_compound() {
+            let aliases = alias_map(&[("a", "x.a")]);
+
+            let predicate: Predicate = all!(
+                predicate!("a.b" == 1),
+                any!(not!(predicate!("a.c" == 2)), predicate!("d" == 3)),
+                none!(predicate!("a" is_null))
+            )
+            .into();
+
+            assert_eq!(
+                predicate.normalize_paths(&aliases),
+                all!(
+                    predicate!("x.a.b" == 1),
+                    any!(not!(predicate!("x.a.c" == 2)), predicate!("d" == 3)),
+                    none!(predicate!("x.a" is_null))
+                )
+                .into()
+            );
+        }
+
+        #[test]
+        fn test_apply_input_mapping() {
+            let mapping = alias_map(&[("user.age", "customer.age"), ("user", "customer")]);
+
+            let predicate: Predicate = all!(
+                predicate!("user.age" >= 18),
+                any!(
+                    not!(predicate!("user" is_null)),
+                    predicate!("user.name" == "Jo")
+                ),
+                predicate!("user.age.years" > 1)
+            )
+            .into();
+
+            // Only whole paths are replaced, unlike with `normalize_paths`.
+            assert_eq!(
+                predicate.apply_input_mapping(&mapping),
+                all!(
+                    predicate!("customer.age" >= 18),
+                    any!(
+                        not!(predicate!("customer" is_null)),
+                        predicate!("user.name" == "Jo")
+                    ),
+                    predicate!("user.age.years" > 1)
+                )
+                .into()
+            );
+
+            let one_of: Predicate = one_of!("user.age", 1, 2).into();
+            assert_eq!(
+                one_of.apply_input_mapping(&mapping),
+                one_of!("customer.age", 1, 2).into()
+            );
+
+            let rule = Rule {
+                condition: Some(predicate!("user" is_not_null).into()),
+                ..rule!("rule-1", "message", predicate!("user.age" >= 18))
+            };
+            let mapped = rule.apply_input_mapping(&mapping);
+
+            assert_eq!(mapped.predicate, predicate!("customer.age" >= 18).into());
+            assert_eq!(
+                mapped.condition,
+                Some(predicate!("customer" is_not_null).into())
+            );
+            assert_eq!(mapped.evaluate(&json!({"customer": {"age": 20}})), Ok(true));
+        }
+
+        #[test]
+        fn test_rule_path_aliases() {
+            let rule = Rule {
+                path_aliases: alias_map(&[("applicant", "data.applicant")]),
+                condition: Some(predicate!("applicant.age" >= 18).into()),
+                ..rule!("rule-1", "message", predicate!("applicant.income" > 100))
+            };
+            let input = json!({"data": {"applicant": {"income": 200, "age": 20}}});
+
+            assert_eq!(rule.evaluate(&input), Ok(true));
+            assert!(rule.dry_run(&input).is_empty());
+            assert_eq!(rule.referenced_paths(), vec!["applicant.income"]);
+
+            let resolved = rule.resolve_aliases().into_owned();
+            assert!(resolved.path_aliases.is_empty());
+            assert_eq!(resolved.referenced_paths(), vec!["data.applicant.income"]);
+            assert_eq!(
+                resolved.condition,
+                Some(predicate!("data.applicant.age" >= 18).into())
+            );
+            // Resolving again has no aliases left to apply.
+            assert_eq!(resolved.resolve_aliases().into_owned(), resolved);
+        }
     }
-}
 
-impl From<CompoundPredicate> for Predicate {
-    fn from(value: CompoundPredicate) -> Self {
-        Predicate::Compound(value)
+    mod yaml {
+        use super::*;
+
+        macro_rules! assert_yaml_round_trip {
+            ($rule:expr) => {{
+                let rule = $rule;
+                let yaml = rule.to_yaml().expect("unable to serialize rule to YAML");
+
+                let parsed = Rule::from_yaml(&yaml).expect("unable to parse YAML");
+
+                assert_eq!(parsed, rule, "{yaml}");
+            }};
+        }
+
+        #[test]
+        fn test_round_trip() {
+            assert_yaml_round_trip!(rule!("rule-1", "message", predicate!("foo" == 10)));
+
+            assert_yaml_round_trip!(rule!(
+                "rule-2",
+                "message",
+                all!(
+                    predicate!("age" >= 12.5),
+                    any!(
+                        predicate!("tags" contains "foo"),
+                        not!(predicate!("foo" == json!({"bar": [1, null, "baz"]})))
+                    ),
+                    none!(predicate!("foo" != true))
+                ),
+                depends_on: ["rule-1"]
+            ));
+        }
+
+        #[test]
+        fn test_from_yaml() {
+            let parsed = Rule::from_yaml(
+                r#"
+                id: rule-1
+                message: Important rule failed
+                predicate:
+                  any:
+                    - path: foo
+                      operator: ">="
+                      value: 12
+                "#,
+            )
+            .expect("unable to parse YAML");
+
+            assert_eq!(
+                parsed,
+                rule!(
+                    "rule-1",
+                    "Important rule failed",
+                    any!(predicate!("foo" >= 12))
+                )
+            );
+        }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub struct RawPredicate {
-    pub path: String,
-    pub operator: Operator,
-    pub value: serde_json::Value,
-}
+    mod round_trip {
+        use super::*;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "camelCase")]
-pub enum CompoundPredicate {
-    Not(Box<Predicate>),
-    Any(Vec<Predicate>),
-    All(Vec<Predicate>),
-    None(Vec<Predicate>),
-}
+        macro_rules! test_operator {
+            ($name:ident, $operator:expr, $camel_case:literal $(, $alias:literal)*) => {
+                #[test]
+                fn $name() {
+                    let camel_case = format!("\"{}\"", $camel_case);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum Operator {
-    #[serde(alias = "==")]
-    Equal,
-    #[serde(alias = ">")]
-    Greater,
-    #[serde(alias = "<")]
-    Less,
-    #[serde(alias = ">=")]
-    GreaterEqual,
-    #[serde(alias = "<=")]
-    LessEqual,
-    #[serde(alias = "!=")]
-    NotEqual,
-    #[serde(alias = "in")]
-    Contains,
-}
+                    assert_eq!(
+                        serde_json::from_str::<Operator>(&camel_case).expect("unable to parse JSON"),
+                        $operator
+                    );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+                    $(
+                        assert_eq!(
+                            serde_json::from_str::<Operator>(&format!("\"{}\"", $alias))
+                                .expect("unable to parse JSON"),
+                            $operator,
+                            "alias {}", $alias
+                        );
+                    )*
+
+                    assert_eq!(
+                        serde_json::to_string(&$operator).expect("unable to serialize operator"),
+                        camel_case
+                    );
+                }
+            };
+        }
+
+        test_operator!(test_equal, Operator::Equal, "equal", "==");
+        test_operator!(test_greater, Operator::Greater, "greater", ">");
+        test_operator!(test_less, Operator::Less, "less", "<");
+        test_operator!(
+            test_greater_equal,
+            Operator::GreaterEqual,
+            "greaterEqual",
+            ">="
+        );
+        test_operator!(test_less_equal, Operator::LessEqual, "lessEqual", "<=");
+        test_operator!(test_not_equal, Operator::NotEqual, "notEqual", "!=");
+        test_operator!(test_contains, Operator::Contains, "contains", "in");
+        test_operator!(test_starts_with, Operator::StartsWith, "startsWith");
+        test_operator!(test_ends_with, Operator::EndsWith, "endsWith");
+        test_operator!(test_divisible, Operator::Divisible, "divisible");
+        test_operator!(test_not_divisible, Operator::NotDivisible, "notDivisible");
+        test_operator!(test_is_null, Operator::IsNull, "isNull");
+        test_operator!(test_is_not_null, Operator::IsNotNull, "isNotNull");
+
+        #[test]
+        fn test_path_syntax() {
+            let json = r#"{"path":"/a/b","pathSyntax":"jsonPointer","operator":"isNull"}"#;
+
+            let predicate: RawPredicate = serde_json::from_str(json).expect("unable to parse JSON");
+            assert_eq!(predicate.path_syntax, PathSyntax::JsonPointer);
+            assert_eq!(
+                serde_json::to_string(&predicate).expect("unable to serialize predicate"),
+                json
+            );
+
+            // The default syntax is left out so existing rules serialize as before.
+            let predicate: RawPredicate =
+                serde_json::from_str(r#"{"path":"a.b","operator":"isNull"}"#)
+                    .expect("unable to parse JSON");
+            assert_eq!(predicate, predicate!("a.b" is_null));
+            assert_eq!(
+                serde_json::to_string(&predicate).expect("unable to serialize predicate"),
+                r#"{"path":"a.b","operator":"isNull"}"#
+            );
+        }
+
+        #[test]
+        fn test_operator_display() {
+            assert_eq!(Operator::StartsWith.to_string(), "starts_with");
+            assert_eq!(Operator::EndsWith.to_string(), "ends_with");
+            assert_eq!(Operator::NotDivisible.to_string(), "not_divisible");
+            assert_eq!(Operator::Equal.to_string(), Operator::Equal.symbol());
+        }
+
+        macro_rules! test_compound {
+            ($name:ident, $tag:literal, $predicate:expr, $children:literal) => {
+                #[test]
+                fn $name() {
+                    let json = format!(r#"{{"{}":{}}}"#, $tag, $children);
+                    let predicate: CompoundPredicate = $predicate;
+
+                    assert_eq!(
+                        serde_json::from_str::<CompoundPredicate>(&json)
+                            .expect("unable to parse JSON"),
+                        predicate
+                    );
+                    assert_eq!(
+                        serde_json::to_string(&predicate).expect("unable to serialize predicate"),
+                        json
+                    );
+                }
+            };
+        }
 
-    use crate::{all, any, predicate, rule};
+        test_compound!(
+            test_not,
+            "not",
+            not!(predicate!("foo" == 1)),
+            r#"{"path":"foo","operator":"equal","value":1}"#
+        );
+        test_compound!(
+            test_any,
+            "any",
+            any!(predicate!("foo" == 1)),
+            r#"[{"path":"foo","operator":"equal","value":1}]"#
+        );
+        test_compound!(
+            test_all,
+            "all",
+            all!(predicate!("foo" == 1)),
+            r#"[{"path":"foo","operator":"equal","value":1}]"#
+        );
+        test_compound!(
+            test_none,
+            "none",
+            none!(predicate!("foo" == 1)),
+            r#"[{"path":"foo","operator":"equal","value":1}]"#
+        );
+    }
 
     mod deserialize {
         use serde_json::json;
@@ -109,6 +3171,18 @@ mod tests {
                 r#"{"path": "foo.bar.baz", "operator": "==", "value": {"bob": 10, "alice": "Red", "eve": [1, 2, 3]}}"#,
                 predicate!("foo.bar.baz" == json!({"bob": 10, "alice": "Red", "eve": [1,2,3]}))
             );
+
+            assert_deserialize!(
+                RawPredicate,
+                r#"{"path": "foo", "operator": "isNull"}"#,
+                predicate!("foo" is_null)
+            );
+
+            assert_deserialize!(
+                RawPredicate,
+                r#"{"path": "foo", "operator": "isNotNull"}"#,
+                predicate!("foo" is_not_null)
+            );
         }
 
         #[test]
@@ -136,6 +3210,38 @@ mod tests {
                     predicate!("bar" contains 1950)
                 )
             );
+
+            assert_deserialize!(
+                CompoundPredicate,
+                r#"{"oneOf": {"path": "status", "values": ["a", "b", 3]}}"#,
+                one_of!("status", "a", "b", 3)
+            );
+            let if_json = r#"{
+                "if": {
+                    "condition": {"path": "country", "operator": "==", "value": "US"},
+                    "then": {"path": "age", "operator": ">=", "value": 21},
+                    "else": {"any": [{"path": "age", "operator": ">=", "value": 18}]}
+                }
+            }"#;
+            let if_predicate = if_then_else!(
+                predicate!("country" == "US"),
+                predicate!("age" >= 21),
+                any!(predicate!("age" >= 18))
+            );
+            let parsed: CompoundPredicate =
+                serde_json::from_str(if_json).expect("unable to parse JSON");
+            assert_eq!(parsed, if_predicate);
+
+            let serialized =
+                serde_json::to_value(&if_predicate).expect("predicate should serialize");
+            assert_eq!(serialized["if"]["else"]["any"][0]["path"], "age");
+            assert!(serialized["if"].get("otherwise").is_none());
+
+            // `otherwise` is only the name of the Rust field.
+            assert!(
+                serde_json::from_str::<CompoundPredicate>(&if_json.replace("else", "otherwise"))
+                    .is_err()
+            );
         }
 
         #[test]
@@ -211,5 +3317,45 @@ mod tests {
                 )
             );
         }
+
+        #[test]
+        fn test_rule_depends_on() {
+            assert_deserialize!(
+                Rule,
+                r#"{
+                    "id": "rule-2",
+                    "message": "Important rule failed",
+                    "dependsOn": ["rule-1"],
+                    "predicate": {
+                        "path": "foo",
+                        "operator": ">=",
+                        "value": 12
+                    }
+                }"#,
+                rule!(
+                    "rule-2",
+                    "Important rule failed",
+                    predicate!("foo" >= 12),
+                    depends_on: ["rule-1"]
+                )
+            );
+        }
+
+        #[test]
+        fn test_rule_weight() {
+            assert_deserialize!(
+                Rule,
+                r#"{
+                    "id": "rule-1",
+                    "message": "message",
+                    "weight": 2.5,
+                    "predicate": {"path": "foo", "operator": "==", "value": 10}
+                }"#,
+                Rule {
+                    weight: 2.5,
+                    ..rule!("rule-1", "message", predicate!("foo" == 10))
+                }
+            );
+        }
     }
 }
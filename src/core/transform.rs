@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+type JsonValue = serde_json::Value;
+
+/// Preprocessing of an input before it's evaluated, for inputs which don't quite have the shape
+/// the rules expect, see `RuleRepository::evaluate_with_transform`. Chosen by name, e.g. with
+/// `?transform=lowercase_keys`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InputTransform {
+    /// See `lowercase_keys`.
+    LowercaseKeys,
+    /// See `flatten_arrays`.
+    #[serde(alias = "flatten")]
+    FlattenArrays,
+}
+
+impl InputTransform {
+    /// The function applying the transform.
+    pub fn function(self) -> fn(JsonValue) -> JsonValue {
+        match self {
+            InputTransform::LowercaseKeys => lowercase_keys,
+            InputTransform::FlattenArrays => flatten_arrays,
+        }
+    }
+
+    pub fn apply(self, input: JsonValue) -> JsonValue {
+        self.function()(input)
+    }
+}
+
+/// `input` with the keys of every object in it lowercased, so paths match regardless of the case
+/// the input was sent in. If two keys of an object only differ in case, one of their values is
+/// kept.
+pub fn lowercase_keys(input: JsonValue) -> JsonValue {
+    match input {
+        JsonValue::Object(fields) => JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_lowercase(), lowercase_keys(value)))
+                .collect(),
+        ),
+        JsonValue::Array(values) => {
+            JsonValue::Array(values.into_iter().map(lowercase_keys).collect())
+        }
+        value => value,
+    }
+}
+
+/// `input` with the elements of every array nested in another array moved into the outer array,
+/// e.g. `[[1, 2], [3, [4]]]` becomes `[1, 2, 3, 4]`, so `contains` finds elements however deeply
+/// they're nested.
+pub fn flatten_arrays(input: JsonValue) -> JsonValue {
+    fn flatten_into(values: Vec<JsonValue>, flattened: &mut Vec<JsonValue>) {
+        for value in values {
+            match value {
+                JsonValue::Array(values) => flatten_into(values, flattened),
+                value => flattened.push(flatten_arrays(value)),
+            }
+        }
+    }
+
+    match input {
+        JsonValue::Object(fields) => JsonValue::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, flatten_arrays(value)))
+                .collect(),
+        ),
+        JsonValue::Array(values) => {
+            let mut flattened = Vec::with_capacity(values.len());
+            flatten_into(values, &mut flattened);
+
+            JsonValue::Array(flattened)
+        }
+        value => value,
+    }
+}
+
+/// `input` with the fields of `defaults` it's missing, or has as `null`, filled in. Objects are
+/// merged recursively, any other value in `input` is kept as is.
+pub fn merge_defaults(input: JsonValue, defaults: &JsonValue) -> JsonValue {
+    match (input, defaults) {
+        (JsonValue::Null, defaults) => defaults.clone(),
+        (JsonValue::Object(mut fields), JsonValue::Object(defaults)) => {
+            for (key, default) in defaults {
+                let value = fields.remove(key).unwrap_or_default();
+                fields.insert(key.clone(), merge_defaults(value, default));
+            }
+
+            JsonValue::Object(fields)
+        }
+        (input, _) => input,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lowercase_keys() {
+        assert_eq!(
+            lowercase_keys(json!({
+                "User": {"FirstName": "Jo", "Tags": [{"Color": "RED"}]},
+                "age": 30
+            })),
+            json!({
+                "user": {"firstname": "Jo", "tags": [{"color": "RED"}]},
+                "age": 30
+            })
+        );
+        assert_eq!(lowercase_keys(json!("ABC")), json!("ABC"));
+        assert_eq!(lowercase_keys(json!([{"A": 1}])), json!([{"a": 1}]));
+    }
+
+    #[test]
+    fn test_flatten_arrays() {
+        assert_eq!(
+            flatten_arrays(json!([[1, 2], [3, [4, [5]]], 6])),
+            json!([1, 2, 3, 4, 5, 6])
+        );
+        assert_eq!(
+            flatten_arrays(json!({
+                "tags": [["a"], ["b", ["c"]]],
+                "items": [{"ids": [[1], [2]]}],
+                "name": "Jo"
+            })),
+            json!({
+                "tags": ["a", "b", "c"],
+                "items": [{"ids": [1, 2]}],
+                "name": "Jo"
+            })
+        );
+        assert_eq!(flatten_arrays(json!([[], [[]]])), json!([]));
+    }
+
+    #[test]
+    fn test_merge_defaults() {
+        assert_eq!(
+            merge_defaults(
+                json!({"age": null, "user": {"name": "Jo"}, "tags": ["a"]}),
+                &json!({"age": 0, "user": {"name": "", "country": "UK"}, "tags": [], "score": 1})
+            ),
+            json!({
+                "age": 0,
+                "user": {"name": "Jo", "country": "UK"},
+                "tags": ["a"],
+                "score": 1
+            })
+        );
+        assert_eq!(
+            merge_defaults(json!(null), &json!({"a": 1})),
+            json!({"a": 1})
+        );
+        assert_eq!(merge_defaults(json!(10), &json!({"a": 1})), json!(10));
+        assert_eq!(merge_defaults(json!({"a": 1}), &json!(10)), json!({"a": 1}));
+    }
+
+    #[test]
+    fn test_input_transform() {
+        let transform: InputTransform =
+            serde_json::from_value(json!("lowercase_keys")).expect("transform should parse");
+        assert_eq!(transform, InputTransform::LowercaseKeys);
+        assert_eq!(transform.apply(json!({"A": 1})), json!({"a": 1}));
+
+        for name in ["flatten_arrays", "flatten"] {
+            let transform: InputTransform =
+                serde_json::from_value(json!(name)).expect("transform should parse");
+            assert_eq!(transform, InputTransform::FlattenArrays);
+            assert_eq!(transform.apply(json!([[1], [2]])), json!([1, 2]));
+        }
+    }
+}
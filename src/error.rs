@@ -1,19 +1,26 @@
+use crate::core::json_logic::ConversionError;
 use crate::pretty_json::PrettyJson;
 use crate::repository::{
-    CreateRuleError, DeleteRuleError, EvaluateRuleError, GetAllRulesError, GetRuleError,
-    UpdateRuleError,
+    CloneRuleError, CreateRuleError, DeleteRuleError, EvaluateRuleError, ExportError,
+    GetAllRulesError, GetRuleError, ImportError, UpdateRuleError,
 };
 use actix_web::{
     HttpResponse, HttpResponseBuilder, ResponseError, body::BoxBody, http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
 
 macro_rules! impl_response_error {
     ($($error:tt {
         $(
             $variant:pat => $status_code:expr
         ),+
-    }),+) => {
+    } $(details {
+        $(
+            $details_variant:pat => $details:expr
+        ),+
+    })?),+) => {
         $(
             impl ResponseError for $error {
             fn status_code(&self) -> StatusCode {
@@ -25,7 +32,19 @@ macro_rules! impl_response_error {
             }
 
             fn error_response(&self) -> HttpResponse<BoxBody> {
-                HttpResponseBuilder::new(self.status_code()).json_pretty(ApiError::from(self))
+                #[allow(unused_mut)]
+                let mut api_error = ApiError::from(self);
+
+                $(
+                    api_error.error.details = match self {
+                        $(
+                            $details_variant => $details,
+                        )+
+                        _ => None,
+                    };
+                )?
+
+                HttpResponseBuilder::new(self.status_code()).json_pretty(api_error)
             }
         }
         )+
@@ -34,38 +53,97 @@ macro_rules! impl_response_error {
 
 impl_response_error!(
     GetAllRulesError {
-        GetAllRulesError::Unknown => StatusCode::INTERNAL_SERVER_ERROR
+        GetAllRulesError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR
     },
     GetRuleError {
-        GetRuleError::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
+        GetRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
         GetRuleError::NoSuchRule(_) => StatusCode::NOT_FOUND
     },
     CreateRuleError {
-        CreateRuleError::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
-        CreateRuleError::Duplicate(_) => StatusCode::BAD_REQUEST
+        CreateRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        CreateRuleError::Duplicate(_) => StatusCode::BAD_REQUEST,
+        CreateRuleError::Invalid(_) => StatusCode::BAD_REQUEST,
+        CreateRuleError::Validation(_) => StatusCode::BAD_REQUEST
+    } details {
+        CreateRuleError::Invalid(error) => error.details()
     },
     DeleteRuleError {
-        DeleteRuleError::Unknown => StatusCode::INTERNAL_SERVER_ERROR
+        DeleteRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR
+    },
+    CloneRuleError {
+        CloneRuleError::NoSuchRule(_) => StatusCode::NOT_FOUND,
+        CloneRuleError::Duplicate(_) => StatusCode::CONFLICT,
+        CloneRuleError::MissingId => StatusCode::BAD_REQUEST,
+        CloneRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR
     },
     UpdateRuleError {
-        UpdateRuleError::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
-        UpdateRuleError::NoSuchRule(_) => StatusCode::NOT_FOUND
+        UpdateRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        UpdateRuleError::NoSuchRule(_) => StatusCode::NOT_FOUND,
+        UpdateRuleError::Duplicate(_) => StatusCode::CONFLICT,
+        UpdateRuleError::Invalid(_) => StatusCode::BAD_REQUEST,
+        UpdateRuleError::Validation(_) => StatusCode::BAD_REQUEST
+    } details {
+        UpdateRuleError::Invalid(error) => error.details()
     },
     EvaluateRuleError {
         EvaluateRuleError::NoSuchRule(_) => StatusCode::NOT_FOUND,
         EvaluateRuleError::EvaluationError(_, _) => StatusCode::BAD_REQUEST,
-        EvaluateRuleError::Unknown => StatusCode::INTERNAL_SERVER_ERROR
+        EvaluateRuleError::CyclicDependency(_) => StatusCode::BAD_REQUEST,
+        EvaluateRuleError::EmptyCategory(_) => StatusCode::NOT_FOUND,
+        EvaluateRuleError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        EvaluateRuleError::SchemaValidationFailed { .. } => StatusCode::BAD_REQUEST,
+        EvaluateRuleError::TooManyRules { .. } => StatusCode::BAD_REQUEST
+    } details {
+        EvaluateRuleError::SchemaValidationFailed { errors, .. } => Some(errors.clone())
+    },
+    ImportError {
+        ImportError::Io(_) => StatusCode::BAD_GATEWAY,
+        ImportError::HttpError(_) => StatusCode::BAD_GATEWAY,
+        ImportError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ImportError::TooLarge(_) => StatusCode::BAD_GATEWAY,
+        ImportError::Parse(_) => StatusCode::BAD_REQUEST,
+        ImportError::DuplicateId(_) => StatusCode::BAD_REQUEST,
+        ImportError::Invalid(_) => StatusCode::BAD_REQUEST,
+        ImportError::Validation(_) => StatusCode::BAD_REQUEST,
+        ImportError::HostNotAllowed(_) => StatusCode::FORBIDDEN
+    } details {
+        ImportError::Invalid(error) => error.details()
+    },
+    ExportError {
+        ExportError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        ExportError::Serialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        ExportError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR
+    },
+    JsonSizeLimitError {
+        JsonSizeLimitError { .. } => StatusCode::PAYLOAD_TOO_LARGE
+    },
+    ConversionError {
+        ConversionError::UnsupportedOperator(_) => StatusCode::BAD_REQUEST,
+        ConversionError::UnsupportedValue { .. } => StatusCode::BAD_REQUEST,
+        ConversionError::UnsupportedPath(_) => StatusCode::BAD_REQUEST,
+        ConversionError::UnknownOperator(_) => StatusCode::BAD_REQUEST,
+        ConversionError::Malformed(_) => StatusCode::BAD_REQUEST
     }
 );
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A JSON request body larger than the server accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("the request body is larger than the limit of {limit} bytes")]
+pub struct JsonSizeLimitError {
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ApiError {
     pub error: InnerError,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct InnerError {
     pub message: String,
+    /// Each individual problem, when the error is made up of several.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Vec<String>>,
 }
 
 impl<E> From<E> for ApiError
@@ -76,6 +154,7 @@ where
         Self {
             error: InnerError {
                 message: error.to_string(),
+                details: None,
             },
         }
     }
@@ -1,320 +1,4101 @@
-use std::fs;
-
 use actix_web::{
-    App, HttpResponse, HttpServer, Responder, dev,
+    App, HttpRequest, HttpResponse, HttpResponseBuilder, HttpServer, Responder, dev,
+    error::JsonPayloadError,
+    guard,
+    http::header::{self, Header, HttpDate, IfModifiedSince, LastModified},
+    mime,
     web::{self},
 };
+use chrono::{DateTime, SubsecRound, Utc};
 use evaluator::{
-    core::rule::Rule,
+    core::rule::{
+        CompoundPredicate, Operator, PathSyntax, Predicate, Quantifier, RawPredicate, Rule,
+    },
+    core::transform::InputTransform,
+    error::{ApiError, InnerError, JsonSizeLimitError},
     pretty_json::PrettyJson,
-    repository::{InMemRuleRepository, RuleRepository},
+    repository::{
+        BooleanEvaluation, BooleanEvaluationResult, EvaluateRuleError, Evaluation,
+        EvaluationContext, EvaluationMode, EvaluationOptions, EvaluationReason, EvaluationResult,
+        GetRuleError, InMemRuleRepository, IndexedEvaluation, RuleChangeEvent, RuleRepository,
+        RuleStats, format::RuleFileFormat, remote,
+    },
 };
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    time::SystemTime,
+};
+use tokio_stream::StreamExt;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct GetRulesParams {
+    /// Comma separated list of rule ids to fetch instead of every rule
+    ids: Option<String>,
+    /// Only return rules in this category
+    category: Option<String>,
+    /// Only return rules whose predicate uses this operator, e.g. `startsWith`
+    operator: Option<Operator>,
+    /// Only return rules created by this author
+    author: Option<String>,
+    /// Only return rules which have (`true`) or haven't (`false`) been reviewed
+    reviewed: Option<bool>,
+    /// Only return rules created at or after this RFC 3339 timestamp
+    created_after: Option<DateTime<Utc>>,
+    /// Only return rules created before this RFC 3339 timestamp
+    created_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GetManyResponse {
+    rules: Vec<Rule>,
+    /// Requested ids which don't have a rule
+    not_found: Vec<String>,
+}
+
+/// Response body of `GET /rules`, depending on whether specific ids were requested.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+enum GetRulesResponse {
+    All(Vec<Rule>),
+    Many(GetManyResponse),
+}
 
+#[utoipa::path(
+    get,
+    path = "/rules",
+    params(GetRulesParams),
+    responses(
+        (status = 200, description = "All stored rules, or the requested rules if `ids` is given", body = GetRulesResponse),
+        (status = 500, body = ApiError)
+    )
+)]
 async fn get_all_rules_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
+    params: web::Query<GetRulesParams>,
 ) -> Result<impl Responder, actix_web::Error> {
-    let rules = state.rule_repository.get_all().await?;
+    let matches_filters = |rule: &Rule| {
+        params
+            .category
+            .as_ref()
+            .is_none_or(|category| rule.category.as_ref() == Some(category))
+            && params
+                .operator
+                .is_none_or(|operator| rule.applicable_operators().contains(&operator))
+            && params
+                .author
+                .as_ref()
+                .is_none_or(|author| rule.author.as_ref() == Some(author))
+            && params
+                .reviewed
+                .is_none_or(|reviewed| rule.reviewed_by.is_some() == reviewed)
+            && rule.was_created_within(params.created_after, params.created_before)
+    };
+
+    let Some(ids) = &params.ids else {
+        // Narrow down the rules fetched using one of the filters, the rest are applied after.
+        let repository = &state.rule_repository;
+        let mut rules = if let Some(category) = &params.category {
+            repository.get_by_category(category).await?
+        } else if let Some(operator) = params.operator {
+            repository.find_by_operator(operator).await?
+        } else if let Some(author) = &params.author {
+            repository.find_by_author(author).await?
+        } else if let Some(reviewed) = params.reviewed {
+            repository.find_by_review_status(reviewed).await?
+        } else if params.created_after.is_some() || params.created_before.is_some() {
+            repository
+                .find_by_date_range(params.created_after, params.created_before)
+                .await?
+        } else {
+            repository.get_all().await?
+        };
+        rules.retain(matches_filters);
+
+        return Ok(HttpResponse::Ok().json_pretty(GetRulesResponse::All(rules)));
+    };
+
+    let ids: Vec<String> = ids
+        .split(',')
+        .filter(|id| !id.is_empty())
+        .map(String::from)
+        .collect();
+
+    let rules = state.rule_repository.get_many(&ids).await?;
+
+    let not_found = ids
+        .into_iter()
+        .filter(|id| !rules.iter().any(|rule| &rule.id == id))
+        .collect();
+
+    // Rules not matching the filters exist, so they're left out rather than reported as not found.
+    let rules = rules.into_iter().filter(matches_filters).collect();
 
-    Ok(HttpResponse::Ok().json_pretty(rules))
+    Ok(
+        HttpResponse::Ok()
+            .json_pretty(GetRulesResponse::Many(GetManyResponse { rules, not_found })),
+    )
 }
 
+#[utoipa::path(
+    get,
+    path = "/rules/{id}",
+    params(
+        ("id" = String, Path, description = "Id of the rule"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Respond with `304` if the rule hasn't changed since this HTTP date")
+    ),
+    responses(
+        (status = 200, description = "The rule with the given id", content(
+            (Rule = "application/json"),
+            (Rule = "application/yaml")
+        ), headers(("Last-Modified" = String, description = "When the rule was last created or updated"))),
+        (status = 304, description = "The rule hasn't changed since `If-Modified-Since`"),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
 async fn get_rule_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
+    req: HttpRequest,
     id: web::Path<String>,
 ) -> Result<impl Responder, actix_web::Error> {
     let rule = state.rule_repository.get(&id).await?;
 
-    Ok(HttpResponse::Ok().json_pretty(rule))
+    Ok(rule_response(&req, &rule, |response| {
+        response.json_pretty(&rule)
+    }))
 }
 
-async fn create_rule_handler<RR: RuleRepository>(
+async fn get_rule_yaml_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
-    rule: web::Json<Rule>,
+    req: HttpRequest,
+    id: web::Path<String>,
 ) -> Result<impl Responder, actix_web::Error> {
-    state.rule_repository.create(rule.into_inner()).await?;
+    let rule = state.rule_repository.get(&id).await?;
 
-    Ok(HttpResponse::Created())
+    Ok(rule_response(&req, &rule, |response| {
+        match rule.to_yaml() {
+            Ok(yaml) => response.content_type(YAML_CONTENT_TYPE).body(yaml),
+            Err(err) => HttpResponse::InternalServerError().json_pretty(ApiError::from(err)),
+        }
+    }))
 }
 
-async fn delete_rule_handler<RR: RuleRepository>(
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// The format of the file, `json` by default
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[utoipa::path(
+    get,
+    path = "/rules/export",
+    params(ExportParams),
+    responses(
+        (status = 200, description = "Every rule as a file which can be imported again", content(
+            (Vec<Rule> = "application/json"),
+            (Vec<Rule> = "application/yaml")
+        )),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn export_rules_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
-    id: web::Path<String>,
+    params: web::Query<ExportParams>,
 ) -> Result<impl Responder, actix_web::Error> {
-    state.rule_repository.delete(&id.into_inner()).await?;
+    let (format, content_type, filename) = match params.format {
+        ExportFormat::Json => (
+            RuleFileFormat::Json,
+            mime::APPLICATION_JSON.as_ref(),
+            "rules.json",
+        ),
+        ExportFormat::Yaml => (RuleFileFormat::Yaml, YAML_CONTENT_TYPE, "rules.yaml"),
+    };
 
-    Ok(HttpResponse::Ok())
+    let body = state.rule_repository.export_as(format).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(header::ContentDisposition::attachment(filename))
+        .body(body))
 }
 
-async fn update_rule_handler<RR: RuleRepository>(
+/// Responds to a request for a single rule with `Last-Modified` set from its `updated_at`. If the
+/// rule hasn't changed since the request's `If-Modified-Since` the response is `304 Not Modified`,
+/// otherwise it's a `200` with the body added by `body`.
+fn rule_response(
+    req: &HttpRequest,
+    rule: &Rule,
+    body: impl FnOnce(&mut HttpResponseBuilder) -> HttpResponse,
+) -> HttpResponse {
+    // HTTP dates only have second precision.
+    let last_modified = rule
+        .updated_at
+        .map(|updated_at| HttpDate::from(SystemTime::from(updated_at.trunc_subsecs(0))));
+
+    let not_modified = match (last_modified, IfModifiedSince::parse(req)) {
+        (Some(last_modified), Ok(IfModifiedSince(since))) => last_modified <= since,
+        _ => false,
+    };
+
+    let mut response = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+
+    if let Some(last_modified) = last_modified {
+        response.insert_header(LastModified(last_modified));
+    }
+
+    if not_modified {
+        response.finish()
+    } else {
+        body(&mut response)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/rules/{id}/stats",
+    params(("id" = String, Path, description = "Id of the rule")),
+    responses(
+        (status = 200, description = "How often the rule has been evaluated and how many times it uses each operator", body = RuleStatsResponse),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn stats_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
     id: web::Path<String>,
-    rule: web::Json<Rule>,
 ) -> Result<impl Responder, actix_web::Error> {
-    state
-        .rule_repository
-        .update(id.into_inner(), rule.into_inner())
-        .await?;
+    let stats = state.rule_repository.stats(&id).await?;
+    let rule = state.rule_repository.get(&id).await?;
 
-    Ok(HttpResponse::Ok())
+    Ok(HttpResponse::Ok().json_pretty(RuleStatsResponse {
+        stats,
+        operators: rule.operator_histogram(),
+    }))
 }
 
-#[derive(Debug, Deserialize)]
-pub struct EvaluateParams {
-    rules: Option<String>,
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleStatsResponse {
+    #[serde(flatten)]
+    stats: RuleStats,
+    /// How many times the rule's predicate uses each operator
+    operators: HashMap<Operator, usize>,
 }
 
-async fn evaluate_rules_handler<RR: RuleRepository>(
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestResult {
+    passed: bool,
+    /// The rule's message, or why its predicate couldn't be evaluated
+    message: String,
+    /// What the predicate evaluated to, `null` if it couldn't be evaluated
+    predicate_result: Value,
+}
+
+#[utoipa::path(
+    post,
+    path = "/rules/{id}/test",
+    params(("id" = String, Path, description = "Id of the rule")),
+    request_body(content = Object, description = "Arbitrary JSON input to test the rule against"),
+    responses(
+        (status = 200, description = "Whether the rule's predicate passes for the input", body = TestResult),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn test_rule_handler<RR: RuleRepository>(
     state: web::Data<AppState<RR>>,
-    ids: web::Query<EvaluateParams>,
+    id: web::Path<String>,
     input: web::Json<Value>,
 ) -> Result<impl Responder, actix_web::Error> {
-    let rules = ids
-        .into_inner()
-        .rules
-        .map(|r| r.split(",").map(String::from).collect::<Vec<_>>())
-        .unwrap_or_default();
+    let rule = state.rule_repository.get(&id).await?;
 
-    let result = state
-        .rule_repository
-        .evaluate(&rules, input.into_inner())
-        .await?;
+    // Only the predicate is evaluated, the rule's condition, active window and evaluation limit
+    // are ignored and it isn't counted in its stats.
+    let result = match rule.evaluate(&input) {
+        Ok(passed) => TestResult {
+            passed,
+            message: rule.message,
+            predicate_result: passed.into(),
+        },
+        Err(err) => TestResult {
+            passed: false,
+            message: err.to_string(),
+            predicate_result: Value::Null,
+        },
+    };
 
     Ok(HttpResponse::Ok().json_pretty(result))
 }
 
-#[derive(Debug, Clone)]
-struct AppState<RR: RuleRepository> {
-    rule_repository: RR,
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PathsResponse {
+    paths: Vec<String>,
 }
 
-fn configure_app<RR: RuleRepository>(cfg: &mut web::ServiceConfig) {
-    cfg.route("/rules", web::get().to(get_all_rules_handler::<RR>))
-        .route("/rules/{id}", web::get().to(get_rule_handler::<RR>))
-        .route("/rules", web::post().to(create_rule_handler::<RR>))
-        .route("/rules/{id}", web::put().to(update_rule_handler::<RR>))
-        .route("/rules/{id}", web::delete().to(delete_rule_handler::<RR>))
-        .route("/evaluate", web::post().to(evaluate_rules_handler::<RR>));
-}
+#[utoipa::path(
+    get,
+    path = "/rules/{id}/paths",
+    params(("id" = String, Path, description = "Id of the rule")),
+    responses(
+        (status = 200, description = "The paths read by the rule", body = PathsResponse),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn paths_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = state.rule_repository.get(&id).await?;
 
-fn create_server<RR: RuleRepository>(rule_repository: RR) -> Result<dev::Server, std::io::Error> {
-    Ok(HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(AppState {
-                rule_repository: rule_repository.clone(),
-            }))
-            .configure(configure_app::<RR>)
-    })
-    .bind(("0.0.0.0", 8080))?
-    .run())
+    Ok(HttpResponse::Ok().json_pretty(PathsResponse {
+        paths: rule
+            .referenced_paths()
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    }))
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let starting_rules: Vec<Rule> = serde_json::from_str(
-        fs::read_to_string("rules.json")
-            .expect("failed to read rules.json")
-            .as_str(),
-    )
-    .expect("failed to parse rules from rules.json");
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompatibleParams {
+    /// JSON Schema (draft-07) of the inputs the rule would be evaluated against
+    schema: String,
+}
 
-    create_server(InMemRuleRepository::new(&starting_rules))?.await
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompatibleResponse {
+    compatible: bool,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use actix_web::http::StatusCode;
-    use actix_web::{App, test, web};
-    use evaluator::repository::{Evaluation, EvaluationReason, EvaluationResult};
-    use evaluator::{predicate, rule};
-    use serde_json::json;
+#[utoipa::path(
+    get,
+    path = "/rules/{id}/compatible",
+    params(("id" = String, Path, description = "Id of the rule"), CompatibleParams),
+    responses(
+        (status = 200, description = "Whether every path read by the rule is declared by the schema", body = CompatibleResponse),
+        (status = 400, description = "The schema isn't valid JSON", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn compatible_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+    params: web::Query<CompatibleParams>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = state.rule_repository.get(&id).await?;
 
-    macro_rules! create_test_app {
-        () => {
-            test::init_service(
-                App::new()
-                    .app_data(web::Data::new(AppState {
-                        rule_repository: InMemRuleRepository::empty(),
-                    }))
-                    .configure(configure_app::<InMemRuleRepository>),
-            )
-            .await
-        };
-    }
+    let schema: Value = match serde_json::from_str(&params.schema) {
+        Ok(schema) => schema,
+        Err(err) => return Ok(HttpResponse::BadRequest().json_pretty(ApiError::from(err))),
+    };
 
-    macro_rules! get_rules {
-        ($app:expr) => {{
-            let req = test::TestRequest::get().uri("/rules").to_request();
-            let resp: Vec<Rule> = test::call_and_read_body_json(&$app, req).await;
+    Ok(HttpResponse::Ok().json_pretty(CompatibleResponse {
+        compatible: rule.applies_to_schema(&schema),
+    }))
+}
 
-            resp
-        }};
-    }
+#[utoipa::path(
+    get,
+    path = "/rules/{id}/diagram",
+    params(("id" = String, Path, description = "Id of the rule")),
+    responses(
+        (status = 200, description = "The predicate tree of the rule", body = String, content_type = "text/plain"),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn diagram_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = state.rule_repository.get(&id).await?;
 
-    macro_rules! create_rule {
-        ($app:expr, $rule:expr) => {{
-            let req = test::TestRequest::post()
-                .uri("/rules")
-                .set_json(&$rule)
-                .to_request();
-            let resp = test::call_service(&$app, req).await;
+    Ok(HttpResponse::Ok()
+        .content_type(mime::TEXT_PLAIN_UTF_8)
+        .body(rule.to_predicate_diagram()))
+}
 
-            resp
-        }};
-    }
+/// How many passing and failing examples `GET /rules/{id}/evaluate-examples` returns at most.
+const MAX_EXAMPLES: usize = 3;
 
-    macro_rules! get_rule {
-        ($app:expr, $id:expr) => {
-            get_rule!(Rule, $app, $id)
-        };
-        ($kind:tt, $app:expr, $id:expr) => {{
-            let req = test::TestRequest::get()
-                .uri(&format!("/rules/{}", $id))
-                .to_request();
-            let resp: $kind = test::call_and_read_body_json(&$app, req).await;
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExamplesResponse {
+    /// Inputs the rule's predicate passes for
+    passing: Vec<Value>,
+    /// Inputs the rule's predicate fails for
+    failing: Vec<Value>,
+}
 
-            resp
-        }};
-    }
+#[utoipa::path(
+    get,
+    path = "/rules/{id}/evaluate-examples",
+    params(("id" = String, Path, description = "Id of the rule")),
+    responses(
+        (status = 200, description = "Generated inputs the rule's predicate passes and fails for", body = ExamplesResponse),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+        (status = 501, description = "No examples could be generated for the rule's predicate", body = ApiError)
+    )
+)]
+async fn examples_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = state.rule_repository.get(&id).await?;
 
-    macro_rules! delete_rule {
-        ($app:expr, $id:expr) => {{
-            let req = test::TestRequest::delete()
-                .uri(&format!("/rules/{}", $id))
-                .to_request();
-            let resp = test::call_service(&$app, req).await;
+    let examples = ExamplesResponse {
+        passing: rule.examples(true, MAX_EXAMPLES),
+        failing: rule.examples(false, MAX_EXAMPLES),
+    };
 
-            resp
-        }};
+    if examples.passing.is_empty() && examples.failing.is_empty() {
+        return Ok(HttpResponse::NotImplemented().json_pretty(ApiError {
+            error: InnerError {
+                message: format!("examples can't be generated for the predicate of rule {id}"),
+                details: None,
+            },
+        }));
     }
 
-    macro_rules! update_rule {
-        ($app:expr, $id:expr, $rule:expr) => {{
-            let req = test::TestRequest::put()
-                .uri(&format!("/rules/{}", $id))
-                .set_json(&$rule)
-                .to_request();
-            let resp = test::call_service(&$app, req).await;
+    Ok(HttpResponse::Ok().json_pretty(examples))
+}
 
-            resp
-        }};
-    }
+#[utoipa::path(
+    get,
+    path = "/rules/events",
+    responses(
+        (status = 101, description = "Upgrades to a WebSocket which receives a JSON message for every change to the rules", body = RuleChangeEvent),
+        (status = 400, description = "The request isn't a WebSocket handshake")
+    )
+)]
+async fn rule_events_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> Result<impl Responder, actix_web::Error> {
+    let (response, mut session, mut messages) = actix_ws::handle(&req, body)?;
+    let mut events = Box::pin(state.rule_repository.watch());
 
-    macro_rules! evaluate {
-        ($app:expr, $ids:expr, $input:expr) => {{
-            let ids = $ids
-                .into_iter()
-                .map(|s| String::from(s))
-                .collect::<Vec<_>>()
-                .join(",");
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(event) = events.next() => {
+                    let Ok(event) = serde_json::to_string(&event) else {
+                        continue;
+                    };
 
-            let req = test::TestRequest::post()
-                .uri(&format!("/evaluate?rules={}", ids))
-                .set_json(&$input)
-                .to_request();
-            let resp: Evaluation = test::call_and_read_body_json(&$app, req).await;
+                    if session.text(event).await.is_err() {
+                        return;
+                    }
+                }
+                message = messages.recv() => match message {
+                    Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(actix_ws::Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
 
-            resp
-        }};
-    }
+        let _ = session.close(None).await;
+    });
 
-    #[actix_web::test]
-    async fn test_get_rules_empty() {
-        let app = create_test_app!();
-        let resp = get_rules!(app);
-        assert_eq!(resp.len(), 0);
-    }
+    Ok(response)
+}
 
-    #[actix_web::test]
-    async fn test_create_rule() {
-        let app = create_test_app!();
+#[utoipa::path(
+    post,
+    path = "/rules",
+    params(
+        ("X-Author" = Option<String>, Header, description = "Who is creating the rule, replacing any `author` in the body")
+    ),
+    request_body(content(
+        (Rule = "application/json"),
+        (Rule = "application/yaml")
+    )),
+    responses(
+        (status = 201, description = "The rule was created"),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn create_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    req: HttpRequest,
+    rule: web::Json<Rule>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = with_author(rule.into_inner(), &req);
+    state.rule_repository.create(rule).await?;
 
-        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+    Ok(HttpResponse::Created())
+}
 
-        let resp = create_rule!(app, rule);
-        assert!(resp.response().status().is_success());
+async fn create_rule_yaml_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    req: HttpRequest,
+    body: String,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = match Rule::from_yaml(&body) {
+        Ok(rule) => rule,
+        Err(err) => return Ok(HttpResponse::BadRequest().json_pretty(ApiError::from(err))),
+    };
 
-        let resp = get_rule!(app, "rule-1");
-        assert_eq!(resp, rule);
+    state
+        .rule_repository
+        .create(with_author(rule, &req))
+        .await?;
 
-        let resp = get_rules!(app);
-        assert!(resp.contains(&rule));
+    Ok(HttpResponse::Created().finish())
+}
+
+/// Sets the author of `rule` from the `X-Author` header, if given.
+fn with_author(mut rule: Rule, req: &HttpRequest) -> Rule {
+    if let Some(author) = req
+        .headers()
+        .get(AUTHOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        rule.author = Some(author.to_owned());
     }
 
-    #[actix_web::test]
-    async fn test_delete_rule() {
-        let app = create_test_app!();
+    rule
+}
 
-        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReviewRequest {
+    reviewer: String,
+}
 
-        let resp = create_rule!(app, rule);
-        assert!(resp.response().status().is_success());
+#[utoipa::path(
+    put,
+    path = "/rules/{id}/review",
+    params(("id" = String, Path, description = "Id of the rule to approve")),
+    request_body = ReviewRequest,
+    responses(
+        (status = 200, description = "The reviewed rule", body = Rule),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn review_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+    request: web::Json<ReviewRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let mut rule = state.rule_repository.get(&id).await?;
+    rule.reviewed_by = Some(request.into_inner().reviewer);
 
-        let resp = get_rule!(app, "rule-1");
-        assert_eq!(resp, rule);
+    state
+        .rule_repository
+        .update(id.into_inner(), rule.clone())
+        .await?;
 
-        let resp = delete_rule!(app, "rule-1");
-        assert!(resp.response().status().is_success());
+    Ok(HttpResponse::Ok().json_pretty(rule))
+}
 
-        let resp = delete_rule!(app, "rule-1");
-        assert!(
-            resp.response().status().is_success(),
-            "delete should be idempotent"
-        );
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportRequest {
+    url: String,
+}
 
-        let resp = get_rules!(app);
-        assert_eq!(resp.len(), 0);
-    }
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    imported: usize,
+}
 
-    #[actix_web::test]
+/// Either every rule is imported or none are, see `RuleRepository::create_many`. Rules can only be
+/// imported from the hosts in `EVALUATOR_IMPORT_ALLOWED_HOSTS`.
+#[utoipa::path(
+    post,
+    path = "/rules/import",
+    request_body = ImportRequest,
+    responses(
+        (status = 201, description = "The rules were created", body = ImportResponse),
+        (status = 400, body = ApiError),
+        (status = 403, body = ApiError),
+        (status = 502, body = ApiError),
+        (status = 504, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn import_rules_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    request: web::Json<ImportRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rules = remote::import_from_url(&request.url, &state.import_allowed_hosts).await?;
+    let imported = rules.len();
+
+    state.rule_repository.create_many(rules).await?;
+
+    Ok(HttpResponse::Created().json_pretty(ImportResponse { imported }))
+}
+
+/// Body of `POST /rules/import/json-logic`, a rule whose predicate is written in JSON Logic.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportJsonLogicRequest {
+    id: String,
+    message: String,
+    /// The JSON Logic rule to convert into the predicate, see `Predicate::from_json_logic`
+    logic: Value,
+}
+
+#[utoipa::path(
+    post,
+    path = "/rules/import/json-logic",
+    params(
+        ("X-Author" = Option<String>, Header, description = "Who is creating the rule")
+    ),
+    request_body = ImportJsonLogicRequest,
+    responses(
+        (status = 201, description = "The rule was created", body = Rule),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn import_json_logic_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    req: HttpRequest,
+    request: web::Json<ImportJsonLogicRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let ImportJsonLogicRequest { id, message, logic } = request.into_inner();
+
+    let predicate = Predicate::from_json_logic(&logic)?;
+    let rule = with_author(evaluator::rule!(id, message, predicate), &req);
+
+    state.rule_repository.create(rule.clone()).await?;
+
+    Ok(HttpResponse::Created().json_pretty(rule))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/rules/{id}",
+    params(("id" = String, Path, description = "Id of the rule")),
+    responses(
+        (status = 200, description = "The rule no longer exists"),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn delete_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+) -> Result<impl Responder, actix_web::Error> {
+    state.rule_repository.delete(&id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok())
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CloneRuleRequest {
+    new_id: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/rules/{id}/clone",
+    params(("id" = String, Path, description = "Id of the rule to clone")),
+    request_body = CloneRuleRequest,
+    responses(
+        (status = 201, description = "The cloned rule", body = Rule),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn clone_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+    request: web::Json<CloneRuleRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let new_id = request.into_inner().new_id.unwrap_or_default();
+
+    let rule = state.rule_repository.clone_rule(&id, new_id).await?;
+
+    Ok(HttpResponse::Created().json_pretty(rule))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenameRuleRequest {
+    new_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/rules/{id}/rename",
+    params(("id" = String, Path, description = "Id of the rule to rename")),
+    request_body = RenameRuleRequest,
+    responses(
+        (status = 200, description = "The renamed rule", body = Rule),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn rename_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+    request: web::Json<RenameRuleRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule = state
+        .rule_repository
+        .rename(id.into_inner(), request.into_inner().new_id)
+        .await?;
+
+    Ok(HttpResponse::Ok().json_pretty(rule))
+}
+
+#[utoipa::path(
+    put,
+    path = "/rules/{id}",
+    params(("id" = String, Path, description = "Id of the rule to replace")),
+    request_body = Rule,
+    responses(
+        (status = 200, description = "The rule was replaced"),
+        (status = 404, body = ApiError),
+        (status = 409, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn update_rule_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    id: web::Path<String>,
+    rule: web::Json<Rule>,
+) -> Result<impl Responder, actix_web::Error> {
+    state
+        .rule_repository
+        .update(id.into_inner(), rule.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct EvaluateParams {
+    /// Comma separated list of rule ids to evaluate
+    rules: Option<String>,
+    /// Preprocessing applied to the input before it's evaluated, `lowercase_keys` or `flatten_arrays`
+    transform: Option<InputTransform>,
+    /// Whether to stop evaluating after the first rule which fails or errors, whatever the `mode`. Defaults to `false`
+    #[serde(default)]
+    stop_on_first_failure: bool,
+    /// Whether to stop evaluating after the first rule which decides whether the overall result passes for the `mode`. Defaults to `false`
+    #[serde(default)]
+    stop_when_decided: bool,
+    /// Whether the overall result is always `PASS`, with each reason still recording its rule's actual result. Defaults to `false`
+    #[serde(default)]
+    pass_through: bool,
+    /// Whether the overall result passes if `all` of the rules, `any` of them or `none` of them pass. Defaults to `all`
+    #[serde(default)]
+    mode: EvaluationMode,
+    /// Whether to include the per rule `reasons` in the response. Defaults to `true`
+    #[serde(default = "default_true")]
+    include_reasons: bool,
+    /// Whether to include the value read from the input in each reason. Defaults to `true`
+    #[serde(default = "default_true")]
+    include_input_values: bool,
+    /// Whether to include how long each rule took to evaluate in its reason. Defaults to `false`
+    #[serde(default)]
+    timing: bool,
+    /// Whether to include the predicate each rule was evaluated with in its reason. Defaults to `false`
+    #[serde(default)]
+    snapshot_predicates: bool,
+    /// Whether to include how many of each rule's raw predicates matched in its reason. Defaults to `false`
+    #[serde(default)]
+    include_match_counts: bool,
+    /// Whether to include the operator and path of each rule with a single (non-compound) predicate in its reason. Defaults to `false`
+    #[serde(default)]
+    include_operator_context: bool,
+}
+
+/// Body of `POST /evaluate`, the input to evaluate the rules against. It can be any JSON value,
+/// the rules to evaluate and how are given as query parameters.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct EvaluateRequest(Value);
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct EvaluateCategoryParams {
+    /// Whether a category without any rules is a `404` rather than passing. Defaults to `false`
+    #[serde(default)]
+    fail_on_empty: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EvaluationFormatParams {
+    /// How results are written, `standard` or `boolean`. Defaults to `standard`
+    #[serde(default)]
+    format: EvaluationFormat,
+}
+
+/// How the results of `POST /evaluate` are written in the response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EvaluationFormat {
+    /// As `"PASS"`, `"FAIL"`, `"SKIPPED"` or `"ERROR"`.
+    #[default]
+    Standard,
+    /// As `true` for `PASS` and `false` for `FAIL`, for consumers expecting booleans. `SKIPPED`
+    /// and `ERROR` are written as in `standard`.
+    Boolean,
+}
+
+/// Response body of `POST /evaluate`, optionally leaving out the reasons behind the result.
+#[derive(Debug, Serialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+enum EvaluationResponse {
+    Full(Evaluation),
+    Boolean(BooleanEvaluation),
+    ResultOnly {
+        result: EvaluationResult,
+        weighted_score: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        context: Option<EvaluationContext>,
+    },
+    BooleanResultOnly {
+        result: BooleanEvaluationResult,
+        weighted_score: f64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        context: Option<EvaluationContext>,
+    },
+}
+
+impl EvaluationResponse {
+    /// The response with its results written in `format`.
+    fn with_format(self, format: EvaluationFormat) -> EvaluationResponse {
+        match (self, format) {
+            (EvaluationResponse::Full(evaluation), EvaluationFormat::Boolean) => {
+                EvaluationResponse::Boolean(evaluation.to_boolean_form())
+            }
+            (
+                EvaluationResponse::ResultOnly {
+                    result,
+                    weighted_score,
+                    context,
+                },
+                EvaluationFormat::Boolean,
+            ) => EvaluationResponse::BooleanResultOnly {
+                result: BooleanEvaluationResult(result),
+                weighted_score,
+                context,
+            },
+            (response, _) => response,
+        }
+    }
+}
+
+impl EvaluateParams {
+    fn rule_ids(&self) -> Vec<String> {
+        self.rules
+            .as_ref()
+            .map(|r| r.split(",").map(String::from).collect::<Vec<_>>())
+            .unwrap_or_default()
+    }
+
+    /// `input` with the requested transform applied, if any.
+    fn input(&self, input: Value) -> Value {
+        match self.transform {
+            Some(transform) => transform.apply(input),
+            None => input,
+        }
+    }
+
+    fn response(&self, evaluation: Evaluation) -> EvaluationResponse {
+        if self.include_reasons {
+            EvaluationResponse::Full(evaluation)
+        } else {
+            EvaluationResponse::ResultOnly {
+                result: evaluation.result,
+                weighted_score: evaluation.weighted_score,
+                context: evaluation.context,
+            }
+        }
+    }
+
+    /// The requested options, with at most `max_rules` rules evaluated, see `AppState`.
+    fn options(&self, max_rules: Option<usize>) -> EvaluationOptions {
+        EvaluationOptions {
+            stop_on_first_failure: self.stop_on_first_failure,
+            stop_when_decided: self.stop_when_decided,
+            pass_through: self.pass_through,
+            include_input_values: self.include_input_values,
+            include_timings: self.timing,
+            snapshot_predicates: self.snapshot_predicates,
+            include_match_counts: self.include_match_counts,
+            include_operator_context: self.include_operator_context,
+            max_rules,
+            mode: self.mode,
+            ..Default::default()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate",
+    params(
+        EvaluateParams,
+        EvaluationFormatParams,
+        ("X-Request-Id" = Option<String>, Header, description = "Identifies the request, echoed back in the `context` of the evaluation")
+    ),
+    request_body(content = Object, description = "Arbitrary JSON input to evaluate the rules against"),
+    responses(
+        (status = 200, description = "Result of evaluating the rules, with `true`/`false` results if `format=boolean`", body = Evaluation),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 413, description = "The input is larger than `EVALUATOR_MAX_BODY_SIZE`", body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_rules_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    req: HttpRequest,
+    ids: web::Query<EvaluateParams>,
+    format: web::Query<EvaluationFormatParams>,
+    input: web::Json<EvaluateRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rules = ids.rule_ids();
+
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    let result = match request_id {
+        Some(request_id) => {
+            state
+                .rule_repository
+                .evaluate_with_context(
+                    &rules,
+                    ids.input(input.into_inner().0),
+                    ids.options(state.max_eval_rules),
+                    EvaluationContext::new(request_id),
+                )
+                .await?
+        }
+        None => {
+            state
+                .rule_repository
+                .evaluate(
+                    &rules,
+                    ids.input(input.into_inner().0),
+                    ids.options(state.max_eval_rules),
+                )
+                .await?
+        }
+    };
+
+    Ok(HttpResponse::Ok().json_pretty(ids.response(result).with_format(format.format)))
+}
+
+const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/stream",
+    params(EvaluateParams),
+    request_body(content = Object, description = "Arbitrary JSON input to evaluate the rules against"),
+    responses(
+        (status = 200, description = "The reason for each rule as a line of JSON, sent as soon as the rule is evaluated", body = EvaluationReason, content_type = "application/x-ndjson"),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_stream_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    ids: web::Query<EvaluateParams>,
+    input: web::Json<Value>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule_ids = ids.rule_ids();
+    ids.options(state.max_eval_rules)
+        .check_rule_count(rule_ids.len())?;
+
+    let mut reasons = Box::pin(
+        state
+            .rule_repository
+            .evaluate_streaming(&rule_ids, ids.input(input.into_inner())),
+    );
+
+    // Errors before the first reason, e.g. a missing rule, get a proper status. Any later error
+    // can only end the response early.
+    let first = reasons.next().await.transpose()?;
+
+    let lines = tokio_stream::iter(first.map(Ok))
+        .chain(reasons)
+        .map(|reason| {
+            let mut line = serde_json::to_vec(&reason?)
+                .map_err(|err| EvaluateRuleError::Internal(err.to_string()))?;
+            line.push(b'\n');
+
+            Ok::<_, EvaluateRuleError>(web::Bytes::from(line))
+        });
+
+    Ok(HttpResponse::Ok()
+        .content_type(NDJSON_CONTENT_TYPE)
+        .streaming(lines))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EvaluateAtRequest {
+    /// The time to evaluate the rules at, deciding which rules are active
+    at: DateTime<Utc>,
+    /// Ids of the rules to evaluate
+    rules: Vec<String>,
+    /// Arbitrary JSON input to evaluate the rules against
+    input: Value,
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/at",
+    request_body = EvaluateAtRequest,
+    responses(
+        (status = 200, description = "Result of evaluating the rules as if at the given time", body = Evaluation),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_at_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    request: web::Json<EvaluateAtRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let EvaluateAtRequest { at, rules, input } = request.into_inner();
+
+    let options = EvaluationOptions {
+        evaluate_at: Some(at),
+        max_rules: state.max_eval_rules,
+        ..Default::default()
+    };
+
+    let result = state
+        .rule_repository
+        .evaluate(&rules, input, options)
+        .await?;
+
+    Ok(HttpResponse::Ok().json_pretty(result))
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EvaluateGroupedRequest {
+    /// Id of the rule to evaluate
+    rule_id: String,
+    /// The JSON inputs to group and evaluate the rule against
+    inputs: Vec<Value>,
+    /// The path to group the inputs by, instead of the rule's `groupBy`
+    group_by: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/grouped",
+    request_body = EvaluateGroupedRequest,
+    responses(
+        (status = 200, description = "Result of evaluating the rule for each group of inputs, keyed by the value they're grouped by", body = HashMap<String, Evaluation>),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_grouped_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    request: web::Json<EvaluateGroupedRequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let EvaluateGroupedRequest {
+        rule_id,
+        inputs,
+        group_by,
+    } = request.into_inner();
+
+    let evaluations = state
+        .rule_repository
+        .evaluate_grouped(&rule_id, inputs, group_by, EvaluationOptions::default())
+        .await?;
+
+    Ok(HttpResponse::Ok().json_pretty(evaluations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/indexed",
+    params(EvaluateParams),
+    request_body(content = Object, description = "Arbitrary JSON input to evaluate the rules against"),
+    responses(
+        (status = 200, description = "Result of evaluating the rules, with the reasons keyed by rule id", body = IndexedEvaluation),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_indexed_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    ids: web::Query<EvaluateParams>,
+    input: web::Json<Value>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rule_ids = ids.rule_ids();
+    ids.options(state.max_eval_rules)
+        .check_rule_count(rule_ids.len())?;
+
+    let result = state
+        .rule_repository
+        .evaluate_indexed(&rule_ids, ids.input(input.into_inner()))
+        .await?;
+
+    Ok(HttpResponse::Ok().json_pretty(result))
+}
+
+const XML_CONTENT_TYPE: &str = "application/xml";
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct JunitParams {
+    /// Name of the test suite in the report. Defaults to `evaluator`
+    #[serde(default = "default_suite_name")]
+    suite_name: String,
+}
+
+fn default_suite_name() -> String {
+    "evaluator".to_owned()
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/junit",
+    params(EvaluateParams, JunitParams),
+    request_body(content = Object, description = "Arbitrary JSON input to evaluate the rules against"),
+    responses(
+        (status = 200, description = "Result of evaluating the rules as a JUnit XML report, with a test case for each rule", body = String, content_type = "application/xml"),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_junit_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    ids: web::Query<EvaluateParams>,
+    junit_params: web::Query<JunitParams>,
+    input: web::Json<Value>,
+) -> Result<impl Responder, actix_web::Error> {
+    let result = state
+        .rule_repository
+        .evaluate(
+            &ids.rule_ids(),
+            ids.input(input.into_inner()),
+            ids.options(state.max_eval_rules),
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(XML_CONTENT_TYPE)
+        .body(result.to_junit_xml(&junit_params.suite_name)))
+}
+
+const MARKDOWN_CONTENT_TYPE: &str = "text/markdown; charset=utf-8";
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReportParams {
+    /// URL encoded JSON input to evaluate the rules against
+    input: String,
+    /// How the report is written, only `markdown` is supported. Defaults to `markdown`
+    #[serde(default)]
+    format: ReportFormat,
+    /// Title of the report. Defaults to `Evaluation report`
+    #[serde(default = "default_report_title")]
+    title: String,
+}
+
+fn default_report_title() -> String {
+    "Evaluation report".to_owned()
+}
+
+/// How the report of `GET /evaluate/report` is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// A Markdown document, see `Evaluation::to_markdown_report`.
+    #[default]
+    Markdown,
+}
+
+#[utoipa::path(
+    get,
+    path = "/evaluate/report",
+    params(EvaluateParams, ReportParams),
+    responses(
+        (status = 200, description = "Result of evaluating the rules as a report with a row for each rule", body = String, content_type = "text/markdown"),
+        (status = 400, description = "The input isn't valid JSON, or the rules can't be evaluated against it", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_report_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    ids: web::Query<EvaluateParams>,
+    report_params: web::Query<ReportParams>,
+) -> Result<impl Responder, actix_web::Error> {
+    let input: Value = match serde_json::from_str(&report_params.input) {
+        Ok(input) => input,
+        Err(err) => return Ok(HttpResponse::BadRequest().json_pretty(ApiError::from(err))),
+    };
+
+    let result = state
+        .rule_repository
+        .evaluate(
+            &ids.rule_ids(),
+            ids.input(input),
+            ids.options(state.max_eval_rules),
+        )
+        .await?;
+
+    let report = match report_params.format {
+        ReportFormat::Markdown => result.to_markdown_report(&report_params.title),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(MARKDOWN_CONTENT_TYPE)
+        .body(report))
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/category/{category}",
+    params(
+        ("category" = String, Path, description = "Category of the rules to evaluate"),
+        EvaluateParams,
+        EvaluateCategoryParams
+    ),
+    request_body(content = Object, description = "Arbitrary JSON input to evaluate the rules against"),
+    responses(
+        (status = 200, description = "Result of evaluating every rule in the category", body = Evaluation),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn evaluate_category_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    category: web::Path<String>,
+    params: web::Query<EvaluateParams>,
+    category_params: web::Query<EvaluateCategoryParams>,
+    input: web::Json<Value>,
+) -> Result<impl Responder, actix_web::Error> {
+    let result = state
+        .rule_repository
+        .evaluate_by_category(
+            &category,
+            params.input(input.into_inner()),
+            params.options(state.max_eval_rules),
+        )
+        .await?;
+
+    // Every evaluated rule has a reason, even when skipped.
+    if result.reasons.is_empty() && category_params.fail_on_empty {
+        return Err(EvaluateRuleError::EmptyCategory(category.into_inner()).into());
+    }
+
+    Ok(HttpResponse::Ok().json_pretty(params.response(result)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/evaluate/schema",
+    responses((status = 200, description = "JSON Schema of the body accepted by `POST /evaluate`", body = Object))
+)]
+async fn evaluate_schema_handler() -> impl Responder {
+    HttpResponse::Ok().json_pretty(schemars::schema_for!(EvaluateRequest))
+}
+
+#[utoipa::path(
+    get,
+    path = "/evaluation/response-schema",
+    responses((status = 200, description = "JSON Schema of the evaluation returned by `POST /evaluate`", body = Object))
+)]
+async fn evaluation_response_schema_handler() -> impl Responder {
+    HttpResponse::Ok().json_pretty(schemars::schema_for!(Evaluation))
+}
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    responses((status = 200, description = "OpenAPI specification of this API"))
+)]
+async fn openapi_handler() -> impl Responder {
+    HttpResponse::Ok().json_pretty(ApiDoc::openapi())
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_all_rules_handler,
+        get_rule_handler,
+        export_rules_handler,
+        paths_handler,
+        stats_handler,
+        test_rule_handler,
+        compatible_handler,
+        diagram_handler,
+        examples_handler,
+        rule_events_handler,
+        create_rule_handler,
+        import_rules_handler,
+        import_json_logic_handler,
+        delete_rule_handler,
+        update_rule_handler,
+        review_rule_handler,
+        clone_rule_handler,
+        rename_rule_handler,
+        evaluate_rules_handler,
+        evaluate_category_handler,
+        evaluate_at_handler,
+        evaluate_grouped_handler,
+        evaluate_report_handler,
+        evaluate_stream_handler,
+        evaluate_indexed_handler,
+        evaluate_junit_handler,
+        dry_run_handler,
+        evaluate_schema_handler,
+        evaluation_response_schema_handler,
+        openapi_handler
+    ),
+    components(schemas(
+        Rule,
+        Predicate,
+        RawPredicate,
+        CompoundPredicate,
+        Operator,
+        PathSyntax,
+        Quantifier,
+        Evaluation,
+        EvaluationReason,
+        EvaluationResult,
+        EvaluationContext,
+        IndexedEvaluation,
+        RuleChangeEvent,
+        RuleStats,
+        RuleStatsResponse,
+        ApiError,
+        InnerError,
+        TestResult,
+        PathsResponse,
+        CompatibleResponse,
+        ExamplesResponse,
+        GetManyResponse,
+        GetRulesResponse,
+        CloneRuleRequest,
+        RenameRuleRequest,
+        ReviewRequest,
+        ImportRequest,
+        ImportJsonLogicRequest,
+        ImportResponse,
+        EvaluateAtRequest,
+        EvaluateGroupedRequest,
+        ReportFormat,
+        ExportFormat,
+        EvaluationFormat,
+        EvaluationMode,
+        DryRunResult
+    ))
+)]
+struct ApiDoc;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DryRunResult {
+    valid: bool,
+    issues: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/evaluate/dry-run",
+    params(EvaluateParams),
+    request_body(content = Object, description = "Arbitrary JSON input to check the rules against"),
+    responses(
+        (status = 200, description = "Issues which would prevent the rules from being evaluated", body = DryRunResult),
+        (status = 500, body = ApiError)
+    )
+)]
+async fn dry_run_handler<RR: RuleRepository>(
+    state: web::Data<AppState<RR>>,
+    ids: web::Query<EvaluateParams>,
+    input: web::Json<Value>,
+) -> Result<impl Responder, actix_web::Error> {
+    let rules = ids.rule_ids();
+    let input = ids.input(input.into_inner());
+
+    let mut issues = Vec::new();
+
+    for id in &rules {
+        match state.rule_repository.get(id).await {
+            Ok(rule) => issues.extend(rule.dry_run(&input)),
+            Err(err @ GetRuleError::NoSuchRule(_)) => issues.push(err.to_string()),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json_pretty(DryRunResult {
+        valid: issues.is_empty(),
+        issues,
+    }))
+}
+
+#[derive(Debug, Clone)]
+struct AppState<RR: RuleRepository> {
+    rule_repository: RR,
+    /// See `EvaluationOptions::max_rules`.
+    max_eval_rules: Option<usize>,
+    /// Hosts `POST /rules/import` may fetch rules from, see `remote::import_from_url`.
+    import_allowed_hosts: HashSet<String>,
+}
+
+const YAML_CONTENT_TYPE: &str = "application/yaml";
+
+/// Header identifying a request, echoed back in the `context` of an evaluation.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Header naming who created a rule, see `Rule::author`.
+const AUTHOR_HEADER: &str = "x-author";
+
+/// Overrides the file the rules are loaded from at startup. The format is detected from its
+/// extension, which must be one of `.json`, `.toml`, `.yaml` or `.yml`.
+const RULES_FILE_ENV: &str = "EVALUATOR_RULES_FILE";
+const DEFAULT_RULES_FILE: &str = "rules.json";
+
+/// Overrides the largest JSON request body accepted, in bytes. Larger bodies are rejected with a
+/// `JsonSizeLimitError`.
+const MAX_BODY_SIZE_ENV: &str = "EVALUATOR_MAX_BODY_SIZE";
+const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Overrides the most rules a single request can evaluate, see `EvaluationOptions::max_rules`.
+const MAX_EVAL_RULES_ENV: &str = "EVALUATOR_MAX_EVAL_RULES";
+const DEFAULT_MAX_EVAL_RULES: usize = 1000;
+
+fn max_eval_rules() -> usize {
+    match std::env::var(MAX_EVAL_RULES_ENV) {
+        Ok(max) => max
+            .parse()
+            .unwrap_or_else(|err| panic!("invalid {MAX_EVAL_RULES_ENV} {max}: {err}")),
+        Err(_) => DEFAULT_MAX_EVAL_RULES,
+    }
+}
+
+/// Comma separated hosts `POST /rules/import` may fetch rules from, e.g. `config.internal`. Unless
+/// set, rules can't be imported from anywhere.
+const IMPORT_ALLOWED_HOSTS_ENV: &str = "EVALUATOR_IMPORT_ALLOWED_HOSTS";
+
+fn import_allowed_hosts() -> HashSet<String> {
+    std::env::var(IMPORT_ALLOWED_HOSTS_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(|host| host.trim().to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+fn json_config() -> web::JsonConfig {
+    let limit = match std::env::var(MAX_BODY_SIZE_ENV) {
+        Ok(limit) => limit
+            .parse()
+            .unwrap_or_else(|err| panic!("invalid {MAX_BODY_SIZE_ENV} {limit}: {err}")),
+        Err(_) => DEFAULT_MAX_BODY_SIZE,
+    };
+
+    web::JsonConfig::default()
+        .limit(limit)
+        .error_handler(|err, _| match err {
+            JsonPayloadError::Overflow { limit }
+            | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+                JsonSizeLimitError { limit }.into()
+            }
+            err => err.into(),
+        })
+}
+
+fn configure_app<RR: RuleRepository>(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(json_config())
+        .route("/rules", web::get().to(get_all_rules_handler::<RR>))
+        .route("/rules/events", web::get().to(rule_events_handler::<RR>))
+        .route("/rules/export", web::get().to(export_rules_handler::<RR>))
+        .route("/rules/{id}/paths", web::get().to(paths_handler::<RR>))
+        .route("/rules/{id}/stats", web::get().to(stats_handler::<RR>))
+        .route(
+            "/rules/{id}/compatible",
+            web::get().to(compatible_handler::<RR>),
+        )
+        .route("/rules/{id}/diagram", web::get().to(diagram_handler::<RR>))
+        .route(
+            "/rules/{id}/evaluate-examples",
+            web::get().to(examples_handler::<RR>),
+        )
+        .route(
+            "/rules/{id}",
+            web::get()
+                .guard(guard::Header(header::ACCEPT.as_str(), YAML_CONTENT_TYPE))
+                .to(get_rule_yaml_handler::<RR>),
+        )
+        .route("/rules/{id}", web::get().to(get_rule_handler::<RR>))
+        .route(
+            "/rules",
+            web::post()
+                .guard(guard::Header(
+                    header::CONTENT_TYPE.as_str(),
+                    YAML_CONTENT_TYPE,
+                ))
+                .to(create_rule_yaml_handler::<RR>),
+        )
+        .route("/rules", web::post().to(create_rule_handler::<RR>))
+        .route("/rules/import", web::post().to(import_rules_handler::<RR>))
+        .route(
+            "/rules/import/json-logic",
+            web::post().to(import_json_logic_handler::<RR>),
+        )
+        .route("/rules/{id}", web::put().to(update_rule_handler::<RR>))
+        .route("/rules/{id}", web::delete().to(delete_rule_handler::<RR>))
+        .route(
+            "/rules/{id}/clone",
+            web::post().to(clone_rule_handler::<RR>),
+        )
+        .route(
+            "/rules/{id}/rename",
+            web::post().to(rename_rule_handler::<RR>),
+        )
+        .route("/rules/{id}/test", web::post().to(test_rule_handler::<RR>))
+        .route(
+            "/rules/{id}/review",
+            web::put().to(review_rule_handler::<RR>),
+        )
+        .route("/evaluate", web::post().to(evaluate_rules_handler::<RR>))
+        .route("/evaluate/dry-run", web::post().to(dry_run_handler::<RR>))
+        .route("/evaluate/at", web::post().to(evaluate_at_handler::<RR>))
+        .route(
+            "/evaluate/grouped",
+            web::post().to(evaluate_grouped_handler::<RR>),
+        )
+        .route(
+            "/evaluate/stream",
+            web::post().to(evaluate_stream_handler::<RR>),
+        )
+        .route(
+            "/evaluate/indexed",
+            web::post().to(evaluate_indexed_handler::<RR>),
+        )
+        .route(
+            "/evaluate/report",
+            web::get().to(evaluate_report_handler::<RR>),
+        )
+        .route(
+            "/evaluate/junit",
+            web::post().to(evaluate_junit_handler::<RR>),
+        )
+        .route(
+            "/evaluate/category/{category}",
+            web::post().to(evaluate_category_handler::<RR>),
+        )
+        .route("/evaluate/schema", web::get().to(evaluate_schema_handler))
+        .route(
+            "/evaluation/response-schema",
+            web::get().to(evaluation_response_schema_handler),
+        )
+        .route("/openapi.json", web::get().to(openapi_handler));
+}
+
+fn create_server<RR: RuleRepository>(rule_repository: RR) -> Result<dev::Server, std::io::Error> {
+    let max_eval_rules = max_eval_rules();
+    let import_allowed_hosts = import_allowed_hosts();
+
+    Ok(HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(AppState {
+                rule_repository: rule_repository.clone(),
+                max_eval_rules: Some(max_eval_rules),
+                import_allowed_hosts: import_allowed_hosts.clone(),
+            }))
+            .configure(configure_app::<RR>)
+    })
+    .bind(("0.0.0.0", 8080))?
+    .run())
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let rules_path =
+        std::env::var(RULES_FILE_ENV).unwrap_or_else(|_| DEFAULT_RULES_FILE.to_owned());
+    let format = RuleFileFormat::detect(&rules_path)
+        .unwrap_or_else(|| panic!("unsupported rules file format of {rules_path}"));
+
+    let rules_file = tokio::fs::File::open(&rules_path)
+        .await
+        .unwrap_or_else(|err| panic!("failed to read {rules_path}: {err}"));
+
+    let rule_repository = InMemRuleRepository::import_from_reader_as(rules_file, format)
+        .await
+        .unwrap_or_else(|err| panic!("failed to import rules from {rules_path}: {err}"));
+
+    create_server(rule_repository)?.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+    use actix_web::http::StatusCode;
+    use actix_web::{App, test, web};
+    use evaluator::repository::{
+        CreateRuleError, DeleteRuleError, Evaluation, EvaluationReason, EvaluationResult,
+        GetAllRulesError, UpdateRuleError,
+    };
+    use evaluator::{all, any, one_of, predicate, rule};
+    use serde_json::json;
+    use tokio_stream::Stream;
+
+    macro_rules! create_test_app {
+        () => {
+            create_test_app!(max_eval_rules: DEFAULT_MAX_EVAL_RULES)
+        };
+        (max_eval_rules: $max_eval_rules:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new(AppState {
+                        rule_repository: InMemRuleRepository::empty(),
+                        max_eval_rules: Some($max_eval_rules),
+                        // The host of the servers `serve` starts.
+                        import_allowed_hosts: HashSet::from(["127.0.0.1".to_owned()]),
+                    }))
+                    .configure(configure_app::<InMemRuleRepository>),
+            )
+            .await
+        };
+    }
+
+    macro_rules! get_rules {
+        ($app:expr) => {{
+            let req = test::TestRequest::get().uri("/rules").to_request();
+            let resp: Vec<Rule> = test::call_and_read_body_json(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! create_rule {
+        ($app:expr, $rule:expr) => {{
+            let req = test::TestRequest::post()
+                .uri("/rules")
+                .set_json(&$rule)
+                .to_request();
+            let resp = test::call_service(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! get_rule {
+        ($app:expr, $id:expr) => {
+            get_rule!(Rule, $app, $id)
+        };
+        ($kind:tt, $app:expr, $id:expr) => {{
+            let req = test::TestRequest::get()
+                .uri(&format!("/rules/{}", $id))
+                .to_request();
+            let resp: $kind = test::call_and_read_body_json(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! delete_rule {
+        ($app:expr, $id:expr) => {{
+            let req = test::TestRequest::delete()
+                .uri(&format!("/rules/{}", $id))
+                .to_request();
+            let resp = test::call_service(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! update_rule {
+        ($app:expr, $id:expr, $rule:expr) => {{
+            let req = test::TestRequest::put()
+                .uri(&format!("/rules/{}", $id))
+                .set_json(&$rule)
+                .to_request();
+            let resp = test::call_service(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! clone_rule {
+        ($app:expr, $id:expr, $body:expr) => {{
+            let req = test::TestRequest::post()
+                .uri(&format!("/rules/{}/clone", $id))
+                .set_json(&$body)
+                .to_request();
+            let resp = test::call_service(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! dry_run {
+        ($app:expr, $ids:expr, $input:expr) => {{
+            let req = test::TestRequest::post()
+                .uri(&format!("/evaluate/dry-run?rules={}", $ids.join(",")))
+                .set_json(&$input)
+                .to_request();
+            let resp: DryRunResult = test::call_and_read_body_json(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! evaluate {
+        ($app:expr, $ids:expr, $input:expr) => {
+            evaluate!($app, $ids, $input, "")
+        };
+        ($app:expr, $ids:expr, $input:expr, $query:expr) => {{
+            let ids = $ids
+                .into_iter()
+                .map(|s| String::from(s))
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let req = test::TestRequest::post()
+                .uri(&format!("/evaluate?rules={}{}", ids, $query))
+                .set_json(&$input)
+                .to_request();
+            let resp: Evaluation = test::call_and_read_body_json(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    macro_rules! assert_api_error {
+        ($resp:expr, $status:expr, $message:expr) => {{
+            let resp = $resp;
+            assert_eq!(resp.status(), $status);
+
+            let body: ApiError = test::read_body_json(resp).await;
+            assert_eq!(body.error.message, $message);
+        }};
+    }
+
+    #[actix_web::test]
+    async fn test_get_rules_empty() {
+        let app = create_test_app!();
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_get_many_rules() {
+        let app = create_test_app!();
+
+        let rules = [
+            rule!("rule-1", "message", predicate!("foo" == 1)),
+            rule!("rule-2", "message", predicate!("foo" == 2)),
+            rule!("rule-3", "message", predicate!("foo" == 3)),
+        ];
+
+        for rule in &rules {
+            create_rule!(app, rule);
+        }
+
+        let get_many = |ids: &str| {
+            test::TestRequest::get()
+                .uri(&format!("/rules?ids={ids}"))
+                .to_request()
+        };
+
+        let resp: GetManyResponse =
+            test::call_and_read_body_json(&app, get_many("rule-3,rule-1")).await;
+        assert_eq!(resp.rules, vec![rules[2].clone(), rules[0].clone()]);
+        assert!(resp.not_found.is_empty());
+
+        let resp: GetManyResponse =
+            test::call_and_read_body_json(&app, get_many("rule-2,rule-4")).await;
+        assert_eq!(resp.rules, vec![rules[1].clone()]);
+        assert_eq!(resp.not_found, vec!["rule-4"]);
+
+        let resp: GetManyResponse =
+            test::call_and_read_body_json(&app, get_many("rule-4,rule-5")).await;
+        assert!(resp.rules.is_empty());
+        assert_eq!(resp.not_found, vec!["rule-4", "rule-5"]);
+
+        // Without `ids` every rule is returned as before.
+        assert_eq!(get_rules!(app).len(), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_grouped() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            Rule {
+                group_by: Some("tenantId".to_owned()),
+                ..rule!("rule-1", "message", predicate!("total" > 0))
+            }
+        );
+
+        let evaluate_grouped = |body: Value| {
+            test::TestRequest::post()
+                .uri("/evaluate/grouped")
+                .set_json(body)
+                .to_request()
+        };
+        let inputs = json!([
+            {"tenantId": "a", "region": "eu", "total": 1},
+            {"tenantId": "b", "region": "eu", "total": 0},
+            {"tenantId": "a", "region": "us", "total": 2},
+            {"tenantId": "b", "region": "us", "total": 3},
+        ]);
+
+        let resp: HashMap<String, Evaluation> = test::call_and_read_body_json(
+            &app,
+            evaluate_grouped(json!({"rule_id": "rule-1", "inputs": inputs})),
+        )
+        .await;
+        assert_eq!(resp.len(), 2);
+        assert_eq!(resp["a"].result, EvaluationResult::Pass);
+        assert_eq!(resp["b"].result, EvaluationResult::Fail);
+        assert_eq!(resp["b"].reasons.len(), 2);
+
+        // The rule's `groupBy` can be overridden.
+        let resp: HashMap<String, Evaluation> = test::call_and_read_body_json(
+            &app,
+            evaluate_grouped(json!({"rule_id": "rule-1", "inputs": inputs, "group_by": "region"})),
+        )
+        .await;
+        assert_eq!(resp["eu"].result, EvaluationResult::Fail);
+        assert_eq!(resp["us"].result, EvaluationResult::Pass);
+
+        let resp = test::call_service(
+            &app,
+            evaluate_grouped(json!({"rule_id": "rule-2", "inputs": inputs})),
+        )
+        .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_at() {
+        let app = create_test_app!();
+
+        let at = |s: &str| s.parse::<DateTime<Utc>>().expect("invalid timestamp");
+
+        create_rule!(
+            app,
+            Rule {
+                active_from: Some(at("2024-01-01T00:00:00Z")),
+                active_until: Some(at("2025-01-01T00:00:00Z")),
+                ..rule!("rule-1", "message", predicate!("foo" == 12))
+            }
+        );
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" == 10)));
+
+        let evaluate_at = |timestamp: &str| {
+            test::TestRequest::post()
+                .uri("/evaluate/at")
+                .set_json(json!({
+                    "at": timestamp,
+                    "rules": ["rule-1", "rule-2"],
+                    "input": {"foo": 10},
+                }))
+                .to_request()
+        };
+
+        let resp: Evaluation =
+            test::call_and_read_body_json(&app, evaluate_at("2024-06-01T00:00:00Z")).await;
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        assert_eq!(resp.reasons[0].evaluation, EvaluationResult::Fail);
+
+        for timestamp in ["2023-12-31T23:59:59Z", "2025-01-01T00:00:00Z"] {
+            let resp: Evaluation =
+                test::call_and_read_body_json(&app, evaluate_at(timestamp)).await;
+            assert_eq!(resp.result, EvaluationResult::Pass);
+            assert_eq!(resp.reasons[0].evaluation, EvaluationResult::Skipped);
+            assert_eq!(resp.reasons[1].evaluation, EvaluationResult::Pass);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/at")
+            .set_json(json!({"at": "yesterday", "rules": ["rule-1"], "input": {}}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_rules_by_operator() {
+        let app = create_test_app!();
+
+        let rules = [
+            rule!("rule-1", "message", predicate!("amount" < 1000)),
+            rule!(
+                "rule-2",
+                "message",
+                all!(
+                    predicate!("amount" > 10),
+                    predicate!("email" ends_with "@example.com")
+                )
+            ),
+        ];
+
+        for rule in &rules {
+            create_rule!(app, rule);
+        }
+
+        let get_rules = |uri: &str| test::TestRequest::get().uri(uri).to_request();
+
+        let resp: Vec<Rule> =
+            test::call_and_read_body_json(&app, get_rules("/rules?operator=endsWith")).await;
+        assert_eq!(resp, vec![rules[1].clone()]);
+
+        // Adding a rule using the operator makes it show up.
+        let suffix = rule!("rule-3", "message", predicate!("name" ends_with "son"));
+        create_rule!(app, suffix);
+
+        let mut resp: Vec<Rule> =
+            test::call_and_read_body_json(&app, get_rules("/rules?operator=endsWith")).await;
+        resp.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(resp, vec![rules[1].clone(), suffix.clone()]);
+
+        let resp: GetManyResponse = test::call_and_read_body_json(
+            &app,
+            get_rules("/rules?ids=rule-1,rule-3&operator=endsWith"),
+        )
+        .await;
+        assert_eq!(resp.rules, vec![suffix]);
+        assert!(resp.not_found.is_empty());
+
+        let resp: Vec<Rule> =
+            test::call_and_read_body_json(&app, get_rules("/rules?operator=isNull")).await;
+        assert!(resp.is_empty());
+
+        let resp = test::call_service(&app, get_rules("/rules?operator=matches")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_governance() {
+        let app = create_test_app!();
+
+        for (id, author) in [("rule-1", "alice"), ("rule-2", "bob"), ("rule-3", "alice")] {
+            let req = test::TestRequest::post()
+                .uri("/rules")
+                .insert_header((AUTHOR_HEADER, author))
+                .set_json(rule!(id, "message", predicate!("foo" == 10)))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), StatusCode::CREATED);
+        }
+
+        let get_ids = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let rules: Vec<Rule> = test::call_and_read_body_json(app, req).await;
+                rules.into_iter().map(|rule| rule.id).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(get_ids("/rules?author=alice").await, ["rule-1", "rule-3"]);
+        assert_eq!(get_ids("/rules?author=carol").await, Vec::<String>::new());
+        assert_eq!(
+            get_ids("/rules?reviewed=false").await,
+            ["rule-1", "rule-2", "rule-3"]
+        );
+
+        let req = test::TestRequest::put()
+            .uri("/rules/rule-3/review")
+            .set_json(json!({"reviewer": "bob"}))
+            .to_request();
+        let rule: Rule = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(rule.author.as_deref(), Some("alice"));
+        assert_eq!(rule.reviewed_by.as_deref(), Some("bob"));
+
+        assert_eq!(get_ids("/rules?reviewed=false").await, ["rule-1", "rule-2"]);
+        assert_eq!(get_ids("/rules?reviewed=true").await, ["rule-3"]);
+        assert_eq!(
+            get_ids("/rules?author=alice&reviewed=false").await,
+            ["rule-1"]
+        );
+
+        let req = test::TestRequest::put()
+            .uri("/rules/missing/review")
+            .set_json(json!({"reviewer": "bob"}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_get_rules_by_creation_date() {
+        let app = create_test_app!();
+
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        let threshold = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Nanos, true);
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" == 10)));
+
+        let get_ids = |uri: String| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(&uri).to_request();
+                let rules: Vec<Rule> = test::call_and_read_body_json(app, req).await;
+                rules.into_iter().map(|rule| rule.id).collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(
+            get_ids(format!("/rules?createdAfter={threshold}")).await,
+            ["rule-2"]
+        );
+        assert_eq!(
+            get_ids(format!("/rules?createdBefore={threshold}")).await,
+            ["rule-1"]
+        );
+        assert_eq!(
+            get_ids(format!(
+                "/rules?createdAfter={threshold}&createdBefore={threshold}"
+            ))
+            .await,
+            Vec::<String>::new()
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/rules?ids=rule-1,rule-2&createdAfter={threshold}"
+            ))
+            .to_request();
+        let resp: GetManyResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.rules.len(), 1);
+        assert_eq!(resp.rules[0].id, "rule-2");
+        assert!(resp.not_found.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/rules?createdAfter=yesterday")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_max_eval_rules() {
+        let app = create_test_app!(max_eval_rules: 2);
+
+        for (id, category) in [("rule-1", "a"), ("rule-2", "a"), ("rule-3", "a")] {
+            create_rule!(
+                app,
+                Rule {
+                    category: Some(category.to_owned()),
+                    ..rule!(id, "message", predicate!("foo" == 10))
+                }
+            );
+        }
+
+        let evaluate = |uri: &str| {
+            test::TestRequest::post()
+                .uri(uri)
+                .set_json(json!({"foo": 10}))
+                .to_request()
+        };
+
+        let resp: Evaluation =
+            test::call_and_read_body_json(&app, evaluate("/evaluate?rules=rule-1,rule-2")).await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+
+        for uri in [
+            "/evaluate?rules=rule-1,rule-2,rule-3",
+            "/evaluate/junit?rules=rule-1,rule-2,rule-3",
+            "/evaluate/indexed?rules=rule-1,rule-2,rule-3",
+            "/evaluate/stream?rules=rule-1,rule-2,rule-3",
+            "/evaluate/category/a",
+        ] {
+            assert_api_error!(
+                test::call_service(&app, evaluate(uri)).await,
+                StatusCode::BAD_REQUEST,
+                "cannot evaluate 3 rules at once, the limit is 2"
+            );
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/at")
+            .set_json(json!({
+                "at": "2024-01-01T00:00:00Z",
+                "rules": ["rule-1", "rule-2", "rule-3"],
+                "input": {"foo": 10},
+            }))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::BAD_REQUEST,
+            "cannot evaluate 3 rules at once, the limit is 2"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluation_mode() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" > 20)));
+        create_rule!(
+            app,
+            Rule {
+                condition: Some(predicate!("bar" > 0).into()),
+                ..rule!("rule-3", "message", predicate!("foo" == 10))
+            }
+        );
+
+        let evaluate = |query: &str, input: Value| {
+            let req = test::TestRequest::post()
+                .uri(&format!("/evaluate?{query}"))
+                .set_json(input)
+                .to_request();
+
+            async {
+                let resp: Evaluation = test::call_and_read_body_json(&app, req).await;
+                resp.result
+            }
+        };
+
+        for (mode, expected) in [
+            ("", EvaluationResult::Fail),
+            ("&mode=all", EvaluationResult::Fail),
+            ("&mode=any", EvaluationResult::Pass),
+            ("&mode=none", EvaluationResult::Fail),
+        ] {
+            // rule-1 passes and rule-2 fails.
+            assert_eq!(
+                evaluate(&format!("rules=rule-1,rule-2{mode}"), json!({"foo": 10})).await,
+                expected,
+                "{mode}"
+            );
+        }
+
+        for (mode, expected) in [
+            ("all", EvaluationResult::Fail),
+            ("any", EvaluationResult::Fail),
+            ("none", EvaluationResult::Pass),
+        ] {
+            // Both rules fail.
+            assert_eq!(
+                evaluate(
+                    &format!("rules=rule-1,rule-2&mode={mode}"),
+                    json!({"foo": 1})
+                )
+                .await,
+                expected,
+                "{mode}"
+            );
+        }
+
+        for (mode, expected) in [
+            ("all", EvaluationResult::Error),
+            ("any", EvaluationResult::Pass),
+            ("none", EvaluationResult::Fail),
+        ] {
+            // rule-1 passes and the condition of rule-3 errors.
+            assert_eq!(
+                evaluate(
+                    &format!("rules=rule-1,rule-3&mode={mode}"),
+                    json!({"foo": 10, "bar": "1"})
+                )
+                .await,
+                expected,
+                "{mode}"
+            );
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1&mode=some")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_category() {
+        let app = create_test_app!();
+
+        let fraud = |id: &str, predicate: Predicate| Rule {
+            category: Some("fraud".to_owned()),
+            ..rule!(id, "message", predicate)
+        };
+
+        let rules = [
+            fraud("fraud-2", predicate!("amount" < 1000).into()),
+            fraud("fraud-1", predicate!("country" != "XX").into()),
+            rule!("other", "message", predicate!("amount" > 5000)),
+        ];
+
+        for rule in &rules {
+            create_rule!(app, rule);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/rules?category=fraud")
+            .to_request();
+        let mut resp: Vec<Rule> = test::call_and_read_body_json(&app, req).await;
+        resp.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(resp, vec![rules[1].clone(), rules[0].clone()]);
+
+        let req = test::TestRequest::get()
+            .uri("/rules?ids=fraud-1,other&category=fraud")
+            .to_request();
+        let resp: GetManyResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.rules, vec![rules[1].clone()]);
+        assert!(resp.not_found.is_empty());
+
+        let evaluate_category = |uri: &str, input: Value| {
+            test::TestRequest::post()
+                .uri(uri)
+                .set_json(input)
+                .to_request()
+        };
+
+        let resp: Evaluation = test::call_and_read_body_json(
+            &app,
+            evaluate_category(
+                "/evaluate/category/fraud",
+                json!({"amount": 2000, "country": "UK"}),
+            ),
+        )
+        .await;
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        let evaluated: Vec<_> = resp.reasons.iter().map(|reason| &reason.rule).collect();
+        assert_eq!(evaluated, ["fraud-1", "fraud-2"]);
+
+        // An empty category passes unless asked to fail.
+        let resp: Evaluation = test::call_and_read_body_json(
+            &app,
+            evaluate_category("/evaluate/category/missing", json!({})),
+        )
+        .await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+        assert!(resp.reasons.is_empty());
+
+        assert_api_error!(
+            test::call_service(
+                &app,
+                evaluate_category("/evaluate/category/missing?failOnEmpty=true", json!({}))
+            )
+            .await,
+            StatusCode::NOT_FOUND,
+            "there are no rules in category missing"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_create_rule() {
+        let app = create_test_app!();
+
+        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+
+        let resp = create_rule!(app, rule);
+        assert!(resp.response().status().is_success());
+
+        let resp = get_rule!(app, "rule-1");
+        assert_eq!(resp, rule);
+
+        let resp = get_rules!(app);
+        assert!(resp.contains(&rule));
+    }
+
+    #[actix_web::test]
+    async fn test_delete_rule() {
+        let app = create_test_app!();
+
+        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+
+        let resp = create_rule!(app, rule);
+        assert!(resp.response().status().is_success());
+
+        let resp = get_rule!(app, "rule-1");
+        assert_eq!(resp, rule);
+
+        let resp = delete_rule!(app, "rule-1");
+        assert!(resp.response().status().is_success());
+
+        let resp = delete_rule!(app, "rule-1");
+        assert!(
+            resp.response().status().is_success(),
+            "delete should be idempotent"
+        );
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 0);
+    }
+
+    #[actix_web::test]
     async fn test_update_rule() {
         let app = create_test_app!();
-        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+
+        let resp = create_rule!(app, rule);
+        assert!(resp.response().status().is_success());
+
+        let updated_rule = rule!("rule-2", "some other message", predicate!("foo" == 12));
+
+        let resp = update_rule!(app, "rule-1", updated_rule);
+        assert_eq!(resp.response().status(), StatusCode::OK);
+
+        let resp = get_rule!(app, "rule-2");
+        assert_eq!(resp, updated_rule);
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 1);
+        assert!(resp.contains(&updated_rule));
+        assert!(!resp.contains(&rule));
+
+        // Renaming onto a taken id is a conflict, as with `POST /rules/{id}/rename`.
+        create_rule!(app, rule);
+        assert_api_error!(
+            update_rule!(app, "rule-1", updated_rule),
+            StatusCode::CONFLICT,
+            "a rule with id rule-2 already exists"
+        );
+        assert_eq!(get_rules!(app).len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate() {
+        let app = create_test_app!();
+        let rule1 = rule!("rule-1", "some message", predicate!("foo" == 10));
+        let rule2 = rule!("rule-2", "some other message", predicate!("foo" == 14));
+        let rule3 = rule!("rule-3", "unused rule", predicate!("foo" < 0));
+
+        create_rule!(app, rule1);
+        create_rule!(app, rule2);
+        create_rule!(app, rule3);
+
+        let resp = evaluate!(app, ["rule-1", "rule-2"], json!({"foo": 10}));
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        assert_eq!(resp.reasons.len(), 2);
+
+        assert!(resp.reasons.contains(&EvaluationReason {
+            rule: "rule-1".to_owned(),
+            requirement: "some message".to_owned(),
+            evaluation: EvaluationResult::Pass,
+            weight: 1.0,
+            input_value: Some(json!(10)),
+            elapsed_micros: None,
+            predicate_snapshot: None,
+            match_count: None,
+            total_predicate_count: None,
+            operator: None,
+            path: None,
+            skip_reason: None,
+        }));
+
+        assert!(resp.reasons.contains(&EvaluationReason {
+            rule: "rule-2".to_owned(),
+            requirement: "some other message".to_owned(),
+            evaluation: EvaluationResult::Fail,
+            weight: 1.0,
+            input_value: Some(json!(10)),
+            elapsed_micros: None,
+            predicate_snapshot: None,
+            match_count: None,
+            total_predicate_count: None,
+            operator: None,
+            path: None,
+            skip_reason: None,
+        }));
+    }
+
+    const SLOW_RULE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+    /// Delegates to an `InMemRuleRepository`, but takes a while to evaluate the rule `slow`.
+    #[derive(Debug, Clone)]
+    struct SlowRepository {
+        inner: InMemRuleRepository,
+    }
+
+    impl RuleRepository for SlowRepository {
+        async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+            self.inner.get_all().await
+        }
+
+        async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+            self.inner.get(id).await
+        }
+
+        async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+            self.inner.create(rule).await
+        }
+
+        async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+            self.inner.delete(id).await
+        }
+
+        async fn update(
+            &self,
+            id: String,
+            new_rule: Rule,
+        ) -> Result<Option<Rule>, UpdateRuleError> {
+            self.inner.update(id, new_rule).await
+        }
+
+        fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+            self.inner.watch()
+        }
+
+        async fn evaluate(
+            &self,
+            ids: &[String],
+            input: Value,
+            options: EvaluationOptions,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            if ids.iter().any(|id| id == "slow") {
+                tokio::time::sleep(SLOW_RULE_DELAY).await;
+            }
+
+            self.inner.evaluate(ids, input, options).await
+        }
+
+        async fn evaluate_with_context(
+            &self,
+            ids: &[String],
+            input: Value,
+            options: EvaluationOptions,
+            context: EvaluationContext,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            self.inner
+                .evaluate_with_context(ids, input, options, context)
+                .await
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_stream() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(AppState {
+                    rule_repository: SlowRepository {
+                        inner: InMemRuleRepository::new(&[
+                            rule!("fast", "message", predicate!("foo" == 10)),
+                            rule!("slow", "message", predicate!("foo" == 20)),
+                        ]),
+                    },
+                    max_eval_rules: None,
+                    import_allowed_hosts: HashSet::new(),
+                }))
+                .configure(configure_app::<SlowRepository>),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/stream?rules=fast,slow")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static(NDJSON_CONTENT_TYPE))
+        );
+
+        let start = std::time::Instant::now();
+        let mut body = Box::pin(resp.into_body());
+
+        let first = std::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .expect("the first reason should be sent")
+            .expect("the body should not fail");
+        assert!(
+            start.elapsed() < SLOW_RULE_DELAY,
+            "the first reason should be sent before the slow rule is evaluated"
+        );
+
+        let first: EvaluationReason =
+            serde_json::from_slice(&first).expect("line should be a reason");
+        assert_eq!(first.rule, "fast");
+        assert_eq!(first.evaluation, EvaluationResult::Pass);
+
+        let rest = actix_web::body::to_bytes(body)
+            .await
+            .expect("the body should not fail");
+        let rest: Vec<EvaluationReason> = serde_json::Deserializer::from_slice(&rest)
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .expect("lines should be reasons");
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].rule, "slow");
+        assert_eq!(rest[0].evaluation, EvaluationResult::Fail);
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/stream?rules=missing")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_FOUND,
+            "a rule with id missing does not exist"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_request_id() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1")
+            .insert_header((REQUEST_ID_HEADER, "request-123"))
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp: Evaluation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+
+        let context = resp.context.expect("context should be echoed");
+        assert_eq!(context.request_id, "request-123");
+        assert_eq!(context.caller_id, None);
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1&includeReasons=false")
+            .insert_header((REQUEST_ID_HEADER, "request-456"))
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["context"]["requestId"], "request-456");
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.get("context"), None);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_indexed() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" > 20)));
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/indexed?rules=rule-1,rule-2")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp: IndexedEvaluation = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        assert_eq!(resp.reasons.len(), 2);
+        assert_eq!(
+            resp.get("rule-1").map(|reason| reason.evaluation),
+            Some(EvaluationResult::Pass)
+        );
+        assert_eq!(
+            resp.get("rule-2").map(|reason| reason.evaluation),
+            Some(EvaluationResult::Fail)
+        );
+        assert_eq!(resp.get("rule-3"), None);
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/indexed?rules=missing")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_FOUND,
+            "a rule with id missing does not exist"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_transform() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("name" == "Jo")));
+        create_rule!(
+            app,
+            rule!("rule-2", "message", predicate!("tags" contains "red"))
+        );
+
+        let input = json!({"Name": "Jo", "tags": [["blue"], ["red"]]});
+
+        for (query, expected) in [
+            ("", ["FAIL", "FAIL"]),
+            ("&transform=lowercase_keys", ["PASS", "FAIL"]),
+            ("&transform=flatten_arrays", ["FAIL", "PASS"]),
+            ("&transform=flatten", ["FAIL", "PASS"]),
+        ] {
+            let req = test::TestRequest::post()
+                .uri(&format!("/evaluate?rules=rule-1,rule-2{query}"))
+                .set_json(&input)
+                .to_request();
+            let resp: Value = test::call_and_read_body_json(&app, req).await;
+
+            assert_eq!(resp["reasons"][0]["evaluation"], expected[0], "{query}");
+            assert_eq!(resp["reasons"][1]["evaluation"], expected[1], "{query}");
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1&transform=uppercase_keys")
+            .set_json(&input)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_boolean_format() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" > 20)));
+
+        let evaluate = |query: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/evaluate?rules=rule-1,rule-2{query}"))
+                .set_json(json!({"foo": 10}))
+                .to_request()
+        };
+
+        let resp: Value = test::call_and_read_body_json(&app, evaluate("&format=boolean")).await;
+        assert_eq!(resp["result"], json!(false));
+        assert_eq!(resp["reasons"][0]["evaluation"], json!(true));
+        assert_eq!(resp["reasons"][1]["evaluation"], json!(false));
+        assert_eq!(resp["reasons"][0]["rule"], "rule-1");
+
+        let resp: Value =
+            test::call_and_read_body_json(&app, evaluate("&format=boolean&includeReasons=false"))
+                .await;
+        assert_eq!(resp["result"], json!(false));
+        assert!(resp.get("reasons").is_none());
+
+        let resp: Value = test::call_and_read_body_json(&app, evaluate("&format=standard")).await;
+        assert_eq!(resp["result"], "FAIL");
+        assert_eq!(resp["reasons"][0]["evaluation"], "PASS");
+
+        let resp = test::call_service(&app, evaluate("&format=yes_no")).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_report() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "foo must be 10", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "foo must be > 20", predicate!("foo" > 20))
+        );
+
+        let report = |params: &[(&str, &str)]| {
+            let query = serde_urlencoded::to_string(params).expect("query should be encoded");
+
+            test::TestRequest::get()
+                .uri(&format!("/evaluate/report?{query}"))
+                .to_request()
+        };
+
+        let resp = test::call_service(
+            &app,
+            report(&[
+                ("rules", "rule-1,rule-2"),
+                ("input", r#"{"foo": 10}"#),
+                ("format", "markdown"),
+                ("title", "Checkout"),
+            ]),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static(MARKDOWN_CONTENT_TYPE))
+        );
+
+        let body = test::read_body(resp).await;
+        let markdown = std::str::from_utf8(&body).expect("body should be UTF-8");
+        assert!(markdown.starts_with("# Checkout\n"));
+        assert!(markdown.contains("Overall: FAIL ❌\n"));
+        assert!(markdown.contains("| rule-1 | foo must be 10 | PASS ✅ |\n"));
+        assert!(markdown.contains("| rule-2 | foo must be > 20 | FAIL ❌ |\n"));
+
+        let resp = test::call_service(
+            &app,
+            report(&[("rules", "rule-1"), ("input", r#"{"foo": 10}"#)]),
+        )
+        .await;
+        let body = test::read_body(resp).await;
+        let markdown = std::str::from_utf8(&body).expect("body should be UTF-8");
+        assert!(markdown.starts_with("# Evaluation report\n"));
+        assert!(markdown.contains("Overall: PASS ✅\n"));
+
+        for (params, status) in [
+            (
+                [("rules", "rule-1"), ("input", "not json")],
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                [("rules", "missing"), ("input", "{}")],
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            assert_eq!(
+                test::call_service(&app, report(&params)).await.status(),
+                status
+            );
+        }
+
+        let resp = test::call_service(
+            &app,
+            report(&[("rules", "rule-1"), ("input", "{}"), ("format", "html")]),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_junit() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "foo must be 10", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "foo must be > 20", predicate!("foo" > 20))
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/junit?rules=rule-1,rule-2&suiteName=checkout")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static(XML_CONTENT_TYPE))
+        );
+
+        let body = test::read_body(resp).await;
+        let xml = std::str::from_utf8(&body).expect("body should be UTF-8");
+        let document = roxmltree::Document::parse(xml).expect("XML should be parseable");
+
+        assert_eq!(document.root_element().attribute("name"), Some("checkout"));
+        assert_eq!(
+            document
+                .descendants()
+                .filter(|node| node.has_tag_name("testcase"))
+                .count(),
+            2
+        );
+
+        let failures: Vec<_> = document
+            .descendants()
+            .filter(|node| node.has_tag_name("failure"))
+            .collect();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].attribute("message"), Some("foo must be > 20"));
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate/junit?rules=missing")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_FOUND,
+            "a rule with id missing does not exist"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_input_schema() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        create_rule!(
+            app,
+            Rule {
+                input_schema: Some(json!({
+                    "type": "object",
+                    "properties": {"bar": {"type": "string"}},
+                    "required": ["bar"]
+                })),
+                ..rule!("rule-2", "message", predicate!("bar" is_not_null))
+            }
+        );
+
+        let evaluate = |rules: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/evaluate?rules={rules}"))
+                .set_json(json!({"foo": 10}))
+                .to_request()
+        };
+
+        let resp: Evaluation = test::call_and_read_body_json(&app, evaluate("rule-1")).await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+
+        for rules in ["rule-2", "rule-1,rule-2"] {
+            let resp = test::call_service(&app, evaluate(rules)).await;
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+            let body: ApiError = test::read_body_json(resp).await;
+            assert_eq!(
+                body.error.message,
+                "the input does not satisfy the input schema of rule rule-2"
+            );
+            assert_eq!(
+                body.error.details,
+                Some(vec![r#""bar" is a required property"#.to_owned()])
+            );
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-2")
+            .set_json(json!({"bar": "baz"}))
+            .to_request();
+        let resp: Evaluation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+
+        let resp = create_rule!(
+            app,
+            Rule {
+                input_schema: Some(json!({"minimum": "ten"})),
+                ..rule!("rule-3", "message", predicate!("foo" == 10))
+            }
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_body_size_limit() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        let input = |size: usize| json!({"foo": 10, "padding": "a".repeat(size)});
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1")
+            .set_json(input(1024))
+            .to_request();
+        let resp: Evaluation = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.result, EvaluationResult::Pass);
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1")
+            .set_json(input(2 * 1024 * 1024))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "the request body is larger than the limit of 1048576 bytes"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_stop_on_first_failure() {
+        let app = create_test_app!();
+        let rule1 = rule!("rule-1", "some message", predicate!("foo" == 14));
+        let rule2 = rule!("rule-2", "some other message", predicate!("foo" == 10));
+
+        create_rule!(app, rule1);
+        create_rule!(app, rule2);
+
+        let resp = evaluate!(
+            app,
+            ["rule-1", "rule-2"],
+            json!({"foo": 10}),
+            "&stopOnFirstFailure=true"
+        );
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        assert_eq!(resp.reasons.len(), 1);
+        assert_eq!(resp.reasons[0].rule, "rule-1");
+
+        let resp = evaluate!(app, ["rule-1", "rule-2"], json!({"foo": 10}));
+        assert_eq!(resp.result, EvaluationResult::Fail);
+        assert_eq!(resp.reasons.len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_stop_when_decided() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 14)));
+        create_rule!(app, rule!("rule-2", "message", predicate!("foo" == 10)));
+        create_rule!(app, rule!("rule-3", "message", predicate!("foo" == 14)));
+
+        for (mode, result, evaluated) in [
+            ("all", EvaluationResult::Fail, 1),
+            ("any", EvaluationResult::Pass, 2),
+            ("none", EvaluationResult::Fail, 2),
+        ] {
+            let resp = evaluate!(
+                app,
+                ["rule-1", "rule-2", "rule-3"],
+                json!({"foo": 10}),
+                &format!("&stopWhenDecided=true&mode={mode}")
+            );
+            assert_eq!(resp.result, result, "{mode}");
+            assert_eq!(resp.reasons.len(), evaluated, "{mode}");
+
+            let resp = evaluate!(
+                app,
+                ["rule-1", "rule-2", "rule-3"],
+                json!({"foo": 10}),
+                &format!("&stopOnFirstFailure=true&mode={mode}")
+            );
+            assert_eq!(resp.reasons.len(), 1, "{mode}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_pass_through() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 14))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "some other message", predicate!("foo" > 10))
+        );
+
+        let resp = evaluate!(
+            app,
+            ["rule-1", "rule-2"],
+            json!({"foo": 10}),
+            "&passThrough=true"
+        );
+        assert_eq!(resp.result, EvaluationResult::Pass);
+        assert_eq!(resp.reasons.len(), 2);
+        assert!(
+            resp.reasons
+                .iter()
+                .all(|reason| reason.evaluation == EvaluationResult::Fail)
+        );
+
+        let resp = evaluate!(app, ["rule-1", "rule-2"], json!({"foo": 10}));
+        assert_eq!(resp.result, EvaluationResult::Fail);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_stats() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            json!({
+                "id": "rule-1",
+                "message": "some message",
+                "predicate": {"path": "foo", "operator": "==", "value": 10},
+                "maxEvaluations": 2
+            })
+        );
+
+        let stats = |app| async move {
+            let req = test::TestRequest::get()
+                .uri("/rules/rule-1/stats")
+                .to_request();
+            let resp: Value = test::call_and_read_body_json(app, req).await;
+            resp
+        };
+
+        assert_eq!(
+            stats(&app).await,
+            json!({"evaluations": 0, "passes": 0, "fails": 0, "operators": {"equal": 1}})
+        );
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10}));
+        assert_eq!(resp.result, EvaluationResult::Pass);
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 5}));
+        assert_eq!(resp.result, EvaluationResult::Fail);
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 5}));
+        assert_eq!(resp.reasons[0].evaluation, EvaluationResult::Skipped);
+        assert_eq!(
+            resp.reasons[0].skip_reason.as_deref(),
+            Some("evaluation limit reached")
+        );
+
+        assert_eq!(
+            stats(&app).await,
+            json!({"evaluations": 2, "passes": 1, "fails": 1, "operators": {"equal": 1}})
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rules/missing/stats")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_test() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "foo must be 10", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "foo must be over 10", predicate!("foo" > 10))
+        );
+
+        let test_rule = |app, id: &'static str, input: Value| async move {
+            let req = test::TestRequest::post()
+                .uri(&format!("/rules/{id}/test"))
+                .set_json(input)
+                .to_request();
+            let resp: Value = test::call_and_read_body_json(app, req).await;
+            resp
+        };
+
+        assert_eq!(
+            test_rule(&app, "rule-1", json!({"foo": 10})).await,
+            json!({"passed": true, "message": "foo must be 10", "predicateResult": true})
+        );
+        assert_eq!(
+            test_rule(&app, "rule-1", json!({"foo": 5})).await,
+            json!({"passed": false, "message": "foo must be 10", "predicateResult": false})
+        );
+        assert_eq!(
+            test_rule(&app, "rule-2", json!({"foo": "bar"})).await,
+            json!({
+                "passed": false,
+                "message": "cannot compare string with number using operator Greater",
+                "predicateResult": null
+            })
+        );
+
+        // Testing a rule isn't an evaluation.
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-1/stats")
+            .to_request();
+        let stats: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(stats["evaluations"], 0);
+
+        let req = test::TestRequest::post()
+            .uri("/rules/missing/test")
+            .set_json(json!({}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_examples() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!(
+                "rule-1",
+                "message",
+                any!(
+                    predicate!("status" == "gold"),
+                    all!(predicate!("age" >= 18), predicate!("banned" is_null))
+                )
+            )
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "message", predicate!("foo" divisible 0))
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-1/evaluate-examples")
+            .to_request();
+        let resp: Value = test::call_and_read_body_json(&app, req).await;
+
+        let examples = |key: &str| resp[key].as_array().cloned().unwrap_or_default();
+        assert_eq!(examples("passing").len(), 2);
+        assert_eq!(examples("failing").len(), 1);
+
+        for (key, passed) in [("passing", true), ("failing", false)] {
+            for example in examples(key) {
+                let req = test::TestRequest::post()
+                    .uri("/rules/rule-1/test")
+                    .set_json(&example)
+                    .to_request();
+                let resp: Value = test::call_and_read_body_json(&app, req).await;
+
+                assert_eq!(resp["predicateResult"], passed, "{example}");
+            }
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-2/evaluate-examples")
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_IMPLEMENTED,
+            "examples can't be generated for the predicate of rule rule-2"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rules/missing/evaluate-examples")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_schemas() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!(
+                "rule-2",
+                "some other message",
+                any!(predicate!("bar" is_null), predicate!("foo" > 20))
+            )
+        );
+
+        let schema = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let schema: Value = test::call_and_read_body_json(app, req).await;
+
+                assert!(jsonschema::meta::is_valid(&schema), "{schema} is not valid");
+                jsonschema::validator_for(&schema).expect("schema should compile")
+            }
+        };
+
+        let request_schema = schema("/evaluate/schema").await;
+        let response_schema = schema("/evaluation/response-schema").await;
+
+        let input = json!({"foo": 10, "bar": {"baz": [1, 2]}});
+        assert!(request_schema.is_valid(&input));
+
+        for query in [
+            "",
+            "&includeReasons=false",
+            "&timing=true&snapshotPredicates=true&includeMatchCounts=true&includeOperatorContext=true",
+        ] {
+            let req = test::TestRequest::post()
+                .uri(&format!("/evaluate?rules=rule-1,rule-2{query}"))
+                .insert_header((REQUEST_ID_HEADER, "request-1"))
+                .set_json(&input)
+                .to_request();
+            let evaluation: Value = test::call_and_read_body_json(&app, req).await;
+
+            assert!(
+                response_schema.is_valid(&evaluation),
+                "{evaluation} doesn't match the schema"
+            );
+        }
+
+        assert!(!response_schema.is_valid(&json!({"result": "MAYBE", "weightedScore": 1.0})));
+    }
+
+    #[actix_web::test]
+    async fn test_openapi_spec() {
+        let app = create_test_app!();
+
+        let req = test::TestRequest::get().uri("/openapi.json").to_request();
+        let spec: Value = test::call_and_read_body_json(&app, req).await;
+
+        let routes = [
+            ("/rules", "get"),
+            ("/rules", "post"),
+            ("/rules/{id}", "get"),
+            ("/rules/{id}", "put"),
+            ("/rules/{id}", "delete"),
+            ("/rules/{id}/paths", "get"),
+            ("/rules/{id}/stats", "get"),
+            ("/rules/{id}/diagram", "get"),
+            ("/rules/events", "get"),
+            ("/rules/{id}/clone", "post"),
+            ("/rules/import", "post"),
+            ("/rules/export", "get"),
+            ("/evaluate", "post"),
+            ("/evaluate/dry-run", "post"),
+            ("/evaluate/category/{category}", "post"),
+            ("/evaluate/at", "post"),
+            ("/openapi.json", "get"),
+        ];
+
+        for (path, method) in routes {
+            assert!(
+                spec["paths"][path][method].is_object(),
+                "{method} {path} missing from the OpenAPI spec"
+            );
+        }
+
+        for schema in ["Rule", "Predicate", "Evaluation", "ApiError"] {
+            assert!(
+                spec["components"]["schemas"][schema].is_object(),
+                "schema {schema} missing from the OpenAPI spec"
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_rule_paths() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!(
+                "rule-2",
+                "some other message",
+                all!(
+                    predicate!("age" >= 12),
+                    any!(
+                        predicate!("height.feet" > 5),
+                        all!(
+                            predicate!("height.feet" == 5),
+                            predicate!("height.inches" >= 2)
+                        )
+                    )
+                )
+            )
+        );
+
+        let resp = get_rule!(PathsResponse, app, "rule-1/paths");
+        assert_eq!(resp.paths, ["foo"]);
+
+        let resp = get_rule!(PathsResponse, app, "rule-2/paths");
+        assert_eq!(resp.paths, ["age", "height.feet", "height.inches"]);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-3/paths")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_compatible() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!(
+                "rule-1",
+                "message",
+                all!(predicate!("age" >= 12), predicate!("height.feet" > 5))
+            )
+        );
+
+        let compatible = |id: &str, schema: Value| {
+            let query = serde_urlencoded::to_string([("schema", schema.to_string())])
+                .expect("query should be encoded");
+
+            test::TestRequest::get()
+                .uri(&format!("/rules/{id}/compatible?{query}"))
+                .to_request()
+        };
+
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "age": {"type": "integer"},
+                "height": {"type": "object", "properties": {"feet": {"type": "integer"}}}
+            }
+        });
+        let resp: CompatibleResponse =
+            test::call_and_read_body_json(&app, compatible("rule-1", schema)).await;
+        assert!(resp.compatible);
+
+        let schema = json!({"type": "object", "properties": {"age": {"type": "integer"}}});
+        let resp: CompatibleResponse =
+            test::call_and_read_body_json(&app, compatible("rule-1", schema)).await;
+        assert!(!resp.compatible);
+
+        let resp = test::call_service(&app, compatible("missing", json!({}))).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-1/compatible?schema=not%20json")
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_rule_diagram() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!(
+                "rule-1",
+                "some message",
+                all!(
+                    predicate!("age" >= 18),
+                    any!(
+                        predicate!("status" == "active"),
+                        predicate!("role" == "admin")
+                    )
+                )
+            )
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-1/diagram")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get(header::CONTENT_TYPE)
+                .expect("content type should be set"),
+            "text/plain; charset=utf-8"
+        );
+
+        let body = test::read_body(resp).await;
+        assert_eq!(
+            body,
+            "all\n\
+             ├── age >= 18\n\
+             └── any\n    \
+             ├── status == \"active\"\n    \
+             └── role == \"admin\""
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-2/diagram")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_rule_events_handshake() {
+        let app = create_test_app!();
 
-        let resp = create_rule!(app, rule);
-        assert!(resp.response().status().is_success());
+        let req = test::TestRequest::get()
+            .uri("/rules/events")
+            .insert_header((header::CONNECTION, "upgrade"))
+            .insert_header((header::UPGRADE, "websocket"))
+            .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+            .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
 
-        let updated_rule = rule!("rule-2", "some other message", predicate!("foo" == 12));
+        let req = test::TestRequest::get().uri("/rules/events").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
 
-        let resp = update_rule!(app, "rule-1", updated_rule);
-        assert_eq!(resp.response().status(), StatusCode::OK);
+    #[actix_web::test]
+    async fn test_clone_rule() {
+        let app = create_test_app!();
+        let rule = rule!("rule-1", "some message", predicate!("foo" == 10));
+
+        create_rule!(app, rule);
+
+        let resp = clone_rule!(app, "rule-1", json!({"new_id": "rule-2"}));
+        assert_eq!(resp.status(), StatusCode::CREATED);
 
         let resp = get_rule!(app, "rule-2");
-        assert_eq!(resp, updated_rule);
+        assert_eq!(
+            resp,
+            rule!("rule-2", "some message", predicate!("foo" == 10))
+        );
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 2);
+        assert!(resp.contains(&rule));
+    }
+
+    #[actix_web::test]
+    async fn test_clone_rule_err() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "some other message", predicate!("foo" == 12))
+        );
+
+        let resp = clone_rule!(app, "rule-3", json!({"new_id": "rule-4"}));
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let resp = clone_rule!(app, "rule-1", json!({"new_id": "rule-2"}));
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let resp = clone_rule!(app, "rule-1", json!({"new_id": ""}));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = clone_rule!(app, "rule-1", json!({}));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 2);
+    }
+
+    macro_rules! rename_rule {
+        ($app:expr, $id:expr, $body:expr) => {{
+            let req = test::TestRequest::post()
+                .uri(&format!("/rules/{}/rename", $id))
+                .set_json(&$body)
+                .to_request();
+
+            test::call_service(&$app, req).await
+        }};
+    }
+
+    #[actix_web::test]
+    async fn test_rename_rule() {
+        let app = create_test_app!();
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+
+        let resp = rename_rule!(app, "rule-1", json!({"new_id": "rule-2"}));
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let renamed: Rule = test::read_body_json(resp).await;
+        assert_eq!(renamed.id, "rule-2");
+
+        assert_eq!(
+            get_rule!(app, "rule-2"),
+            rule!("rule-2", "some message", predicate!("foo" == 10))
+        );
 
         let resp = get_rules!(app);
         assert_eq!(resp.len(), 1);
-        assert!(resp.contains(&updated_rule));
-        assert!(!resp.contains(&rule));
     }
 
     #[actix_web::test]
-    async fn test_evaluate() {
+    async fn test_rename_rule_err() {
         let app = create_test_app!();
-        let rule1 = rule!("rule-1", "some message", predicate!("foo" == 10));
-        let rule2 = rule!("rule-2", "some other message", predicate!("foo" == 14));
-        let rule3 = rule!("rule-3", "unused rule", predicate!("foo" < 0));
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "some other message", predicate!("foo" == 12))
+        );
 
-        create_rule!(app, rule1);
-        create_rule!(app, rule2);
-        create_rule!(app, rule3);
+        assert_api_error!(
+            rename_rule!(app, "rule-3", json!({"new_id": "rule-4"})),
+            StatusCode::NOT_FOUND,
+            "a rule with id rule-3 does not exist"
+        );
 
-        let resp = evaluate!(app, ["rule-1", "rule-2"], json!({"foo": 10}));
+        assert_api_error!(
+            rename_rule!(app, "rule-1", json!({"new_id": "rule-2"})),
+            StatusCode::CONFLICT,
+            "a rule with id rule-2 already exists"
+        );
+
+        let resp = rename_rule!(app, "rule-1", json!({}));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 2);
+        assert!(resp.iter().any(|rule| rule.id == "rule-1"));
+    }
+
+    #[actix_web::test]
+    async fn test_import_json_logic() {
+        let app = create_test_app!();
+        let logic = json!({"and": [
+            {">=": [{"var": "age"}, 18]},
+            {"in": [{"var": "country"}, ["UK", "IE"]]}
+        ]});
+
+        let req = test::TestRequest::post()
+            .uri("/rules/import/json-logic")
+            .set_json(json!({"id": "rule-1", "message": "message", "logic": logic}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let expected = rule!(
+            "rule-1",
+            "message",
+            all!(predicate!("age" >= 18), one_of!("country", "UK", "IE"))
+        );
+        assert_eq!(get_rule!(app, "rule-1"), expected);
+
+        let req = test::TestRequest::post()
+            .uri("/rules/import/json-logic")
+            .set_json(json!({
+                "id": "rule-2",
+                "message": "message",
+                "logic": {"substr": [{"var": "name"}, 1]}
+            }))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::BAD_REQUEST,
+            "JSON Logic operator substr has no equivalent predicate"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/rules/import/json-logic")
+            .set_json(json!({"id": "rule-1", "message": "message", "logic": true}))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 1);
+    }
+
+    /// Serves `body` as the response to `GET /rules.json` until the server is dropped, returning
+    /// the server and the URL to request.
+    async fn serve(body: String) -> (wiremock::MockServer, String) {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+        // Not taken from wiremock's pool, so the server stops listening once dropped.
+        let server = MockServer::builder().start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/rules.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/rules.json", server.uri());
+        (server, url)
+    }
+
+    macro_rules! import_rules {
+        ($app:expr, $url:expr) => {{
+            let req = test::TestRequest::post()
+                .uri("/rules/import")
+                .set_json(json!({"url": $url}))
+                .to_request();
+            let resp = test::call_service(&$app, req).await;
+
+            resp
+        }};
+    }
+
+    #[actix_web::test]
+    async fn test_import_rules() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        let rules = [
+            rule!("rule-2", "message", predicate!("foo" == 20)),
+            rule!("rule-3", "message", predicate!("foo" == 30)),
+        ];
+        let (server, url) =
+            serve(serde_json::to_string(&rules).expect("rules should serialize")).await;
+
+        let resp = import_rules!(app, url);
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp: Value = test::read_body_json(resp).await;
+        assert_eq!(resp, json!({"imported": 2}));
+
+        let resp = get_rules!(app);
+        assert_eq!(resp.len(), 3);
+        assert!(rules.iter().all(|rule| resp.contains(rule)));
+
+        // Nothing is imported if any id is already taken.
+        let rules = [
+            rule!("rule-4", "message", predicate!("foo" == 40)),
+            rule!("rule-1", "message", predicate!("foo" == 50)),
+        ];
+        let (_other_server, other_url) =
+            serve(serde_json::to_string(&rules).expect("rules should serialize")).await;
+        assert_api_error!(
+            import_rules!(app, other_url),
+            StatusCode::BAD_REQUEST,
+            "a rule with id rule-1 already exists"
+        );
+        assert_eq!(get_rules!(app).len(), 3);
+
+        assert_api_error!(
+            import_rules!(app, "http://example.com/rules.json"),
+            StatusCode::FORBIDDEN,
+            "importing rules from example.com is not allowed"
+        );
+
+        // The rules aren't served anymore.
+        drop(server);
+        let resp = import_rules!(app, url);
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[actix_web::test]
+    async fn test_export_rules() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+        create_rule!(
+            app,
+            rule!("rule-2", "message", all!(predicate!("bar" > 5)), depends_on: ["rule-1"])
+        );
+
+        let req = test::TestRequest::get().uri("/rules/export").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_DISPOSITION),
+            Some(&header::HeaderValue::from_static(
+                "attachment; filename=\"rules.json\""
+            ))
+        );
+
+        let export = test::read_body(resp).await;
+        let export = String::from_utf8(export.to_vec()).expect("export should be UTF-8");
+
+        // Importing the export into an empty server recreates the same rules.
+        let imported = create_test_app!();
+        let (_server, url) = serve(export).await;
+        let resp = import_rules!(imported, url);
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let mut rules = get_rules!(app);
+        let mut imported = get_rules!(imported);
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+        imported.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(imported, rules);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/export?format=yaml")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static(YAML_CONTENT_TYPE))
+        );
+
+        let export = test::read_body(resp).await;
+        let mut exported = RuleFileFormat::Yaml
+            .parse(&export)
+            .expect("export should be YAML");
+        exported.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(exported, rules);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/export?format=xml")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_dry_run() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "some other message", predicate!("bar.baz" > 10))
+        );
+
+        let resp = dry_run!(app, ["rule-1", "rule-2"], json!({"bar": {"baz": 5}}));
+        assert!(resp.valid);
+        assert!(resp.issues.is_empty());
+
+        let resp = dry_run!(
+            app,
+            ["rule-1", "rule-2", "rule-3"],
+            json!({"foo": 10, "bar": {"baz": "5"}})
+        );
+        assert!(!resp.valid);
+        assert_eq!(
+            resp.issues,
+            [
+                "rule-2: cannot compare string with number using operator Greater",
+                "a rule with id rule-3 does not exist"
+            ]
+        );
+
+        let resp = dry_run!(app, ["rule-2"], json!({"bar": []}));
+        assert!(!resp.valid);
+        assert_eq!(
+            resp.issues,
+            ["rule-2: cannot read field `baz` of type array"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_get_rule_if_modified_since() {
+        let app = create_test_app!();
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        let get_rule = |if_modified_since: Option<&str>, accept: &str| {
+            let mut req = test::TestRequest::get()
+                .uri("/rules/rule-1")
+                .insert_header((header::ACCEPT, accept));
+
+            if let Some(if_modified_since) = if_modified_since {
+                req = req.insert_header((header::IF_MODIFIED_SINCE, if_modified_since));
+            }
+
+            req.to_request()
+        };
+
+        // Without `If-Modified-Since` the rule is always returned.
+        let resp = test::call_service(&app, get_rule(None, "application/json")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let last_modified = resp
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .expect("response should have Last-Modified")
+            .to_str()
+            .expect("Last-Modified should be a string")
+            .to_owned();
+
+        for accept in ["application/json", YAML_CONTENT_TYPE] {
+            let resp = test::call_service(&app, get_rule(Some(&last_modified), accept)).await;
+            assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(
+                resp.headers().get(header::LAST_MODIFIED),
+                Some(&header::HeaderValue::from_str(&last_modified).expect("valid header"))
+            );
+            assert!(test::read_body(resp).await.is_empty());
+
+            let resp = test::call_service(
+                &app,
+                get_rule(Some("Thu, 01 Jan 1970 00:00:00 GMT"), accept),
+            )
+            .await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+            assert!(!test::read_body(resp).await.is_empty());
+        }
+
+        // An invalid date is ignored.
+        let resp = test::call_service(&app, get_rule(Some("yesterday"), "application/json")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn test_yaml_rules() {
+        let app = create_test_app!();
+        let rule = rule!(
+            "rule-1",
+            "some message",
+            all!(predicate!("foo" == 10), predicate!("bar" > 5))
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/rules")
+            .insert_header((header::CONTENT_TYPE, "application/yaml"))
+            .set_payload(rule.to_yaml().expect("unable to serialize rule to YAML"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = get_rule!(app, "rule-1");
+        assert_eq!(resp, rule);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/rule-1")
+            .insert_header((header::ACCEPT, "application/yaml"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/yaml"
+        );
+
+        let body = test::read_body(resp).await;
+        let parsed = Rule::from_yaml(std::str::from_utf8(&body).expect("body should be UTF-8"))
+            .expect("body should be valid YAML");
+        assert_eq!(parsed, rule);
+
+        let req = test::TestRequest::post()
+            .uri("/rules")
+            .insert_header((header::CONTENT_TYPE, "application/yaml"))
+            .set_payload("id: rule-2\nmessage: [")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_without_reasons() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("foo" == 10))
+        );
+        create_rule!(
+            app,
+            rule!("rule-2", "some other message", predicate!("foo" == 14))
+        );
+
+        let evaluate_body = |query: &'static str| {
+            test::TestRequest::post()
+                .uri(&format!("/evaluate?rules=rule-1,rule-2{query}"))
+                .set_json(json!({"foo": 10}))
+                .to_request()
+        };
+
+        let full = test::call_and_read_body(&app, evaluate_body("")).await;
+        let summary = test::call_and_read_body(&app, evaluate_body("&includeReasons=false")).await;
+
+        assert!(summary.len() < full.len());
+
+        let full: Value = serde_json::from_slice(&full).expect("body should be valid JSON");
+        let summary: Value = serde_json::from_slice(&summary).expect("body should be valid JSON");
+
+        assert_eq!(full["result"], "FAIL");
+        assert_eq!(full["reasons"].as_array().map(Vec::len), Some(2));
+        assert_eq!(summary, json!({"result": "FAIL", "weightedScore": 0.5}));
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10}), "&includeReasons=false");
+        assert_eq!(resp.result, EvaluationResult::Pass);
+        assert!(resp.reasons.is_empty());
+    }
+    #[actix_web::test]
+    async fn test_evaluate_include_input_values() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!("rule-1", "some message", predicate!("user.ssn" == "123"))
+        );
+
+        let resp = evaluate!(app, ["rule-1"], json!({"user": {"ssn": "456"}}));
+        assert_eq!(resp.reasons[0].input_value, Some(json!("456")));
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1&includeInputValues=false")
+            .set_json(json!({"user": {"ssn": "456"}}))
+            .to_request();
+        let body: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(body["result"], "FAIL");
+        assert!(body["reasons"][0].get("inputValue").is_none());
+        assert!(!body.to_string().contains("456"));
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_timing() {
+        let app = create_test_app!();
+
+        create_rule!(app, rule!("rule-1", "message", predicate!("foo" == 10)));
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10}));
+        assert_eq!(resp.reasons[0].elapsed_micros, None);
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10}), "&timing=true");
+        assert!(resp.reasons[0].elapsed_micros.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_snapshot_predicates() {
+        let app = create_test_app!();
+
+        let rule = rule!(
+            "rule-1",
+            "message",
+            all!(predicate!("foo" == 10), predicate!("bar" contains 1))
+        );
+        create_rule!(app, rule);
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10, "bar": [1]}));
+        assert_eq!(resp.reasons[0].predicate_snapshot, None);
+
+        let resp = evaluate!(
+            app,
+            ["rule-1"],
+            json!({"foo": 10, "bar": [1]}),
+            "&snapshotPredicates=true"
+        );
+        let stored = get_rule!(app, "rule-1");
+        assert_eq!(
+            resp.reasons[0].predicate_snapshot,
+            Some(serde_json::to_value(&stored.predicate).expect("predicate should serialize"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_evaluate_match_counts() {
+        let app = create_test_app!();
+
+        create_rule!(
+            app,
+            rule!(
+                "rule-1",
+                "message",
+                all!(predicate!("foo" == 10), predicate!("bar" contains 1))
+            )
+        );
+
+        let resp = evaluate!(app, ["rule-1"], json!({"foo": 10, "bar": [2]}));
+        assert_eq!(resp.reasons[0].match_count, None);
+
+        let resp = evaluate!(
+            app,
+            ["rule-1"],
+            json!({"foo": 10, "bar": [2]}),
+            "&includeMatchCounts=true"
+        );
         assert_eq!(resp.result, EvaluationResult::Fail);
-        assert_eq!(resp.reasons.len(), 2);
+        assert_eq!(resp.reasons[0].match_count, Some(1));
+        assert_eq!(resp.reasons[0].total_predicate_count, Some(2));
+    }
 
-        assert!(resp.reasons.contains(&EvaluationReason {
-            rule: "rule-1".to_owned(),
-            requirement: "some message".to_owned(),
-            evaluation: EvaluationResult::Pass,
-        }));
+    #[actix_web::test]
+    async fn test_evaluate_operator_context() {
+        let app = create_test_app!();
 
-        assert!(resp.reasons.contains(&EvaluationReason {
-            rule: "rule-2".to_owned(),
-            requirement: "some other message".to_owned(),
-            evaluation: EvaluationResult::Fail,
-        }));
+        create_rule!(app, rule!("rule-1", "message", predicate!("age" >= 18)));
+        create_rule!(
+            app,
+            rule!(
+                "rule-2",
+                "message",
+                any!(predicate!("age" >= 18), predicate!("guardian" is_not_null))
+            )
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1,rule-2&includeOperatorContext=true")
+            .set_json(json!({"age": 17}))
+            .to_request();
+        let resp: Value = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp["reasons"][0]["operator"], json!("greaterEqual"));
+        assert_eq!(resp["reasons"][0]["path"], json!("age"));
+        assert_eq!(resp["reasons"][0]["inputValue"], json!(17));
+
+        let reason = resp["reasons"][1]
+            .as_object()
+            .expect("reason should be an object");
+        assert!(!reason.contains_key("operator"));
+        assert!(!reason.contains_key("path"));
+    }
+
+    #[actix_web::test]
+    async fn test_error_bodies() {
+        let app = create_test_app!();
+        let rule = rule!("rule-1", "some message", predicate!("foo.bar" == 10));
+
+        create_rule!(app, rule);
+
+        let req = test::TestRequest::get()
+            .uri("/rules/nonexistent")
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_FOUND,
+            "a rule with id nonexistent does not exist"
+        );
+
+        assert_api_error!(
+            create_rule!(app, rule),
+            StatusCode::BAD_REQUEST,
+            "a rule with id rule-1 already exists"
+        );
+
+        assert_api_error!(
+            update_rule!(app, "missing", rule),
+            StatusCode::NOT_FOUND,
+            "a rule with id missing does not exist"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1")
+            .set_json(json!({"foo": 10}))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::BAD_REQUEST,
+            "failed to evaluate rule rule-1: cannot read field `bar` of type number"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/evaluate?rules=rule-1,rule-2")
+            .set_json(json!({"foo": {"bar": 10}}))
+            .to_request();
+        assert_api_error!(
+            test::call_service(&app, req).await,
+            StatusCode::NOT_FOUND,
+            "a rule with id rule-2 does not exist"
+        );
+
+        // Details are only included when there's more than one problem.
+        let resp = create_rule!(app, rule!("rule-2", "", predicate!("foo" == 10)));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(
+            body.error.message,
+            "the message of rule rule-2 must not be empty"
+        );
+        assert_eq!(body.error.details, None);
+    }
+
+    #[actix_web::test]
+    async fn test_validation_details() {
+        let app = create_test_app!();
+
+        let resp = create_rule!(app, rule!("", "", predicate!("" == 10)));
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let body: ApiError = test::read_body_json(resp).await;
+        assert_eq!(body.error.message, "the rule has 3 problems");
+        assert_eq!(
+            body.error.details,
+            Some(vec![
+                "the id of a rule must not be empty".to_owned(),
+                "the message of rule  must not be empty".to_owned(),
+                "the path of every predicate of rule  must not be empty".to_owned(),
+            ])
+        );
+
+        assert!(get_rules!(app).is_empty());
     }
 }
@@ -1,4 +1,7 @@
-use actix_web::{HttpResponse, HttpResponseBuilder, error::JsonPayloadError, http::header, mime};
+use actix_web::{
+    HttpResponse, HttpResponseBuilder, error::JsonPayloadError, http::StatusCode, http::header,
+    mime,
+};
 use serde::Serialize;
 
 pub trait PrettyJson {
@@ -16,3 +19,69 @@ impl PrettyJson for HttpResponseBuilder {
         }
     }
 }
+
+impl PrettyJson for &mut HttpResponseBuilder {
+    fn json_pretty(&mut self, value: impl Serialize) -> HttpResponse {
+        (**self).json_pretty(value)
+    }
+}
+
+/// Replaces the body of the response, keeping only its status code.
+impl PrettyJson for HttpResponse {
+    fn json_pretty(&mut self, value: impl Serialize) -> HttpResponse {
+        json_pretty_with_status(self.status(), value)
+    }
+}
+
+pub fn json_pretty_with_status(status: StatusCode, value: impl Serialize) -> HttpResponse {
+    HttpResponseBuilder::new(status).json_pretty(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use serde_json::{Value, json};
+
+    async fn read_json(response: HttpResponse) -> Value {
+        let body = to_bytes(response.into_body())
+            .await
+            .expect("body should be readable");
+
+        serde_json::from_slice(&body).expect("body should be valid JSON")
+    }
+
+    #[actix_web::test]
+    async fn test_http_response() {
+        let mut response = HttpResponse::NotFound().finish();
+        let response = response.json_pretty(json!({"foo": 10}));
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static("application/json"))
+        );
+        assert_eq!(read_json(response).await, json!({"foo": 10}));
+    }
+
+    #[actix_web::test]
+    async fn test_mut_builder() {
+        fn respond(mut responder: impl PrettyJson) -> HttpResponse {
+            responder.json_pretty(json!([1, 2, 3]))
+        }
+
+        let mut builder = HttpResponse::Created();
+        let response = respond(&mut builder);
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(read_json(response).await, json!([1, 2, 3]));
+    }
+
+    #[actix_web::test]
+    async fn test_json_pretty_with_status() {
+        let response = json_pretty_with_status(StatusCode::IM_A_TEAPOT, json!({"tea": true}));
+
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(read_json(response).await, json!({"tea": true}));
+    }
+}
@@ -1,76 +1,797 @@
-use crate::core::{eval::EvaluationError, rule::Rule};
+pub mod cache;
+pub mod erased;
+pub mod file;
+pub mod format;
+pub mod logging;
+pub mod remote;
+pub mod stack;
+pub mod transaction;
+
+use crate::core::{
+    eval::{EvaluationError, FieldPresence, evaluate_many, follow_path},
+    rule::{
+        InputValidator, Operator, Predicate, Rule, RuleValidationError, default_weight,
+        is_default_weight,
+    },
+};
+use crate::repository::{
+    erased::ErasedRepository, format::RuleFileFormat, transaction::TransactionGuard,
+};
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, mapref::entry::Entry as DashEntry};
+use indexmap::{IndexMap, map::Entry};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::Hash,
-    sync::{Arc, RwLock},
+    ops::{BitAnd, BitOr},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
 };
 use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{RwLock, broadcast};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+use tracing::Instrument;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct Evaluation {
     pub result: EvaluationResult,
+    #[serde(default)]
     pub reasons: Vec<EvaluationReason>,
+    /// See `Evaluation::weighted_score`.
+    pub weighted_score: f64,
+    /// The context the rules were evaluated in, if evaluated with one, so the evaluation can be
+    /// correlated with the request it was made for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<EvaluationContext>,
+}
+
+impl Evaluation {
+    pub fn new(result: EvaluationResult, reasons: Vec<EvaluationReason>) -> Self {
+        let mut evaluation = Self {
+            result,
+            reasons,
+            weighted_score: 0.0,
+            context: None,
+        };
+
+        evaluation.weighted_score = evaluation.weighted_score();
+        evaluation
+    }
+
+    /// An evaluation whose result is derived from `reasons`: every reason must pass, ignoring
+    /// skipped rules, and any error takes precedence over a failure.
+    pub fn from_reasons(reasons: Vec<EvaluationReason>) -> Self {
+        let result = EvaluationMode::All.combine(reasons.iter().map(|reason| reason.evaluation));
+
+        Self::new(result, reasons)
+    }
+
+    /// Combines the reasons of two evaluations, e.g. of batches evaluated separately. When both
+    /// evaluated the same rule the reason from `other` is kept, as the later evaluation, in the
+    /// position of the rule in `self`. See `from_reasons` for how the result is derived. The
+    /// context of `self` is kept.
+    pub fn merge(self, other: Evaluation) -> Evaluation {
+        let mut reasons = self.reasons;
+
+        for reason in other.reasons {
+            match reasons
+                .iter_mut()
+                .find(|existing| existing.rule == reason.rule)
+            {
+                Some(existing) => *existing = reason,
+                None => reasons.push(reason),
+            }
+        }
+
+        Evaluation {
+            context: self.context,
+            ..Self::from_reasons(reasons)
+        }
+    }
+
+    /// The weight of the passing rules as a fraction of the weight of all evaluated rules. Skipped
+    /// rules aren't counted and rules that errored count as failed. If nothing with any weight was
+    /// evaluated the score is `1.0`, as nothing failed.
+    pub fn weighted_score(&self) -> f64 {
+        let (passed, total) = self
+            .reasons
+            .iter()
+            .fold((0.0, 0.0), |(passed, total), reason| {
+                match reason.evaluation {
+                    EvaluationResult::Pass => (passed + reason.weight, total + reason.weight),
+                    EvaluationResult::Fail | EvaluationResult::Error => {
+                        (passed, total + reason.weight)
+                    }
+                    EvaluationResult::Skipped => (passed, total),
+                }
+            });
+
+        if total > 0.0 { passed / total } else { 1.0 }
+    }
+
+    /// The evaluation with its results serialized as `true`/`false` rather than `"PASS"`/`"FAIL"`,
+    /// see `BooleanEvaluationResult`.
+    pub fn to_boolean_form(&self) -> BooleanEvaluation {
+        BooleanEvaluation {
+            result: BooleanEvaluationResult(self.result),
+            reasons: self
+                .reasons
+                .iter()
+                .cloned()
+                .map(BooleanEvaluationReason)
+                .collect(),
+            weighted_score: self.weighted_score,
+            context: self.context.clone(),
+        }
+    }
+
+    /// The evaluation as a JUnit XML test suite named `suite_name`, for CI systems to report on.
+    /// Each reason is a test case named after its rule: a `Fail` has a `<failure>` and an `Error`
+    /// an `<error>`, both with the rule's requirement as the message, and a `Skipped` rule is
+    /// `<skipped>`.
+    pub fn to_junit_xml(&self, suite_name: &str) -> String {
+        let count = |result: EvaluationResult| {
+            self.reasons
+                .iter()
+                .filter(|reason| reason.evaluation == result)
+                .count()
+        };
+
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">\n",
+            escape_xml(suite_name),
+            self.reasons.len(),
+            count(EvaluationResult::Fail),
+            count(EvaluationResult::Error),
+            count(EvaluationResult::Skipped),
+        );
+
+        for reason in &self.reasons {
+            xml.push_str(&format!(
+                "  <testcase name=\"{}\" classname=\"{}\"",
+                escape_xml(&reason.rule),
+                escape_xml(suite_name)
+            ));
+
+            if let Some(elapsed_micros) = reason.elapsed_micros {
+                xml.push_str(&format!(
+                    " time=\"{}\"",
+                    elapsed_micros as f64 / 1_000_000.0
+                ));
+            }
+
+            let requirement = escape_xml(&reason.requirement);
+
+            match reason.evaluation {
+                EvaluationResult::Pass => xml.push_str("/>\n"),
+                EvaluationResult::Fail => xml.push_str(&format!(
+                    ">\n    <failure message=\"{requirement}\"/>\n  </testcase>\n"
+                )),
+                EvaluationResult::Error => xml.push_str(&format!(
+                    ">\n    <error message=\"{requirement}\"/>\n  </testcase>\n"
+                )),
+                EvaluationResult::Skipped => match &reason.skip_reason {
+                    Some(skip_reason) => xml.push_str(&format!(
+                        ">\n    <skipped message=\"{}\"/>\n  </testcase>\n",
+                        escape_xml(skip_reason)
+                    )),
+                    None => xml.push_str(">\n    <skipped/>\n  </testcase>\n"),
+                },
+            }
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+
+    /// The evaluation as a Markdown document titled `title`, e.g. for a wiki page or pull request:
+    /// the overall result followed by a table of each reason's rule, requirement and result. Passes
+    /// are marked ✅, and failures and errors ❌.
+    pub fn to_markdown_report(&self, title: &str) -> String {
+        let result = |result: EvaluationResult| match result {
+            EvaluationResult::Pass => "PASS ✅",
+            EvaluationResult::Fail => "FAIL ❌",
+            EvaluationResult::Error => "ERROR ❌",
+            EvaluationResult::Skipped => "SKIPPED",
+        };
+
+        let mut markdown = format!(
+            "# {}\n\n\
+             Overall: {}\n\n\
+             | Rule | Requirement | Result |\n\
+             | --- | --- | --- |\n",
+            title.replace('\n', " "),
+            result(self.result),
+        );
+
+        for reason in &self.reasons {
+            markdown.push_str(&format!(
+                "| {} | {} | {} |\n",
+                escape_markdown_cell(&reason.rule),
+                escape_markdown_cell(&reason.requirement),
+                result(reason.evaluation),
+            ));
+        }
+
+        markdown
+    }
+}
+
+/// `text` escaped for use in a cell of a Markdown table, which can't contain a line break or an
+/// unescaped `|`.
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// `text` escaped for use in XML text and attribute values.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// An `Evaluation` with its reasons keyed by rule id, for looking up the result of a specific rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedEvaluation {
+    pub result: EvaluationResult,
+    pub reasons: HashMap<String, EvaluationReason>,
+}
+
+impl IndexedEvaluation {
+    /// The reason for the rule `rule_id`, if it was evaluated.
+    pub fn get(&self, rule_id: &str) -> Option<&EvaluationReason> {
+        self.reasons.get(rule_id)
+    }
+}
+
+impl From<Evaluation> for IndexedEvaluation {
+    fn from(evaluation: Evaluation) -> Self {
+        Self {
+            result: evaluation.result,
+            reasons: evaluation
+                .reasons
+                .into_iter()
+                .map(|reason| (reason.rule.clone(), reason))
+                .collect(),
+        }
+    }
+}
+
+/// An `Evaluation` for consumers expecting boolean results, see `Evaluation::to_boolean_form`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BooleanEvaluation {
+    pub result: BooleanEvaluationResult,
+    pub reasons: Vec<BooleanEvaluationReason>,
+    pub weighted_score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<EvaluationContext>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// An `EvaluationResult` serialized as `true` for `Pass` and `false` for `Fail`. `Skipped` and
+/// `Error` have no boolean equivalent and are serialized as usual, as `"SKIPPED"` and `"ERROR"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BooleanEvaluationResult(pub EvaluationResult);
+
+impl Serialize for BooleanEvaluationResult {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            EvaluationResult::Pass => serializer.serialize_bool(true),
+            EvaluationResult::Fail => serializer.serialize_bool(false),
+            result => result.serialize(serializer),
+        }
+    }
+}
+
+/// An `EvaluationReason` with its `evaluation` serialized as a `BooleanEvaluationResult`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BooleanEvaluationReason(pub EvaluationReason);
+
+impl Serialize for BooleanEvaluationReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        let mut reason = serde_json::to_value(&self.0).map_err(S::Error::custom)?;
+        reason["evaluation"] = serde_json::to_value(BooleanEvaluationResult(self.0.evaluation))
+            .map_err(S::Error::custom)?;
+
+        reason.serialize(serializer)
+    }
+}
+
+/// The results of `evaluate_many` as an `Evaluation`, with a reason for each rule in the order
+/// given. Unlike `InMemRuleRepository::evaluate`, which fails the whole evaluation, a rule whose
+/// predicate can't be evaluated has an `Error` reason.
+pub fn evaluate_many_to_evaluation(rules: &[Rule], input: &serde_json::Value) -> Evaluation {
+    let reasons = rules
+        .iter()
+        .zip(evaluate_many(rules, input))
+        .map(|(rule, result)| EvaluationReason {
+            rule: rule.id.clone(),
+            requirement: rule.message.clone(),
+            evaluation: match result {
+                Ok(true) => EvaluationResult::Pass,
+                Ok(false) => EvaluationResult::Fail,
+                Err(_) => EvaluationResult::Error,
+            },
+            weight: rule.weight,
+            input_value: None,
+            elapsed_micros: None,
+            predicate_snapshot: None,
+            skip_reason: None,
+            match_count: None,
+            total_predicate_count: None,
+            operator: None,
+            path: None,
+        })
+        .collect();
+
+    Evaluation::from_reasons(reasons)
+}
+
+/// Evaluates `rule` separately for each group of `inputs` with the same value at the rule's
+/// `group_by` path, e.g. each tenant's records. A group's evaluation has a reason for each of its
+/// inputs in the order given, so it only passes if the rule passes for all of them, see
+/// `evaluate_many_to_evaluation`.
+///
+/// Groups are keyed by the value, see `group_key`. `RuleRepository::evaluate_grouped` groups the
+/// same way, evaluating each input through the repository instead.
+pub fn evaluate_grouped(rule: &Rule, inputs: &[serde_json::Value]) -> HashMap<String, Evaluation> {
+    let evaluations = inputs
+        .iter()
+        .map(|input| evaluate_many_to_evaluation(std::slice::from_ref(rule), input));
+
+    group_evaluations(rule.group_by.as_deref(), inputs, evaluations)
+}
+
+/// Merges the `evaluations` of each of `inputs` by the group the input belongs to when grouped by
+/// the value at `path`, keeping the reasons of each group in the order given.
+fn group_evaluations(
+    path: Option<&str>,
+    inputs: &[serde_json::Value],
+    evaluations: impl IntoIterator<Item = Evaluation>,
+) -> HashMap<String, Evaluation> {
+    let mut groups: HashMap<String, Vec<EvaluationReason>> = HashMap::new();
+
+    for (input, evaluation) in inputs.iter().zip(evaluations) {
+        groups
+            .entry(group_key(path, input))
+            .or_default()
+            .extend(evaluation.reasons);
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, reasons)| (key, Evaluation::from_reasons(reasons)))
+        .collect()
+}
+
+/// The key of the group `input` belongs to when grouped by the value at `path`: strings as they
+/// are and other values as JSON. Inputs the path can't be read from are grouped as `null`.
+fn group_key(path: Option<&str>, input: &serde_json::Value) -> String {
+    match path.and_then(|path| follow_path(path, input).ok()) {
+        Some(serde_json::Value::String(key)) => key.clone(),
+        Some(value) => value.to_string(),
+        None => serde_json::Value::Null.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct EvaluationReason {
     pub rule: String,
     pub requirement: String,
     pub evaluation: EvaluationResult,
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    pub weight: f64,
+    /// The value read from the input, if the rule's predicate is a raw predicate and
+    /// `EvaluationOptions::include_input_values` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_value: Option<serde_json::Value>,
+    /// How long the rule took to evaluate, if `EvaluationOptions::include_timings` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_micros: Option<u64>,
+    /// The rule's predicate as it was when evaluated, if `EvaluationOptions::snapshot_predicates`
+    /// is set. The rule may have been updated since the request was made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicate_snapshot: Option<serde_json::Value>,
+    /// Why the rule was skipped, when that isn't evident from the rule itself, e.g.
+    /// `EVALUATION_LIMIT_REACHED`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_reason: Option<String>,
+    /// How many raw predicates of the rule matched the input, see `Predicate::match_count`, if
+    /// `EvaluationOptions::include_match_counts` is set and the rule was evaluated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_count: Option<usize>,
+    /// How many raw predicates the rule has, if `EvaluationOptions::include_match_counts` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_predicate_count: Option<usize>,
+    /// The operator of the rule's predicate, if it's a raw predicate and
+    /// `EvaluationOptions::include_operator_context` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<Operator>,
+    /// The path read by the rule's predicate, if it's a raw predicate and
+    /// `EvaluationOptions::include_operator_context` is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Results are ordered from worst to best: `Error < Fail < Skipped < Pass`. They can be combined
+/// with `&` (every result must pass) and `|` (any result must pass), where `Skipped` doesn't
+/// affect the other result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema, JsonSchema)]
 #[serde(rename_all = "UPPERCASE")]
+#[non_exhaustive]
 pub enum EvaluationResult {
     Pass,
     Fail,
+    /// The rule wasn't evaluated, e.g. because it isn't active. Doesn't affect the overall result.
+    Skipped,
+    /// The rule's condition couldn't be evaluated against the input. Takes precedence over `Fail`
+    /// in the overall result.
+    Error,
+}
+
+impl EvaluationResult {
+    fn rank(self) -> u8 {
+        match self {
+            EvaluationResult::Error => 0,
+            EvaluationResult::Fail => 1,
+            EvaluationResult::Skipped => 2,
+            EvaluationResult::Pass => 3,
+        }
+    }
+}
+
+impl PartialOrd for EvaluationResult {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EvaluationResult {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+impl BitAnd for EvaluationResult {
+    type Output = Self;
+
+    /// The worse of the two results, ignoring `Skipped`.
+    fn bitand(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (EvaluationResult::Skipped, other) | (other, EvaluationResult::Skipped) => other,
+            (lhs, rhs) => lhs.min(rhs),
+        }
+    }
+}
+
+impl BitOr for EvaluationResult {
+    type Output = Self;
+
+    /// The better of the two results, ignoring `Skipped`.
+    fn bitor(self, rhs: Self) -> Self {
+        match (self, rhs) {
+            (EvaluationResult::Skipped, other) | (other, EvaluationResult::Skipped) => other,
+            (lhs, rhs) => lhs.max(rhs),
+        }
+    }
+}
+
+/// How often a rule has been evaluated, see `RuleRepository::stats`. Rules skipped because they
+/// aren't active, their condition doesn't hold or they reached `Rule::max_evaluations` aren't
+/// counted, and neither are evaluations which failed with an error, so `evaluations` is
+/// `passes + fails`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct RuleStats {
+    pub evaluations: u64,
+    pub passes: u64,
+    pub fails: u64,
+}
+
+/// The `skip_reason` of a rule skipped because it reached `Rule::max_evaluations`.
+pub const EVALUATION_LIMIT_REACHED: &str = "evaluation limit reached";
+
+/// A successful write to a repository, see `RuleRepository::watch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
+pub enum RuleChangeEvent {
+    Created(Rule),
+    Updated {
+        old: Rule,
+        new: Rule,
+    },
+    /// The id of the deleted rule.
+    Deleted(String),
+}
+
+/// Ambient metadata about an evaluation which isn't part of the input itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationContext {
+    pub request_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller_id: Option<String>,
+    /// The time the evaluation takes place at, used to decide which rules are active.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl EvaluationContext {
+    pub fn new(request_id: impl Into<String>) -> Self {
+        Self {
+            request_id: request_id.into(),
+            caller_id: None,
+            timestamp: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct EvaluationOptions {
+    /// Stop evaluating as soon as a rule fails or errors, whatever the `mode`. `reasons` will only
+    /// contain the rules evaluated up to and including the first failure.
+    pub stop_on_first_failure: bool,
+    /// Stop evaluating as soon as a rule decides whether the overall result passes, see
+    /// `EvaluationMode::is_decided_by`. `reasons` will only contain the rules evaluated up to and
+    /// including that rule.
+    pub stop_when_decided: bool,
+    /// Always report the overall result as `Pass`, e.g. to collect violations as warnings without
+    /// blocking anything. Each reason still records the rule's actual result.
+    pub pass_through: bool,
+    /// Include the value read from the input in each reason. Off by default so potentially
+    /// sensitive input isn't echoed back unless asked for.
+    pub include_input_values: bool,
+    /// Record how long each rule takes to evaluate in `EvaluationReason::elapsed_micros`. Off by
+    /// default to avoid the overhead of reading the clock.
+    pub include_timings: bool,
+    /// Record the predicate each rule was evaluated with in
+    /// `EvaluationReason::predicate_snapshot`.
+    pub snapshot_predicates: bool,
+    /// Record how many of each rule's raw predicates matched in `EvaluationReason::match_count`.
+    /// Off by default as every raw predicate has to be evaluated.
+    pub include_match_counts: bool,
+    /// Record the operator and path of each rule's predicate in `EvaluationReason::operator` and
+    /// `EvaluationReason::path`, for rules whose predicate is a raw predicate.
+    pub include_operator_context: bool,
+    /// Evaluate as if at this time rather than now, deciding which rules are active by their
+    /// `active_from` and `active_until`. Takes precedence over `EvaluationContext::timestamp`.
+    pub evaluate_at: Option<DateTime<Utc>>,
+    /// Whether reading a field missing from the input is an error rather than `null`.
+    pub field_presence: FieldPresence,
+    /// The most rules which can be evaluated at once, requesting more is a
+    /// `EvaluateRuleError::TooManyRules`. Guards against evaluating every rule by mistake.
+    pub max_rules: Option<usize>,
+    /// How the results of the rules are combined into the overall result.
+    pub mode: EvaluationMode,
+}
+
+impl EvaluationOptions {
+    /// Checks that `requested` rules can be evaluated at once, see `max_rules`. Needed before
+    /// evaluations which don't take options, like `RuleRepository::evaluate_indexed`.
+    pub fn check_rule_count(&self, requested: usize) -> Result<(), EvaluateRuleError> {
+        match self.max_rules {
+            Some(max) if requested > max => Err(EvaluateRuleError::TooManyRules { requested, max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How the overall result of an evaluation is derived from the results of its rules, like the
+/// compound predicate of the same name. Skipped rules are ignored, see `EvaluationResult`'s `&`
+/// and `|`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EvaluationMode {
+    /// Every rule must pass, with an error taking precedence over a failure.
+    #[default]
+    All,
+    /// At least one rule must pass, so nothing passes if every rule is skipped. A failure takes
+    /// precedence over an error, as it's the better of the two, so the result is only an error if
+    /// every rule which wasn't skipped errored.
+    Any,
+    /// No rule may pass. A rule which passed decides the result, otherwise an error takes
+    /// precedence, as the rule which errored might have passed.
+    None,
+}
+
+impl EvaluationMode {
+    /// The overall result of rules with the given `results`.
+    pub fn combine(self, results: impl IntoIterator<Item = EvaluationResult>) -> EvaluationResult {
+        let results = results.into_iter();
+
+        match self {
+            EvaluationMode::All => results.fold(EvaluationResult::Pass, BitAnd::bitand),
+            EvaluationMode::Any => match results.reduce(BitOr::bitor) {
+                None | Some(EvaluationResult::Skipped) => EvaluationResult::Fail,
+                Some(result) => result,
+            },
+            EvaluationMode::None => {
+                let mut combined = EvaluationResult::Pass;
+
+                for result in results {
+                    match result {
+                        EvaluationResult::Pass => return EvaluationResult::Fail,
+                        EvaluationResult::Error => combined = EvaluationResult::Error,
+                        EvaluationResult::Fail | EvaluationResult::Skipped => {}
+                    }
+                }
+
+                combined
+            }
+        }
+    }
+
+    /// Whether a rule with the given `result` decides whether the overall result passes regardless
+    /// of the rules after it: a failure or error for `All`, and a pass for `Any` and `None`.
+    pub fn is_decided_by(self, result: EvaluationResult) -> bool {
+        match self {
+            EvaluationMode::All => {
+                matches!(result, EvaluationResult::Fail | EvaluationResult::Error)
+            }
+            EvaluationMode::Any | EvaluationMode::None => result == EvaluationResult::Pass,
+        }
+    }
+}
+
+/// A problem with a single field of a rule found by the repository, rather than by
+/// `Rule::validate` which only checks the rule's own definition.
+#[derive(Debug, Clone, Error, PartialEq, Eq, Hash)]
+#[error("invalid {field}: {message}")]
+pub struct ValidationError {
+    /// The field as written in JSON, e.g. `author`.
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum CreateRuleError {
     #[error("a rule with id {0} already exists")]
     Duplicate(String),
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error(transparent)]
+    Invalid(#[from] RuleValidationError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum DeleteRuleError {
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum GetRuleError {
     #[error("a rule with id {0} does not exist")]
     NoSuchRule(String),
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum UpdateRuleError {
     #[error("a rule with id {0} does not exist")]
     NoSuchRule(String),
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error("a rule with id {0} already exists")]
+    Duplicate(String),
+    #[error(transparent)]
+    Invalid(#[from] RuleValidationError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum CloneRuleError {
+    #[error("a rule with id {0} does not exist")]
+    NoSuchRule(String),
+    #[error("a rule with id {0} already exists")]
+    Duplicate(String),
+    #[error("the id of the cloned rule must not be empty")]
+    MissingId,
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum GetAllRulesError {
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 #[derive(Debug, Error, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum EvaluateRuleError {
     #[error("a rule with id {0} does not exist")]
     NoSuchRule(String),
     #[error("failed to evaluate rule {0}: {1}")]
     EvaluationError(String, EvaluationError),
-    #[error("an unknown error occured")]
-    Unknown,
+    #[error("cyclic dependency between rules {}", .0.join(" -> "))]
+    CyclicDependency(Vec<String>),
+    #[error("there are no rules in category {0}")]
+    EmptyCategory(String),
+    #[error("internal error: {0}")]
+    Internal(String),
+    /// The input doesn't satisfy the rule's `input_schema`, see `Rule::validate_input`.
+    #[error("the input does not satisfy the input schema of rule {rule_id}")]
+    SchemaValidationFailed {
+        rule_id: String,
+        errors: Vec<String>,
+    },
+    /// More rules were requested than `EvaluationOptions::max_rules` allows.
+    #[error("cannot evaluate {requested} rules at once, the limit is {max}")]
+    TooManyRules { requested: usize, max: usize },
+}
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum ImportError {
+    #[error("failed to read rules: {0}")]
+    Io(String),
+    #[error("failed to parse rules: {0}")]
+    Parse(String),
+    #[error("a rule with id {0} is defined more than once")]
+    DuplicateId(String),
+    #[error(transparent)]
+    Invalid(#[from] RuleValidationError),
+    #[error(transparent)]
+    Validation(#[from] ValidationError),
+    #[error("fetching rules failed with HTTP status {0}")]
+    HttpError(u16),
+    #[error("timed out fetching rules")]
+    Timeout,
+    #[error("the rules fetched are larger than the limit of {0} bytes")]
+    TooLarge(usize),
+    #[error("importing rules from {0} is not allowed")]
+    HostNotAllowed(String),
+}
+
+#[derive(Debug, Error, PartialEq, Eq, Hash)]
+pub enum ExportError {
+    #[error("failed to write rules: {0}")]
+    Io(String),
+    #[error("failed to serialize rules: {0}")]
+    Serialize(String),
+    #[error("internal error: {0}")]
+    Internal(String),
 }
 
 pub trait RuleRepository: Clone + Send + Sync + 'static {
@@ -97,292 +818,3773 @@ pub trait RuleRepository: Clone + Send + Sync + 'static {
         &self,
         ids: &[String],
         input: serde_json::Value,
+        options: EvaluationOptions,
     ) -> impl Future<Output = Result<Evaluation, EvaluateRuleError>> + Send;
-}
 
-#[derive(Debug, Clone)]
-pub struct InMemRuleRepository {
-    rules: Arc<RwLock<HashMap<String, Rule>>>,
-}
+    fn evaluate_with_context(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> impl Future<Output = Result<Evaluation, EvaluateRuleError>> + Send;
 
-impl InMemRuleRepository {
-    pub fn new(rules: &[Rule]) -> Self {
-        Self {
-            rules: Arc::new(RwLock::new(
-                rules
-                    .iter()
-                    .cloned()
-                    .map(|rule| (rule.id.clone(), rule))
-                    .collect(),
-            )),
-        }
+    /// Like `evaluate` with the default options, evaluating the rules against `input` with
+    /// `transform` applied, e.g. one of the functions in `core::transform`.
+    fn evaluate_with_transform(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        transform: fn(serde_json::Value) -> serde_json::Value,
+    ) -> impl Future<Output = Result<Evaluation, EvaluateRuleError>> + Send {
+        self.evaluate(ids, transform(input), EvaluationOptions::default())
     }
 
-    pub fn empty() -> Self {
-        Self {
-            rules: Arc::default(),
+    /// Like `evaluate` with the default options, with the reasons keyed by rule id.
+    fn evaluate_indexed(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Future<Output = Result<IndexedEvaluation, EvaluateRuleError>> + Send {
+        async move {
+            self.evaluate(ids, input, EvaluationOptions::default())
+                .await
+                .map(IndexedEvaluation::from)
         }
     }
-}
 
-impl RuleRepository for InMemRuleRepository {
-    async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
-        let rules = self.rules.read().map_err(|_| GetAllRulesError::Unknown)?;
+    /// Like `evaluate` with the default options, but yields the reason for each rule as soon as
+    /// it's evaluated rather than waiting for every rule. By default the rules are evaluated one
+    /// at a time in the requested order, without ordering them by their dependencies.
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Stream<Item = Result<EvaluationReason, EvaluateRuleError>> + Send + 'static {
+        let repository = self.clone();
+
+        tokio_stream::iter(ids.to_vec()).then(move |id| {
+            let repository = repository.clone();
+            let input = input.clone();
 
-        Ok(rules.values().cloned().collect())
+            async move {
+                let evaluation = repository
+                    .evaluate(
+                        std::slice::from_ref(&id),
+                        input,
+                        EvaluationOptions::default(),
+                    )
+                    .await?;
+
+                evaluation.reasons.into_iter().next().ok_or_else(|| {
+                    EvaluateRuleError::Internal(format!("rule {id} was evaluated without a reason"))
+                })
+            }
+        })
     }
 
-    async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
-        let rules = self.rules.read().map_err(|_| GetRuleError::Unknown)?;
+    /// Streams every change made to the rules from now on. A subscriber which falls too far behind
+    /// misses the oldest changes rather than slowing down writes.
+    fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static;
 
-        if let Some(rule) = rules.get(id) {
-            Ok(rule.clone())
-        } else {
-            Err(GetRuleError::NoSuchRule(id.clone()))
+    /// The rules with the given ids in the order they were requested. Ids without a rule are
+    /// left out rather than being an error.
+    fn get_many(
+        &self,
+        ids: &[String],
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = Vec::with_capacity(ids.len());
+
+            for id in ids {
+                match self.get(id).await {
+                    Ok(rule) => rules.push(rule),
+                    Err(GetRuleError::NoSuchRule(_)) => {}
+                    Err(GetRuleError::Internal(err)) => {
+                        return Err(GetAllRulesError::Internal(err));
+                    }
+                }
+            }
+
+            Ok(rules)
         }
     }
 
-    async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
-        let mut rules = self.rules.write().map_err(|_| CreateRuleError::Unknown)?;
+    /// Every rule whose category is `category`.
+    fn get_by_category(
+        &self,
+        category: &str,
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = self.get_all().await?;
+            rules.retain(|rule| rule.category.as_deref() == Some(category));
 
-        let id = rule.id().to_owned();
+            Ok(rules)
+        }
+    }
 
-        #[allow(clippy::map_entry)]
-        if rules.contains_key(&id) {
-            Err(CreateRuleError::Duplicate(id.clone()))
-        } else {
-            rules.insert(id, rule);
+    /// How often the rule `id` has been evaluated. Repositories which don't count evaluations
+    /// report every rule as never evaluated.
+    #[allow(clippy::ptr_arg)]
+    fn stats(&self, id: &String) -> impl Future<Output = Result<RuleStats, GetRuleError>> + Send {
+        async move {
+            self.get(id).await?;
 
-            Ok(())
+            Ok(RuleStats::default())
         }
     }
 
-    async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
-        let mut rules = self.rules.write().map_err(|_| DeleteRuleError::Unknown)?;
+    /// Every rule whose predicate uses `operator`, see `Rule::applicable_operators`.
+    fn find_by_operator(
+        &self,
+        operator: Operator,
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = self.get_all().await?;
+            rules.retain(|rule| rule.applicable_operators().contains(&operator));
 
-        Ok(rules.remove(id))
+            Ok(rules)
+        }
     }
 
-    async fn update(&self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
-        let mut rules = self.rules.write().map_err(|_| UpdateRuleError::Unknown)?;
+    /// Every rule created by `author`, see `Rule::author`.
+    fn find_by_author(
+        &self,
+        author: &str,
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = self.get_all().await?;
+            rules.retain(|rule| rule.author.as_deref() == Some(author));
 
-        if !rules.contains_key(&id) {
-            return Err(UpdateRuleError::NoSuchRule(id.clone()));
+            Ok(rules)
         }
+    }
 
-        let old_rule = rules.remove(&id);
+    /// Every rule which has, or hasn't, been reviewed, see `Rule::reviewed_by`.
+    fn find_by_review_status(
+        &self,
+        reviewed: bool,
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = self.get_all().await?;
+            rules.retain(|rule| rule.reviewed_by.is_some() == reviewed);
+
+            Ok(rules)
+        }
+    }
 
-        rules.insert(new_rule.id.clone(), new_rule);
+    /// Every rule created at or after `from` and before `to`, see `Rule::was_created_within`.
+    fn find_by_date_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = Result<Vec<Rule>, GetAllRulesError>> + Send {
+        async move {
+            let mut rules = self.get_all().await?;
+            rules.retain(|rule| rule.was_created_within(from, to));
 
-        Ok(old_rule)
+            Ok(rules)
+        }
     }
 
-    async fn evaluate(
+    /// Evaluates every rule in `category`, see `evaluate`. Rules are evaluated in order of their
+    /// ids, and a category without any rules passes.
+    fn evaluate_by_category(
         &self,
-        ids: &[String],
+        category: &str,
         input: serde_json::Value,
-    ) -> Result<Evaluation, EvaluateRuleError> {
-        let rules = self.rules.read().map_err(|_| EvaluateRuleError::Unknown)?;
-
-        let mut reasons = Vec::with_capacity(ids.len());
+        options: EvaluationOptions,
+    ) -> impl Future<Output = Result<Evaluation, EvaluateRuleError>> + Send {
+        async move {
+            let mut ids: Vec<String> = self
+                .get_by_category(category)
+                .await
+                .map_err(|err| EvaluateRuleError::Internal(err.to_string()))?
+                .into_iter()
+                .map(|rule| rule.id)
+                .collect();
 
-        let mut is_pass = true;
+            ids.sort();
 
-        for id in ids {
-            let Some(rule) = rules.get(id) else {
-                return Err(EvaluateRuleError::NoSuchRule(id.clone()));
-            };
+            self.evaluate(&ids, input, options).await
+        }
+    }
 
-            let evaluation = rule
-                .evaluate(&input)
-                .map_err(|err| EvaluateRuleError::EvaluationError(id.clone(), err))?;
+    /// Evaluates the rule `id` separately for each group of `inputs` with the same value at
+    /// `group_by`, or the rule's own `group_by` if not given, e.g. each tenant's records. Groups
+    /// are keyed like `evaluate_grouped`, and every input is in the `null` group if there's
+    /// nothing to group by.
+    ///
+    /// Each input is evaluated with `evaluate`, so the rule's condition, active period, input
+    /// schema and evaluation limit apply as usual and every evaluation is counted in its stats. A
+    /// group's evaluation has a reason for each of its inputs in the order given, so it only
+    /// passes if the rule passes for all of them.
+    #[allow(clippy::ptr_arg)]
+    fn evaluate_grouped(
+        &self,
+        id: &String,
+        inputs: Vec<serde_json::Value>,
+        group_by: Option<String>,
+        options: EvaluationOptions,
+    ) -> impl Future<Output = Result<HashMap<String, Evaluation>, EvaluateRuleError>> + Send {
+        async move {
+            let rule = self.get(id).await.map_err(|err| match err {
+                GetRuleError::NoSuchRule(id) => EvaluateRuleError::NoSuchRule(id),
+                GetRuleError::Internal(err) => EvaluateRuleError::Internal(err),
+            })?;
+            let group_by = group_by.or(rule.group_by);
 
-            if evaluation {
-                reasons.push(EvaluationReason {
-                    rule: id.clone(),
-                    evaluation: EvaluationResult::Pass,
-                    requirement: rule.message.clone(),
-                });
-            } else {
-                reasons.push(EvaluationReason {
-                    rule: id.clone(),
-                    evaluation: EvaluationResult::Fail,
-                    requirement: rule.message.clone(),
-                });
+            let mut evaluations = Vec::with_capacity(inputs.len());
+            for input in &inputs {
+                evaluations.push(
+                    self.evaluate(std::slice::from_ref(id), input.clone(), options.clone())
+                        .await?,
+                );
             }
 
-            is_pass &= evaluation;
+            Ok(group_evaluations(group_by.as_deref(), &inputs, evaluations))
         }
-
-        Ok(Evaluation {
-            result: if is_pass {
-                EvaluationResult::Pass
-            } else {
-                EvaluationResult::Fail
-            },
-            reasons,
-        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{predicate, rule};
+    /// Creates a copy of the rule `id` under `new_id`, returning the new rule.
+    #[allow(clippy::ptr_arg)]
+    fn clone_rule(
+        &self,
+        id: &String,
+        new_id: String,
+    ) -> impl Future<Output = Result<Rule, CloneRuleError>> + Send {
+        async move {
+            if new_id.is_empty() {
+                return Err(CloneRuleError::MissingId);
+            }
+
+            let mut rule = self.get(id).await.map_err(|err| match err {
+                GetRuleError::NoSuchRule(id) => CloneRuleError::NoSuchRule(id),
+                GetRuleError::Internal(err) => CloneRuleError::Internal(err),
+            })?;
+
+            rule.id = new_id;
+
+            self.create(rule.clone()).await.map_err(|err| match err {
+                CreateRuleError::Duplicate(id) => CloneRuleError::Duplicate(id),
+                // The rule was already validated when it was first created.
+                CreateRuleError::Invalid(_) | CreateRuleError::Validation(_) => {
+                    CloneRuleError::Internal(err.to_string())
+                }
+                CreateRuleError::Internal(err) => CloneRuleError::Internal(err),
+            })?;
+
+            Ok(rule)
+        }
+    }
+
+    /// Changes the id of the rule `old_id` to `new_id`, keeping everything else, returning the
+    /// renamed rule. See `update` for what renaming resets.
+    ///
+    /// By default the rule is read and then updated, so the returned rule has the timestamps it
+    /// had before the rename.
+    fn rename(
+        &self,
+        old_id: String,
+        new_id: String,
+    ) -> impl Future<Output = Result<Rule, UpdateRuleError>> + Send {
+        async move {
+            let get_error = |err| match err {
+                GetRuleError::NoSuchRule(id) => UpdateRuleError::NoSuchRule(id),
+                GetRuleError::Internal(err) => UpdateRuleError::Internal(err),
+            };
+
+            let mut rule = self.get(&old_id).await.map_err(get_error)?;
+            rule.id = new_id;
+            self.update(old_id, rule.clone()).await?;
+
+            Ok(rule)
+        }
+    }
+
+    /// Creates every rule in `rules`, see `create`, stopping at the first which can't be.
+    ///
+    /// By default the rules are created one at a time, so the rules created before a failure are
+    /// kept. Repositories which can should create them atomically, so either every rule is created
+    /// or none are.
+    fn create_many(
+        &self,
+        rules: Vec<Rule>,
+    ) -> impl Future<Output = Result<(), CreateRuleError>> + Send {
+        async move {
+            for rule in rules {
+                self.create(rule).await?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Ids of stored rules which are identical to or contradict `new_rule`, see
+    /// `Predicate::conflicts_with`. A stored rule with the same id as `new_rule` is ignored, so
+    /// this can also be used before an update.
+    fn find_conflicting_rules(
+        &self,
+        new_rule: &Rule,
+    ) -> impl Future<Output = Result<Vec<String>, GetAllRulesError>> + Send {
+        async move {
+            let mut ids: Vec<String> = self
+                .get_all()
+                .await?
+                .into_iter()
+                .filter(|rule| rule.id != new_rule.id && rule.conflicts_with(new_rule))
+                .map(|rule| rule.id)
+                .collect();
+
+            ids.sort();
+            Ok(ids)
+        }
+    }
+
+    /// Every rule as a pretty printed JSON array, ordered by id, which can be imported again with
+    /// `InMemRuleRepository::import_from_reader`.
+    fn export_json(&self) -> impl Future<Output = Result<String, ExportError>> + Send {
+        self.export_as(RuleFileFormat::Json)
+    }
+
+    /// Every rule in the given format, ordered by id, see `export_json`.
+    fn export_as(
+        &self,
+        format: RuleFileFormat,
+    ) -> impl Future<Output = Result<String, ExportError>> + Send {
+        async move {
+            let mut rules = self
+                .get_all()
+                .await
+                .map_err(|err| ExportError::Internal(err.to_string()))?;
+            rules.sort_by(|a, b| a.id.cmp(&b.id));
+
+            let buffer = format.serialize(&rules).map_err(ExportError::Serialize)?;
+
+            String::from_utf8(buffer).map_err(|err| ExportError::Serialize(err.to_string()))
+        }
+    }
+
+    /// Boxes the repository so its concrete type can be chosen at runtime.
+    fn into_erased(self) -> ErasedRepository {
+        Arc::new(self)
+    }
+}
+
+/// Orders `ids` so that every rule is preceded by the rules it depends on. Dependencies which
+/// aren't part of `ids` are not evaluated and so don't affect the order. Otherwise the requested
+/// order is preserved.
+fn order_by_dependencies<'a>(
+    ids: &'a [String],
+    rules: &'a HashMap<String, Rule>,
+) -> Result<Vec<&'a Rule>, EvaluateRuleError> {
+    enum State {
+        Visiting,
+        Visited,
+    }
+
+    fn visit<'a>(
+        rule: &'a Rule,
+        requested: &HashMap<&str, &'a Rule>,
+        states: &mut HashMap<&'a str, State>,
+        stack: &mut Vec<&'a str>,
+        ordered: &mut Vec<&'a Rule>,
+    ) -> Result<(), EvaluateRuleError> {
+        match states.get(rule.id()) {
+            Some(State::Visited) => return Ok(()),
+            Some(State::Visiting) => {
+                let start = stack.iter().position(|id| *id == rule.id()).unwrap_or(0);
+
+                let mut cycle = stack[start..]
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>();
+                cycle.push(rule.id.clone());
+
+                return Err(EvaluateRuleError::CyclicDependency(cycle));
+            }
+            None => {}
+        }
+
+        states.insert(rule.id(), State::Visiting);
+        stack.push(rule.id());
+
+        for dependency in rule.depends_on() {
+            if let Some(dependency) = requested.get(dependency.as_str()) {
+                visit(dependency, requested, states, stack, ordered)?;
+            }
+        }
+
+        stack.pop();
+        states.insert(rule.id(), State::Visited);
+        ordered.push(rule);
+
+        Ok(())
+    }
+
+    let mut requested = HashMap::with_capacity(ids.len());
+
+    for id in ids {
+        let Some(rule) = rules.get(id) else {
+            return Err(EvaluateRuleError::NoSuchRule(id.clone()));
+        };
+
+        requested.insert(id.as_str(), rule);
+    }
+
+    let mut states = HashMap::with_capacity(ids.len());
+    let mut ordered = Vec::with_capacity(ids.len());
+
+    for id in ids {
+        visit(
+            requested[id.as_str()],
+            &requested,
+            &mut states,
+            &mut Vec::new(),
+            &mut ordered,
+        )?;
+    }
+
+    Ok(ordered)
+}
+
+/// Deployment specific restrictions on the rules a repository accepts, checked on top of
+/// `Rule::validate` whenever a rule is created or updated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RulePolicy {
+    /// The operators rules may use, any operator is allowed when `None`.
+    pub allowed_operators: Option<HashSet<Operator>>,
+}
+
+impl RulePolicy {
+    pub fn check(&self, rule: &Rule) -> Result<(), RuleValidationError> {
+        if let Some(allowed) = &self.allowed_operators {
+            rule.validate_operators(allowed)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the fields recording who wrote and approved a rule, which aren't part of its definition
+/// so aren't checked by `Rule::validate`.
+fn check_governance(rule: &Rule) -> Result<(), ValidationError> {
+    let fields = [("author", &rule.author), ("reviewedBy", &rule.reviewed_by)];
+
+    for (field, value) in fields {
+        if value.as_ref().is_some_and(|value| value.trim().is_empty()) {
+            return Err(ValidationError::new(field, "must not be empty"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs every check a rule must pass to be stored in a repository with `policy`.
+fn check_rule<E>(policy: &RulePolicy, rule: &Rule) -> Result<(), E>
+where
+    E: From<RuleValidationError> + From<ValidationError>,
+{
+    rule.validate()?;
+    policy.check(rule)?;
+    check_governance(rule)?;
+
+    Ok(())
+}
+
+/// How many changes a subscriber of `InMemRuleRepository::watch` can fall behind by.
+const EVENT_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct InMemRuleRepository {
+    /// Each rule with its position in the order the rules were created, so `get_all` is
+    /// deterministic without locking every rule for each write.
+    rules: Arc<DashMap<String, StoredRule>>,
+    /// The position of the next rule created, see `StoredRule`.
+    next_position: Arc<AtomicU64>,
+    events: broadcast::Sender<RuleChangeEvent>,
+    /// Shared by individual writes and held exclusively by a `TransactionGuard`.
+    writes: Arc<RwLock<()>>,
+    policy: Arc<RulePolicy>,
+    /// Kept for every rule evaluated since it was created, see `RuleRepository::stats`.
+    counters: Arc<DashMap<String, Arc<RuleCounters>>>,
+}
+
+/// A rule of an `InMemRuleRepository`, listed in order of `position`. An updated rule keeps its
+/// position, even if it's renamed.
+#[derive(Debug, Clone)]
+struct StoredRule {
+    position: u64,
+    rule: Rule,
+    /// The rule's compiled `input_schema`, so it isn't compiled on every evaluation.
+    input_validator: Option<InputValidator>,
+}
+
+impl StoredRule {
+    fn new(position: u64, rule: Rule) -> Self {
+        Self {
+            position,
+            input_validator: rule.input_validator(),
+            rule,
+        }
+    }
+}
+
+/// Counts the evaluations of a rule, see `RuleStats`.
+#[derive(Debug, Default)]
+struct RuleCounters {
+    evaluations: AtomicU64,
+    passes: AtomicU64,
+    fails: AtomicU64,
+}
+
+impl RuleCounters {
+    /// The counters of the rule `id`, created if it hasn't been evaluated yet.
+    fn of(counters: &DashMap<String, Arc<RuleCounters>>, id: &str) -> Arc<Self> {
+        if let Some(rule_counters) = counters.get(id) {
+            return Arc::clone(&rule_counters);
+        }
+
+        Arc::clone(&counters.entry(id.to_owned()).or_default())
+    }
+
+    /// Counts an evaluation unless there have already been `max` of them, returning whether it
+    /// was counted.
+    fn try_count(&self, max: Option<u64>) -> bool {
+        self.evaluations
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |evaluations| {
+                max.is_none_or(|max| evaluations < max)
+                    .then_some(evaluations + 1)
+            })
+            .is_ok()
+    }
+
+    /// Takes back an evaluation counted by `try_count` which failed, so it doesn't use up the
+    /// rule's `max_evaluations`. Evaluations refused in the meantime stay refused.
+    fn uncount(&self) {
+        self.evaluations.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    fn record(&self, evaluation: EvaluationResult) {
+        match evaluation {
+            EvaluationResult::Pass => self.passes.fetch_add(1, Ordering::Relaxed),
+            EvaluationResult::Fail => self.fails.fetch_add(1, Ordering::Relaxed),
+            _ => return,
+        };
+    }
+
+    fn stats(&self) -> RuleStats {
+        RuleStats {
+            evaluations: self.evaluations.load(Ordering::Relaxed),
+            passes: self.passes.load(Ordering::Relaxed),
+            fails: self.fails.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl InMemRuleRepository {
+    pub fn new(rules: &[Rule]) -> Self {
+        rules.iter().cloned().collect()
+    }
+
+    pub fn empty() -> Self {
+        Self::from_map(IndexMap::new())
+    }
+
+    fn from_map(mut rules: IndexMap<String, Rule>) -> Self {
+        // Rules loaded without timestamps, e.g. from a file, count as created and updated when
+        // loaded.
+        let now = Utc::now();
+        for rule in rules.values_mut() {
+            rule.created_at.get_or_insert(now);
+            rule.updated_at.get_or_insert(now);
+        }
+
+        let next_position = rules.len() as u64;
+        let rules = rules
+            .into_values()
+            .zip(0..)
+            .map(|(rule, position)| (rule.id.clone(), StoredRule::new(position, rule)))
+            .collect();
+
+        Self {
+            rules: Arc::new(rules),
+            next_position: Arc::new(AtomicU64::new(next_position)),
+            events: broadcast::Sender::new(EVENT_CAPACITY),
+            writes: Arc::default(),
+            policy: Arc::default(),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Checks rules created or updated from now on against `policy`. Rules already in the
+    /// repository aren't checked, so this should be set when the repository is constructed.
+    pub fn with_policy(mut self, policy: RulePolicy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Creates a repository from `rules`, checking each of them as `create` would. Unlike
+    /// collecting into a repository, fails on the first rule whose id was already seen.
+    pub fn from_rules_checked(rules: impl Iterator<Item = Rule>) -> Result<Self, CreateRuleError> {
+        let mut rules_by_id = IndexMap::with_capacity(rules.size_hint().0);
+        // The repository doesn't have a policy until `with_policy` is called.
+        let policy = RulePolicy::default();
+
+        for rule in rules {
+            check_rule::<CreateRuleError>(&policy, &rule)?;
+
+            match rules_by_id.entry(rule.id.clone()) {
+                Entry::Occupied(_) => return Err(CreateRuleError::Duplicate(rule.id)),
+                Entry::Vacant(entry) => entry.insert(rule),
+            };
+        }
+
+        Ok(Self::from_map(rules_by_id))
+    }
+
+    /// Creates a repository from a JSON array of rules read from `reader`.
+    pub async fn import_from_reader<R: AsyncRead + Unpin>(reader: R) -> Result<Self, ImportError> {
+        Self::import_from_reader_as(reader, RuleFileFormat::Json).await
+    }
+
+    /// Creates a repository from rules in the given format read from `reader`.
+    pub async fn import_from_reader_as<R: AsyncRead + Unpin>(
+        mut reader: R,
+        format: RuleFileFormat,
+    ) -> Result<Self, ImportError> {
+        let mut buffer = Vec::new();
+        reader
+            .read_to_end(&mut buffer)
+            .await
+            .map_err(|err| ImportError::Io(err.to_string()))?;
+
+        let rules = format.parse(&buffer).map_err(ImportError::Parse)?;
+
+        Self::from_rules_checked(rules.into_iter()).map_err(|err| match err {
+            CreateRuleError::Duplicate(id) => ImportError::DuplicateId(id),
+            CreateRuleError::Invalid(err) => ImportError::Invalid(err),
+            CreateRuleError::Validation(err) => ImportError::Validation(err),
+            CreateRuleError::Internal(_) => {
+                unreachable!("checked construction only checks rules and their ids")
+            }
+        })
+    }
+
+    /// Writes all rules to `writer` as a JSON array, in the format read by `import_from_reader`.
+    pub async fn export_to_writer<W: AsyncWrite + Unpin>(
+        &self,
+        writer: W,
+    ) -> Result<(), ExportError> {
+        self.export_to_writer_as(writer, RuleFileFormat::Json).await
+    }
+
+    /// Writes all rules to `writer` in the given format, see `import_from_reader_as`.
+    pub async fn export_to_writer_as<W: AsyncWrite + Unpin>(
+        &self,
+        mut writer: W,
+        format: RuleFileFormat,
+    ) -> Result<(), ExportError> {
+        let buffer = self.export_as(format).await?;
+
+        writer
+            .write_all(buffer.as_bytes())
+            .await
+            .map_err(|err| ExportError::Io(err.to_string()))?;
+
+        writer
+            .flush()
+            .await
+            .map_err(|err| ExportError::Io(err.to_string()))
+    }
+
+    /// The rules for which `filter` holds, in the order they were created. Only the matching
+    /// rules are cloned.
+    fn rules_where(&self, filter: impl Fn(&Rule) -> bool) -> Vec<Rule> {
+        let mut rules: Vec<StoredRule> = self
+            .rules
+            .iter()
+            .filter(|stored| filter(&stored.rule))
+            .map(|stored| stored.value().clone())
+            .collect();
+
+        rules.sort_by_key(|stored| stored.position);
+        rules.into_iter().map(|stored| stored.rule).collect()
+    }
+
+    /// Sends `event` to every subscriber of `watch`, if there are any.
+    fn notify(&self, event: RuleChangeEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Starts a transaction, waiting for in-flight writes to finish. Writes made outside of the
+    /// transaction wait until it's committed or rolled back, reads and evaluations don't.
+    pub async fn begin_transaction(&self) -> TransactionGuard<'_> {
+        TransactionGuard::new(self, self.writes.write().await)
+    }
+
+    fn create_unlocked(&self, mut rule: Rule) -> Result<RuleChangeEvent, CreateRuleError> {
+        check_rule::<CreateRuleError>(&self.policy, &rule)?;
+
+        let now = Utc::now();
+        rule.created_at = Some(now);
+        rule.updated_at = Some(now);
+
+        match self.rules.entry(rule.id().to_owned()) {
+            DashEntry::Occupied(entry) => Err(CreateRuleError::Duplicate(entry.key().clone())),
+            DashEntry::Vacant(entry) => {
+                let position = self.next_position.fetch_add(1, Ordering::Relaxed);
+                entry.insert(StoredRule::new(position, rule.clone()));
+
+                Ok(RuleChangeEvent::Created(rule))
+            }
+        }
+    }
+
+    fn delete_unlocked(&self, id: &String) -> Option<(Rule, RuleChangeEvent)> {
+        let (_, StoredRule { rule: old_rule, .. }) = self.rules.remove(id)?;
+        self.counters.remove(id);
+
+        Some((old_rule, RuleChangeEvent::Deleted(id.clone())))
+    }
+
+    /// A rename removes the rule and inserts it under its new id, so must only be made while no
+    /// other writes are, see `update`.
+    fn update_unlocked(
+        &self,
+        id: String,
+        mut new_rule: Rule,
+    ) -> Result<(Rule, RuleChangeEvent), UpdateRuleError> {
+        check_rule::<UpdateRuleError>(&self.policy, &new_rule)?;
+
+        new_rule.updated_at = Some(Utc::now());
+
+        let old_rule = if new_rule.id == id {
+            let Some(mut stored) = self.rules.get_mut(&id) else {
+                return Err(UpdateRuleError::NoSuchRule(id));
+            };
+
+            new_rule.created_at = stored.rule.created_at;
+            stored.input_validator = new_rule.input_validator();
+            std::mem::replace(&mut stored.rule, new_rule.clone())
+        } else {
+            if !self.rules.contains_key(&id) {
+                return Err(UpdateRuleError::NoSuchRule(id));
+            }
+
+            if self.rules.contains_key(&new_rule.id) {
+                return Err(UpdateRuleError::Duplicate(new_rule.id));
+            }
+
+            let (_, StoredRule { position, rule, .. }) = self
+                .rules
+                .remove(&id)
+                .ok_or_else(|| UpdateRuleError::NoSuchRule(id.clone()))?;
+            new_rule.created_at = rule.created_at;
+
+            // Evaluations are counted against the id, so only carry over if it's unchanged.
+            self.counters.remove(&id);
+
+            // Keep the rule where it was rather than moving it to the end.
+            self.rules.insert(
+                new_rule.id.clone(),
+                StoredRule::new(position, new_rule.clone()),
+            );
+
+            rule
+        };
+
+        Ok((
+            old_rule.clone(),
+            RuleChangeEvent::Updated {
+                old: old_rule,
+                new: new_rule,
+            },
+        ))
+    }
+
+    /// A snapshot of the rules `ids` in the order they should be evaluated, see
+    /// `order_by_dependencies`, each with its compiled `input_schema`.
+    fn ordered_rules(
+        &self,
+        ids: &[String],
+    ) -> Result<Vec<(Rule, Option<InputValidator>)>, EvaluateRuleError> {
+        // Evaluate against a snapshot of the requested rules so concurrent writes can't change
+        // them part way through.
+        let mut validators = HashMap::with_capacity(ids.len());
+        let rules = ids
+            .iter()
+            .filter_map(|id| {
+                let stored = self.rules.get(id)?;
+                validators.insert(id.clone(), stored.input_validator.clone());
+                Some((id.clone(), stored.rule.clone()))
+            })
+            .collect::<HashMap<_, _>>();
+
+        Ok(order_by_dependencies(ids, &rules)?
+            .into_iter()
+            .map(|rule| (rule.clone(), validators.remove(&rule.id).flatten()))
+            .collect())
+    }
+
+    /// Evaluates the rules `ids` as of time `at`, skipping rules which aren't active at that time.
+    fn evaluate_at(
+        &self,
+        ids: &[String],
+        input: &serde_json::Value,
+        options: EvaluationOptions,
+        at: DateTime<Utc>,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        options.check_rule_count(ids.len())?;
+
+        let mut reasons = Vec::with_capacity(ids.len());
+
+        for (rule, validator) in self.ordered_rules(ids)? {
+            let counters = RuleCounters::of(&self.counters, &rule.id);
+            let reason = evaluate_rule(&rule, validator.as_ref(), input, &options, at, &counters)?;
+            let evaluation = reason.evaluation;
+            reasons.push(reason);
+
+            let failed = matches!(evaluation, EvaluationResult::Fail | EvaluationResult::Error);
+            if (options.stop_on_first_failure && failed)
+                || (options.stop_when_decided && options.mode.is_decided_by(evaluation))
+            {
+                break;
+            }
+        }
+
+        let result = if options.pass_through {
+            EvaluationResult::Pass
+        } else {
+            options
+                .mode
+                .combine(reasons.iter().map(|reason| reason.evaluation))
+        };
+
+        Ok(Evaluation::new(result, reasons))
+    }
+}
+
+/// Evaluates a single rule as part of `InMemRuleRepository::evaluate_at`, checking the input
+/// against the rule's compiled `input_schema`, `validator`.
+fn evaluate_rule(
+    rule: &Rule,
+    validator: Option<&InputValidator>,
+    input: &serde_json::Value,
+    options: &EvaluationOptions,
+    at: DateTime<Utc>,
+    counters: &RuleCounters,
+) -> Result<EvaluationReason, EvaluateRuleError> {
+    let rule = rule.resolve_aliases();
+    let rule = rule.as_ref();
+    let id = &rule.id;
+    let start = options.include_timings.then(Instant::now);
+
+    let counted = |evaluation| {
+        matches!(evaluation, EvaluationResult::Pass | EvaluationResult::Fail)
+            && options.include_match_counts
+    };
+
+    let operator_context = match &rule.predicate {
+        Predicate::Raw(predicate) if options.include_operator_context => Some(predicate),
+        _ => None,
+    };
+
+    let reason = |evaluation, input_value| EvaluationReason {
+        rule: id.clone(),
+        evaluation,
+        requirement: rule.message.clone(),
+        weight: rule.weight,
+        input_value,
+        elapsed_micros: start
+            .map(|start| start.elapsed().as_micros().try_into().unwrap_or(u64::MAX)),
+        predicate_snapshot: options
+            .snapshot_predicates
+            .then(|| serde_json::to_value(&rule.predicate).ok())
+            .flatten(),
+        // A raw predicate failing to evaluate is left out rather than failing a rule which
+        // short circuited before reaching it.
+        match_count: counted(evaluation)
+            .then(|| {
+                rule.predicate
+                    .match_count_in(input, options.field_presence)
+                    .ok()
+            })
+            .flatten(),
+        total_predicate_count: options
+            .include_match_counts
+            .then(|| rule.predicate.raw_predicates().len()),
+        operator: operator_context.map(|predicate| predicate.operator),
+        path: operator_context.map(|predicate| predicate.path.clone()),
+        skip_reason: None,
+    };
+
+    let condition = match &rule.condition {
+        Some(condition) => condition.evaluate_in(input, options.field_presence),
+        None => Ok(true),
+    };
+
+    match condition {
+        Ok(true) if rule.is_active_at(at) => {}
+        Ok(_) => return Ok(reason(EvaluationResult::Skipped, None)),
+        Err(err) => {
+            tracing::warn!(rule = %id, %err, "failed to evaluate rule condition");
+            return Ok(reason(EvaluationResult::Error, None));
+        }
+    }
+
+    validator
+        .map_or(Ok(()), |validator| validator.validate(input))
+        .map_err(|errors| EvaluateRuleError::SchemaValidationFailed {
+            rule_id: id.clone(),
+            errors,
+        })?;
+
+    if !counters.try_count(rule.max_evaluations) {
+        return Ok(EvaluationReason {
+            skip_reason: Some(EVALUATION_LIMIT_REACHED.to_owned()),
+            ..reason(EvaluationResult::Skipped, None)
+        });
+    }
+
+    let (evaluation, input_value) = rule
+        .evaluate_with_retries(input, options.field_presence)
+        .map_err(|err| {
+            counters.uncount();
+            EvaluateRuleError::EvaluationError(id.clone(), err)
+        })?;
+
+    let input_value = input_value.filter(|_| options.include_input_values);
+
+    let evaluation = if evaluation {
+        EvaluationResult::Pass
+    } else {
+        EvaluationResult::Fail
+    };
+    counters.record(evaluation);
+
+    Ok(reason(evaluation, input_value))
+}
+
+/// Keeps the last rule for each id, see `InMemRuleRepository::from_rules_checked` to reject
+/// duplicates instead.
+impl FromIterator<Rule> for InMemRuleRepository {
+    fn from_iter<I: IntoIterator<Item = Rule>>(rules: I) -> Self {
+        Self::from_map(
+            rules
+                .into_iter()
+                .map(|rule| (rule.id.clone(), rule))
+                .collect(),
+        )
+    }
+}
+
+impl RuleRepository for InMemRuleRepository {
+    async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+        Ok(self.rules_where(|_| true))
+    }
+
+    async fn stats(&self, id: &String) -> Result<RuleStats, GetRuleError> {
+        if !self.rules.contains_key(id) {
+            return Err(GetRuleError::NoSuchRule(id.clone()));
+        }
+
+        Ok(self
+            .counters
+            .get(id)
+            .map(|counters| counters.stats())
+            .unwrap_or_default())
+    }
+
+    async fn find_by_operator(&self, operator: Operator) -> Result<Vec<Rule>, GetAllRulesError> {
+        // Only the matching rules are cloned, rather than every rule as with `get_all`.
+        Ok(self.rules_where(|rule| rule.applicable_operators().contains(&operator)))
+    }
+
+    async fn find_by_author(&self, author: &str) -> Result<Vec<Rule>, GetAllRulesError> {
+        Ok(self.rules_where(|rule| rule.author.as_deref() == Some(author)))
+    }
+
+    async fn find_by_review_status(&self, reviewed: bool) -> Result<Vec<Rule>, GetAllRulesError> {
+        Ok(self.rules_where(|rule| rule.reviewed_by.is_some() == reviewed))
+    }
+
+    async fn find_by_date_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Rule>, GetAllRulesError> {
+        Ok(self.rules_where(|rule| rule.was_created_within(from, to)))
+    }
+
+    async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+        if let Some(stored) = self.rules.get(id) {
+            Ok(stored.rule.clone())
+        } else {
+            Err(GetRuleError::NoSuchRule(id.clone()))
+        }
+    }
+
+    async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+        let _write = self.writes.read().await;
+
+        self.notify(self.create_unlocked(rule)?);
+
+        Ok(())
+    }
+
+    /// Creates the rules in a transaction, so either every rule is created or none are, and
+    /// watchers are only notified once all of them have been. Readers can see the rules created
+    /// before a failure until they're rolled back.
+    async fn create_many(&self, rules: Vec<Rule>) -> Result<(), CreateRuleError> {
+        let mut transaction = self.begin_transaction().await;
+
+        for rule in rules {
+            transaction.create(rule)?;
+        }
+
+        transaction.commit();
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+        let _write = self.writes.read().await;
+
+        let Some((old_rule, event)) = self.delete_unlocked(id) else {
+            return Ok(None);
+        };
+
+        self.notify(event);
+
+        Ok(Some(old_rule))
+    }
+
+    /// Renames under the write lock, so the rule can't change or the new id be taken in between
+    /// reading the rule and renaming it.
+    async fn rename(&self, old_id: String, new_id: String) -> Result<Rule, UpdateRuleError> {
+        let _write = self.writes.write().await;
+
+        let mut rule = self
+            .rules
+            .get(&old_id)
+            .map(|stored| stored.rule.clone())
+            .ok_or_else(|| UpdateRuleError::NoSuchRule(old_id.clone()))?;
+        rule.id = new_id;
+
+        let (_, event) = self.update_unlocked(old_id, rule)?;
+        let RuleChangeEvent::Updated { new, .. } = &event else {
+            unreachable!("an update is always an Updated event")
+        };
+        let renamed = new.clone();
+
+        self.notify(event);
+
+        Ok(renamed)
+    }
+
+    /// Renames wait for every other write to finish, as they change the rules of two ids.
+    async fn update(&self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
+        let (old_rule, event) = if new_rule.id == id {
+            let _write = self.writes.read().await;
+            self.update_unlocked(id, new_rule)?
+        } else {
+            let _write = self.writes.write().await;
+            self.update_unlocked(id, new_rule)?
+        };
+
+        self.notify(event);
+
+        Ok(Some(old_rule))
+    }
+
+    fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+        BroadcastStream::new(self.events.subscribe()).filter_map(Result::ok)
+    }
+
+    async fn evaluate(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        let at = options.evaluate_at.unwrap_or_else(Utc::now);
+
+        self.evaluate_at(ids, &input, options, at)
+    }
+
+    /// Rules are ordered by their dependencies, and a missing rule or cyclic dependency is the
+    /// only item of the stream.
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Stream<Item = Result<EvaluationReason, EvaluateRuleError>> + Send + 'static {
+        let at = Utc::now();
+        let options = EvaluationOptions::default();
+
+        let rules = match self.ordered_rules(ids) {
+            Ok(rules) => rules.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        };
+
+        let counters = Arc::clone(&self.counters);
+
+        tokio_stream::iter(rules).map(move |rule| {
+            rule.and_then(|(rule, validator)| {
+                let rule_counters = RuleCounters::of(&counters, &rule.id);
+                evaluate_rule(
+                    &rule,
+                    validator.as_ref(),
+                    &input,
+                    &options,
+                    at,
+                    &rule_counters,
+                )
+            })
+        })
+    }
+
+    async fn evaluate_with_context(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        let span = tracing::info_span!(
+            "evaluate",
+            request_id = %context.request_id,
+            caller_id = context.caller_id.as_deref(),
+            timestamp = %context.timestamp,
+        );
+
+        async {
+            let at = options.evaluate_at.unwrap_or(context.timestamp);
+            let evaluation = self
+                .evaluate_at(ids, &input, options, at)
+                .map(|evaluation| Evaluation {
+                    context: Some(context.clone()),
+                    ..evaluation
+                });
+
+            match &evaluation {
+                Ok(evaluation) => {
+                    tracing::info!(rules = ?ids, result = ?evaluation.result, "evaluated rules")
+                }
+                Err(err) => tracing::warn!(rules = ?ids, %err, "failed to evaluate rules"),
+            }
+
+            evaluation
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rule::Predicate;
+    use crate::{all, any, not, predicate, rule};
+    use serde_json::json;
+    use tracing_test::traced_test;
 
     mod in_mem_rule_repository {
         use super::*;
 
-        macro_rules! assert_repository_size {
-            ($db:expr, $expected:literal) => {{
-                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
-                assert_eq!(rules.len(), $expected);
+        macro_rules! assert_repository_size {
+            ($db:expr, $expected:literal) => {{
+                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
+                assert_eq!(rules.len(), $expected);
+            }};
+        }
+
+        macro_rules! assert_repository_contains {
+            ($db:expr, $rule:expr) => {{
+                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
+
+                assert!(rules.contains(&$rule));
+
+                let fetched_rule = $db.get(&$rule.id).await.expect("get failed unexpectedly");
+                assert_eq!(fetched_rule, $rule);
+            }};
+        }
+
+        macro_rules! assert_repository_does_not_contain {
+            ($db:expr, $rule:expr) => {{
+                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
+
+                assert!(!rules.contains(&$rule));
+
+                let fetched_rule = $db.get(&$rule.id).await;
+
+                match fetched_rule {
+                    err @ Err(_) => {
+                        assert_eq!(err, Err(GetRuleError::NoSuchRule($rule.id.clone())));
+                    }
+                    Ok(fetched_rule) => {
+                        assert_ne!(fetched_rule, $rule);
+                    }
+                }
+            }};
+        }
+
+        #[tokio::test]
+        async fn test_create_rule() {
+            let db = InMemRuleRepository::empty();
+            assert_repository_size!(db, 0);
+
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            assert_repository_size!(db, 1);
+            assert_repository_contains!(db, rule);
+        }
+
+        #[tokio::test]
+        async fn test_delete_rule() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            assert_repository_size!(db, 1);
+            assert_repository_contains!(db, rule);
+
+            db.delete(&rule.id).await.expect("delete should not fail");
+
+            assert_repository_size!(db, 0);
+            assert_repository_does_not_contain!(db, rule);
+        }
+
+        #[tokio::test]
+        async fn test_create_duplicate_err() {
+            let db = InMemRuleRepository::empty();
+            assert_repository_size!(db, 0);
+
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            let creation_result = db.create(rule.clone()).await;
+
+            assert!(matches!(
+                creation_result,
+                Err(CreateRuleError::Duplicate(_))
+            ))
+        }
+
+        #[tokio::test]
+        async fn test_create_many() {
+            let rule_1 = rule!("rule-1", "message", predicate!("foo" == 10));
+            let rule_2 = rule!("rule-2", "message", predicate!("foo" == 10));
+            let rule_3 = rule!("rule-3", "message", predicate!("foo" == 10));
+
+            let db = InMemRuleRepository::new(std::slice::from_ref(&rule_1));
+            let events = db.watch();
+
+            assert_eq!(
+                db.create_many(vec![rule_2.clone(), rule_1.clone()]).await,
+                Err(CreateRuleError::Duplicate(rule_1.id.clone()))
+            );
+            assert_eq!(db.get_all().await, Ok(vec![rule_1.clone()]));
+
+            db.create_many(vec![rule_2.clone(), rule_3.clone()])
+                .await
+                .expect("rules should be created");
+            assert_eq!(
+                db.get_all().await,
+                Ok(vec![rule_1, rule_2.clone(), rule_3.clone()])
+            );
+
+            // The failed call created nothing watchers would see.
+            drop(db);
+
+            assert_eq!(
+                events.collect::<Vec<_>>().await,
+                vec![
+                    RuleChangeEvent::Created(rule_2),
+                    RuleChangeEvent::Created(rule_3),
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_delete_idempotent() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            assert_repository_size!(db, 1);
+            assert_repository_contains!(db, rule);
+
+            db.delete(&rule.id).await.expect("delete should not fail");
+
+            assert_repository_size!(db, 0);
+            assert_repository_does_not_contain!(db, rule);
+
+            db.delete(&rule.id)
+                .await
+                .expect("delete of non existing rule should not fail");
+
+            assert_repository_size!(db, 0);
+            assert_repository_does_not_contain!(db, rule);
+        }
+
+        #[tokio::test]
+        async fn test_update() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            let updated_rule = rule!("rule-2", "updated message", predicate!("foo" == 10));
+
+            assert_repository_contains!(db, rule);
+            assert_repository_does_not_contain!(db, updated_rule);
+
+            db.update(rule.id.clone(), updated_rule.clone())
+                .await
+                .expect("update should not fail");
+
+            assert_repository_contains!(db, updated_rule);
+            assert_repository_does_not_contain!(db, rule);
+        }
+
+        #[tokio::test]
+        async fn test_update_input_schema() {
+            let with_schema = |id: &str, schema| Rule {
+                input_schema: Some(schema),
+                ..rule!(id, "message", predicate!("foo" == 10))
+            };
+            let rejects = |result: Result<Evaluation, EvaluateRuleError>| {
+                matches!(
+                    result,
+                    Err(EvaluateRuleError::SchemaValidationFailed { .. })
+                )
+            };
+
+            let db = InMemRuleRepository::empty();
+            db.create(with_schema("rule-1", json!({"required": ["foo"]})))
+                .await
+                .expect("rule creation should not fail");
+
+            let evaluate = async |id: &str, input| {
+                db.evaluate(&[id.to_owned()], input, EvaluationOptions::default())
+                    .await
+            };
+            assert!(rejects(evaluate("rule-1", json!({})).await));
+            assert!(!rejects(evaluate("rule-1", json!({"foo": 10})).await));
+
+            db.update(
+                "rule-1".to_owned(),
+                with_schema("rule-1", json!({"required": ["foo", "bar"]})),
+            )
+            .await
+            .expect("update should not fail");
+            assert!(rejects(evaluate("rule-1", json!({"foo": 10})).await));
+
+            db.update("rule-1".to_owned(), with_schema("rule-2", json!({})))
+                .await
+                .expect("update should not fail");
+            assert!(!rejects(evaluate("rule-2", json!({"foo": 10})).await));
+        }
+
+        #[tokio::test]
+        async fn test_updated_at() {
+            let updated_at = |rule: Result<Rule, GetRuleError>| {
+                rule.expect("rule should exist")
+                    .updated_at
+                    .expect("rule should have a timestamp")
+            };
+
+            let loaded = rule!("rule-1", "message", predicate!("foo" == 10));
+            let db = InMemRuleRepository::new(std::slice::from_ref(&loaded));
+
+            let before = Utc::now();
+            let loaded_at = updated_at(db.get(&loaded.id).await);
+            assert!(loaded_at <= before);
+
+            db.create(rule!("rule-2", "message", predicate!("foo" == 10)))
+                .await
+                .expect("rule should be created");
+            assert!(updated_at(db.get(&"rule-2".to_owned()).await) >= before);
+
+            db.update(
+                loaded.id.clone(),
+                rule!("rule-1", "updated", predicate!("foo" == 10)),
+            )
+            .await
+            .expect("rule should be updated");
+            assert!(updated_at(db.get(&loaded.id).await) > loaded_at);
+
+            // A timestamp given by the caller is replaced.
+            let stale = Rule {
+                updated_at: Some(DateTime::UNIX_EPOCH),
+                ..rule!("rule-3", "message", predicate!("foo" == 10))
+            };
+            db.create(stale).await.expect("rule should be created");
+            assert!(updated_at(db.get(&"rule-3".to_owned()).await) >= before);
+        }
+
+        #[tokio::test]
+        async fn test_get_all_order() {
+            let ids = |rules: Vec<Rule>| rules.into_iter().map(|rule| rule.id).collect::<Vec<_>>();
+
+            let rules = ["rule-c", "rule-a", "rule-b"]
+                .map(|id| rule!(id, "message", predicate!("foo" == 10)));
+
+            let db = InMemRuleRepository::new(&rules);
+            assert_eq!(
+                ids(db.get_all().await.expect("get_all should not fail")),
+                ["rule-c", "rule-a", "rule-b"]
+            );
+
+            let db = InMemRuleRepository::empty();
+            for rule in rules.iter().rev() {
+                db.create(rule.clone())
+                    .await
+                    .expect("rule creation should not fail");
+            }
+            assert_eq!(
+                ids(db.get_all().await.expect("get_all should not fail")),
+                ["rule-b", "rule-a", "rule-c"]
+            );
+
+            // Deleting a rule doesn't reorder the others, and updated rules keep their place.
+            db.delete(&"rule-b".to_owned())
+                .await
+                .expect("delete should not fail");
+            db.update(
+                "rule-a".to_owned(),
+                rule!("rule-d", "message", predicate!("foo" == 10)),
+            )
+            .await
+            .expect("update should not fail");
+            db.create(rule!("rule-b", "message", predicate!("foo" == 10)))
+                .await
+                .expect("rule creation should not fail");
+
+            assert_eq!(
+                ids(db.get_all().await.expect("get_all should not fail")),
+                ["rule-d", "rule-c", "rule-b"]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_created_at() {
+            let created_at = |rule: Result<Rule, GetRuleError>| {
+                rule.expect("rule should exist")
+                    .created_at
+                    .expect("rule should have a timestamp")
+            };
+
+            let loaded = rule!("rule-1", "message", predicate!("foo" == 10));
+            let db = InMemRuleRepository::new(std::slice::from_ref(&loaded));
+            let loaded_at = created_at(db.get(&loaded.id).await);
+
+            let threshold = Utc::now();
+            db.create(rule!("rule-2", "message", predicate!("foo" == 10)))
+                .await
+                .expect("rule should be created");
+            assert!(created_at(db.get(&"rule-2".to_owned()).await) >= threshold);
+
+            // Updating a rule, even renaming it, keeps when it was created.
+            db.update(
+                loaded.id.clone(),
+                rule!("rule-3", "updated", predicate!("foo" == 10)),
+            )
+            .await
+            .expect("rule should be updated");
+            assert_eq!(created_at(db.get(&"rule-3".to_owned()).await), loaded_at);
+
+            let ids = |rules: Result<Vec<Rule>, GetAllRulesError>| {
+                rules
+                    .expect("find_by_date_range should not fail")
+                    .into_iter()
+                    .map(|rule| rule.id)
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                ids(db.find_by_date_range(Some(threshold), None).await),
+                ["rule-2"]
+            );
+            assert_eq!(
+                ids(db.find_by_date_range(None, Some(threshold)).await),
+                ["rule-3"]
+            );
+            assert_eq!(
+                ids(db.find_by_date_range(None, None).await),
+                ["rule-3", "rule-2"]
+            );
+            assert_eq!(
+                ids(db
+                    .find_by_date_range(Some(threshold), Some(threshold))
+                    .await),
+                Vec::<String>::new()
+            );
+
+            // The default implementation agrees with the in memory one.
+            let erased = db.clone().into_erased();
+            assert_eq!(
+                ids(erased.find_by_date_range(Some(threshold), None).await),
+                ["rule-2"]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_update_err() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            let updated_rule = rule!("rule-2", "updated message", predicate!("foo" == 10));
+
+            let update_result = db.update("rule-3".to_owned(), updated_rule.clone()).await;
+
+            assert!(matches!(update_result, Err(UpdateRuleError::NoSuchRule(_))));
+        }
+
+        #[tokio::test]
+        async fn test_validation_errors() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+            db.create(rule!("rule-2", "message", predicate!("foo" == 10)))
+                .await
+                .expect("rule creation should not fail");
+
+            let anonymous = Rule {
+                author: Some(" ".to_owned()),
+                ..rule!("rule-3", "message", predicate!("foo" == 10))
+            };
+            assert_eq!(
+                db.create(anonymous).await,
+                Err(CreateRuleError::Validation(ValidationError::new(
+                    "author",
+                    "must not be empty"
+                )))
+            );
+
+            let unreviewed = Rule {
+                reviewed_by: Some(String::new()),
+                ..rule.clone()
+            };
+            assert_eq!(
+                db.update(rule.id.clone(), unreviewed).await,
+                Err(UpdateRuleError::Validation(ValidationError::new(
+                    "reviewedBy",
+                    "must not be empty"
+                )))
+            );
+
+            // Renaming a rule can't replace another one.
+            let renamed = rule!("rule-2", "renamed", predicate!("foo" == 10));
+            assert_eq!(
+                db.update(rule.id.clone(), renamed).await,
+                Err(UpdateRuleError::Duplicate("rule-2".to_owned()))
+            );
+
+            assert_eq!(
+                db.get_all().await.map(|rules| rules.len()),
+                Ok(2),
+                "no rule should have been written"
+            );
+            assert_repository_contains!(db, rule);
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_indexed() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", predicate!("bar" == 10)),
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            let indexed = db
+                .evaluate_indexed(&ids, json!({"foo": 10}))
+                .await
+                .expect("evaluation should not fail");
+
+            assert_eq!(indexed.result, EvaluationResult::Fail);
+            assert_eq!(indexed.get("rule-1"), Some(&evaluation.reasons[0]));
+            assert_eq!(indexed.get("rule-2"), Some(&evaluation.reasons[1]));
+            assert_eq!(indexed.get("rule-3"), None);
+            assert_eq!(IndexedEvaluation::from(evaluation), indexed);
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_with_transform() {
+            use crate::core::transform::{flatten_arrays, lowercase_keys};
+
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("name" == "Jo")),
+                rule!("rule-2", "message", predicate!("tags" contains "red")),
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+            let input = json!({"Name": "Jo", "tags": [["blue"], ["red"]]});
+
+            let result = |evaluation: Result<Evaluation, _>| {
+                evaluation
+                    .expect("evaluation should not fail")
+                    .reasons
+                    .into_iter()
+                    .map(|reason| reason.evaluation)
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                result(
+                    db.evaluate(&ids, input.clone(), EvaluationOptions::default())
+                        .await
+                ),
+                [EvaluationResult::Fail, EvaluationResult::Fail]
+            );
+            assert_eq!(
+                result(
+                    db.evaluate_with_transform(&ids, input.clone(), lowercase_keys)
+                        .await
+                ),
+                [EvaluationResult::Pass, EvaluationResult::Fail]
+            );
+            assert_eq!(
+                result(
+                    db.evaluate_with_transform(&ids, input, flatten_arrays)
+                        .await
+                ),
+                [EvaluationResult::Fail, EvaluationResult::Pass]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_many_to_evaluation() {
+            let rules = [
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                Rule {
+                    weight: 3.0,
+                    ..rule!(
+                        "rule-2",
+                        "message",
+                        all!(predicate!("foo" > 5), predicate!("bar" == 1))
+                    )
+                },
+            ];
+            let db = InMemRuleRepository::new(&rules);
+            let ids = ["rule-2".to_owned(), "rule-1".to_owned()];
+            let ordered = [rules[1].clone(), rules[0].clone()];
+
+            for input in [
+                json!({"foo": 10, "bar": 1}),
+                json!({"foo": 10, "bar": 2}),
+                json!({"foo": 1}),
+            ] {
+                let evaluation = db
+                    .evaluate(&ids, input.clone(), EvaluationOptions::default())
+                    .await
+                    .expect("evaluation should not fail");
+
+                assert_eq!(
+                    evaluate_many_to_evaluation(&ordered, &input),
+                    evaluation,
+                    "{input}"
+                );
+            }
+
+            let evaluation = evaluate_many_to_evaluation(&rules, &json!({"foo": "10"}));
+            assert_eq!(evaluation.result, EvaluationResult::Error);
+            assert_eq!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.evaluation)
+                    .collect::<Vec<_>>(),
+                [EvaluationResult::Fail, EvaluationResult::Error]
+            );
+        }
+
+        #[test]
+        fn test_evaluate_grouped_rule() {
+            let rule = Rule {
+                group_by: Some("tenant.id".to_owned()),
+                ..rule!("rule-1", "message", predicate!("total" > 0))
+            };
+            let inputs = [
+                json!({"tenant": {"id": "a"}, "total": 1}),
+                json!({"tenant": {"id": 2}, "total": 0}),
+                json!({"tenant": {"id": "a"}, "total": 2}),
+                json!({"tenant": {"id": 2}, "total": 1}),
+                json!({"tenant": {"id": "c"}, "total": "1"}),
+                json!({"total": 1}),
+            ];
+
+            let evaluations = evaluate_grouped(&rule, &inputs);
+            let result_of = |key: &str| evaluations[key].result;
+
+            assert_eq!(evaluations.len(), 4);
+            assert_eq!(result_of("a"), EvaluationResult::Pass);
+            assert_eq!(result_of("2"), EvaluationResult::Fail);
+            assert_eq!(result_of("c"), EvaluationResult::Error);
+            assert_eq!(result_of("null"), EvaluationResult::Pass);
+
+            // A reason for each input of the group, in order.
+            assert_eq!(
+                evaluations["2"]
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.evaluation)
+                    .collect::<Vec<_>>(),
+                [EvaluationResult::Fail, EvaluationResult::Pass]
+            );
+
+            let ungrouped = evaluate_grouped(
+                &Rule {
+                    group_by: None,
+                    ..rule
+                },
+                &inputs,
+            );
+            assert_eq!(ungrouped.len(), 1);
+            assert_eq!(ungrouped["null"].reasons.len(), inputs.len());
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_grouped() {
+            let db = InMemRuleRepository::new(&[Rule {
+                group_by: Some("tenant.id".to_owned()),
+                condition: Some(predicate!("enabled" == true).into()),
+                ..rule!("rule-1", "message", predicate!("total" > 0))
+            }]);
+            let id = "rule-1".to_owned();
+            let inputs = vec![
+                json!({"tenant": {"id": "a"}, "enabled": true, "total": 1}),
+                json!({"tenant": {"id": 2}, "enabled": true, "total": 0}),
+                json!({"tenant": {"id": "a"}, "enabled": true, "total": 2}),
+                json!({"tenant": {"id": 2}, "enabled": false, "total": 0}),
+                json!({"tenant": {"id": {"b": 1}}, "enabled": true, "total": 1}),
+                json!({"enabled": true, "total": 1}),
+            ];
+
+            let evaluations = db
+                .evaluate_grouped(&id, inputs.clone(), None, EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            let result_of = |key: &str| evaluations[key].result;
+
+            // Strings are keyed as they are, other values as JSON.
+            assert_eq!(evaluations.len(), 4);
+            assert_eq!(result_of("a"), EvaluationResult::Pass);
+            assert_eq!(result_of("2"), EvaluationResult::Fail);
+            assert_eq!(result_of(r#"{"b":1}"#), EvaluationResult::Pass);
+            assert_eq!(result_of("null"), EvaluationResult::Pass);
+
+            // A reason for each input of the group, in order, with the rule's condition applied.
+            assert_eq!(
+                evaluations["2"]
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.evaluation)
+                    .collect::<Vec<_>>(),
+                [EvaluationResult::Fail, EvaluationResult::Skipped]
+            );
+
+            // Every evaluated input is counted.
+            let stats = db.stats(&id).await.expect("rule should exist");
+            assert_eq!(stats.evaluations, 5);
+
+            let ungrouped = db
+                .evaluate_grouped(
+                    &id,
+                    inputs.clone(),
+                    Some("missing".to_owned()),
+                    EvaluationOptions::default(),
+                )
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(ungrouped.len(), 1);
+            assert_eq!(ungrouped["null"].reasons.len(), inputs.len());
+            assert_eq!(ungrouped["null"].result, EvaluationResult::Fail);
+
+            assert_eq!(
+                db.evaluate_grouped(
+                    &"rule-2".to_owned(),
+                    inputs,
+                    None,
+                    EvaluationOptions::default()
+                )
+                .await,
+                Err(EvaluateRuleError::NoSuchRule("rule-2".to_owned()))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_max_rules() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", predicate!("foo" == 10)),
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+            let options = |max_rules| EvaluationOptions {
+                max_rules,
+                ..Default::default()
+            };
+
+            for max_rules in [None, Some(2)] {
+                assert!(
+                    db.evaluate(&ids, json!({"foo": 10}), options(max_rules))
+                        .await
+                        .is_ok()
+                );
+            }
+
+            assert_eq!(
+                db.evaluate(&ids, json!({"foo": 10}), options(Some(1)))
+                    .await,
+                Err(EvaluateRuleError::TooManyRules {
+                    requested: 2,
+                    max: 1
+                })
+            );
+            assert_eq!(
+                db.evaluate_with_context(
+                    &ids,
+                    json!({"foo": 10}),
+                    options(Some(1)),
+                    EvaluationContext::new("request-1")
+                )
+                .await,
+                Err(EvaluateRuleError::TooManyRules {
+                    requested: 2,
+                    max: 1
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_timings() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!(
+                    "rule-2",
+                    "message",
+                    all!(predicate!("foo" > 5), predicate!("bar" == 1))
+                ),
+            ]);
+
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+
+            assert!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .all(|reason| reason.elapsed_micros.is_none())
+            );
+
+            let evaluation = db
+                .evaluate(
+                    &ids,
+                    json!({"foo": 10}),
+                    EvaluationOptions {
+                        include_timings: true,
+                        ..EvaluationOptions::default()
+                    },
+                )
+                .await
+                .expect("evaluation should not fail");
+
+            assert!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .all(|reason| reason.elapsed_micros.is_some())
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_predicate_snapshots() {
+            let rule = rule!(
+                "rule-1",
+                "message",
+                any!(predicate!("foo" == 10), predicate!("bar" is_null))
+            );
+            let db = InMemRuleRepository::new(std::slice::from_ref(&rule));
+            let ids = ["rule-1".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].predicate_snapshot, None);
+
+            let options = EvaluationOptions {
+                snapshot_predicates: true,
+                ..EvaluationOptions::default()
+            };
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), options.clone())
+                .await
+                .expect("evaluation should not fail");
+            let snapshot = evaluation.reasons[0]
+                .predicate_snapshot
+                .clone()
+                .expect("predicate should be snapshotted");
+            assert_eq!(
+                serde_json::from_value::<Predicate>(snapshot).expect("snapshot should parse"),
+                rule.predicate
+            );
+
+            // Later evaluations see the updated predicate.
+            let updated = rule!("rule-1", "message", predicate!("foo" > 5));
+            db.update("rule-1".to_owned(), updated.clone())
+                .await
+                .expect("rule should be updated");
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), options)
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(
+                evaluation.reasons[0].predicate_snapshot,
+                Some(json!({"path": "foo", "operator": "greater", "value": 5}))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_match_counts() {
+            let db = InMemRuleRepository::new(&[
+                rule!(
+                    "rule-1",
+                    "message",
+                    all!(predicate!("foo" == 10), predicate!("bar" == 10))
+                ),
+                Rule {
+                    active_until: Some(DateTime::UNIX_EPOCH),
+                    ..rule!("rule-2", "message", predicate!("foo" == 10))
+                },
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].match_count, None);
+            assert_eq!(evaluation.reasons[0].total_predicate_count, None);
+
+            let options = EvaluationOptions {
+                include_match_counts: true,
+                ..EvaluationOptions::default()
+            };
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), options)
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Fail);
+            assert_eq!(evaluation.reasons[0].match_count, Some(1));
+            assert_eq!(evaluation.reasons[0].total_predicate_count, Some(2));
+
+            // Rules which aren't evaluated have nothing to count.
+            assert_eq!(evaluation.reasons[1].evaluation, EvaluationResult::Skipped);
+            assert_eq!(evaluation.reasons[1].match_count, None);
+            assert_eq!(evaluation.reasons[1].total_predicate_count, Some(1));
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_operator_context() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("age" >= 18)),
+                rule!(
+                    "rule-2",
+                    "message",
+                    all!(predicate!("age" >= 18), predicate!("name" is_not_null))
+                ),
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"age": 17}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].operator, None);
+            assert_eq!(evaluation.reasons[0].path, None);
+
+            let options = EvaluationOptions {
+                include_operator_context: true,
+                ..EvaluationOptions::default()
+            };
+
+            let evaluation = db
+                .evaluate(&ids, json!({"age": 17}), options)
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].operator, Some(Operator::GreaterEqual));
+            assert_eq!(evaluation.reasons[0].path.as_deref(), Some("age"));
+
+            // Compound predicates have no single operator to report.
+            assert_eq!(evaluation.reasons[1].operator, None);
+            assert_eq!(evaluation.reasons[1].path, None);
+        }
+
+        #[tokio::test]
+        async fn test_watch() {
+            let db = InMemRuleRepository::empty();
+            let events = db.watch();
+
+            let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+            let updated_rule = rule!("rule-1", "updated message", predicate!("foo" == 12));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+            db.create(rule.clone())
+                .await
+                .expect_err("duplicate rule should not be created");
+            db.update(rule.id.clone(), updated_rule.clone())
+                .await
+                .expect("update should not fail");
+            db.delete(&rule.id).await.expect("delete should not fail");
+            db.delete(&rule.id).await.expect("delete should not fail");
+
+            // Dropping the last handle to the repository ends the stream.
+            drop(db);
+
+            assert_eq!(
+                events.collect::<Vec<_>>().await,
+                vec![
+                    RuleChangeEvent::Created(rule.clone()),
+                    RuleChangeEvent::Updated {
+                        old: rule.clone(),
+                        new: updated_rule,
+                    },
+                    RuleChangeEvent::Deleted(rule.id),
+                ]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_pass_through() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 20)),
+                rule!("rule-2", "message", predicate!("foo" > 10)),
+                Rule {
+                    condition: Some(predicate!("bar" > 10).into()),
+                    ..rule!("rule-3", "message", predicate!("foo" == 10))
+                },
+            ]);
+            let ids = [
+                "rule-1".to_owned(),
+                "rule-2".to_owned(),
+                "rule-3".to_owned(),
+            ];
+            let input = json!({"foo": 10, "bar": "ten"});
+
+            let evaluation = db
+                .evaluate(&ids, input.clone(), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.result, EvaluationResult::Error);
+
+            let evaluation = db
+                .evaluate(
+                    &ids,
+                    input,
+                    EvaluationOptions {
+                        pass_through: true,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .expect("evaluation should not fail");
+
+            // Every rule fails or errors, but the overall result still passes.
+            assert_eq!(evaluation.result, EvaluationResult::Pass);
+            assert_eq!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.evaluation)
+                    .collect::<Vec<_>>(),
+                [
+                    EvaluationResult::Fail,
+                    EvaluationResult::Fail,
+                    EvaluationResult::Error
+                ]
+            );
+            assert_eq!(evaluation.weighted_score, 0.0);
+        }
+
+        #[tokio::test]
+        async fn test_stats() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                Rule {
+                    condition: Some(predicate!("bar" is_not_null).into()),
+                    ..rule!("rule-2", "message", predicate!("foo" == 10))
+                },
+            ]);
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            assert_eq!(db.stats(&ids[0]).await, Ok(RuleStats::default()));
+
+            for input in [json!({"foo": 10}), json!({"foo": 10}), json!({"foo": 5})] {
+                db.evaluate(&ids, input, EvaluationOptions::default())
+                    .await
+                    .expect("evaluation should not fail");
+            }
+
+            let reasons: Vec<_> = db
+                .evaluate_streaming(&ids[..1], json!({"foo": 5}))
+                .collect()
+                .await;
+            assert_eq!(reasons.len(), 1);
+
+            assert_eq!(
+                db.stats(&ids[0]).await,
+                Ok(RuleStats {
+                    evaluations: 4,
+                    passes: 2,
+                    fails: 2
+                })
+            );
+            // Skipped evaluations aren't counted.
+            assert_eq!(db.stats(&ids[1]).await, Ok(RuleStats::default()));
+
+            db.delete(&ids[0]).await.expect("rule should be deleted");
+            assert_eq!(
+                db.stats(&ids[0]).await,
+                Err(GetRuleError::NoSuchRule("rule-1".to_owned()))
+            );
+
+            db.create(rule!("rule-1", "message", predicate!("foo" == 10)))
+                .await
+                .expect("rule should be created");
+            assert_eq!(db.stats(&ids[0]).await, Ok(RuleStats::default()));
+        }
+
+        #[tokio::test]
+        async fn test_max_evaluations() {
+            let db = InMemRuleRepository::new(&[Rule {
+                max_evaluations: Some(2),
+                ..rule!("rule-1", "message", predicate!("foo" == 10))
+            }]);
+            let ids = ["rule-1".to_owned()];
+
+            for _ in 0..2 {
+                let evaluation = db
+                    .evaluate(&ids, json!({"foo": 5}), EvaluationOptions::default())
+                    .await
+                    .expect("evaluation should not fail");
+                assert_eq!(evaluation.result, EvaluationResult::Fail);
+                assert_eq!(evaluation.reasons[0].skip_reason, None);
+            }
+
+            // Once the limit is reached the rule is skipped, and no longer fails the evaluation.
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 5}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.result, EvaluationResult::Pass);
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Skipped);
+            assert_eq!(
+                evaluation.reasons[0].skip_reason.as_deref(),
+                Some(EVALUATION_LIMIT_REACHED)
+            );
+
+            assert_eq!(
+                db.stats(&ids[0]).await,
+                Ok(RuleStats {
+                    evaluations: 2,
+                    passes: 0,
+                    fails: 2
+                })
+            );
+
+            // Raising the limit allows further evaluations, as updates keep the counts.
+            db.update(
+                "rule-1".to_owned(),
+                Rule {
+                    max_evaluations: Some(3),
+                    ..rule!("rule-1", "message", predicate!("foo" == 10))
+                },
+            )
+            .await
+            .expect("rule should be updated");
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Pass);
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Skipped);
+        }
+
+        #[tokio::test]
+        async fn test_max_evaluations_error() {
+            let db = InMemRuleRepository::new(&[Rule {
+                max_evaluations: Some(1),
+                ..rule!("rule-1", "message", predicate!("foo" > 10))
+            }]);
+            let ids = ["rule-1".to_owned()];
+
+            // An evaluation which errors doesn't use up the limit or count in the stats.
+            for _ in 0..2 {
+                assert!(matches!(
+                    db.evaluate(&ids, json!({"foo": "bar"}), EvaluationOptions::default())
+                        .await,
+                    Err(EvaluateRuleError::EvaluationError(id, _)) if id == "rule-1"
+                ));
+            }
+            assert_eq!(db.stats(&ids[0]).await, Ok(RuleStats::default()));
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 20}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Pass);
+            assert_eq!(
+                db.stats(&ids[0]).await,
+                Ok(RuleStats {
+                    evaluations: 1,
+                    passes: 1,
+                    fails: 0
+                })
+            );
+        }
+
+        #[tokio::test]
+        async fn test_stop_on_first_failure() {
+            let db = InMemRuleRepository::empty();
+
+            let ids = (1..=10).map(|i| format!("rule-{i}")).collect::<Vec<_>>();
+
+            for (i, id) in ids.iter().enumerate() {
+                let expected = if i == 2 { 0 } else { 10 };
+
+                db.create(rule!(id, "rule failed", predicate!("foo" == expected)))
+                    .await
+                    .expect("rule creation should not fail");
+            }
+
+            let evaluation = db
+                .evaluate(
+                    &ids,
+                    json!({"foo": 10}),
+                    EvaluationOptions {
+                        stop_on_first_failure: true,
+                        ..Default::default()
+                    },
+                )
+                .await
+                .expect("evaluation should not fail");
+
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert_eq!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.rule.as_str())
+                    .collect::<Vec<_>>(),
+                ["rule-1", "rule-2", "rule-3"]
+            );
+            assert_eq!(
+                evaluation.reasons.last().map(|reason| &reason.evaluation),
+                Some(&EvaluationResult::Fail)
+            );
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert_eq!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.rule.clone())
+                    .collect::<Vec<_>>(),
+                ids
+            );
+        }
+
+        #[tokio::test]
+        async fn test_stop_when_decided() {
+            let db = InMemRuleRepository::empty();
+            let ids = ["rule-1", "rule-2", "rule-3"].map(String::from);
+
+            // rule-1 fails, rule-2 passes and rule-3 fails.
+            for (id, expected) in ids.iter().zip([0, 10, 0]) {
+                db.create(rule!(id, "rule failed", predicate!("foo" == expected)))
+                    .await
+                    .expect("rule creation should not fail");
+            }
+
+            let evaluate = async |options| {
+                db.evaluate(&ids, json!({"foo": 10}), options)
+                    .await
+                    .expect("evaluation should not fail")
+            };
+
+            for (mode, result, evaluated) in [
+                (EvaluationMode::All, EvaluationResult::Fail, 1),
+                (EvaluationMode::Any, EvaluationResult::Pass, 2),
+                (EvaluationMode::None, EvaluationResult::Fail, 2),
+            ] {
+                let evaluation = evaluate(EvaluationOptions {
+                    stop_when_decided: true,
+                    mode,
+                    ..Default::default()
+                })
+                .await;
+                assert_eq!(evaluation.result, result, "{mode:?}");
+                assert_eq!(evaluation.reasons.len(), evaluated, "{mode:?}");
+
+                // Stopping on the first failure stops at rule-1 whatever the mode.
+                let evaluation = evaluate(EvaluationOptions {
+                    stop_on_first_failure: true,
+                    mode,
+                    ..Default::default()
+                })
+                .await;
+                assert_eq!(evaluation.reasons.len(), 1, "{mode:?}");
+            }
+        }
+
+        macro_rules! evaluation_order {
+            ($db:expr, $ids:expr) => {{
+                let ids = $ids.map(String::from);
+
+                $db.evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                    .await
+                    .map(|evaluation| {
+                        evaluation
+                            .reasons
+                            .into_iter()
+                            .map(|reason| reason.rule)
+                            .collect::<Vec<_>>()
+                    })
             }};
         }
 
-        macro_rules! assert_repository_contains {
-            ($db:expr, $rule:expr) => {{
-                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
+        #[tokio::test]
+        async fn test_evaluate_linear_dependencies() {
+            let db = InMemRuleRepository::new(&[
+                rule!("c", "message", predicate!("foo" == 10), depends_on: ["b"]),
+                rule!("b", "message", predicate!("foo" == 10), depends_on: ["a"]),
+                rule!("a", "message", predicate!("foo" == 10)),
+            ]);
+
+            assert_eq!(
+                evaluation_order!(db, ["c", "b", "a"]),
+                Ok(vec!["a".to_owned(), "b".to_owned(), "c".to_owned()])
+            );
+
+            assert_eq!(
+                evaluation_order!(db, ["c", "a"]),
+                Ok(vec!["c".to_owned(), "a".to_owned()]),
+                "dependencies which weren't requested should not affect the order"
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_streaming() {
+            let db = InMemRuleRepository::new(&[
+                rule!("b", "message", predicate!("foo" == 10), depends_on: ["a"]),
+                rule!("a", "message", predicate!("foo" == 20)),
+            ]);
+            let ids = ["b".to_owned(), "a".to_owned()];
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+
+            let reasons: Result<Vec<_>, _> = db
+                .evaluate_streaming(&ids, json!({"foo": 10}))
+                .collect()
+                .await;
+            assert_eq!(reasons, Ok(evaluation.reasons));
+
+            let reasons: Vec<_> = db
+                .evaluate_streaming(&["a".to_owned(), "c".to_owned()], json!({"foo": 10}))
+                .collect()
+                .await;
+            assert_eq!(
+                reasons,
+                vec![Err(EvaluateRuleError::NoSuchRule("c".to_owned()))]
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_diamond_dependencies() {
+            let db = InMemRuleRepository::new(&[
+                rule!("d", "message", predicate!("foo" == 10), depends_on: ["b", "c"]),
+                rule!("c", "message", predicate!("foo" == 10), depends_on: ["a"]),
+                rule!("b", "message", predicate!("foo" == 10), depends_on: ["a"]),
+                rule!("a", "message", predicate!("foo" == 10)),
+            ]);
+
+            assert_eq!(
+                evaluation_order!(db, ["d", "c", "b", "a"]),
+                Ok(vec![
+                    "a".to_owned(),
+                    "b".to_owned(),
+                    "c".to_owned(),
+                    "d".to_owned()
+                ])
+            );
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_cyclic_dependencies_err() {
+            let db = InMemRuleRepository::new(&[
+                rule!("a", "message", predicate!("foo" == 10), depends_on: ["c"]),
+                rule!("b", "message", predicate!("foo" == 10), depends_on: ["a"]),
+                rule!("c", "message", predicate!("foo" == 10), depends_on: ["b"]),
+                rule!("d", "message", predicate!("foo" == 10), depends_on: ["d"]),
+            ]);
+
+            assert_eq!(
+                evaluation_order!(db, ["a", "b", "c"]),
+                Err(EvaluateRuleError::CyclicDependency(vec![
+                    "a".to_owned(),
+                    "c".to_owned(),
+                    "b".to_owned(),
+                    "a".to_owned()
+                ]))
+            );
+
+            assert_eq!(
+                evaluation_order!(db, ["d"]),
+                Err(EvaluateRuleError::CyclicDependency(vec![
+                    "d".to_owned(),
+                    "d".to_owned()
+                ]))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_clone_rule() {
+            let db = InMemRuleRepository::empty();
+            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+
+            db.create(rule.clone())
+                .await
+                .expect("rule creation should not fail");
+
+            let cloned_rule = db
+                .clone_rule(&rule.id, "rule-2".to_owned())
+                .await
+                .expect("clone should not fail");
+
+            assert_eq!(
+                cloned_rule,
+                rule!("rule-2", "important rule failed", predicate!("foo" == 10))
+            );
+
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, rule);
+            assert_repository_contains!(db, cloned_rule);
+        }
+
+        #[tokio::test]
+        async fn test_clone_rule_err() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "important rule failed", predicate!("foo" == 10)),
+                rule!("rule-2", "important rule failed", predicate!("foo" == 12)),
+            ]);
+
+            assert_eq!(
+                db.clone_rule(&"rule-3".to_owned(), "rule-4".to_owned())
+                    .await,
+                Err(CloneRuleError::NoSuchRule("rule-3".to_owned()))
+            );
+
+            assert_eq!(
+                db.clone_rule(&"rule-1".to_owned(), "rule-2".to_owned())
+                    .await,
+                Err(CloneRuleError::Duplicate("rule-2".to_owned()))
+            );
+
+            assert_eq!(
+                db.clone_rule(&"rule-1".to_owned(), String::new()).await,
+                Err(CloneRuleError::MissingId)
+            );
+
+            assert_repository_size!(db, 2);
+        }
+
+        #[tokio::test]
+        async fn test_rename() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", predicate!("foo" == 12)),
+            ]);
+
+            let renamed = db
+                .rename("rule-1".to_owned(), "rule-3".to_owned())
+                .await
+                .expect("rename should not fail");
+
+            assert_eq!(renamed.id, "rule-3");
+            assert_eq!(renamed.predicate, predicate!("foo" == 10).into());
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, renamed);
+            assert_eq!(
+                db.get(&"rule-1".to_owned()).await,
+                Err(GetRuleError::NoSuchRule("rule-1".to_owned()))
+            );
+
+            assert_eq!(
+                db.rename("rule-1".to_owned(), "rule-4".to_owned()).await,
+                Err(UpdateRuleError::NoSuchRule("rule-1".to_owned()))
+            );
+
+            assert_eq!(
+                db.rename("rule-2".to_owned(), "rule-3".to_owned()).await,
+                Err(UpdateRuleError::Duplicate("rule-3".to_owned()))
+            );
+
+            assert_repository_size!(db, 2);
+        }
+
+        #[tokio::test]
+        async fn test_from_iter() {
+            let rule_1 = rule!("rule-1", "message", predicate!("foo" == 10));
+            let rule_2 = rule!("rule-2", "message", any!());
+
+            let db: InMemRuleRepository = [rule_1.clone(), rule_2.clone()].into_iter().collect();
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, rule_1);
+            assert_repository_contains!(db, rule_2);
+
+            // The last rule with a given id wins.
+            let replacement = rule!("rule-1", "other message", all!());
+            let db: InMemRuleRepository = [rule_1.clone(), rule_2, replacement.clone()]
+                .into_iter()
+                .collect();
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, replacement);
+            assert_repository_does_not_contain!(db, rule_1);
+        }
+
+        #[tokio::test]
+        async fn test_from_rules_checked() {
+            let rule_1 = rule!("rule-1", "message", predicate!("foo" == 10));
+            let rule_2 = rule!("rule-2", "message", any!(predicate!("bar" is_null)));
+
+            let db = InMemRuleRepository::from_rules_checked(
+                [rule_1.clone(), rule_2.clone()].into_iter(),
+            )
+            .expect("rules should be unique");
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, rule_1);
+            assert_repository_contains!(db, rule_2);
+
+            let duplicate = rule!("rule-1", "other message", all!(predicate!("bar" is_null)));
+            assert!(matches!(
+                InMemRuleRepository::from_rules_checked([rule_1, rule_2, duplicate].into_iter()),
+                Err(CreateRuleError::Duplicate(id)) if id == "rule-1"
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_import_from_reader() {
+            let input = r#"[
+                {"id": "rule-1", "message": "message", "predicate": {"path": "foo", "operator": "==", "value": 10}},
+                {"id": "rule-2", "message": "message", "predicate": {"any": [{"path": "bar", "operator": "isNull"}]}}
+            ]"#;
+
+            let db = InMemRuleRepository::import_from_reader(input.as_bytes())
+                .await
+                .expect("import should not fail");
+
+            assert_repository_size!(db, 2);
+            assert_repository_contains!(db, rule!("rule-1", "message", predicate!("foo" == 10)));
+            assert_repository_contains!(
+                db,
+                rule!("rule-2", "message", any!(predicate!("bar" is_null)))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_import_from_reader_err() {
+            let input = r#"[
+                {"id": "rule-1", "message": "message", "predicate": {"any": [{"path": "bar", "operator": "isNull"}]}},
+                {"id": "rule-1", "message": "other message", "predicate": {"all": [{"path": "bar", "operator": "isNull"}]}}
+            ]"#;
+
+            assert!(matches!(
+                InMemRuleRepository::import_from_reader(input.as_bytes()).await,
+                Err(ImportError::DuplicateId(id)) if id == "rule-1"
+            ));
+
+            assert!(matches!(
+                InMemRuleRepository::import_from_reader(r#"[{"id": "rule-1"}]"#.as_bytes()).await,
+                Err(ImportError::Parse(_))
+            ));
+
+            // Empty compound predicates deserialize, but are rejected on validation.
+            let input = r#"[{"id": "rule-1", "message": "message", "predicate": {"any": []}}]"#;
+            assert!(matches!(
+                InMemRuleRepository::import_from_reader(input.as_bytes()).await,
+                Err(ImportError::Invalid(RuleValidationError::EmptyCompound(id))) if id == "rule-1"
+            ));
+
+            let input = r#"[{"id": "rule-1", "message": "message", "predicate": {"path": "foo", "operator": "isNull"}, "author": ""}]"#;
+            assert!(matches!(
+                InMemRuleRepository::import_from_reader(input.as_bytes()).await,
+                Err(ImportError::Validation(err)) if err.field == "author"
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_export_to_writer() {
+            let rules = [
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", all!(predicate!("bar" > 5)), depends_on: ["rule-1"]),
+            ];
+
+            let db = InMemRuleRepository::new(&rules);
+
+            let mut buffer = Vec::new();
+            db.export_to_writer(&mut buffer)
+                .await
+                .expect("export should not fail");
+
+            let imported = InMemRuleRepository::import_from_reader(buffer.as_slice())
+                .await
+                .expect("import should not fail");
+
+            assert_repository_size!(imported, 2);
+            assert_repository_contains!(imported, rules[0]);
+            assert_repository_contains!(imported, rules[1]);
+        }
+
+        #[tokio::test]
+        async fn test_export_json() {
+            let rules = [
+                rule!("rule-2", "message", predicate!("foo" == 20)),
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+            ];
 
-                assert!(rules.contains(&$rule));
+            let db = InMemRuleRepository::new(&rules);
 
-                let fetched_rule = $db.get(&$rule.id).await.expect("get failed unexpectedly");
-                assert_eq!(fetched_rule, $rule);
-            }};
+            let json = db.export_json().await.expect("export should not fail");
+            assert!(
+                json.contains("\n  {"),
+                "export should be pretty printed: {json}"
+            );
+
+            let exported: Vec<Rule> = serde_json::from_str(&json).expect("export should be JSON");
+            assert_eq!(exported, [rules[1].clone(), rules[0].clone()]);
+
+            let yaml = db
+                .export_as(RuleFileFormat::Yaml)
+                .await
+                .expect("export should not fail");
+            assert_eq!(RuleFileFormat::Yaml.parse(yaml.as_bytes()), Ok(exported));
         }
 
-        macro_rules! assert_repository_does_not_contain {
-            ($db:expr, $rule:expr) => {{
-                let rules = $db.get_all().await.expect("get_all failed unexpectedly");
+        #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+        async fn test_concurrent_access() {
+            let db =
+                InMemRuleRepository::new(&[rule!("shared", "message", predicate!("foo" == 10))]);
 
-                assert!(!rules.contains(&$rule));
+            let mut tasks = Vec::new();
 
-                let fetched_rule = $db.get(&$rule.id).await;
+            for writer in 0..10 {
+                let db = db.clone();
 
-                match fetched_rule {
-                    err @ Err(_) => {
-                        assert_eq!(err, Err(GetRuleError::NoSuchRule($rule.id.clone())));
+                tasks.push(tokio::spawn(async move {
+                    for i in 0..50 {
+                        let id = format!("rule-{writer}-{i}");
+
+                        db.create(rule!(id.clone(), "message", predicate!("foo" == i)))
+                            .await
+                            .expect("rule creation should not fail");
+
+                        db.update(
+                            id.clone(),
+                            rule!(id.clone(), "updated message", predicate!("foo" == i)),
+                        )
+                        .await
+                        .expect("update should not fail");
+
+                        if i % 2 == 0 {
+                            db.delete(&id).await.expect("delete should not fail");
+                        }
                     }
-                    Ok(fetched_rule) => {
-                        assert_ne!(fetched_rule, $rule);
+                }));
+            }
+
+            for _ in 0..100 {
+                let db = db.clone();
+
+                tasks.push(tokio::spawn(async move {
+                    for _ in 0..50 {
+                        db.get_all().await.expect("get_all should not fail");
+
+                        let evaluation = db
+                            .evaluate(
+                                &["shared".to_owned()],
+                                json!({"foo": 10}),
+                                EvaluationOptions::default(),
+                            )
+                            .await
+                            .expect("evaluation should not fail");
+
+                        assert_eq!(evaluation.result, EvaluationResult::Pass);
                     }
-                }
-            }};
+                }));
+            }
+
+            for task in tasks {
+                task.await.expect("task should not panic");
+            }
+
+            let rules = db.get_all().await.expect("get_all should not fail");
+            assert_eq!(rules.len(), 1 + 10 * 25);
+            assert!(
+                rules
+                    .iter()
+                    .filter(|rule| rule.id != "shared")
+                    .all(|rule| rule.message == "updated message")
+            );
         }
 
         #[tokio::test]
-        async fn test_create_rule() {
-            let db = InMemRuleRepository::empty();
-            assert_repository_size!(db, 0);
+        async fn test_evaluate_with_context_active_window() {
+            let at = |s: &str| s.parse::<DateTime<Utc>>().expect("invalid timestamp");
 
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+            let db = InMemRuleRepository::new(&[
+                Rule {
+                    active_from: Some(at("2024-01-01T00:00:00Z")),
+                    active_until: Some(at("2025-01-01T00:00:00Z")),
+                    ..rule!("rule-1", "message", predicate!("foo" == 12))
+                },
+                rule!("rule-2", "message", predicate!("foo" == 10)),
+            ]);
 
-            db.create(rule.clone())
+            let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+            let evaluate_at = |timestamp: &str| {
+                db.evaluate_with_context(
+                    &ids,
+                    json!({"foo": 10}),
+                    EvaluationOptions::default(),
+                    EvaluationContext {
+                        timestamp: at(timestamp),
+                        ..EvaluationContext::new("request-1")
+                    },
+                )
+            };
+
+            let evaluation = evaluate_at("2024-06-01T00:00:00Z")
                 .await
-                .expect("rule creation should not fail");
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Fail);
 
-            assert_repository_size!(db, 1);
-            assert_repository_contains!(db, rule);
+            for timestamp in ["2023-06-01T00:00:00Z", "2025-01-01T00:00:00Z"] {
+                let evaluation = evaluate_at(timestamp)
+                    .await
+                    .expect("evaluation should not fail");
+
+                assert_eq!(evaluation.result, EvaluationResult::Pass);
+                assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Skipped);
+                assert_eq!(evaluation.reasons[1].evaluation, EvaluationResult::Pass);
+            }
         }
 
         #[tokio::test]
-        async fn test_delete_rule() {
-            let db = InMemRuleRepository::empty();
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+        async fn test_evaluate_at_option() {
+            let at = |s: &str| s.parse::<DateTime<Utc>>().expect("invalid timestamp");
 
-            db.create(rule.clone())
+            let db = InMemRuleRepository::new(&[Rule {
+                active_from: Some(at("2024-01-01T00:00:00Z")),
+                active_until: Some(at("2025-01-01T00:00:00Z")),
+                ..rule!("rule-1", "message", predicate!("foo" == 10))
+            }]);
+
+            let ids = ["rule-1".to_owned()];
+            let options = |timestamp: &str| EvaluationOptions {
+                evaluate_at: Some(at(timestamp)),
+                ..EvaluationOptions::default()
+            };
+
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), options("2024-06-01T00:00:00Z"))
                 .await
-                .expect("rule creation should not fail");
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Pass);
 
-            assert_repository_size!(db, 1);
-            assert_repository_contains!(db, rule);
+            let evaluation = db
+                .evaluate(&ids, json!({"foo": 10}), options("2023-06-01T00:00:00Z"))
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Skipped);
 
-            db.delete(&rule.id).await.expect("delete should not fail");
+            // The option takes precedence over the context's timestamp.
+            let evaluation = db
+                .evaluate_with_context(
+                    &ids,
+                    json!({"foo": 10}),
+                    options("2025-06-01T00:00:00Z"),
+                    EvaluationContext {
+                        timestamp: at("2024-06-01T00:00:00Z"),
+                        ..EvaluationContext::new("request-1")
+                    },
+                )
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.reasons[0].evaluation, EvaluationResult::Skipped);
+        }
 
-            assert_repository_size!(db, 0);
-            assert_repository_does_not_contain!(db, rule);
+        #[tokio::test]
+        async fn test_evaluate_condition() {
+            let db = InMemRuleRepository::new(&[
+                Rule {
+                    condition: Some(predicate!("env" == "staging").into()),
+                    ..rule!(
+                        "new_checkout",
+                        "new checkout must be enabled in staging",
+                        predicate!("flags.new_checkout" == true)
+                    )
+                },
+                rule!("foo_rule", "message", predicate!("foo" == 10)),
+            ]);
+
+            let ids = ["new_checkout".to_owned(), "foo_rule".to_owned()];
+
+            let evaluate = |input, field_presence| {
+                db.evaluate(
+                    &ids,
+                    input,
+                    EvaluationOptions {
+                        field_presence,
+                        ..EvaluationOptions::default()
+                    },
+                )
+            };
+
+            for (input, field_presence, expected) in [
+                (
+                    json!({"env": "production", "flags": {"new_checkout": false}, "foo": 10}),
+                    FieldPresence::Lenient,
+                    EvaluationResult::Skipped,
+                ),
+                (
+                    json!({"env": "staging", "flags": {"new_checkout": false}, "foo": 10}),
+                    FieldPresence::Lenient,
+                    EvaluationResult::Fail,
+                ),
+                (
+                    json!({"env": "staging", "flags": {"new_checkout": true}, "foo": 10}),
+                    FieldPresence::Lenient,
+                    EvaluationResult::Pass,
+                ),
+                (
+                    json!({"flags": {"new_checkout": true}, "foo": 10}),
+                    FieldPresence::Lenient,
+                    EvaluationResult::Skipped,
+                ),
+                (
+                    json!({"flags": {"new_checkout": true}, "foo": 10}),
+                    FieldPresence::Strict,
+                    EvaluationResult::Error,
+                ),
+            ] {
+                let evaluation = evaluate(input, field_presence)
+                    .await
+                    .expect("evaluation should not fail");
+
+                let expected_result = match expected {
+                    EvaluationResult::Skipped => EvaluationResult::Pass,
+                    result => result,
+                };
+
+                assert_eq!(evaluation.result, expected_result);
+                assert_eq!(evaluation.reasons[0].evaluation, expected);
+                assert_eq!(evaluation.reasons[1].evaluation, EvaluationResult::Pass);
+            }
         }
 
+        #[traced_test]
         #[tokio::test]
-        async fn test_create_duplicate_err() {
-            let db = InMemRuleRepository::empty();
-            assert_repository_size!(db, 0);
+        async fn test_evaluate_with_context_logs() {
+            let db =
+                InMemRuleRepository::new(&[rule!("rule-1", "message", predicate!("foo" == 10))]);
 
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+            let context = EvaluationContext {
+                caller_id: Some("caller-42".to_owned()),
+                ..EvaluationContext::new("request-123")
+            };
 
-            db.create(rule.clone())
+            let evaluation = db
+                .evaluate_with_context(
+                    &["rule-1".to_owned()],
+                    json!({"foo": 10}),
+                    EvaluationOptions::default(),
+                    context.clone(),
+                )
                 .await
-                .expect("rule creation should not fail");
+                .expect("evaluation should not fail");
 
-            let creation_result = db.create(rule.clone()).await;
+            assert!(logs_contain("request_id=request-123"));
+            assert!(logs_contain("caller_id=\"caller-42\""));
+            assert!(logs_contain("evaluated rules"));
+
+            assert_eq!(evaluation.context, Some(context));
+        }
+
+        #[traced_test]
+        #[tokio::test]
+        async fn test_retry_on_error() {
+            let retried = Rule {
+                retry_on_error: true,
+                max_retries: 2,
+                input_defaults: Some(json!({"age": 0})),
+                ..rule!("rule-1", "message", predicate!("age" >= 18))
+            };
+            let without_defaults = Rule {
+                id: "rule-2".to_owned(),
+                input_defaults: None,
+                ..retried.clone()
+            };
+            let not_retried = Rule {
+                id: "rule-3".to_owned(),
+                retry_on_error: false,
+                ..retried.clone()
+            };
+
+            let db = InMemRuleRepository::new(&[retried, without_defaults, not_retried]);
+            let evaluate = |id: &str| {
+                let ids = [id.to_owned()];
+                let db = db.clone();
+
+                async move {
+                    db.evaluate(&ids, json!({"age": null}), EvaluationOptions::default())
+                        .await
+                }
+            };
+
+            let evaluation = evaluate("rule-1")
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert!(logs_contain("retrying rule evaluation"));
+            assert!(logs_contain("attempt=1"));
+            assert!(!logs_contain("attempt=2"));
 
             assert!(matches!(
-                creation_result,
-                Err(CreateRuleError::Duplicate(_))
-            ))
+                evaluate("rule-2").await,
+                Err(EvaluateRuleError::EvaluationError(id, err))
+                    if id == "rule-2" && err.is_retryable()
+            ));
+            assert!(logs_contain("attempt=2"));
+
+            assert!(matches!(
+                evaluate("rule-3").await,
+                Err(EvaluateRuleError::EvaluationError(id, _)) if id == "rule-3"
+            ));
         }
 
+        #[traced_test]
         #[tokio::test]
-        async fn test_delete_idempotent() {
-            let db = InMemRuleRepository::empty();
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+        async fn test_no_retry_on_rule_error() {
+            let rule = Rule {
+                retry_on_error: true,
+                input_defaults: Some(json!({"foo": 10})),
+                ..rule!("rule-1", "message", predicate!("foo" divisible 0))
+            };
+            let db = InMemRuleRepository::new(&[rule]);
 
-            db.create(rule.clone())
+            assert!(matches!(
+                db.evaluate(
+                    &["rule-1".to_owned()],
+                    json!({"foo": 10}),
+                    EvaluationOptions::default()
+                )
+                .await,
+                Err(EvaluateRuleError::EvaluationError(
+                    _,
+                    EvaluationError::DivisionByZero
+                ))
+            ));
+            assert!(!logs_contain("retrying rule evaluation"));
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_input_values() {
+            let db = InMemRuleRepository::new(&[
+                rule!("equal", "message", predicate!("a" == 10)),
+                rule!("not-equal", "message", predicate!("b" != "foo")),
+                rule!("greater", "message", predicate!("c.d" > 1.5)),
+                rule!("less", "message", predicate!("c.e" < 0)),
+                rule!("greater-equal", "message", predicate!("f" >= 3)),
+                rule!("less-equal", "message", predicate!("g" <= 3)),
+                rule!("contains", "message", predicate!("h" contains "x")),
+                rule!("missing", "message", predicate!("i" == 1)),
+                rule!(
+                    "compound",
+                    "message",
+                    all!(predicate!("a" == 10), predicate!("f" >= 3))
+                ),
+            ]);
+
+            let input = json!({
+                "a": 10,
+                "b": "bar",
+                "c": {"d": 2.5, "e": -1},
+                "f": 3,
+                "g": 4,
+                "h": ["x", "y"],
+            });
+
+            let ids = [
+                "equal",
+                "not-equal",
+                "greater",
+                "less",
+                "greater-equal",
+                "less-equal",
+                "contains",
+                "missing",
+                "compound",
+            ]
+            .map(String::from);
+
+            let evaluation = db
+                .evaluate(
+                    &ids,
+                    input.clone(),
+                    EvaluationOptions {
+                        include_input_values: true,
+                        ..Default::default()
+                    },
+                )
                 .await
-                .expect("rule creation should not fail");
+                .expect("evaluation should not fail");
 
-            assert_repository_size!(db, 1);
-            assert_repository_contains!(db, rule);
+            let input_values = evaluation
+                .reasons
+                .iter()
+                .map(|reason| (reason.rule.as_str(), reason.input_value.clone()))
+                .collect::<Vec<_>>();
 
-            db.delete(&rule.id).await.expect("delete should not fail");
+            assert_eq!(
+                input_values,
+                [
+                    ("equal", Some(json!(10))),
+                    ("not-equal", Some(json!("bar"))),
+                    ("greater", Some(json!(2.5))),
+                    ("less", Some(json!(-1))),
+                    ("greater-equal", Some(json!(3))),
+                    ("less-equal", Some(json!(4))),
+                    ("contains", Some(json!(["x", "y"]))),
+                    ("missing", Some(json!(null))),
+                    ("compound", None),
+                ]
+            );
 
-            assert_repository_size!(db, 0);
-            assert_repository_does_not_contain!(db, rule);
+            let evaluation = db
+                .evaluate(&ids, input, EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
 
-            db.delete(&rule.id)
+            assert!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .all(|reason| reason.input_value.is_none())
+            );
+        }
+
+        macro_rules! weighted_score {
+            ($db:expr, $ids:expr, $input:expr) => {
+                $db.evaluate(
+                    &$ids.map(String::from),
+                    $input,
+                    EvaluationOptions::default(),
+                )
                 .await
-                .expect("delete of non existing rule should not fail");
+                .expect("evaluation should not fail")
+                .weighted_score
+            };
+        }
 
-            assert_repository_size!(db, 0);
-            assert_repository_does_not_contain!(db, rule);
+        #[tokio::test]
+        async fn test_weighted_score_uniform() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", predicate!("bar" == 10)),
+                rule!("rule-3", "message", predicate!("baz" == 10)),
+                rule!("rule-4", "message", predicate!("qux" == 10)),
+            ]);
+            let ids = ["rule-1", "rule-2", "rule-3", "rule-4"];
+
+            assert_eq!(weighted_score!(db, ids, json!({})), 0.0);
+            assert_eq!(weighted_score!(db, ids, json!({"foo": 10})), 0.25);
+            assert_eq!(weighted_score!(db, ids, json!({"foo": 10, "bar": 10})), 0.5);
+            assert_eq!(
+                weighted_score!(db, ids, json!({"foo": 10, "bar": 10, "baz": 10, "qux": 10})),
+                1.0
+            );
+            assert_eq!(weighted_score!(db, [] as [&str; 0], json!({})), 1.0);
         }
 
         #[tokio::test]
-        async fn test_update() {
+        async fn test_weighted_score_zero_weight() {
+            let db = InMemRuleRepository::new(&[
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                Rule {
+                    weight: 0.0,
+                    ..rule!("rule-2", "message", predicate!("bar" == 10))
+                },
+            ]);
+
+            let evaluation = db
+                .evaluate(
+                    &["rule-1".to_owned(), "rule-2".to_owned()],
+                    json!({"foo": 10}),
+                    EvaluationOptions::default(),
+                )
+                .await
+                .expect("evaluation should not fail");
+
+            // A failing rule without weight still fails the evaluation, it just doesn't count
+            // towards the score.
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert_eq!(evaluation.weighted_score, 1.0);
+
+            assert_eq!(weighted_score!(db, ["rule-2"], json!({})), 1.0);
+        }
+
+        #[tokio::test]
+        async fn test_weighted_score_different_weights() {
+            let db = InMemRuleRepository::new(&[
+                Rule {
+                    weight: 3.0,
+                    ..rule!("rule-1", "message", predicate!("foo" == 10))
+                },
+                Rule {
+                    weight: 1.0,
+                    ..rule!("rule-2", "message", predicate!("bar" == 10))
+                },
+                Rule {
+                    weight: 0.5,
+                    active_until: Some(DateTime::UNIX_EPOCH),
+                    ..rule!("rule-3", "message", predicate!("baz" == 10))
+                },
+            ]);
+            let ids = ["rule-1", "rule-2", "rule-3"];
+
+            assert_eq!(weighted_score!(db, ids, json!({"foo": 10})), 0.75);
+            assert_eq!(weighted_score!(db, ids, json!({"bar": 10})), 0.25);
+        }
+
+        #[tokio::test]
+        async fn test_invalid_weight() {
             let db = InMemRuleRepository::empty();
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
 
-            db.create(rule.clone())
+            for weight in [-1.0, f64::NAN, f64::INFINITY] {
+                let rule = Rule {
+                    weight,
+                    ..rule!("rule-1", "message", predicate!("foo" == 10))
+                };
+
+                assert_eq!(
+                    db.create(rule.clone()).await,
+                    Err(CreateRuleError::Invalid(
+                        RuleValidationError::InvalidWeight("rule-1".to_owned())
+                    ))
+                );
+
+                db.create(rule!("rule-1", "message", predicate!("foo" == 10)))
+                    .await
+                    .expect("rule should be created");
+
+                assert_eq!(
+                    db.update("rule-1".to_owned(), rule).await,
+                    Err(UpdateRuleError::Invalid(
+                        RuleValidationError::InvalidWeight("rule-1".to_owned())
+                    ))
+                );
+
+                db.delete(&"rule-1".to_owned())
+                    .await
+                    .expect("rule should be deleted");
+            }
+        }
+
+        #[tokio::test]
+        async fn test_find_by_operator() {
+            let rules = [
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!(
+                    "rule-2",
+                    "message",
+                    any!(
+                        predicate!("foo" > 10),
+                        not!(predicate!("name" starts_with "a"))
+                    )
+                ),
+                rule!("rule-3", "message", predicate!("name" starts_with "b")),
+            ];
+            let in_mem = InMemRuleRepository::new(&rules);
+            let erased = InMemRuleRepository::new(&rules).into_erased();
+
+            let mut found = in_mem
+                .find_by_operator(Operator::StartsWith)
                 .await
-                .expect("rule creation should not fail");
+                .expect("find_by_operator should not fail");
+            found.sort_by(|a, b| a.id.cmp(&b.id));
+            assert_eq!(found, [rules[1].clone(), rules[2].clone()]);
 
-            let updated_rule = rule!("rule-2", "updated message", predicate!("foo" == 10));
+            // The default implementation agrees with the in memory one.
+            let mut found = erased
+                .find_by_operator(Operator::StartsWith)
+                .await
+                .expect("find_by_operator should not fail");
+            found.sort_by(|a, b| a.id.cmp(&b.id));
+            assert_eq!(found, [rules[1].clone(), rules[2].clone()]);
 
-            assert_repository_contains!(db, rule);
-            assert_repository_does_not_contain!(db, updated_rule);
+            assert_eq!(in_mem.find_by_operator(Operator::IsNull).await, Ok(vec![]));
+        }
 
-            db.update(rule.id.clone(), updated_rule.clone())
+        #[tokio::test]
+        async fn test_find_by_author_and_review_status() {
+            let governed = |id: &str, author: &str, reviewed_by: Option<&str>| Rule {
+                author: Some(author.to_owned()),
+                reviewed_by: reviewed_by.map(str::to_owned),
+                ..rule!(id, "message", predicate!("foo" == 10))
+            };
+
+            let rules = [
+                governed("rule-1", "alice", None),
+                governed("rule-2", "bob", Some("alice")),
+                governed("rule-3", "alice", Some("bob")),
+                rule!("rule-4", "message", predicate!("foo" == 10)),
+            ];
+            let in_mem = InMemRuleRepository::new(&rules);
+            let erased = InMemRuleRepository::new(&rules).into_erased();
+
+            let ids = |rules: Vec<Rule>| rules.into_iter().map(|rule| rule.id).collect::<Vec<_>>();
+
+            assert_eq!(
+                ids(in_mem
+                    .find_by_author("alice")
+                    .await
+                    .expect("find_by_author should not fail")),
+                ["rule-1", "rule-3"]
+            );
+            assert_eq!(
+                ids(in_mem
+                    .find_by_review_status(false)
+                    .await
+                    .expect("find_by_review_status should not fail")),
+                ["rule-1", "rule-4"]
+            );
+
+            // The default implementations agree with the in memory ones.
+            assert_eq!(
+                ids(erased
+                    .find_by_author("alice")
+                    .await
+                    .expect("find_by_author should not fail")),
+                ["rule-1", "rule-3"]
+            );
+            assert_eq!(
+                ids(erased
+                    .find_by_review_status(true)
+                    .await
+                    .expect("find_by_review_status should not fail")),
+                ["rule-2", "rule-3"]
+            );
+
+            assert_eq!(in_mem.find_by_author("carol").await, Ok(vec![]));
+        }
+
+        #[tokio::test]
+        async fn test_evaluate_by_category() {
+            let in_category = |id: &str, category: &str, predicate: Predicate| Rule {
+                category: Some(category.to_owned()),
+                ..rule!(id, "message", predicate)
+            };
+
+            let db = InMemRuleRepository::new(&[
+                in_category("rule-2", "fraud", predicate!("foo" == 10).into()),
+                in_category("rule-1", "fraud", predicate!("bar" == 10).into()),
+                in_category("rule-3", "kyc", predicate!("foo" == 20).into()),
+                rule!("rule-4", "message", predicate!("foo" == 30)),
+            ]);
+
+            let mut fraud = db
+                .get_by_category("fraud")
                 .await
-                .expect("update should not fail");
+                .expect("get_by_category should not fail");
+            fraud.sort_by(|a, b| a.id.cmp(&b.id));
+            assert_eq!(
+                fraud.iter().map(Rule::id).collect::<Vec<_>>(),
+                ["rule-1", "rule-2"]
+            );
 
-            assert_repository_contains!(db, updated_rule);
-            assert_repository_does_not_contain!(db, rule);
+            let evaluation = db
+                .evaluate_by_category(
+                    "fraud",
+                    json!({"foo": 10, "bar": 5}),
+                    EvaluationOptions::default(),
+                )
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(evaluation.result, EvaluationResult::Fail);
+            assert_eq!(
+                evaluation
+                    .reasons
+                    .iter()
+                    .map(|reason| reason.rule.as_str())
+                    .collect::<Vec<_>>(),
+                ["rule-1", "rule-2"]
+            );
+
+            let evaluation = db
+                .evaluate_by_category("empty", json!({}), EvaluationOptions::default())
+                .await
+                .expect("evaluation should not fail");
+            assert_eq!(
+                evaluation,
+                Evaluation::new(EvaluationResult::Pass, Vec::new())
+            );
         }
 
         #[tokio::test]
-        async fn test_update_err() {
-            let db = InMemRuleRepository::empty();
-            let rule = rule!("rule-1", "important rule failed", predicate!("foo" == 10));
+        async fn test_policy() {
+            let db = InMemRuleRepository::empty().with_policy(RulePolicy {
+                allowed_operators: Some(HashSet::from([Operator::Equal, Operator::Greater])),
+            });
 
-            db.create(rule.clone())
+            let allowed = rule!(
+                "rule-1",
+                "message",
+                any!(predicate!("foo" == 10), predicate!("bar" > 5))
+            );
+            db.create(allowed.clone())
                 .await
-                .expect("rule creation should not fail");
+                .expect("rule should be created");
 
-            let updated_rule = rule!("rule-2", "updated message", predicate!("foo" == 10));
+            let prohibited = rule!(
+                "rule-2",
+                "message",
+                all!(
+                    predicate!("foo" == 10),
+                    not!(predicate!("tags" contains "a"))
+                )
+            );
+            assert_eq!(
+                db.create(prohibited).await,
+                Err(CreateRuleError::Invalid(
+                    RuleValidationError::DisallowedOperator(
+                        "rule-2".to_owned(),
+                        Operator::Contains
+                    )
+                ))
+            );
 
-            let update_result = db.update("rule-3".to_owned(), updated_rule.clone()).await;
+            let prohibited_condition = Rule {
+                condition: Some(predicate!("bar" is_null).into()),
+                ..allowed.clone()
+            };
+            assert_eq!(
+                db.update("rule-1".to_owned(), prohibited_condition).await,
+                Err(UpdateRuleError::Invalid(
+                    RuleValidationError::DisallowedOperator("rule-1".to_owned(), Operator::IsNull)
+                ))
+            );
 
-            assert!(matches!(update_result, Err(UpdateRuleError::NoSuchRule(_))));
+            assert_eq!(db.get_all().await, Ok(vec![allowed]));
+
+            // The default policy allows every operator.
+            InMemRuleRepository::empty()
+                .create(rule!("rule-1", "message", predicate!("tags" contains "a")))
+                .await
+                .expect("rule should be created");
+        }
+
+        #[tokio::test]
+        async fn test_find_conflicting_rules() {
+            let db = InMemRuleRepository::new(&[
+                rule!("adult", "message", predicate!("age" >= 18)),
+                rule!("uk", "message", predicate!("country" == "UK")),
+                rule!(
+                    "active-adult",
+                    "message",
+                    all!(predicate!("age" >= 18), predicate!("status" == "active"))
+                ),
+                rule!("minor", "message", predicate!("age" < 18)),
+            ]);
+
+            assert_eq!(
+                db.find_conflicting_rules(&rule!("child", "message", predicate!("age" < 13)))
+                    .await,
+                Ok(vec!["active-adult".to_owned(), "adult".to_owned()])
+            );
+
+            assert_eq!(
+                db.find_conflicting_rules(&rule!("fr", "message", predicate!("country" == "FR")))
+                    .await,
+                Ok(vec!["uk".to_owned()])
+            );
+
+            assert_eq!(
+                db.find_conflicting_rules(&rule!("teen", "message", predicate!("age" < 18)))
+                    .await,
+                Ok(vec![
+                    "active-adult".to_owned(),
+                    "adult".to_owned(),
+                    "minor".to_owned()
+                ])
+            );
+
+            // The stored version of the rule itself isn't a conflict.
+            assert_eq!(
+                db.find_conflicting_rules(&rule!("uk", "message", predicate!("country" == "UK")))
+                    .await,
+                Ok(vec![])
+            );
+
+            assert_eq!(
+                db.find_conflicting_rules(&rule!("tall", "message", predicate!("height" > 2)))
+                    .await,
+                Ok(vec![])
+            );
+        }
+    }
+
+    mod evaluation_result {
+        use super::*;
+        use EvaluationResult::{Error, Fail, Pass, Skipped};
+
+        fn reason(rule: &str, evaluation: EvaluationResult) -> EvaluationReason {
+            EvaluationReason {
+                rule: rule.to_owned(),
+                requirement: "message".to_owned(),
+                evaluation,
+                weight: 1.0,
+                input_value: None,
+                elapsed_micros: None,
+                predicate_snapshot: None,
+                match_count: None,
+                total_predicate_count: None,
+                operator: None,
+                path: None,
+                skip_reason: None,
+            }
+        }
+
+        #[test]
+        fn test_from_reasons() {
+            assert_eq!(Evaluation::from_reasons(Vec::new()).result, Pass);
+            assert_eq!(
+                Evaluation::from_reasons(vec![reason("rule-1", Pass), reason("rule-2", Skipped)])
+                    .result,
+                Pass
+            );
+            assert_eq!(
+                Evaluation::from_reasons(vec![reason("rule-1", Fail), reason("rule-2", Error)])
+                    .result,
+                Error
+            );
+        }
+
+        #[test]
+        fn test_evaluation_mode() {
+            let combine = |mode: EvaluationMode, results: &[EvaluationResult]| {
+                mode.combine(results.iter().copied())
+            };
+
+            for (results, all, any, none) in [
+                (vec![], Pass, Fail, Pass),
+                (vec![Skipped], Pass, Fail, Pass),
+                (vec![Pass, Skipped], Pass, Pass, Fail),
+                (vec![Pass, Fail], Fail, Pass, Fail),
+                (vec![Fail, Fail], Fail, Fail, Pass),
+                (vec![Pass, Error], Error, Pass, Fail),
+                (vec![Fail, Error], Error, Fail, Error),
+                (vec![Error, Pass], Error, Pass, Fail),
+                (vec![Error, Skipped], Error, Error, Error),
+            ] {
+                assert_eq!(combine(EvaluationMode::All, &results), all, "{results:?}");
+                assert_eq!(combine(EvaluationMode::Any, &results), any, "{results:?}");
+                assert_eq!(combine(EvaluationMode::None, &results), none, "{results:?}");
+            }
+        }
+
+        #[test]
+        fn test_merge() {
+            let all_pass = Evaluation::from_reasons(vec![reason("rule-1", Pass)])
+                .merge(Evaluation::from_reasons(vec![reason("rule-2", Pass)]));
+            assert_eq!(
+                all_pass,
+                Evaluation::new(Pass, vec![reason("rule-1", Pass), reason("rule-2", Pass)])
+            );
+
+            let pass_and_fail = Evaluation::from_reasons(vec![reason("rule-1", Pass)])
+                .merge(Evaluation::from_reasons(vec![reason("rule-2", Fail)]));
+            assert_eq!(pass_and_fail.result, Fail);
+            assert_eq!(pass_and_fail.weighted_score, 0.5);
+            assert_eq!(pass_and_fail.reasons.len(), 2);
+        }
+
+        #[test]
+        fn test_merge_overlapping() {
+            let earlier = Evaluation::from_reasons(vec![
+                reason("rule-1", Fail),
+                reason("rule-2", Pass),
+                reason("rule-3", Pass),
+            ]);
+            let later =
+                Evaluation::from_reasons(vec![reason("rule-4", Pass), reason("rule-1", Pass)]);
+
+            assert_eq!(
+                earlier.merge(later),
+                Evaluation::new(
+                    Pass,
+                    vec![
+                        reason("rule-1", Pass),
+                        reason("rule-2", Pass),
+                        reason("rule-3", Pass),
+                        reason("rule-4", Pass),
+                    ]
+                )
+            );
+        }
+
+        #[test]
+        fn test_to_boolean_form() {
+            let mut evaluation = Evaluation::from_reasons(vec![
+                reason("rule-1", Pass),
+                reason("rule-2", Fail),
+                reason("rule-3", Skipped),
+            ]);
+            evaluation.context = Some(EvaluationContext::new("request-1"));
+
+            let boolean = evaluation.to_boolean_form();
+            assert_eq!(boolean.result, BooleanEvaluationResult(Fail));
+            assert_eq!(boolean.reasons[0].0, evaluation.reasons[0]);
+
+            let serialized = serde_json::to_value(&boolean).expect("evaluation should serialize");
+            assert_eq!(serialized["result"], json!(false));
+            assert_eq!(serialized["weightedScore"], json!(0.5));
+            assert_eq!(serialized["context"]["requestId"], json!("request-1"));
+
+            let results: Vec<_> = serialized["reasons"]
+                .as_array()
+                .expect("reasons should be an array")
+                .iter()
+                .map(|reason| (reason["rule"].clone(), reason["evaluation"].clone()))
+                .collect();
+            assert_eq!(
+                results,
+                [
+                    (json!("rule-1"), json!(true)),
+                    (json!("rule-2"), json!(false)),
+                    (json!("rule-3"), json!("SKIPPED")),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_serialize_boolean_evaluation_result() {
+            for (result, expected) in [
+                (Pass, json!(true)),
+                (Fail, json!(false)),
+                (Skipped, json!("SKIPPED")),
+                (Error, json!("ERROR")),
+            ] {
+                assert_eq!(
+                    serde_json::to_value(BooleanEvaluationResult(result))
+                        .expect("result should serialize"),
+                    expected
+                );
+            }
+        }
+
+        #[test]
+        fn test_to_markdown_report() {
+            let mut reasons = vec![
+                reason("rule-1", Pass),
+                reason("rule-2", Fail),
+                reason("rule-3", Skipped),
+            ];
+            reasons[1].requirement = "foo must be 1 | 2\nor 3".to_owned();
+
+            let markdown = Evaluation::from_reasons(reasons).to_markdown_report("Checkout rules");
+            let lines: Vec<_> = markdown.lines().collect();
+
+            assert_eq!(lines[0], "# Checkout rules");
+            assert!(lines.contains(&"Overall: FAIL ❌"));
+
+            let header = lines
+                .iter()
+                .position(|line| *line == "| Rule | Requirement | Result |")
+                .expect("the table should have a header");
+            assert_eq!(lines[header + 1], "| --- | --- | --- |");
+            assert_eq!(
+                lines[header + 2..],
+                [
+                    "| rule-1 | message | PASS ✅ |",
+                    "| rule-2 | foo must be 1 \\| 2 or 3 | FAIL ❌ |",
+                    "| rule-3 | message | SKIPPED |",
+                ]
+            );
+
+            let markdown = Evaluation::from_reasons(Vec::new()).to_markdown_report("Empty");
+            assert!(markdown.contains("Overall: PASS ✅\n"));
+            assert!(markdown.ends_with("| --- | --- | --- |\n"));
+        }
+
+        #[test]
+        fn test_to_junit_xml() {
+            let mut reasons = vec![
+                reason("rule-1", Pass),
+                reason("rule-2", Fail),
+                reason("rule-3", Fail),
+                reason("rule-4", Error),
+                reason("rule-5", Skipped),
+            ];
+            reasons[1].requirement = "foo must be < 10 & \"bar\" set".to_owned();
+            reasons[0].elapsed_micros = Some(1500);
+
+            let xml = Evaluation::from_reasons(reasons).to_junit_xml("rules <ci>");
+            let document = roxmltree::Document::parse(&xml).expect("XML should be parseable");
+
+            let suite = document.root_element();
+            assert_eq!(suite.tag_name().name(), "testsuite");
+            assert_eq!(suite.attribute("name"), Some("rules <ci>"));
+            assert_eq!(suite.attribute("tests"), Some("5"));
+            assert_eq!(suite.attribute("failures"), Some("2"));
+            assert_eq!(suite.attribute("errors"), Some("1"));
+            assert_eq!(suite.attribute("skipped"), Some("1"));
+
+            let elements = |name: &str| {
+                document
+                    .descendants()
+                    .filter(|node| node.has_tag_name(name))
+                    .collect::<Vec<_>>()
+            };
+
+            let test_cases = elements("testcase");
+            assert_eq!(test_cases.len(), 5);
+            assert_eq!(test_cases[0].attribute("name"), Some("rule-1"));
+            assert_eq!(test_cases[0].attribute("time"), Some("0.0015"));
+            assert!(!test_cases[0].has_children());
+
+            let failures = elements("failure");
+            assert_eq!(failures.len(), 2);
+            assert_eq!(
+                failures[0].attribute("message"),
+                Some("foo must be < 10 & \"bar\" set")
+            );
+            assert_eq!(
+                failures[0].parent().and_then(|node| node.attribute("name")),
+                Some("rule-2")
+            );
+
+            assert_eq!(elements("error").len(), 1);
+            assert_eq!(elements("skipped").len(), 1);
+        }
+
+        #[test]
+        fn test_bitand() {
+            for (lhs, rhs, expected) in [
+                (Pass, Pass, Pass),
+                (Pass, Fail, Fail),
+                (Fail, Pass, Fail),
+                (Fail, Fail, Fail),
+                (Pass, Skipped, Pass),
+                (Skipped, Fail, Fail),
+                (Skipped, Skipped, Skipped),
+                (Error, Pass, Error),
+                (Fail, Error, Error),
+                (Skipped, Error, Error),
+            ] {
+                assert_eq!(lhs & rhs, expected, "{lhs:?} & {rhs:?}");
+            }
+        }
+
+        #[test]
+        fn test_bitor() {
+            for (lhs, rhs, expected) in [
+                (Pass, Pass, Pass),
+                (Pass, Fail, Pass),
+                (Fail, Pass, Pass),
+                (Fail, Fail, Fail),
+                (Pass, Skipped, Pass),
+                (Skipped, Fail, Fail),
+                (Skipped, Skipped, Skipped),
+                (Error, Pass, Pass),
+                (Fail, Error, Fail),
+                (Skipped, Error, Error),
+            ] {
+                assert_eq!(lhs | rhs, expected, "{lhs:?} | {rhs:?}");
+            }
+        }
+
+        #[test]
+        fn test_sort_reasons() {
+            let mut reasons: Vec<EvaluationReason> = vec![
+                reason("rule-1", Pass),
+                reason("rule-2", Skipped),
+                reason("rule-3", Fail),
+                reason("rule-4", Error),
+                reason("rule-5", Fail),
+            ];
+
+            reasons.sort_by_key(|reason| reason.evaluation);
+
+            assert_eq!(
+                reasons
+                    .iter()
+                    .map(|reason| reason.rule.as_str())
+                    .collect::<Vec<_>>(),
+                ["rule-4", "rule-3", "rule-5", "rule-2", "rule-1"]
+            );
+            assert!(Pass > Fail);
         }
     }
 }
@@ -0,0 +1,592 @@
+use crate::core::rule::Rule;
+use crate::repository::{
+    CreateRuleError, DeleteRuleError, EvaluateRuleError, Evaluation, EvaluationContext,
+    EvaluationOptions, EvaluationReason, GetAllRulesError, GetRuleError, RuleChangeEvent,
+    RuleRepository, RuleStats, UpdateRuleError,
+};
+use indexmap::IndexMap;
+use std::sync::{Arc, RwLock};
+use tokio_stream::Stream;
+
+/// When the cache of a `ReadThroughCacheRepository` is first populated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CacheMissStrategy {
+    /// Populate the cache on the first call to `get_all`.
+    #[default]
+    Lazy,
+    /// Populate the cache when the repository is constructed.
+    Eager,
+}
+
+/// Wraps a (potentially slow) repository, caching its rules in memory. Once populated, reads are
+/// served from the cache. Writes go to the inner repository and, if they succeed, are applied to
+/// the cache so it stays in sync without having to reload every rule. Rules are cached as they
+/// were written, so fields the inner repository sets itself like `updated_at` are only cached
+/// once the rules are reloaded.
+///
+/// Rules are listed in the order the inner repository listed them, with created rules added to
+/// the end and updated rules keeping their place.
+#[derive(Debug, Clone)]
+pub struct ReadThroughCacheRepository<RR: RuleRepository> {
+    inner: RR,
+    cache: Arc<RwLock<Cache>>,
+}
+
+#[derive(Debug, Default)]
+struct Cache {
+    /// `None` until populated, and after being dropped until the next read.
+    rules: Option<IndexMap<String, Rule>>,
+    /// How many writes have been applied, so a reload can tell whether any were made while it
+    /// was reading the inner repository.
+    writes: u64,
+}
+
+impl<RR: RuleRepository> ReadThroughCacheRepository<RR> {
+    pub async fn new(inner: RR, strategy: CacheMissStrategy) -> Result<Self, GetAllRulesError> {
+        let repository = Self {
+            inner,
+            cache: Arc::default(),
+        };
+
+        if strategy == CacheMissStrategy::Eager {
+            repository.refresh().await?;
+        }
+
+        Ok(repository)
+    }
+
+    /// Like `new` with `CacheMissStrategy::Lazy`, which doesn't need to wait on the inner
+    /// repository.
+    pub fn new_lazy(inner: RR) -> Self {
+        Self {
+            inner,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Reloads every rule from the inner repository.
+    pub async fn refresh(&self) -> Result<(), GetAllRulesError> {
+        self.load().await.map(|_| ())
+    }
+
+    /// Reads every rule from the inner repository and caches them. If a write was made in the
+    /// meantime the rules read may be missing it, so the cache is dropped instead, to be reloaded
+    /// by the next read.
+    async fn load(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+        let writes = self
+            .cache
+            .read()
+            .map_err(|err| GetAllRulesError::Internal(err.to_string()))?
+            .writes;
+
+        let rules = self.inner.get_all().await?;
+
+        let mut cache = self
+            .cache
+            .write()
+            .map_err(|err| GetAllRulesError::Internal(err.to_string()))?;
+
+        cache.rules = (cache.writes == writes).then(|| {
+            rules
+                .iter()
+                .map(|rule| (rule.id.clone(), rule.clone()))
+                .collect()
+        });
+
+        Ok(rules)
+    }
+
+    /// Applies a write which the inner repository has already made to the cache, if it has been
+    /// populated. The write must not fail because of the cache, so if its lock is poisoned the
+    /// cache is dropped instead, to be reloaded by the next read.
+    fn update_cache(&self, f: impl FnOnce(&mut IndexMap<String, Rule>)) {
+        let mut cache = self.cache.write().unwrap_or_else(|poisoned| {
+            self.cache.clear_poison();
+
+            let mut cache = poisoned.into_inner();
+            cache.rules = None;
+            cache
+        });
+
+        cache.writes += 1;
+
+        if let Some(rules) = cache.rules.as_mut() {
+            f(rules);
+        }
+    }
+
+    /// Drops the cache, to be reloaded by the next read, after a write whose effect on the inner
+    /// repository isn't known.
+    fn drop_cache(&self) {
+        let mut cache = self.cache.write().unwrap_or_else(|poisoned| {
+            self.cache.clear_poison();
+            poisoned.into_inner()
+        });
+
+        cache.writes += 1;
+        cache.rules = None;
+    }
+}
+
+impl<RR: RuleRepository> RuleRepository for ReadThroughCacheRepository<RR> {
+    async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+        {
+            let cache = self
+                .cache
+                .read()
+                .map_err(|err| GetAllRulesError::Internal(err.to_string()))?;
+
+            if let Some(rules) = cache.rules.as_ref() {
+                return Ok(rules.values().cloned().collect());
+            }
+        }
+
+        self.load().await
+    }
+
+    async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+        {
+            let cache = self
+                .cache
+                .read()
+                .map_err(|err| GetRuleError::Internal(err.to_string()))?;
+
+            if let Some(rules) = cache.rules.as_ref() {
+                return rules
+                    .get(id)
+                    .cloned()
+                    .ok_or_else(|| GetRuleError::NoSuchRule(id.clone()));
+            }
+        }
+
+        self.inner.get(id).await
+    }
+
+    async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+        self.inner.create(rule.clone()).await?;
+
+        self.update_cache(|rules| {
+            rules.insert(rule.id.clone(), rule);
+        });
+
+        Ok(())
+    }
+
+    /// If creating the rules fails the cache is dropped, as the inner repository may have kept
+    /// some of them.
+    async fn create_many(&self, rules: Vec<Rule>) -> Result<(), CreateRuleError> {
+        if let Err(err) = self.inner.create_many(rules.clone()).await {
+            self.drop_cache();
+            return Err(err);
+        }
+
+        self.update_cache(|cached| {
+            for rule in rules {
+                cached.insert(rule.id.clone(), rule);
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+        let old_rule = self.inner.delete(id).await?;
+
+        self.update_cache(|rules| {
+            rules.shift_remove(id);
+        });
+
+        Ok(old_rule)
+    }
+
+    async fn update(&self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
+        let old_rule = self.inner.update(id.clone(), new_rule.clone()).await?;
+
+        self.update_cache(|rules| {
+            let Some((index, _, _)) = rules.shift_remove_full(&id) else {
+                rules.insert(new_rule.id.clone(), new_rule);
+                return;
+            };
+
+            // Keep the rule where it was rather than moving it to the end.
+            let (new_index, _) = rules.insert_full(new_rule.id.clone(), new_rule);
+            rules.move_index(new_index, index.min(rules.len() - 1));
+        });
+
+        Ok(old_rule)
+    }
+
+    fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+        self.inner.watch()
+    }
+
+    async fn evaluate(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        self.inner.evaluate(ids, input, options).await
+    }
+
+    async fn evaluate_with_context(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        self.inner
+            .evaluate_with_context(ids, input, options, context)
+            .await
+    }
+
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Stream<Item = Result<EvaluationReason, EvaluateRuleError>> + Send + 'static {
+        self.inner.evaluate_streaming(ids, input)
+    }
+
+    async fn stats(&self, id: &String) -> Result<RuleStats, GetRuleError> {
+        self.inner.stats(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemRuleRepository;
+    use crate::{predicate, rule};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::oneshot;
+
+    /// Counts the number of `get_all` calls made to the wrapped repository. `get_all` can be
+    /// paused after reading the rules, see `pause_get_all`.
+    #[derive(Debug, Clone)]
+    struct CountingRepository {
+        inner: InMemRuleRepository,
+        get_all_calls: Arc<AtomicUsize>,
+        get_all_gate: Arc<std::sync::Mutex<Option<GetAllGate>>>,
+    }
+
+    #[derive(Debug)]
+    struct GetAllGate {
+        read: oneshot::Sender<()>,
+        resume: oneshot::Receiver<()>,
+    }
+
+    impl CountingRepository {
+        fn new(rules: &[Rule]) -> Self {
+            Self {
+                inner: InMemRuleRepository::new(rules),
+                get_all_calls: Arc::default(),
+                get_all_gate: Arc::default(),
+            }
+        }
+
+        /// Pauses the next `get_all` after it has read the rules. The first receiver completes
+        /// once they've been read, and the call returns once the sender is used.
+        fn pause_get_all(&self) -> (oneshot::Receiver<()>, oneshot::Sender<()>) {
+            let (read, read_receiver) = oneshot::channel();
+            let (resume_sender, resume) = oneshot::channel();
+
+            *self
+                .get_all_gate
+                .lock()
+                .expect("lock should not be poisoned") = Some(GetAllGate { read, resume });
+
+            (read_receiver, resume_sender)
+        }
+
+        fn get_all_calls(&self) -> usize {
+            self.get_all_calls.load(Ordering::SeqCst)
+        }
+    }
+
+    impl RuleRepository for CountingRepository {
+        async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+            self.get_all_calls.fetch_add(1, Ordering::SeqCst);
+            let rules = self.inner.get_all().await;
+
+            let gate = self
+                .get_all_gate
+                .lock()
+                .expect("lock should not be poisoned")
+                .take();
+
+            if let Some(GetAllGate { read, resume }) = gate {
+                let _ = read.send(());
+                let _ = resume.await;
+            }
+
+            rules
+        }
+
+        async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+            self.inner.get(id).await
+        }
+
+        async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+            self.inner.create(rule).await
+        }
+
+        async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+            self.inner.delete(id).await
+        }
+
+        async fn update(
+            &self,
+            id: String,
+            new_rule: Rule,
+        ) -> Result<Option<Rule>, UpdateRuleError> {
+            self.inner.update(id, new_rule).await
+        }
+
+        fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+            self.inner.watch()
+        }
+
+        async fn evaluate(
+            &self,
+            ids: &[String],
+            input: serde_json::Value,
+            options: EvaluationOptions,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            self.inner.evaluate(ids, input, options).await
+        }
+
+        async fn evaluate_with_context(
+            &self,
+            ids: &[String],
+            input: serde_json::Value,
+            options: EvaluationOptions,
+            context: EvaluationContext,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            self.inner
+                .evaluate_with_context(ids, input, options, context)
+                .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lazy() {
+        let inner = CountingRepository::new(&[rule!("rule-1", "message", predicate!("foo" == 10))]);
+        let db = ReadThroughCacheRepository::new(inner.clone(), CacheMissStrategy::Lazy)
+            .await
+            .expect("construction should not fail");
+
+        assert_eq!(inner.get_all_calls(), 0);
+
+        let rules = db.get_all().await.expect("get_all should not fail");
+        assert_eq!(rules.len(), 1);
+        assert_eq!(inner.get_all_calls(), 1);
+
+        db.get_all().await.expect("get_all should not fail");
+        assert_eq!(inner.get_all_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_eager() {
+        let inner = CountingRepository::new(&[rule!("rule-1", "message", predicate!("foo" == 10))]);
+        let db = ReadThroughCacheRepository::new(inner.clone(), CacheMissStrategy::Eager)
+            .await
+            .expect("construction should not fail");
+
+        assert_eq!(inner.get_all_calls(), 1);
+
+        let rules = db.get_all().await.expect("get_all should not fail");
+        assert_eq!(rules.len(), 1);
+        assert_eq!(inner.get_all_calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_writes_update_cache() {
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        let inner = CountingRepository::new(std::slice::from_ref(&rule));
+        let db = ReadThroughCacheRepository::new(inner.clone(), CacheMissStrategy::Eager)
+            .await
+            .expect("construction should not fail");
+
+        let new_rule = rule!("rule-2", "message", predicate!("foo" == 12));
+        db.create(new_rule.clone())
+            .await
+            .expect("rule creation should not fail");
+
+        let rules = db.get_all().await.expect("get_all should not fail");
+        assert_eq!(rules.len(), 2);
+        assert!(rules.contains(&new_rule));
+        assert_eq!(inner.get_all_calls(), 1);
+
+        let updated_rule = rule!("rule-3", "updated message", predicate!("foo" == 14));
+        db.update(rule.id.clone(), updated_rule.clone())
+            .await
+            .expect("update should not fail");
+        db.delete(&new_rule.id)
+            .await
+            .expect("delete should not fail");
+
+        let rules = db.get_all().await.expect("get_all should not fail");
+        assert_eq!(rules, vec![updated_rule.clone()]);
+        assert_eq!(db.get(&updated_rule.id).await, Ok(updated_rule.clone()));
+        assert_eq!(
+            db.get(&rule.id).await,
+            Err(GetRuleError::NoSuchRule(rule.id.clone()))
+        );
+        assert_eq!(inner.get_all_calls(), 1);
+
+        assert_eq!(
+            inner.get_all().await.expect("get_all should not fail"),
+            vec![updated_rule]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_many() {
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        let inner = CountingRepository::new(std::slice::from_ref(&rule));
+        let db = ReadThroughCacheRepository::new(inner.clone(), CacheMissStrategy::Eager)
+            .await
+            .expect("construction should not fail");
+
+        let rule_2 = rule!("rule-2", "message", predicate!("foo" == 10));
+        db.create_many(vec![rule_2.clone()])
+            .await
+            .expect("rule creation should not fail");
+        assert_eq!(db.get_all().await, Ok(vec![rule.clone(), rule_2.clone()]));
+        assert_eq!(inner.get_all_calls(), 1);
+
+        // The inner repository creates the rules one at a time, so keeps those before the
+        // failure, which the cache reloads to find.
+        let rule_3 = rule!("rule-3", "message", predicate!("foo" == 10));
+        db.create_many(vec![rule_3.clone(), rule.clone()])
+            .await
+            .expect_err("duplicate rule should not be created");
+        assert_eq!(db.get_all().await, Ok(vec![rule, rule_2, rule_3]));
+        assert_eq!(inner.get_all_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_order() {
+        let rules =
+            ["rule-c", "rule-a", "rule-b"].map(|id| rule!(id, "message", predicate!("foo" == 10)));
+        let db = ReadThroughCacheRepository::new(
+            InMemRuleRepository::new(&rules),
+            CacheMissStrategy::Eager,
+        )
+        .await
+        .expect("construction should not fail");
+
+        db.create(rule!("rule-0", "message", predicate!("foo" == 10)))
+            .await
+            .expect("rule creation should not fail");
+        db.update(
+            "rule-a".to_owned(),
+            rule!("rule-d", "message", predicate!("foo" == 10)),
+        )
+        .await
+        .expect("update should not fail");
+
+        let ids = |rules: Vec<Rule>| rules.into_iter().map(|rule| rule.id).collect::<Vec<_>>();
+        let expected = ["rule-c", "rule-d", "rule-b", "rule-0"];
+
+        assert_eq!(
+            ids(db.get_all().await.expect("get_all should not fail")),
+            expected
+        );
+        assert_eq!(
+            ids(db.inner.get_all().await.expect("get_all should not fail")),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let inner = CountingRepository::new(&[]);
+        let db = ReadThroughCacheRepository::new(inner.clone(), CacheMissStrategy::Eager)
+            .await
+            .expect("construction should not fail");
+
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        inner
+            .create(rule.clone())
+            .await
+            .expect("rule creation should not fail");
+
+        assert_eq!(db.get_all().await, Ok(vec![]));
+
+        db.refresh().await.expect("refresh should not fail");
+
+        assert_eq!(db.get_all().await, Ok(vec![rule]));
+        assert_eq!(inner.get_all_calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_concurrent_write() {
+        let inner = CountingRepository::new(&[]);
+        let db = ReadThroughCacheRepository::new_lazy(inner.clone());
+
+        let (read, resume) = inner.pause_get_all();
+        let refresh = tokio::spawn({
+            let db = db.clone();
+            async move { db.refresh().await }
+        });
+
+        // Written after the refresh read the rules, but before it cached them.
+        read.await.expect("get_all should be called");
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        db.create(rule.clone())
+            .await
+            .expect("rule creation should not fail");
+        resume.send(()).expect("get_all should be waiting");
+
+        refresh
+            .await
+            .expect("refresh should not panic")
+            .expect("refresh should not fail");
+
+        assert_eq!(db.get_all().await, Ok(vec![rule]));
+    }
+
+    #[tokio::test]
+    async fn test_poisoned_cache() {
+        let db = ReadThroughCacheRepository::new(
+            InMemRuleRepository::new(&[rule!("rule-1", "message", predicate!("foo" == 10))]),
+            CacheMissStrategy::Eager,
+        )
+        .await
+        .expect("construction should not fail");
+
+        // Panicking while holding the lock poisons it.
+        let cache = Arc::clone(&db.cache);
+        std::thread::spawn(move || {
+            let _guard = cache.write();
+            panic!("poison the cache");
+        })
+        .join()
+        .expect_err("thread should panic");
+
+        assert!(matches!(
+            db.get_all().await,
+            Err(GetAllRulesError::Internal(_))
+        ));
+        assert!(matches!(
+            db.get(&"rule-1".to_owned()).await,
+            Err(GetRuleError::Internal(_))
+        ));
+
+        // Writes to the inner repository still succeed, dropping the cache instead.
+        db.create(rule!("rule-2", "message", predicate!("foo" == 10)))
+            .await
+            .expect("rule creation should not fail");
+        assert!(
+            db.cache
+                .read()
+                .expect("lock should not be poisoned")
+                .rules
+                .is_none()
+        );
+        assert_eq!(db.get_all().await.map(|rules| rules.len()), Ok(2));
+    }
+}
@@ -0,0 +1,780 @@
+use crate::core::rule::{Operator, Rule};
+use crate::repository::{
+    CloneRuleError, CreateRuleError, DeleteRuleError, EvaluateRuleError, Evaluation,
+    EvaluationContext, EvaluationOptions, EvaluationReason, ExportError, GetAllRulesError,
+    GetRuleError, IndexedEvaluation, RuleChangeEvent, RuleRepository, RuleStats, UpdateRuleError,
+    format::RuleFileFormat,
+};
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+use tokio_stream::Stream;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+pub type BoxStream<'a, T> = Pin<Box<dyn Stream<Item = T> + Send + 'a>>;
+
+/// An object safe version of `RuleRepository`, implemented for every `RuleRepository`. Allows
+/// choosing the repository at runtime through an `ErasedRepository`, which should usually be used
+/// through `RuleRepository` rather than this trait directly.
+///
+/// Has a method for every method of `RuleRepository`, including those with a default, so an
+/// erased repository behaves exactly like the repository it wraps.
+pub trait DynRuleRepository {
+    fn get_all(&self) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>>;
+
+    #[allow(clippy::ptr_arg)]
+    fn get<'a>(&'a self, id: &'a String) -> BoxFuture<'a, Result<Rule, GetRuleError>>;
+
+    fn create(&self, rule: Rule) -> BoxFuture<'_, Result<(), CreateRuleError>>;
+
+    #[allow(clippy::ptr_arg)]
+    fn delete<'a>(&'a self, id: &'a String)
+    -> BoxFuture<'a, Result<Option<Rule>, DeleteRuleError>>;
+
+    fn update(
+        &self,
+        id: String,
+        new_rule: Rule,
+    ) -> BoxFuture<'_, Result<Option<Rule>, UpdateRuleError>>;
+
+    fn watch(&self) -> BoxStream<'static, RuleChangeEvent>;
+
+    fn evaluate<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>>;
+
+    fn evaluate_with_context<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>>;
+
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> BoxStream<'static, Result<EvaluationReason, EvaluateRuleError>>;
+
+    #[allow(clippy::ptr_arg)]
+    fn clone_rule<'a>(
+        &'a self,
+        id: &'a String,
+        new_id: String,
+    ) -> BoxFuture<'a, Result<Rule, CloneRuleError>>;
+
+    #[allow(clippy::ptr_arg)]
+    fn stats<'a>(&'a self, id: &'a String) -> BoxFuture<'a, Result<RuleStats, GetRuleError>>;
+
+    fn evaluate_with_transform<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        transform: fn(serde_json::Value) -> serde_json::Value,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>>;
+
+    fn evaluate_indexed<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+    ) -> BoxFuture<'a, Result<IndexedEvaluation, EvaluateRuleError>>;
+
+    fn get_many<'a>(
+        &'a self,
+        ids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn get_by_category<'a>(
+        &'a self,
+        category: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn find_by_operator(
+        &self,
+        operator: Operator,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn find_by_author<'a>(
+        &'a self,
+        author: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn find_by_review_status(
+        &self,
+        reviewed: bool,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn find_by_date_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>>;
+
+    fn evaluate_by_category<'a>(
+        &'a self,
+        category: &'a str,
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>>;
+
+    #[allow(clippy::ptr_arg)]
+    fn evaluate_grouped<'a>(
+        &'a self,
+        id: &'a String,
+        inputs: Vec<serde_json::Value>,
+        group_by: Option<String>,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<HashMap<String, Evaluation>, EvaluateRuleError>>;
+
+    fn rename(
+        &self,
+        old_id: String,
+        new_id: String,
+    ) -> BoxFuture<'_, Result<Rule, UpdateRuleError>>;
+
+    fn create_many(&self, rules: Vec<Rule>) -> BoxFuture<'_, Result<(), CreateRuleError>>;
+
+    fn find_conflicting_rules<'a>(
+        &'a self,
+        new_rule: &'a Rule,
+    ) -> BoxFuture<'a, Result<Vec<String>, GetAllRulesError>>;
+
+    fn export_json(&self) -> BoxFuture<'_, Result<String, ExportError>>;
+
+    fn export_as(&self, format: RuleFileFormat) -> BoxFuture<'_, Result<String, ExportError>>;
+}
+
+impl<RR: RuleRepository> DynRuleRepository for RR {
+    fn get_all(&self) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::get_all(self))
+    }
+
+    fn get<'a>(&'a self, id: &'a String) -> BoxFuture<'a, Result<Rule, GetRuleError>> {
+        Box::pin(RuleRepository::get(self, id))
+    }
+
+    fn create(&self, rule: Rule) -> BoxFuture<'_, Result<(), CreateRuleError>> {
+        Box::pin(RuleRepository::create(self, rule))
+    }
+
+    fn delete<'a>(
+        &'a self,
+        id: &'a String,
+    ) -> BoxFuture<'a, Result<Option<Rule>, DeleteRuleError>> {
+        Box::pin(RuleRepository::delete(self, id))
+    }
+
+    fn update(
+        &self,
+        id: String,
+        new_rule: Rule,
+    ) -> BoxFuture<'_, Result<Option<Rule>, UpdateRuleError>> {
+        Box::pin(RuleRepository::update(self, id, new_rule))
+    }
+
+    fn watch(&self) -> BoxStream<'static, RuleChangeEvent> {
+        Box::pin(RuleRepository::watch(self))
+    }
+
+    fn evaluate<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate(self, ids, input, options))
+    }
+
+    fn evaluate_with_context<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_with_context(
+            self, ids, input, options, context,
+        ))
+    }
+
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> BoxStream<'static, Result<EvaluationReason, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_streaming(self, ids, input))
+    }
+
+    fn clone_rule<'a>(
+        &'a self,
+        id: &'a String,
+        new_id: String,
+    ) -> BoxFuture<'a, Result<Rule, CloneRuleError>> {
+        Box::pin(RuleRepository::clone_rule(self, id, new_id))
+    }
+
+    fn stats<'a>(&'a self, id: &'a String) -> BoxFuture<'a, Result<RuleStats, GetRuleError>> {
+        Box::pin(RuleRepository::stats(self, id))
+    }
+
+    fn evaluate_with_transform<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+        transform: fn(serde_json::Value) -> serde_json::Value,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_with_transform(
+            self, ids, input, transform,
+        ))
+    }
+
+    fn evaluate_indexed<'a>(
+        &'a self,
+        ids: &'a [String],
+        input: serde_json::Value,
+    ) -> BoxFuture<'a, Result<IndexedEvaluation, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_indexed(self, ids, input))
+    }
+
+    fn get_many<'a>(
+        &'a self,
+        ids: &'a [String],
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::get_many(self, ids))
+    }
+
+    fn get_by_category<'a>(
+        &'a self,
+        category: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::get_by_category(self, category))
+    }
+
+    fn find_by_operator(
+        &self,
+        operator: Operator,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::find_by_operator(self, operator))
+    }
+
+    fn find_by_author<'a>(
+        &'a self,
+        author: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::find_by_author(self, author))
+    }
+
+    fn find_by_review_status(
+        &self,
+        reviewed: bool,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::find_by_review_status(self, reviewed))
+    }
+
+    fn find_by_date_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> BoxFuture<'_, Result<Vec<Rule>, GetAllRulesError>> {
+        Box::pin(RuleRepository::find_by_date_range(self, from, to))
+    }
+
+    fn evaluate_by_category<'a>(
+        &'a self,
+        category: &'a str,
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<Evaluation, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_by_category(
+            self, category, input, options,
+        ))
+    }
+
+    fn evaluate_grouped<'a>(
+        &'a self,
+        id: &'a String,
+        inputs: Vec<serde_json::Value>,
+        group_by: Option<String>,
+        options: EvaluationOptions,
+    ) -> BoxFuture<'a, Result<HashMap<String, Evaluation>, EvaluateRuleError>> {
+        Box::pin(RuleRepository::evaluate_grouped(
+            self, id, inputs, group_by, options,
+        ))
+    }
+
+    fn rename(
+        &self,
+        old_id: String,
+        new_id: String,
+    ) -> BoxFuture<'_, Result<Rule, UpdateRuleError>> {
+        Box::pin(RuleRepository::rename(self, old_id, new_id))
+    }
+
+    fn create_many(&self, rules: Vec<Rule>) -> BoxFuture<'_, Result<(), CreateRuleError>> {
+        Box::pin(RuleRepository::create_many(self, rules))
+    }
+
+    fn find_conflicting_rules<'a>(
+        &'a self,
+        new_rule: &'a Rule,
+    ) -> BoxFuture<'a, Result<Vec<String>, GetAllRulesError>> {
+        Box::pin(RuleRepository::find_conflicting_rules(self, new_rule))
+    }
+
+    fn export_json(&self) -> BoxFuture<'_, Result<String, ExportError>> {
+        Box::pin(RuleRepository::export_json(self))
+    }
+
+    fn export_as(&self, format: RuleFileFormat) -> BoxFuture<'_, Result<String, ExportError>> {
+        Box::pin(RuleRepository::export_as(self, format))
+    }
+}
+
+/// A repository whose concrete type is only known at runtime. Implements `RuleRepository` itself
+/// so it can be used anywhere a repository is expected, e.g. as the server's `AppState`.
+pub type ErasedRepository = Arc<dyn DynRuleRepository + Send + Sync>;
+
+impl RuleRepository for ErasedRepository {
+    async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).get_all().await
+    }
+
+    async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+        (**self).get(id).await
+    }
+
+    async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+        (**self).create(rule).await
+    }
+
+    async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+        (**self).delete(id).await
+    }
+
+    async fn update(&self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
+        (**self).update(id, new_rule).await
+    }
+
+    fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+        (**self).watch()
+    }
+
+    async fn evaluate(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        (**self).evaluate(ids, input, options).await
+    }
+
+    async fn evaluate_with_context(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        (**self)
+            .evaluate_with_context(ids, input, options, context)
+            .await
+    }
+
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Stream<Item = Result<EvaluationReason, EvaluateRuleError>> + Send + 'static {
+        (**self).evaluate_streaming(ids, input)
+    }
+
+    async fn clone_rule(&self, id: &String, new_id: String) -> Result<Rule, CloneRuleError> {
+        (**self).clone_rule(id, new_id).await
+    }
+
+    async fn stats(&self, id: &String) -> Result<RuleStats, GetRuleError> {
+        (**self).stats(id).await
+    }
+
+    async fn evaluate_with_transform(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        transform: fn(serde_json::Value) -> serde_json::Value,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        (**self)
+            .evaluate_with_transform(ids, input, transform)
+            .await
+    }
+
+    async fn evaluate_indexed(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> Result<IndexedEvaluation, EvaluateRuleError> {
+        (**self).evaluate_indexed(ids, input).await
+    }
+
+    async fn get_many(&self, ids: &[String]) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).get_many(ids).await
+    }
+
+    async fn get_by_category(&self, category: &str) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).get_by_category(category).await
+    }
+
+    async fn find_by_operator(&self, operator: Operator) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).find_by_operator(operator).await
+    }
+
+    async fn find_by_author(&self, author: &str) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).find_by_author(author).await
+    }
+
+    async fn find_by_review_status(&self, reviewed: bool) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).find_by_review_status(reviewed).await
+    }
+
+    async fn find_by_date_range(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Rule>, GetAllRulesError> {
+        (**self).find_by_date_range(from, to).await
+    }
+
+    async fn evaluate_by_category(
+        &self,
+        category: &str,
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        (**self)
+            .evaluate_by_category(category, input, options)
+            .await
+    }
+
+    async fn evaluate_grouped(
+        &self,
+        id: &String,
+        inputs: Vec<serde_json::Value>,
+        group_by: Option<String>,
+        options: EvaluationOptions,
+    ) -> Result<HashMap<String, Evaluation>, EvaluateRuleError> {
+        (**self)
+            .evaluate_grouped(id, inputs, group_by, options)
+            .await
+    }
+
+    async fn rename(&self, old_id: String, new_id: String) -> Result<Rule, UpdateRuleError> {
+        (**self).rename(old_id, new_id).await
+    }
+
+    async fn create_many(&self, rules: Vec<Rule>) -> Result<(), CreateRuleError> {
+        (**self).create_many(rules).await
+    }
+
+    async fn find_conflicting_rules(
+        &self,
+        new_rule: &Rule,
+    ) -> Result<Vec<String>, GetAllRulesError> {
+        (**self).find_conflicting_rules(new_rule).await
+    }
+
+    async fn export_json(&self) -> Result<String, ExportError> {
+        (**self).export_json().await
+    }
+
+    async fn export_as(&self, format: RuleFileFormat) -> Result<String, ExportError> {
+        (**self).export_as(format).await
+    }
+
+    fn into_erased(self) -> ErasedRepository {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Only `RuleRepository` is imported, as its methods share names with `DynRuleRepository`.
+    use super::ErasedRepository;
+    use crate::core::rule::{Operator, Rule};
+    use crate::repository::{
+        CreateRuleError, DeleteRuleError, EvaluateRuleError, Evaluation, EvaluationContext,
+        EvaluationOptions, EvaluationResult, ExportError, GetAllRulesError, GetRuleError,
+        InMemRuleRepository, IndexedEvaluation, RuleChangeEvent, RuleRepository, UpdateRuleError,
+        cache::{CacheMissStrategy, ReadThroughCacheRepository},
+        format::RuleFileFormat,
+    };
+    use crate::{predicate, rule};
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use std::{collections::HashMap, sync::Arc};
+    use tokio_stream::Stream;
+
+    const OVERRIDDEN: &str = "overridden";
+
+    /// Overrides every method of `RuleRepository` with a default to fail, so it's clear whether
+    /// an erased repository calls the override or the default.
+    #[derive(Debug, Clone)]
+    struct OverridingRepository(InMemRuleRepository);
+
+    impl RuleRepository for OverridingRepository {
+        async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+            self.0.get_all().await
+        }
+
+        async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+            self.0.get(id).await
+        }
+
+        async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+            self.0.create(rule).await
+        }
+
+        async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+            self.0.delete(id).await
+        }
+
+        async fn update(
+            &self,
+            id: String,
+            new_rule: Rule,
+        ) -> Result<Option<Rule>, UpdateRuleError> {
+            self.0.update(id, new_rule).await
+        }
+
+        fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+            self.0.watch()
+        }
+
+        async fn evaluate(
+            &self,
+            ids: &[String],
+            input: serde_json::Value,
+            options: EvaluationOptions,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            self.0.evaluate(ids, input, options).await
+        }
+
+        async fn evaluate_with_context(
+            &self,
+            ids: &[String],
+            input: serde_json::Value,
+            options: EvaluationOptions,
+            context: EvaluationContext,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            self.0
+                .evaluate_with_context(ids, input, options, context)
+                .await
+        }
+
+        async fn evaluate_with_transform(
+            &self,
+            _: &[String],
+            _: serde_json::Value,
+            _: fn(serde_json::Value) -> serde_json::Value,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            Err(EvaluateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn evaluate_indexed(
+            &self,
+            _: &[String],
+            _: serde_json::Value,
+        ) -> Result<IndexedEvaluation, EvaluateRuleError> {
+            Err(EvaluateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn get_many(&self, _: &[String]) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn get_by_category(&self, _: &str) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn find_by_operator(&self, _: Operator) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn find_by_author(&self, _: &str) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn find_by_review_status(&self, _: bool) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn find_by_date_range(
+            &self,
+            _: Option<DateTime<Utc>>,
+            _: Option<DateTime<Utc>>,
+        ) -> Result<Vec<Rule>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn evaluate_by_category(
+            &self,
+            _: &str,
+            _: serde_json::Value,
+            _: EvaluationOptions,
+        ) -> Result<Evaluation, EvaluateRuleError> {
+            Err(EvaluateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn evaluate_grouped(
+            &self,
+            _: &String,
+            _: Vec<serde_json::Value>,
+            _: Option<String>,
+            _: EvaluationOptions,
+        ) -> Result<HashMap<String, Evaluation>, EvaluateRuleError> {
+            Err(EvaluateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn rename(&self, _: String, _: String) -> Result<Rule, UpdateRuleError> {
+            Err(UpdateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn create_many(&self, _: Vec<Rule>) -> Result<(), CreateRuleError> {
+            Err(CreateRuleError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn find_conflicting_rules(&self, _: &Rule) -> Result<Vec<String>, GetAllRulesError> {
+            Err(GetAllRulesError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn export_json(&self) -> Result<String, ExportError> {
+            Err(ExportError::Internal(OVERRIDDEN.to_owned()))
+        }
+
+        async fn export_as(&self, _: RuleFileFormat) -> Result<String, ExportError> {
+            Err(ExportError::Internal(OVERRIDDEN.to_owned()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_erased_in_mem_repository() {
+        let repository = InMemRuleRepository::empty().into_erased();
+
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        repository
+            .create(rule.clone())
+            .await
+            .expect("rule should be created");
+
+        assert_eq!(repository.get(&"rule-1".to_owned()).await, Ok(rule.clone()));
+        assert_eq!(repository.get_all().await, Ok(vec![rule.clone()]));
+
+        let evaluation = repository
+            .evaluate(
+                &["rule-1".to_owned()],
+                json!({"foo": 10}),
+                EvaluationOptions::default(),
+            )
+            .await
+            .expect("evaluation should not fail");
+        assert_eq!(evaluation.result, EvaluationResult::Pass);
+
+        let clone = repository
+            .clone_rule(&"rule-1".to_owned(), "rule-2".to_owned())
+            .await
+            .expect("rule should be cloned");
+        assert_eq!(clone.id, "rule-2");
+
+        assert_eq!(
+            repository.delete(&"rule-1".to_owned()).await,
+            Ok(Some(rule))
+        );
+        assert_eq!(repository.get_all().await, Ok(vec![clone]));
+
+        // Erasing an already erased repository doesn't add another layer of indirection.
+        assert!(Arc::ptr_eq(&repository, &repository.clone().into_erased()));
+    }
+
+    #[tokio::test]
+    async fn test_swap_backends() {
+        let rules = [rule!("rule-1", "message", predicate!("foo" == 10))];
+
+        let in_mem = InMemRuleRepository::new(&rules);
+        let cached = ReadThroughCacheRepository::new(in_mem.clone(), CacheMissStrategy::Eager)
+            .await
+            .expect("cache should be populated");
+
+        let backends: Vec<ErasedRepository> = vec![in_mem.into_erased(), cached.into_erased()];
+
+        for backend in backends {
+            assert_eq!(backend.get_all().await, Ok(rules.to_vec()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forwards_overrides() {
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        let repository =
+            OverridingRepository(InMemRuleRepository::new(std::slice::from_ref(&rule)))
+                .into_erased();
+
+        let id = rule.id.clone();
+        let ids = [id.clone()];
+        let options = EvaluationOptions::default;
+        let internal = || OVERRIDDEN.to_owned();
+
+        assert!(matches!(
+            repository.evaluate_with_transform(&ids, json!({}), |input| input).await,
+            Err(EvaluateRuleError::Internal(err)) if err == internal()
+        ));
+        assert!(matches!(
+            repository.evaluate_indexed(&ids, json!({})).await,
+            Err(EvaluateRuleError::Internal(err)) if err == internal()
+        ));
+        assert!(matches!(
+            repository.evaluate_by_category("fraud", json!({}), options()).await,
+            Err(EvaluateRuleError::Internal(err)) if err == internal()
+        ));
+        assert!(matches!(
+            repository.evaluate_grouped(&id, vec![json!({})], None, options()).await,
+            Err(EvaluateRuleError::Internal(err)) if err == internal()
+        ));
+
+        let finds = [
+            repository.get_many(&ids).await,
+            repository.get_by_category("fraud").await,
+            repository.find_by_operator(Operator::Equal).await,
+            repository.find_by_author("alice").await,
+            repository.find_by_review_status(false).await,
+            repository.find_by_date_range(None, None).await,
+        ];
+        for find in finds {
+            assert_eq!(find, Err(GetAllRulesError::Internal(internal())));
+        }
+        assert_eq!(
+            repository.find_conflicting_rules(&rule).await,
+            Err(GetAllRulesError::Internal(internal()))
+        );
+
+        assert_eq!(
+            repository.rename(id, "rule-2".to_owned()).await,
+            Err(UpdateRuleError::Internal(internal()))
+        );
+        assert_eq!(
+            repository
+                .create_many(vec![rule!("rule-3", "message", predicate!("foo" == 10))])
+                .await,
+            Err(CreateRuleError::Internal(internal()))
+        );
+        assert_eq!(
+            repository.export_json().await,
+            Err(ExportError::Internal(internal()))
+        );
+        assert_eq!(
+            repository.export_as(RuleFileFormat::Yaml).await,
+            Err(ExportError::Internal(internal()))
+        );
+    }
+}
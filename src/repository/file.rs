@@ -0,0 +1,177 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::io::AsyncWriteExt;
+
+/// Replaces the contents of `path` with `content` such that readers, and the file after a crash,
+/// only ever see the old or the new contents. The content is written to a temporary file next to
+/// `path`, see `temp_path`, which is then renamed over it.
+///
+/// Concurrent writes each use their own temporary file, so the last one to finish wins. If a write
+/// is interrupted, e.g. by the future being dropped, its temporary file may be left behind.
+pub async fn write_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let temp_path = temp_path(path);
+
+    let result = async {
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        file.write_all(content).await?;
+        // The content has to be on disk before the rename, otherwise a crash could leave `path`
+        // renamed to an empty file.
+        file.sync_all().await?;
+        drop(file);
+
+        tokio::fs::rename(&temp_path, path).await
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+
+    result
+}
+
+/// Numbers the temporary files of this process, see `temp_path`.
+static TEMP_FILES: AtomicU64 = AtomicU64::new(0);
+
+/// A new temporary file for `write_atomically` to write to, `path` with the process id and a
+/// counter appended, e.g. `rules.json.1234.0.tmp`, so no two writes share one. It's in the same
+/// directory as `path`, as renaming is only atomic within a file system.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let count = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(format!(".{}.{count}.tmp", std::process::id()));
+
+    PathBuf::from(temp_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{future::Future, task::Poll};
+
+    /// The names of the files in `dir`, sorted.
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .expect("directory should be readable")
+            .map(|entry| {
+                entry
+                    .expect("entry should be readable")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn test_write_atomically() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("rules.json");
+
+        write_atomically(&path, b"[]")
+            .await
+            .expect("write should not fail");
+        assert_eq!(std::fs::read(&path).expect("file should exist"), b"[]");
+
+        write_atomically(&path, b"[{}]")
+            .await
+            .expect("write should not fail");
+        assert_eq!(std::fs::read(&path).expect("file should exist"), b"[{}]");
+        assert_eq!(file_names(dir.path()), ["rules.json"]);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("rules.json");
+
+        assert_ne!(temp_path(&path), temp_path(&path));
+
+        let contents: Vec<Vec<u8>> = (0..16)
+            .map(|i| format!("{i}").repeat(64 * 1024).into_bytes())
+            .collect();
+
+        let writes = contents.iter().map(|content| {
+            let path = path.clone();
+            let content = content.clone();
+            tokio::spawn(async move { write_atomically(&path, &content).await })
+        });
+
+        for write in writes.collect::<Vec<_>>() {
+            write
+                .await
+                .expect("write should not panic")
+                .expect("write should not fail");
+        }
+
+        let content = std::fs::read(&path).expect("file should exist");
+        assert!(contents.contains(&content), "file was partially written");
+        assert_eq!(file_names(dir.path()), ["rules.json"]);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_write() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("rules.json");
+
+        let original = b"original".repeat(1024);
+        let updated = b"updated".repeat(64 * 1024);
+        std::fs::write(&path, &original).expect("file should be written");
+
+        // Abandons the write after a growing number of steps, so it's interrupted at every point
+        // up to when it completes.
+        for polls in 1.. {
+            let mut write = Box::pin(write_atomically(&path, &updated));
+            let mut completed = false;
+
+            for _ in 0..polls {
+                let poll = std::future::poll_fn(|cx| Poll::Ready(write.as_mut().poll(cx))).await;
+
+                if let Poll::Ready(result) = poll {
+                    result.expect("write should not fail");
+                    completed = true;
+                    break;
+                }
+
+                tokio::task::yield_now().await;
+            }
+
+            // Any blocking operation started before the write was dropped still runs to completion,
+            // but as each write has its own temporary file it can't affect the writes after it.
+            drop(write);
+
+            let content = std::fs::read(&path).expect("file should exist");
+
+            if completed {
+                assert_eq!(content, updated);
+                break;
+            }
+
+            assert!(
+                content == original || content == updated,
+                "file was partially written after {polls} polls"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_rename() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+
+        // A file can't be renamed over a directory that isn't empty.
+        let path = dir.path().join("rules");
+        std::fs::create_dir(&path).expect("directory should be created");
+        std::fs::write(path.join("rule.json"), b"{}").expect("file should be written");
+
+        assert!(write_atomically(&path, b"[]").await.is_err());
+        assert!(path.is_dir());
+        assert_eq!(file_names(dir.path()), ["rules"]);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::core::rule::Rule;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A file format rules can be imported from and exported to, see
+/// `InMemRuleRepository::import_from_reader_as`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RuleFileFormat {
+    /// A JSON array of rules.
+    #[default]
+    Json,
+    /// An array of tables named `rules`, as TOML documents can't be an array. TOML has no `null`,
+    /// so rules with a `null` nested in their value can't be exported to it.
+    Toml,
+    /// A YAML sequence of rules, with compound predicates written as single key maps.
+    Yaml,
+}
+
+/// The top level table of a TOML rules file.
+#[derive(Serialize, Deserialize)]
+struct TomlRules {
+    rules: Vec<Rule>,
+}
+
+impl RuleFileFormat {
+    /// Detects the format from the extension of `path`, returning `None` if it isn't recognised.
+    pub fn detect(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn parse(self, bytes: &[u8]) -> Result<Vec<Rule>, String> {
+        match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+            Self::Toml => {
+                let text = std::str::from_utf8(bytes).map_err(|err| err.to_string())?;
+
+                toml::from_str::<TomlRules>(text)
+                    .map(|file| file.rules)
+                    .map_err(|err| err.to_string())
+            }
+            Self::Yaml => serde_yaml::from_slice(bytes).map_err(|err| err.to_string()),
+        }
+    }
+
+    pub fn serialize(self, rules: &[Rule]) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec_pretty(rules).map_err(|err| err.to_string()),
+            Self::Toml => toml::to_string_pretty(&TomlRules {
+                rules: rules.to_vec(),
+            })
+            .map(String::into_bytes)
+            .map_err(|err| err.to_string()),
+            Self::Yaml => {
+                let mut serializer = serde_yaml::Serializer::new(Vec::new());
+                serde_yaml::with::singleton_map_recursive::serialize(&rules, &mut serializer)
+                    .map_err(|err| err.to_string())?;
+
+                serializer.into_inner().map_err(|err| err.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{all, any, none, not, predicate, rule};
+
+    fn rules() -> Vec<Rule> {
+        vec![
+            rule!("rule-1", "message", predicate!("foo" == 10)),
+            rule!(
+                "rule-2",
+                "message",
+                all!(
+                    predicate!("age" >= 18.5),
+                    any!(predicate!("tags" contains "vip"), predicate!("name" == "x")),
+                    none!(predicate!("nested.flag" == true)),
+                    not!(predicate!("list" == vec![1, 2]))
+                ),
+                depends_on: ["rule-1"]
+            ),
+            rule!("rule-3", "message", predicate!("foo" is_null)),
+        ]
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            RuleFileFormat::detect("rules.json"),
+            Some(RuleFileFormat::Json)
+        );
+        assert_eq!(
+            RuleFileFormat::detect("config/rules.TOML"),
+            Some(RuleFileFormat::Toml)
+        );
+        assert_eq!(
+            RuleFileFormat::detect("rules.yml"),
+            Some(RuleFileFormat::Yaml)
+        );
+        assert_eq!(RuleFileFormat::detect("rules.txt"), None);
+        assert_eq!(RuleFileFormat::detect("rules"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in [
+            RuleFileFormat::Json,
+            RuleFileFormat::Toml,
+            RuleFileFormat::Yaml,
+        ] {
+            let serialized = format
+                .serialize(&rules())
+                .unwrap_or_else(|err| panic!("{format:?} serialization should not fail: {err}"));
+
+            assert_eq!(format.parse(&serialized), Ok(rules()), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_toml() {
+        let input = r#"
+            [[rules]]
+            id = "rule-1"
+            message = "message"
+            predicate = { path = "foo", operator = "==", value = 10 }
+
+            [[rules]]
+            id = "rule-2"
+            message = "message"
+            predicate = { any = [{ path = "bar", operator = "contains", value = "x" }] }
+        "#;
+
+        assert_eq!(
+            RuleFileFormat::Toml.parse(input.as_bytes()),
+            Ok(vec![
+                rule!("rule-1", "message", predicate!("foo" == 10)),
+                rule!("rule-2", "message", any!(predicate!("bar" contains "x"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_toml_null() {
+        // A `null` value is left out entirely, which reads back as `null`.
+        let rules = [rule!("rule-1", "message", predicate!("foo" == ()))];
+        let serialized = RuleFileFormat::Toml
+            .serialize(&rules)
+            .expect("serialization should not fail");
+        assert_eq!(RuleFileFormat::Toml.parse(&serialized), Ok(rules.to_vec()));
+
+        // Nested `null`s can't be represented.
+        let rules = [rule!(
+            "rule-1",
+            "message",
+            predicate!("foo" == serde_json::json!([1, null]))
+        )];
+        assert!(RuleFileFormat::Toml.serialize(&rules).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::core::rule::Rule;
+use crate::repository::{
+    CreateRuleError, DeleteRuleError, EvaluateRuleError, Evaluation, EvaluationContext,
+    EvaluationOptions, EvaluationReason, GetAllRulesError, GetRuleError, RuleChangeEvent,
+    RuleRepository, RuleStats, UpdateRuleError,
+};
+use std::fmt::Display;
+use tokio_stream::{Stream, StreamExt};
+
+/// Wraps a repository, logging every operation made through it and whether it failed.
+#[derive(Debug, Clone)]
+pub struct LoggingRepository<RR: RuleRepository> {
+    inner: RR,
+}
+
+impl<RR: RuleRepository> LoggingRepository<RR> {
+    pub fn new(inner: RR) -> Self {
+        Self { inner }
+    }
+}
+
+fn log<T, E: Display>(operation: &str, result: Result<T, E>) -> Result<T, E> {
+    match &result {
+        Ok(_) => tracing::debug!(operation, "repository operation succeeded"),
+        Err(err) => tracing::warn!(operation, %err, "repository operation failed"),
+    }
+
+    result
+}
+
+impl<RR: RuleRepository> RuleRepository for LoggingRepository<RR> {
+    async fn get_all(&self) -> Result<Vec<Rule>, GetAllRulesError> {
+        log("get_all", self.inner.get_all().await)
+    }
+
+    async fn get(&self, id: &String) -> Result<Rule, GetRuleError> {
+        log("get", self.inner.get(id).await)
+    }
+
+    async fn create(&self, rule: Rule) -> Result<(), CreateRuleError> {
+        log("create", self.inner.create(rule).await)
+    }
+
+    async fn create_many(&self, rules: Vec<Rule>) -> Result<(), CreateRuleError> {
+        log("create_many", self.inner.create_many(rules).await)
+    }
+
+    async fn delete(&self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+        log("delete", self.inner.delete(id).await)
+    }
+
+    async fn update(&self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
+        log("update", self.inner.update(id, new_rule).await)
+    }
+
+    fn watch(&self) -> impl Stream<Item = RuleChangeEvent> + Send + 'static {
+        tracing::debug!(operation = "watch", "repository operation succeeded");
+        self.inner.watch()
+    }
+
+    async fn evaluate(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        log("evaluate", self.inner.evaluate(ids, input, options).await)
+    }
+
+    async fn evaluate_with_context(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+        options: EvaluationOptions,
+        context: EvaluationContext,
+    ) -> Result<Evaluation, EvaluateRuleError> {
+        log(
+            "evaluate_with_context",
+            self.inner
+                .evaluate_with_context(ids, input, options, context)
+                .await,
+        )
+    }
+
+    fn evaluate_streaming(
+        &self,
+        ids: &[String],
+        input: serde_json::Value,
+    ) -> impl Stream<Item = Result<EvaluationReason, EvaluateRuleError>> + Send + 'static {
+        self.inner
+            .evaluate_streaming(ids, input)
+            .map(|reason| log("evaluate_streaming", reason))
+    }
+
+    async fn stats(&self, id: &String) -> Result<RuleStats, GetRuleError> {
+        log("stats", self.inner.stats(id).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemRuleRepository;
+    use crate::{predicate, rule};
+    use tracing_test::traced_test;
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_logging() {
+        let repository = LoggingRepository::new(InMemRuleRepository::empty());
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+
+        repository
+            .create(rule.clone())
+            .await
+            .expect("rule should be created");
+        assert!(logs_contain("operation=\"create\""));
+
+        assert_eq!(
+            repository.create(rule).await,
+            Err(CreateRuleError::Duplicate("rule-1".to_owned()))
+        );
+        assert!(logs_contain("repository operation failed"));
+        assert!(logs_contain("a rule with id rule-1 already exists"));
+    }
+}
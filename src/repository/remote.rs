@@ -0,0 +1,243 @@
+use crate::core::rule::Rule;
+use crate::repository::{ImportError, format::RuleFileFormat};
+use std::{collections::HashSet, time::Duration};
+
+/// How long `import_from_url` waits for the whole response.
+pub const IMPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Response bodies larger than this are an `ImportError::TooLarge`, so a misbehaving server can't
+/// exhaust memory.
+pub const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Fetches a JSON array of rules from an `http://` or `https://` URL, e.g. a config server,
+/// checking that every rule is valid and that no id is used twice. See `import_from_url_with_timeout`.
+///
+/// Only URLs whose host is in `allowed_hosts` are fetched, anything else is an
+/// `ImportError::HostNotAllowed`, so a caller-supplied URL can't be used to reach arbitrary hosts.
+/// Redirects aren't followed for the same reason.
+pub async fn import_from_url(
+    url: &str,
+    allowed_hosts: &HashSet<String>,
+) -> Result<Vec<Rule>, ImportError> {
+    import_from_url_with_timeout(url, allowed_hosts, IMPORT_TIMEOUT).await
+}
+
+/// Like `import_from_url`, giving up with `ImportError::Timeout` if the response hasn't been read
+/// within `timeout`.
+pub async fn import_from_url_with_timeout(
+    url: &str,
+    allowed_hosts: &HashSet<String>,
+    timeout: Duration,
+) -> Result<Vec<Rule>, ImportError> {
+    let url = reqwest::Url::parse(url).map_err(|err| ImportError::Io(err.to_string()))?;
+
+    let host = url.host_str().unwrap_or_default();
+    if !allowed_hosts.contains(host) {
+        return Err(ImportError::HostNotAllowed(host.to_owned()));
+    }
+
+    let body = tokio::time::timeout(timeout, get(url))
+        .await
+        .map_err(|_| ImportError::Timeout)??;
+
+    let rules = RuleFileFormat::Json
+        .parse(&body)
+        .map_err(ImportError::Parse)?;
+
+    let mut ids = HashSet::with_capacity(rules.len());
+
+    for rule in &rules {
+        rule.validate()?;
+
+        if !ids.insert(rule.id()) {
+            return Err(ImportError::DuplicateId(rule.id.clone()));
+        }
+    }
+
+    Ok(rules)
+}
+
+/// The body of a successful `GET` of `url`, read a chunk at a time so a body larger than
+/// `MAX_BODY_BYTES` is rejected without buffering all of it.
+async fn get(url: reqwest::Url) -> Result<Vec<u8>, ImportError> {
+    let io_error = |err: reqwest::Error| ImportError::Io(err.to_string());
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .map_err(io_error)?;
+
+    let mut response = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(io_error)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(ImportError::HttpError(status.as_u16()));
+    }
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_BODY_BYTES as u64)
+    {
+        return Err(ImportError::TooLarge(MAX_BODY_BYTES));
+    }
+
+    let mut body = Vec::new();
+
+    while let Some(chunk) = response.chunk().await.map_err(io_error)? {
+        if body.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(ImportError::TooLarge(MAX_BODY_BYTES));
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rule::RuleValidationError;
+    use crate::{predicate, rule};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// The host of the servers started by `MockServer::start`.
+    fn allowed_hosts() -> HashSet<String> {
+        HashSet::from(["127.0.0.1".to_owned()])
+    }
+
+    /// Serves `response` to every `GET /rules.json`, returning the URL to request.
+    async fn serve(response: ResponseTemplate) -> (MockServer, String) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rules.json"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/rules.json", server.uri());
+        (server, url)
+    }
+
+    #[tokio::test]
+    async fn test_import_from_url() {
+        let rules = vec![
+            rule!("rule-1", "message", predicate!("foo" == 10)),
+            rule!("rule-2", "message", predicate!("bar" is_null)),
+        ];
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/rules.json"))
+            .and(query_param("v", "1"))
+            .and(header("accept", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&rules))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_eq!(
+            import_from_url(
+                &format!("{}/rules.json?v=1", server.uri()),
+                &allowed_hosts()
+            )
+            .await,
+            Ok(rules)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_from_url_err() {
+        let (_server, url) = serve(ResponseTemplate::new(404)).await;
+        assert_eq!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::HttpError(404))
+        );
+
+        let (_server, url) =
+            serve(ResponseTemplate::new(200).set_body_json(serde_json::json!({"not": "rules"})))
+                .await;
+        assert!(matches!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::Parse(_))
+        ));
+
+        let rules = [rule!("rule-1", "", predicate!("foo" == 10))];
+        let (_server, url) = serve(ResponseTemplate::new(200).set_body_json(&rules)).await;
+        assert_eq!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::Invalid(RuleValidationError::EmptyMessage(
+                "rule-1".to_owned()
+            )))
+        );
+
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+        let (_server, url) =
+            serve(ResponseTemplate::new(200).set_body_json([rule.clone(), rule])).await;
+        assert_eq!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::DuplicateId("rule-1".to_owned()))
+        );
+
+        assert!(matches!(
+            import_from_url("not a url", &allowed_hosts()).await,
+            Err(ImportError::Io(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_import_from_url_too_large() {
+        let body = vec![b' '; MAX_BODY_BYTES + 1];
+        let (_server, url) = serve(ResponseTemplate::new(200).set_body_bytes(body)).await;
+
+        assert_eq!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::TooLarge(MAX_BODY_BYTES))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_from_url_timeout() {
+        let (_server, url) =
+            serve(ResponseTemplate::new(200).set_delay(Duration::from_secs(5))).await;
+
+        assert_eq!(
+            import_from_url_with_timeout(&url, &allowed_hosts(), Duration::from_millis(50)).await,
+            Err(ImportError::Timeout)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_from_url_host_not_allowed() {
+        let (server, url) =
+            serve(ResponseTemplate::new(200).set_body_json(Vec::<Rule>::new())).await;
+
+        assert_eq!(
+            import_from_url(&url, &HashSet::from(["example.com".to_owned()])).await,
+            Err(ImportError::HostNotAllowed("127.0.0.1".to_owned()))
+        );
+        assert_eq!(
+            server
+                .received_requests()
+                .await
+                .map(|requests| requests.len()),
+            Some(0)
+        );
+
+        // Redirects to other hosts aren't followed.
+        let (_server, url) = serve(
+            ResponseTemplate::new(302).insert_header("location", "http://example.com/rules.json"),
+        )
+        .await;
+        assert_eq!(
+            import_from_url(&url, &allowed_hosts()).await,
+            Err(ImportError::HttpError(302))
+        );
+    }
+}
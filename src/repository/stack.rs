@@ -0,0 +1,168 @@
+use crate::repository::{
+    RuleRepository, cache::ReadThroughCacheRepository, erased::ErasedRepository,
+    logging::LoggingRepository,
+};
+
+/// A layer wrapping a repository to add behaviour on top of it, see `RepositoryStack`. Implemented
+/// for closures, e.g. `|inner| MyRepository::new(inner).into_erased()`.
+pub trait RepositoryDecorator {
+    fn decorate(self, inner: ErasedRepository) -> ErasedRepository;
+}
+
+impl<F: FnOnce(ErasedRepository) -> ErasedRepository> RepositoryDecorator for F {
+    fn decorate(self, inner: ErasedRepository) -> ErasedRepository {
+        self(inner)
+    }
+}
+
+/// Caches the rules of the layers below, see `ReadThroughCacheRepository`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CacheLayer;
+
+impl RepositoryDecorator for CacheLayer {
+    fn decorate(self, inner: ErasedRepository) -> ErasedRepository {
+        ReadThroughCacheRepository::new_lazy(inner).into_erased()
+    }
+}
+
+/// Logs every operation made through the layers below, see `LoggingRepository`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingLayer;
+
+impl RepositoryDecorator for LoggingLayer {
+    fn decorate(self, inner: ErasedRepository) -> ErasedRepository {
+        LoggingRepository::new(inner).into_erased()
+    }
+}
+
+/// Builds a repository out of a backend and decorators layered on top of it, e.g.
+///
+/// ```
+/// # use evaluator::repository::{InMemRuleRepository, stack::RepositoryStack};
+/// let repository = RepositoryStack::new(InMemRuleRepository::empty())
+///     .with_cache()
+///     .with_logging()
+///     .build();
+/// ```
+///
+/// Each layer wraps the ones added before it, so operations go through the last layer first.
+#[derive(Clone)]
+pub struct RepositoryStack {
+    repository: ErasedRepository,
+}
+
+impl RepositoryStack {
+    pub fn new<RR: RuleRepository>(inner: RR) -> Self {
+        Self {
+            repository: inner.into_erased(),
+        }
+    }
+
+    pub fn wrap(self, decorator: impl RepositoryDecorator) -> Self {
+        Self {
+            repository: decorator.decorate(self.repository),
+        }
+    }
+
+    pub fn with_cache(self) -> Self {
+        self.wrap(CacheLayer)
+    }
+
+    pub fn with_logging(self) -> Self {
+        self.wrap(LoggingLayer)
+    }
+
+    pub fn build(self) -> ErasedRepository {
+        self.repository
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::{EvaluationOptions, EvaluationResult, InMemRuleRepository};
+    use crate::{predicate, rule};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tracing_test::traced_test;
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_layers() {
+        let backend = InMemRuleRepository::empty();
+        let rule = rule!("rule-1", "message", predicate!("foo" == 10));
+
+        let repository = RepositoryStack::new(backend.clone())
+            .with_cache()
+            .with_logging()
+            .build();
+
+        repository
+            .create(rule.clone())
+            .await
+            .expect("rule should be created");
+
+        // Writes through the stack reach the backend, and are logged by the outer layer.
+        assert_eq!(backend.get(&rule.id).await, Ok(rule.clone()));
+        assert_eq!(repository.get_all().await, Ok(vec![rule.clone()]));
+        assert!(logs_contain("operation=\"create\""));
+
+        let evaluation = repository
+            .evaluate(
+                std::slice::from_ref(&rule.id),
+                json!({"foo": 10}),
+                EvaluationOptions::default(),
+            )
+            .await
+            .expect("evaluation should not fail");
+        assert_eq!(evaluation.result, EvaluationResult::Pass);
+
+        let updated = rule!("rule-1", "updated", predicate!("foo" == 20));
+        repository
+            .update(rule.id.clone(), updated.clone())
+            .await
+            .expect("rule should be updated");
+        assert_eq!(backend.get(&rule.id).await, Ok(updated.clone()));
+        assert_eq!(repository.get(&rule.id).await, Ok(updated));
+
+        repository
+            .delete(&rule.id)
+            .await
+            .expect("rule should be deleted");
+        assert_eq!(backend.get_all().await, Ok(vec![]));
+        assert_eq!(repository.get_all().await, Ok(vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_wrap_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let layer = |name: &'static str| {
+            let order = Arc::clone(&order);
+
+            move |inner: ErasedRepository| {
+                order
+                    .lock()
+                    .expect("lock should not be poisoned")
+                    .push(name);
+                inner
+            }
+        };
+
+        let repository = RepositoryStack::new(InMemRuleRepository::new(&[rule!(
+            "rule-1",
+            "message",
+            predicate!("foo" == 10)
+        )]))
+        .wrap(layer("first"))
+        .with_cache()
+        .wrap(layer("second"))
+        .build();
+
+        assert_eq!(
+            *order.lock().expect("lock should not be poisoned"),
+            ["first", "second"]
+        );
+        assert_eq!(repository.get_all().await.map(|rules| rules.len()), Ok(1));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::core::rule::Rule;
+use crate::repository::{
+    CreateRuleError, DeleteRuleError, InMemRuleRepository, RuleChangeEvent, RuleCounters,
+    StoredRule, UpdateRuleError,
+};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLockWriteGuard;
+
+/// A set of writes to an `InMemRuleRepository` applied as a unit, see
+/// `InMemRuleRepository::begin_transaction`. Holds the repository's write lock until committed or
+/// rolled back, and restores the rules as they were when the transaction began unless committed.
+/// Only the rules written in the transaction are restored, so readers never see the others
+/// missing.
+///
+/// Writes are visible to readers of the repository straight away, but watchers are only notified
+/// of them on `commit`.
+#[derive(Debug)]
+pub struct TransactionGuard<'a> {
+    repository: &'a InMemRuleRepository,
+    _lock: RwLockWriteGuard<'a, ()>,
+    /// The rules written in the transaction as they were before it, `None` once committed.
+    snapshot: Option<Snapshot>,
+    events: Vec<RuleChangeEvent>,
+}
+
+/// What a rolled back `TransactionGuard` restores, by id, with `None` for ids which had no rule.
+/// The counters are kept as deleting or renaming a rule removes its stats.
+#[derive(Debug, Default)]
+struct Snapshot {
+    rules: HashMap<String, Option<StoredRule>>,
+    counters: HashMap<String, Option<Arc<RuleCounters>>>,
+}
+
+impl<'a> TransactionGuard<'a> {
+    pub(super) fn new(repository: &'a InMemRuleRepository, lock: RwLockWriteGuard<'a, ()>) -> Self {
+        Self {
+            repository,
+            _lock: lock,
+            snapshot: Some(Snapshot::default()),
+            events: Vec::new(),
+        }
+    }
+
+    /// Records the rule `id` as it is before the transaction first writes to it.
+    fn snapshot(&mut self, id: &str) {
+        let Some(snapshot) = &mut self.snapshot else {
+            return;
+        };
+
+        if !snapshot.rules.contains_key(id) {
+            let repository = self.repository;
+            snapshot.rules.insert(
+                id.to_owned(),
+                repository.rules.get(id).map(|stored| stored.clone()),
+            );
+            snapshot.counters.insert(
+                id.to_owned(),
+                repository
+                    .counters
+                    .get(id)
+                    .map(|counters| Arc::clone(&counters)),
+            );
+        }
+    }
+
+    /// See `RuleRepository::create`.
+    pub fn create(&mut self, rule: Rule) -> Result<(), CreateRuleError> {
+        self.snapshot(&rule.id);
+        let event = self.repository.create_unlocked(rule)?;
+        self.events.push(event);
+
+        Ok(())
+    }
+
+    /// See `RuleRepository::delete`.
+    #[allow(clippy::ptr_arg)]
+    pub fn delete(&mut self, id: &String) -> Result<Option<Rule>, DeleteRuleError> {
+        self.snapshot(id);
+        let Some((old_rule, event)) = self.repository.delete_unlocked(id) else {
+            return Ok(None);
+        };
+
+        self.events.push(event);
+
+        Ok(Some(old_rule))
+    }
+
+    /// See `RuleRepository::update`.
+    pub fn update(&mut self, id: String, new_rule: Rule) -> Result<Option<Rule>, UpdateRuleError> {
+        self.snapshot(&id);
+        self.snapshot(&new_rule.id);
+        let (old_rule, event) = self.repository.update_unlocked(id, new_rule)?;
+        self.events.push(event);
+
+        Ok(Some(old_rule))
+    }
+
+    /// Keeps every write made in the transaction and notifies watchers of them.
+    pub fn commit(mut self) {
+        self.snapshot = None;
+
+        for event in self.events.drain(..) {
+            self.repository.notify(event);
+        }
+    }
+
+    /// Undoes every write made in the transaction, same as dropping the guard.
+    pub fn rollback(self) {}
+}
+
+impl Drop for TransactionGuard<'_> {
+    fn drop(&mut self) {
+        let Some(snapshot) = self.snapshot.take() else {
+            return;
+        };
+
+        let rules = &self.repository.rules;
+        for (id, stored) in snapshot.rules {
+            match stored {
+                Some(stored) => {
+                    rules.insert(id, stored);
+                }
+                None => {
+                    rules.remove(&id);
+                }
+            }
+        }
+
+        // Counters are shared with evaluations made during the transaction, so restoring them
+        // keeps those evaluations counted. Rules created in the transaction lose theirs.
+        let counters = &self.repository.counters;
+        for (id, rule_counters) in snapshot.counters {
+            match rule_counters {
+                Some(rule_counters) => {
+                    counters.insert(id, rule_counters);
+                }
+                None if !rules.contains_key(&id) => {
+                    counters.remove(&id);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::repository::{
+        CreateRuleError, EvaluationOptions, InMemRuleRepository, RuleChangeEvent, RuleRepository,
+    };
+    use crate::{predicate, rule};
+    use serde_json::json;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_commit() {
+        let rule_1 = rule!("rule-1", "message", predicate!("foo" == 10));
+        let rule_2 = rule!("rule-2", "message", predicate!("bar" is_null));
+
+        let db = InMemRuleRepository::new(std::slice::from_ref(&rule_1));
+        let mut events = db.watch();
+
+        let mut transaction = db.begin_transaction().await;
+        transaction
+            .create(rule_2.clone())
+            .expect("rule should be created");
+        assert_eq!(transaction.delete(&rule_1.id), Ok(Some(rule_1.clone())));
+        transaction.commit();
+
+        assert_eq!(db.get_all().await, Ok(vec![rule_2.clone()]));
+        assert_eq!(events.next().await, Some(RuleChangeEvent::Created(rule_2)));
+        assert_eq!(
+            events.next().await,
+            Some(RuleChangeEvent::Deleted(rule_1.id))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rollback() {
+        let rule_1 = rule!("rule-1", "message", predicate!("foo" == 10));
+        let rule_2 = rule!("rule-2", "message", predicate!("bar" is_null));
+        let rules = vec![rule_1.clone(), rule_2.clone()];
+
+        let db = InMemRuleRepository::new(&rules);
+
+        let mut transaction = db.begin_transaction().await;
+        transaction
+            .create(rule!("rule-3", "message", predicate!("bar" is_null)))
+            .expect("rule should be created");
+        transaction
+            .update(
+                rule_1.id.clone(),
+                rule!("rule-1", "updated", predicate!("bar" is_null)),
+            )
+            .expect("rule should be updated");
+        transaction
+            .delete(&rule_2.id)
+            .expect("rule should be deleted");
+        assert_eq!(
+            transaction.create(rule!("rule-1", "duplicate", predicate!("bar" is_null))),
+            Err(CreateRuleError::Duplicate("rule-1".to_owned()))
+        );
+        transaction.rollback();
+
+        let mut after = db.get_all().await.expect("get_all should not fail");
+        after.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(after, rules);
+
+        // Dropping the guard without committing rolls back too.
+        {
+            let mut transaction = db.begin_transaction().await;
+            transaction
+                .delete(&rule_1.id)
+                .expect("rule should be deleted");
+        }
+
+        assert_eq!(db.get(&rule_1.id).await, Ok(rule_1));
+    }
+
+    #[tokio::test]
+    async fn test_rollback_stats() {
+        let db = InMemRuleRepository::new(&[
+            rule!("rule-1", "message", predicate!("foo" == 10)),
+            rule!("rule-2", "message", predicate!("foo" == 10)),
+        ]);
+        let ids = ["rule-1".to_owned(), "rule-2".to_owned()];
+
+        db.evaluate(&ids, json!({"foo": 10}), EvaluationOptions::default())
+            .await
+            .expect("evaluation should not fail");
+
+        {
+            let mut transaction = db.begin_transaction().await;
+            transaction.delete(&ids[0]).expect("rule should be deleted");
+            transaction
+                .update(
+                    ids[1].clone(),
+                    rule!("rule-3", "message", predicate!("foo" == 10)),
+                )
+                .expect("rule should be renamed");
+        }
+
+        for id in &ids {
+            assert_eq!(
+                db.stats(id).await.map(|stats| stats.evaluations),
+                Ok(1),
+                "{id}"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_rollback_concurrent_reads() {
+        let rules: Vec<_> = (0..100)
+            .map(|i| rule!(&format!("rule-{i}"), "message", predicate!("foo" == 10)))
+            .collect();
+        let db = InMemRuleRepository::new(&rules);
+        let done = Arc::new(AtomicBool::new(false));
+
+        let reader = tokio::spawn({
+            let db = db.clone();
+            let done = Arc::clone(&done);
+            let id = "rule-50".to_owned();
+
+            async move {
+                while !done.load(Ordering::Relaxed) {
+                    assert!(
+                        db.get(&id).await.is_ok(),
+                        "untouched rule should be readable"
+                    );
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+
+        for _ in 0..200 {
+            let mut transaction = db.begin_transaction().await;
+            transaction
+                .create(rule!("rule-new", "message", predicate!("bar" is_null)))
+                .expect("rule should be created");
+            transaction
+                .delete(&"rule-0".to_owned())
+                .expect("rule should be deleted");
+        }
+
+        done.store(true, Ordering::Relaxed);
+        reader.await.expect("reader should not panic");
+
+        assert_eq!(db.get_all().await, Ok(rules));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_writes() {
+        let db = InMemRuleRepository::empty();
+
+        let transaction = db.begin_transaction().await;
+
+        let write = tokio::time::timeout(
+            Duration::from_millis(50),
+            db.create(rule!("rule-1", "message", predicate!("bar" is_null))),
+        )
+        .await;
+        assert!(write.is_err(), "write should wait for the transaction");
+
+        transaction.commit();
+
+        db.create(rule!("rule-1", "message", predicate!("bar" is_null)))
+            .await
+            .expect("rule should be created once the transaction is done");
+    }
+}
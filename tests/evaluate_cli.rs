@@ -0,0 +1,183 @@
+use assert_cmd::Command;
+use evaluator::repository::{Evaluation, EvaluationReason, EvaluationResult};
+use serde_json::json;
+
+macro_rules! evaluate {
+    ($($arg:expr),* $(,)?) => {
+        Command::cargo_bin("evaluate")
+            .expect("evaluate binary should be built")
+            .arg("--rules-file")
+            .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/rules.json"))
+            $(.arg($arg))*
+            .assert()
+    };
+}
+
+fn parse_evaluation(stdout: &[u8]) -> Evaluation {
+    serde_json::from_slice(stdout).expect("stdout should be an evaluation")
+}
+
+#[test]
+fn test_pass() {
+    let assert = evaluate!("--rule-ids", "foo_rule", "--input", r#"{"foo": 10}"#).success();
+
+    assert_eq!(
+        parse_evaluation(&assert.get_output().stdout),
+        Evaluation::new(
+            EvaluationResult::Pass,
+            vec![EvaluationReason {
+                rule: "foo_rule".to_owned(),
+                requirement: "field foo must be 10".to_owned(),
+                evaluation: EvaluationResult::Pass,
+                weight: 1.0,
+                input_value: None,
+                elapsed_micros: None,
+                predicate_snapshot: None,
+                match_count: None,
+                total_predicate_count: None,
+                operator: None,
+                path: None,
+                skip_reason: None,
+            }],
+        )
+    );
+}
+
+#[test]
+fn test_fail() {
+    let assert = evaluate!(
+        "--rule-ids",
+        "foo_rule,and_rule",
+        "--input",
+        r#"{"foo": 10, "fizz": 3, "buzz": 4}"#
+    )
+    .code(1);
+
+    let evaluation = parse_evaluation(&assert.get_output().stdout);
+    assert_eq!(evaluation.result, EvaluationResult::Fail);
+    assert_eq!(evaluation.reasons.len(), 2);
+    assert_eq!(evaluation.reasons[1].evaluation, EvaluationResult::Fail);
+}
+
+#[test]
+fn test_all() {
+    let input = json!({
+        "foo": 10,
+        "fizz": 3,
+        "buzz": 5,
+        "a": { "b": { "c": 20 } },
+        "height": { "feet": 6, "inches": 0 },
+        "age": 12,
+    });
+
+    let assert = evaluate!("--all", "--input", input.to_string()).success();
+
+    let evaluation = parse_evaluation(&assert.get_output().stdout);
+    assert_eq!(evaluation.result, EvaluationResult::Pass);
+    assert_eq!(evaluation.reasons.len(), 5);
+}
+
+#[test]
+fn test_strict() {
+    evaluate!(
+        "--rule-ids",
+        "foo_rule",
+        "--input",
+        r#"{"foo": 10}"#,
+        "--strict"
+    )
+    .success();
+
+    evaluate!("--rule-ids", "foo_rule", "--input", r#"{"bar": 10}"#).code(1);
+
+    let assert = evaluate!(
+        "--rule-ids",
+        "foo_rule",
+        "--input",
+        r#"{"bar": 10}"#,
+        "--strict"
+    )
+    .code(2);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("foo_rule: missing field `foo`"));
+
+    evaluate!(
+        "--rule-ids",
+        "waterpark_age_rule",
+        "--input",
+        r#"{"age": "12"}"#,
+        "--strict"
+    )
+    .code(2);
+}
+
+#[test]
+fn test_csv_output() {
+    evaluate!(
+        "--rule-ids",
+        "foo_rule",
+        "--input",
+        r#"{"foo": 10}"#,
+        "--output",
+        "csv"
+    )
+    .success()
+    .stdout(
+        "rule,requirement,evaluation,weight,inputValue\nfoo_rule,field foo must be 10,PASS,1,\n",
+    );
+}
+
+#[test]
+fn test_csv_output_columns() {
+    let rules = json!([
+        {
+            "id": "weighted_rule",
+            "message": "foo must be 10",
+            "predicate": {"path": "foo", "operator": "equal", "value": 10},
+            "weight": 2.5
+        },
+        {
+            "id": "compound_rule",
+            "message": "fizz must be 3 and buzz must be 5",
+            "predicate": {
+                "all": [
+                    {"path": "fizz", "operator": "==", "value": 3},
+                    {"path": "buzz", "operator": "==", "value": 5}
+                ]
+            }
+        },
+        {
+            "id": "object_rule",
+            "message": "a must be set",
+            "predicate": {"path": "a", "operator": "isNotNull", "value": null}
+        }
+    ]);
+
+    let mut rules_file =
+        tempfile::NamedTempFile::with_suffix(".json").expect("temporary file should be created");
+    serde_json::to_writer(&mut rules_file, &rules).expect("rules should be written");
+
+    Command::cargo_bin("evaluate")
+        .expect("evaluate binary should be built")
+        .arg("--rules-file")
+        .arg(rules_file.path())
+        .args(["--rule-ids", "weighted_rule,compound_rule,object_rule"])
+        .args(["--include-input-values", "--output", "csv"])
+        .arg("--input")
+        .arg(r#"{"foo": 10, "fizz": 3, "buzz": 4, "a": {"b": "c, d"}}"#)
+        .assert()
+        .code(1)
+        .stdout(concat!(
+            "rule,requirement,evaluation,weight,inputValue\n",
+            "weighted_rule,foo must be 10,PASS,2.5,10\n",
+            "compound_rule,fizz must be 3 and buzz must be 5,FAIL,1,\n",
+            "object_rule,a must be set,PASS,1,\"{\"\"b\"\":\"\"c, d\"\"}\"\n",
+        ));
+}
+
+#[test]
+fn test_errors() {
+    evaluate!("--rule-ids", "no_such_rule", "--input", "{}").code(2);
+    evaluate!("--rule-ids", "foo_rule", "--input", "not json").code(2);
+    evaluate!("--input", "{}").code(2);
+}
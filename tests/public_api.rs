@@ -0,0 +1,40 @@
+use evaluator::core::rule::{Operator, Rule};
+use evaluator::repository::{CreateRuleError, EvaluationResult};
+use evaluator::{predicate, rule};
+
+#[test]
+fn test_rule_accessors() {
+    let rule: Rule = rule!("rule-1", "message", predicate!("foo" == 10));
+
+    assert_eq!(rule.id(), "rule-1");
+    assert_eq!(rule.message(), "message");
+    assert_eq!(rule.predicate(), &predicate!("foo" == 10).into());
+    assert!(rule.depends_on().is_empty());
+
+    // The fields themselves are public too, for building and updating rules.
+    assert_eq!(rule.id, rule.id());
+}
+
+#[test]
+fn test_match_non_exhaustive() {
+    let describe = |operator: Operator| match operator {
+        Operator::Equal => "equals",
+        Operator::NotEqual => "not equals",
+        _ => "other",
+    };
+
+    assert_eq!(describe(Operator::Equal), "equals");
+    assert_eq!(describe(Operator::Less), "other");
+
+    let status = |result: EvaluationResult| match result {
+        EvaluationResult::Pass => "passed",
+        EvaluationResult::Fail => "failed",
+        _ => "unknown",
+    };
+
+    assert_eq!(status(EvaluationResult::Pass), "passed");
+    assert_eq!(status(EvaluationResult::Skipped), "unknown");
+
+    let error = CreateRuleError::Duplicate("rule-1".to_owned());
+    assert!(matches!(error, CreateRuleError::Duplicate(id) if id == "rule-1"));
+}